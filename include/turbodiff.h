@@ -0,0 +1,50 @@
+/* Generated with cbindgen from src/ffi.rs; do not edit by hand. */
+
+#ifndef TURBODIFF_H
+#define TURBODIFF_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Diffs the JSON documents `t1` and `t2`, both NUL-terminated UTF-8.
+ *
+ * `options` is NULL or a JSON object of the Python keyword options that
+ * apply to JSON input, such as `{"ignore_order": true}`. Returns the result
+ * dict as JSON, to be released with `turbodiff_string_free`, or NULL on
+ * error, with the message available from `turbodiff_last_error`. A panic
+ * inside the diff is caught and reported the same way.
+ *
+ * # Safety
+ *
+ * `t1` and `t2` must be valid NUL-terminated strings; `options` must be one
+ * too, or NULL.
+ */
+char *turbodiff_diff_json(const char *t1, const char *t2, const char *options);
+
+/**
+ * Releases a string returned by `turbodiff_diff_json`. NULL is ignored.
+ *
+ * # Safety
+ *
+ * `value` must come from `turbodiff_diff_json` and not be freed twice.
+ */
+void turbodiff_string_free(char *value);
+
+/**
+ * The message of the last failed call on this thread, or NULL. The string
+ * stays valid until the next call on this thread.
+ */
+const char *turbodiff_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TURBODIFF_H */
@@ -0,0 +1,64 @@
+//! Aliases that show reported paths in domain terms, such as `container[0]`
+//! for `root['spec']['containers'][0]`.
+
+use crate::path::relabel;
+use crate::rules;
+use regex::Regex;
+
+/// Shows the paths a pattern matches, and those under them, with `label` in
+/// place of the matched part. Each `[*]` of the label repeats the step the
+/// corresponding `[*]` of the pattern matched.
+#[derive(Clone, Debug)]
+pub(crate) struct PathAlias {
+    pub(crate) source: String,
+    pattern: Regex,
+    label: String,
+}
+
+impl PathAlias {
+    pub(crate) fn new(pattern: &str, label: &str) -> Self {
+        Self {
+            source: pattern.to_string(),
+            pattern: Regex::new(&format!("^{}", rules::pattern_regex(pattern, true)))
+                .unwrap_or_else(|_| Regex::new("^$").expect("empty pattern")),
+            label: label.to_string(),
+        }
+    }
+
+    /// `path`, rendered from `root`, through this alias, or `None` when the
+    /// pattern matches neither it nor one of its ancestors.
+    pub(crate) fn apply(&self, path: &str) -> Option<String> {
+        let (mut label, rest) = self.split(path)?;
+        label.push_str(rest);
+        Some(label)
+    }
+
+    /// The label for the part of `path` the pattern matches, and the rest of
+    /// the path after it.
+    pub(crate) fn split<'p>(&self, path: &'p str) -> Option<(String, &'p str)> {
+        let captures = self.pattern.captures(path)?;
+        let rest = &path[captures.get(0)?.end()..];
+        if !rest.is_empty() && !rest.starts_with(['[', '.']) {
+            return None;
+        }
+        let mut steps = captures.iter().skip(1).flatten();
+        let mut out = String::with_capacity(self.label.len());
+        let mut label = self.label.as_str();
+        while let Some(pos) = label.find("[*]") {
+            out.push_str(&label[..pos]);
+            out.push_str(steps.next().map_or("[*]", |step| step.as_str()));
+            label = &label[pos + 3..];
+        }
+        out.push_str(label);
+        Some((out, rest))
+    }
+}
+
+/// `path`, rendered from `root`, as shown to users: through the first of
+/// `aliases` that matches it, or else starting with `root_label`.
+pub(crate) fn display_path(path: &str, root_label: &str, aliases: &[PathAlias]) -> String {
+    aliases
+        .iter()
+        .find_map(|alias| alias.apply(path))
+        .unwrap_or_else(|| relabel(path, root_label))
+}
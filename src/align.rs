@@ -0,0 +1,106 @@
+/// One step of an alignment of two sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Edit {
+    /// Item `.0` of the first sequence equals item `.1` of the second.
+    Keep(usize, usize),
+    /// Item of the first sequence missing from the second.
+    Delete(usize),
+    /// Item of the second sequence missing from the first.
+    Insert(usize),
+}
+
+/// A shortest edit script turning `a` into `b` (Myers' algorithm), in
+/// sequence order, or `None` when it takes more than `max_edits` deletions
+/// and insertions. Common leading and trailing items are kept outright.
+pub(crate) fn align<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Option<Vec<Edit>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|idx| Edit::Keep(idx, idx)).collect();
+    edits.extend(
+        shortest_edits(a_mid, b_mid, max_edits)?
+            .into_iter()
+            .map(|edit| match edit {
+                Edit::Keep(x, y) => Edit::Keep(x + prefix, y + prefix),
+                Edit::Delete(x) => Edit::Delete(x + prefix),
+                Edit::Insert(y) => Edit::Insert(y + prefix),
+            }),
+    );
+    let (tail1, tail2) = (a.len() - suffix, b.len() - suffix);
+    edits.extend((0..suffix).map(|idx| Edit::Keep(tail1 + idx, tail2 + idx)));
+    Some(edits)
+}
+
+/// Myers' greedy search for the furthest reaching path on each diagonal,
+/// keeping the frontier of every round, `2d + 1` wide, to walk back from
+/// the end.
+fn shortest_edits<T: PartialEq>(a: &[T], b: &[T], max_edits: usize) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let limit = (a.len() + b.len()).min(max_edits) as isize;
+    let offset = limit + 1;
+    let mut frontier = vec![0isize; 2 * limit as usize + 3];
+    let mut rounds: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=limit {
+        rounds.push(frontier[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && frontier[at - 1] < frontier[at + 1]) {
+                frontier[at + 1]
+            } else {
+                frontier[at - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            frontier[at] = x;
+            if x >= n && y >= m {
+                return Some(walk_back(&rounds, n, m));
+            }
+        }
+    }
+    None
+}
+
+/// The edits of the path found in `rounds`, from its end at `(n, m)`.
+fn walk_back(rounds: &[Vec<isize>], n: isize, m: isize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..rounds.len() as isize).rev() {
+        // The frontier after round `d - 1`, by diagonal.
+        let frontier = |k: isize| rounds[d as usize][(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && frontier(k - 1) < frontier(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = frontier(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Keep(x as usize, y as usize));
+        }
+        if x == prev_x {
+            edits.push(Edit::Insert(prev_y as usize));
+        } else {
+            edits.push(Edit::Delete(prev_x as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        edits.push(Edit::Keep(x as usize, y as usize));
+    }
+    edits.reverse();
+    edits
+}
@@ -0,0 +1,65 @@
+//! Conversion of Arrow arrays imported through the C data interface.
+
+use crate::tagged::float_value;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrowPrimitiveType};
+use arrow_schema::DataType;
+use serde_json::Value;
+
+/// Reads the values of a boolean, integer, float, string, or null array.
+/// Returns `None` for other types, which the caller converts through Python
+/// instead.
+pub(crate) fn array_values(array: &dyn Array) -> Option<Vec<Value>> {
+    match array.data_type() {
+        DataType::Null => Some(vec![Value::Null; array.len()]),
+        DataType::Boolean => Some(
+            array
+                .as_boolean()
+                .iter()
+                .map(|item| item.map_or(Value::Null, Value::Bool))
+                .collect(),
+        ),
+        DataType::Int8 => Some(primitive_values::<Int8Type>(array, Value::from)),
+        DataType::Int16 => Some(primitive_values::<Int16Type>(array, Value::from)),
+        DataType::Int32 => Some(primitive_values::<Int32Type>(array, Value::from)),
+        DataType::Int64 => Some(primitive_values::<Int64Type>(array, Value::from)),
+        DataType::UInt8 => Some(primitive_values::<UInt8Type>(array, Value::from)),
+        DataType::UInt16 => Some(primitive_values::<UInt16Type>(array, Value::from)),
+        DataType::UInt32 => Some(primitive_values::<UInt32Type>(array, Value::from)),
+        DataType::UInt64 => Some(primitive_values::<UInt64Type>(array, Value::from)),
+        DataType::Float32 => Some(primitive_values::<Float32Type>(array, |f| {
+            float_value(f64::from(f))
+        })),
+        DataType::Float64 => Some(primitive_values::<Float64Type>(array, float_value)),
+        DataType::Utf8 => Some(
+            array
+                .as_string::<i32>()
+                .iter()
+                .map(|item| item.map_or(Value::Null, |s| Value::String(s.to_string())))
+                .collect(),
+        ),
+        DataType::LargeUtf8 => Some(
+            array
+                .as_string::<i64>()
+                .iter()
+                .map(|item| item.map_or(Value::Null, |s| Value::String(s.to_string())))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn primitive_values<T: ArrowPrimitiveType>(
+    array: &dyn Array,
+    to_value: impl Fn(T::Native) -> Value,
+) -> Vec<Value> {
+    array
+        .as_primitive::<T>()
+        .iter()
+        .map(|item| item.map_or(Value::Null, &to_value))
+        .collect()
+}
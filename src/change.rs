@@ -0,0 +1,290 @@
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Report key a change is filed under in the result dict.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    ValuesChanged,
+    TypeChanged,
+    DictionaryItemAdded,
+    DictionaryItemRemoved,
+    AttributeAdded,
+    AttributeRemoved,
+    IterableItemAdded,
+    IterableItemRemoved,
+    /// An item of an ordered list found unchanged at another position, with
+    /// `align_lists`.
+    IterableItemMoved,
+    /// An item of an `ignore_order` list found a different number of times
+    /// on each side, with `report_repetition`.
+    RepetitionChange,
+    /// A value whose type the schema passed to
+    /// [`DeepDiffOptions::schema`](crate::DeepDiffOptions::schema) does not
+    /// allow.
+    SchemaViolation,
+}
+
+impl ChangeKind {
+    pub const ALL: [ChangeKind; 11] = [
+        Self::ValuesChanged,
+        Self::TypeChanged,
+        Self::DictionaryItemAdded,
+        Self::DictionaryItemRemoved,
+        Self::AttributeAdded,
+        Self::AttributeRemoved,
+        Self::IterableItemAdded,
+        Self::IterableItemRemoved,
+        Self::IterableItemMoved,
+        Self::RepetitionChange,
+        Self::SchemaViolation,
+    ];
+
+    /// The kind filed under the report key `key`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == key)
+    }
+
+    /// The kind the same change has seen from the other side: additions and
+    /// removals trade places, other kinds stay.
+    pub fn reversed(self) -> Self {
+        match self {
+            Self::DictionaryItemAdded => Self::DictionaryItemRemoved,
+            Self::DictionaryItemRemoved => Self::DictionaryItemAdded,
+            Self::AttributeAdded => Self::AttributeRemoved,
+            Self::AttributeRemoved => Self::AttributeAdded,
+            Self::IterableItemAdded => Self::IterableItemRemoved,
+            Self::IterableItemRemoved => Self::IterableItemAdded,
+            other => other,
+        }
+    }
+
+    /// The DeepDiff report key, such as `values_changed`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ValuesChanged => "values_changed",
+            Self::TypeChanged => "type_changes",
+            Self::DictionaryItemAdded => "dictionary_item_added",
+            Self::DictionaryItemRemoved => "dictionary_item_removed",
+            Self::AttributeAdded => "attribute_added",
+            Self::AttributeRemoved => "attribute_removed",
+            Self::IterableItemAdded => "iterable_item_added",
+            Self::IterableItemRemoved => "iterable_item_removed",
+            Self::IterableItemMoved => "iterable_item_moved",
+            Self::RepetitionChange => "repetition_change",
+            Self::SchemaViolation => "schema_violations",
+        }
+    }
+}
+
+impl Serialize for ChangeKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// One difference found while diffing, borrowing its values from the inputs
+/// where it can.
+///
+/// Removed items carry only `old_value`, added items only `new_value`; type
+/// changes always carry both type names, and their values except for
+/// DataFrame column dtype changes. Value changes between strings longer than
+/// `large_string_threshold` carry a `summary` of the changed byte ranges
+/// instead of either value. Moved items and repetition changes carry the
+/// item as both values.
+///
+/// Serializes as `{"kind": "values_changed", "path": ..., ...}`, leaving out
+/// the fields that are not set.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Change<'a> {
+    pub kind: ChangeKind,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Cow<'a, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Cow<'a, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Value>,
+    /// The type the schema allows, for schema violations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_type: Option<String>,
+    /// How large a numeric change is, from 0 to 1, with `magnitude_scores`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magnitude: Option<f64>,
+    /// Where a moved item is in the new list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_path: Option<String>,
+    /// How often the item is found on each side, for repetition changes.
+    #[serde(flatten)]
+    pub repetition: Option<Repetition>,
+}
+
+/// The counts and indices of an item repeated a different number of times
+/// in each list, as DeepDiff reports them under `repetition_change`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Repetition {
+    pub old_repeat: usize,
+    pub new_repeat: usize,
+    pub old_indexes: Vec<usize>,
+    pub new_indexes: Vec<usize>,
+}
+
+impl Repetition {
+    /// Reads the counts and indices of a `repetition_change` entry.
+    pub(crate) fn from_entry(entry: &Value) -> Option<Self> {
+        let indexes = |name: &str| -> Option<Vec<usize>> {
+            entry
+                .get(name)?
+                .as_array()?
+                .iter()
+                .map(|idx| idx.as_u64().map(|idx| idx as usize))
+                .collect()
+        };
+        let (old_indexes, new_indexes) = (indexes("old_indexes")?, indexes("new_indexes")?);
+        Some(Self {
+            old_repeat: old_indexes.len(),
+            new_repeat: new_indexes.len(),
+            old_indexes,
+            new_indexes,
+        })
+    }
+
+    /// The entry DeepDiff files under `repetition_change`, holding `value`.
+    pub(crate) fn to_entry(&self, value: Value) -> Value {
+        serde_json::json!({
+            "old_repeat": self.old_repeat,
+            "new_repeat": self.new_repeat,
+            "old_indexes": self.old_indexes,
+            "new_indexes": self.new_indexes,
+            "value": value,
+        })
+    }
+}
+
+impl<'a> Change<'a> {
+    /// A change of `kind` at `path`, with no values or types set.
+    pub fn new(kind: ChangeKind, path: String) -> Self {
+        Self {
+            kind,
+            path,
+            old_type: None,
+            new_type: None,
+            old_value: None,
+            new_value: None,
+            summary: None,
+            expected_type: None,
+            magnitude: None,
+            new_path: None,
+            repetition: None,
+        }
+    }
+
+    /// Sets the old and new values, borrowed from the diffed documents.
+    pub fn values(mut self, old: Option<&'a Value>, new: Option<&'a Value>) -> Self {
+        self.old_value = old.map(Cow::Borrowed);
+        self.new_value = new.map(Cow::Borrowed);
+        self
+    }
+
+    pub(crate) fn summary(mut self, summary: Value) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    pub(crate) fn expected_type(mut self, expected: String) -> Self {
+        self.expected_type = Some(expected);
+        self
+    }
+
+    pub(crate) fn magnitude(mut self, magnitude: Option<f64>) -> Self {
+        self.magnitude = magnitude;
+        self
+    }
+
+    pub(crate) fn new_path(mut self, path: String) -> Self {
+        self.new_path = Some(path);
+        self
+    }
+
+    pub(crate) fn repetition(mut self, repetition: Repetition) -> Self {
+        self.repetition = Some(repetition);
+        self
+    }
+
+    /// Sets the old and new type names, for type changes.
+    pub fn types(mut self, old: &'a str, new: &'a str) -> Self {
+        self.old_type = Some(old);
+        self.new_type = Some(new);
+        self
+    }
+    /// The change a result dict files under `kind` at `path`, borrowing its
+    /// values from `entry`, which paths-only reports lack. Dict keys and
+    /// attributes carry their path only, as in the result dict.
+    pub(crate) fn from_entry(kind: ChangeKind, path: String, entry: Option<&'a Value>) -> Self {
+        let mut change = Self::new(kind, path);
+        let Some(entry) = entry else {
+            return change;
+        };
+        match kind {
+            ChangeKind::ValuesChanged if entry.get("changed_ranges").is_some() => {
+                change.summary = Some(entry.clone());
+            }
+            ChangeKind::ValuesChanged | ChangeKind::TypeChanged | ChangeKind::SchemaViolation => {
+                change.old_type = entry.get("old_type").and_then(Value::as_str);
+                change.new_type = entry.get("new_type").and_then(Value::as_str);
+                change.old_value = entry.get("old_value").map(Cow::Borrowed);
+                change.new_value = entry.get("new_value").map(Cow::Borrowed);
+                change.expected_type = entry
+                    .get("expected_type")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                change.magnitude = entry.get("magnitude").and_then(Value::as_f64);
+            }
+            ChangeKind::IterableItemAdded => change.new_value = Some(Cow::Borrowed(entry)),
+            ChangeKind::IterableItemRemoved => change.old_value = Some(Cow::Borrowed(entry)),
+            ChangeKind::IterableItemMoved => {
+                change.old_value = entry.get("value").map(Cow::Borrowed);
+                change.new_value = change.old_value.clone();
+                change.new_path = entry
+                    .get("new_path")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+            }
+            ChangeKind::RepetitionChange => {
+                change.old_value = entry.get("value").map(Cow::Borrowed);
+                change.new_value = change.old_value.clone();
+                change.repetition = Repetition::from_entry(entry);
+            }
+            _ => {}
+        }
+        change
+    }
+}
+
+/// The entries of a result dict by kind, in [`ChangeKind::ALL`] order, and
+/// by path within a kind: each one's kind, path, and entry, which
+/// paths-only reports and dict keys lack.
+pub(crate) fn result_entries(
+    result: &Value,
+) -> impl Iterator<Item = (ChangeKind, &str, Option<&Value>)> {
+    ChangeKind::ALL.into_iter().flat_map(move |kind| {
+        let entries: Box<dyn Iterator<Item = (&str, Option<&Value>)>> =
+            match result.get(kind.as_str()) {
+                Some(Value::Object(map)) => {
+                    Box::new(map.iter().map(|(path, entry)| (path.as_str(), Some(entry))))
+                }
+                Some(Value::Array(paths)) => Box::new(
+                    paths
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(|path| (path, None)),
+                ),
+                _ => Box::new(std::iter::empty()),
+            };
+        entries.map(move |(path, entry)| (kind, path, entry))
+    })
+}
@@ -0,0 +1,238 @@
+//! Diffs run in resumable steps, for inputs large enough that a batch job
+//! may be preempted before the diff is done.
+
+use crate::engine::{self, DiffAccumulator, Part};
+use crate::error::TurboDiffError;
+use crate::group;
+use crate::hash::deep_hash;
+use crate::options::DeepDiffOptions;
+use crate::path::{format_path, parse_path, PathSegment};
+use crate::rediff;
+use crate::DeepDiff;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io;
+use std::ops::Range;
+use std::path::Path as FsPath;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The progress of a [`DiffJob`]: the parts of the inputs not diffed yet and
+/// the changes found so far. Saved to disk and read back, it lets a job
+/// resume where it stopped instead of starting over.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Hashes of `t1` and `t2` with the job's hasher; a checkpoint only
+    /// resumes the same inputs.
+    inputs: [String; 2],
+    /// Subtrees, and runs of items of the dict or list at `path`, not
+    /// diffed yet; the next one last.
+    pending: Vec<PendingPart>,
+    /// The result for everything diffed so far, with paths from `root`.
+    partial: Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PendingPart {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    items: Option<[usize; 2]>,
+}
+
+impl Checkpoint {
+    /// Number of parts left to diff.
+    pub fn remaining(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, TurboDiffError> {
+        serde_json::from_str(text).map_err(|err| TurboDiffError::InvalidCheckpoint(err.to_string()))
+    }
+
+    /// Writes the checkpoint to `path` through a temporary file renamed over
+    /// it, so a job stopped while saving keeps its previous checkpoint.
+    pub fn save(&self, path: impl AsRef<FsPath>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, self.to_json())?;
+        std::fs::rename(&temporary, path)
+    }
+
+    /// Reads a checkpoint written by [`Checkpoint::save`].
+    pub fn load(path: impl AsRef<FsPath>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_json(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// A diff run in steps, which can stop between any two and resume later,
+/// in another process too, from a [`Checkpoint`].
+///
+/// Large dicts and ordered lists are split into parts of about 16k nodes,
+/// each diffed in one go; the finished diff is the one
+/// [`DeepDiff::with_options`] returns. Options under which changes depend
+/// on more than their subtree, such as `ignore_order`, `schema`, or `rules`,
+/// leave the whole diff as one part.
+pub struct DiffJob {
+    t1: Arc<Value>,
+    t2: Arc<Value>,
+    options: DeepDiffOptions,
+    inputs: [String; 2],
+    pending: Vec<Pending>,
+    partial: Map<String, Value>,
+}
+
+struct Pending {
+    region: Vec<PathSegment>,
+    items: Option<Range<usize>>,
+}
+
+impl DiffJob {
+    pub fn new(t1: Value, t2: Value, options: DeepDiffOptions) -> Self {
+        let inputs = [
+            deep_hash(&t1, options.hasher),
+            deep_hash(&t2, options.hasher),
+        ];
+        let (t1, t2) = group::grouped_sides(Arc::new(t1), Arc::new(t2), &options);
+        Self {
+            inputs,
+            t1,
+            t2,
+            options,
+            pending: vec![Pending {
+                region: Vec::new(),
+                items: None,
+            }],
+            partial: Map::new(),
+        }
+    }
+
+    /// Picks up the job `checkpoint` was taken from. The inputs must be the
+    /// ones the job started with, and the options should be too.
+    pub fn resume(
+        t1: Value,
+        t2: Value,
+        options: DeepDiffOptions,
+        checkpoint: Checkpoint,
+    ) -> Result<Self, TurboDiffError> {
+        let invalid = |reason: &str| Err(TurboDiffError::InvalidCheckpoint(reason.to_string()));
+        let mut job = Self::new(t1, t2, options);
+        if job.inputs != checkpoint.inputs {
+            return invalid("it was taken for other inputs");
+        }
+        let Value::Object(partial) = checkpoint.partial else {
+            return invalid("the partial result is not a dict");
+        };
+        let mut pending = Vec::with_capacity(checkpoint.pending.len());
+        for part in checkpoint.pending {
+            let Some(region) = parse_path(&part.path) else {
+                return invalid(&format!("'{}' is not a path", part.path));
+            };
+            if !region.is_empty() && !job.options.region_local() {
+                return invalid("the options do not allow diffing parts on their own");
+            }
+            pending.push(Pending {
+                region,
+                items: part.items.map(|[start, end]| start..end),
+            });
+        }
+        job.pending = pending;
+        job.partial = partial;
+        Ok(job)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Diffs up to `parts` parts; returns whether the diff is done.
+    pub fn step(&mut self, parts: usize) -> bool {
+        self.run_while(|done| done < parts)
+    }
+
+    /// Diffs parts until the diff is done or `budget` has passed, finishing
+    /// the part under way; returns whether the diff is done.
+    pub fn run_for(&mut self, budget: Duration) -> bool {
+        let started = Instant::now();
+        self.run_while(|_| started.elapsed() < budget)
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            inputs: self.inputs.clone(),
+            pending: self
+                .pending
+                .iter()
+                .map(|part| PendingPart {
+                    path: format_path("root", &part.region),
+                    items: part.items.clone().map(|items| [items.start, items.end]),
+                })
+                .collect(),
+            partial: Value::Object(self.partial.clone()),
+        }
+    }
+
+    /// Diffs what is left and returns the whole diff.
+    pub fn finish(mut self) -> DeepDiff {
+        self.run_while(|_| true);
+        DeepDiff {
+            t1: self.t1,
+            t2: self.t2,
+            options: self.options,
+            result: Value::Object(self.partial),
+        }
+    }
+
+    fn run_while(&mut self, mut more: impl FnMut(usize) -> bool) -> bool {
+        let mut acc = DiffAccumulator::new(&self.options);
+        let mut done = 0;
+        while more(done) {
+            let Some(part) = self.pending.pop() else {
+                break;
+            };
+            self.run_part(part, &mut acc);
+            done += 1;
+        }
+        if let Value::Object(found) = acc.into_value() {
+            rediff::merge(&mut self.partial, found, &self.options);
+        }
+        self.is_done()
+    }
+
+    fn run_part(&mut self, part: Pending, acc: &mut DiffAccumulator) {
+        let (t1, t2) = (Arc::clone(&self.t1), Arc::clone(&self.t2));
+        let options = &self.options;
+        let Pending { region, items } = part;
+        if let Some(items) = items {
+            engine::diff_items(&t1, &t2, &region, items, options, acc);
+            return;
+        }
+        if !options.region_local() {
+            engine::diff_documents(&t1, &t2, options, acc);
+            return;
+        }
+        match engine::split_region(&t1, &t2, &region, options, acc) {
+            Some(parts) => {
+                for part in parts.into_iter().rev() {
+                    self.pending.push(match part {
+                        Part::Item(segment) => Pending {
+                            region: [region.as_slice(), &[segment]].concat(),
+                            items: None,
+                        },
+                        Part::Items(items) => Pending {
+                            region: region.clone(),
+                            items: Some(items),
+                        },
+                    });
+                }
+            }
+            None if region.is_empty() => engine::diff_documents(&t1, &t2, options, acc),
+            None => engine::diff_region(&t1, &t2, &region, options, acc),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::hash::FxHasher;
+use crate::report::{self, StringSummary};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::ops::Range;
+
+/// Chunks never end before this many bytes, so runs of boundary-like bytes
+/// cannot split a string into tiny chunks.
+const MIN_CHUNK: usize = 64;
+/// Chunks are cut here even without a content boundary.
+const MAX_CHUNK: usize = 64 * 1024;
+/// A boundary falls where the top 12 bits of the rolling hash are zero, for
+/// chunks of about 4 KiB on average.
+const BOUNDARY_SHIFT: u32 = 52;
+
+/// Random value per byte for the gear rolling hash. Shifting the hash left
+/// each byte means its top bits depend on the last 64 bytes only, so
+/// boundaries are found at the same content in both strings.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut idx = 0;
+    while idx < 256 {
+        // splitmix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[idx] = z ^ (z >> 31);
+        idx += 1;
+    }
+    table
+}
+
+/// A byte range of the old string replaced by a byte range of the new one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ChangedRange {
+    pub(crate) old: Range<usize>,
+    pub(crate) new: Range<usize>,
+}
+
+/// The `values_changed` entry for two large strings: their lengths and the
+/// byte ranges that differ, instead of both strings.
+pub(crate) fn summary(old: &str, new: &str) -> Value {
+    let changed_ranges = changed_ranges(old.as_bytes(), new.as_bytes())
+        .into_iter()
+        .map(|range| report::ChangedRange {
+            old: [range.old.start, range.old.end],
+            new: [range.new.start, range.new.end],
+        })
+        .collect();
+    let summary = StringSummary {
+        old_length: old.len(),
+        new_length: new.len(),
+        changed_ranges,
+    };
+    serde_json::to_value(summary).unwrap_or_default()
+}
+
+/// Byte ranges where `old` and `new` differ, in order.
+///
+/// The common prefix and suffix are skipped first; the rest of each string is
+/// cut into content-defined chunks, and chunks found in both strings, in the
+/// same order, anchor the unchanged stretches between the reported ranges.
+pub(crate) fn changed_ranges(old: &[u8], new: &[u8]) -> Vec<ChangedRange> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = prefix..old.len() - suffix;
+    let new_mid = prefix..new.len() - suffix;
+    if old_mid.is_empty() && new_mid.is_empty() {
+        return Vec::new();
+    }
+    if old_mid.is_empty() || new_mid.is_empty() {
+        return vec![ChangedRange {
+            old: old_mid,
+            new: new_mid,
+        }];
+    }
+
+    let old_chunks = chunk_bounds(&old[old_mid.clone()], prefix);
+    let new_chunks = chunk_bounds(&new[new_mid.clone()], prefix);
+    let mut new_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, chunk) in new_chunks.iter().enumerate() {
+        new_by_hash
+            .entry(chunk_hash(&new[chunk.clone()]))
+            .or_default()
+            .push(idx);
+    }
+
+    let mut ranges = Vec::new();
+    let mut old_pos = old_mid.start;
+    let mut new_pos = new_mid.start;
+    let mut next_new = 0;
+    for chunk in &old_chunks {
+        let bytes = &old[chunk.clone()];
+        let Some(candidates) = new_by_hash.get(&chunk_hash(bytes)) else {
+            continue;
+        };
+        let found = candidates
+            .iter()
+            .copied()
+            .find(|idx| *idx >= next_new && new[new_chunks[*idx].clone()] == *bytes);
+        let Some(idx) = found else {
+            continue;
+        };
+        let matched = &new_chunks[idx];
+        push_range(
+            &mut ranges,
+            (old, old_pos..chunk.start),
+            (new, new_pos..matched.start),
+        );
+        old_pos = chunk.end;
+        new_pos = matched.end;
+        next_new = idx + 1;
+    }
+    push_range(
+        &mut ranges,
+        (old, old_pos..old_mid.end),
+        (new, new_pos..new_mid.end),
+    );
+    ranges
+}
+
+/// Records the stretch between two matched chunks, narrowed to the bytes
+/// that actually differ.
+fn push_range(
+    ranges: &mut Vec<ChangedRange>,
+    (old, mut old_range): (&[u8], Range<usize>),
+    (new, mut new_range): (&[u8], Range<usize>),
+) {
+    while !old_range.is_empty()
+        && !new_range.is_empty()
+        && old[old_range.start] == new[new_range.start]
+    {
+        old_range.start += 1;
+        new_range.start += 1;
+    }
+    while !old_range.is_empty()
+        && !new_range.is_empty()
+        && old[old_range.end - 1] == new[new_range.end - 1]
+    {
+        old_range.end -= 1;
+        new_range.end -= 1;
+    }
+    if !old_range.is_empty() || !new_range.is_empty() {
+        ranges.push(ChangedRange {
+            old: old_range,
+            new: new_range,
+        });
+    }
+}
+
+/// Content-defined chunk boundaries of `bytes`, offset by `base`.
+fn chunk_bounds(bytes: &[u8], base: usize) -> Vec<Range<usize>> {
+    let mut chunks = Vec::with_capacity(bytes.len() / 4096 + 1);
+    let mut start = 0;
+    let mut hash = 0u64;
+    for (idx, byte) in bytes.iter().enumerate() {
+        hash = (hash << 1).wrapping_add(GEAR[usize::from(*byte)]);
+        let len = idx + 1 - start;
+        if (len >= MIN_CHUNK && hash >> BOUNDARY_SHIFT == 0) || len >= MAX_CHUNK {
+            chunks.push(base + start..base + idx + 1);
+            start = idx + 1;
+            hash = 0;
+        }
+    }
+    if start < bytes.len() {
+        chunks.push(base + start..base + bytes.len());
+    }
+    chunks
+}
+
+fn chunk_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
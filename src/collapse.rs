@@ -0,0 +1,106 @@
+use crate::path::{format_path, parse_path, PathSegment};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+/// Report keys whose runs of consecutive list indices collapse into ranges.
+const COLLAPSIBLE: [&str; 3] = [
+    "values_changed",
+    "iterable_item_added",
+    "iterable_item_removed",
+];
+
+/// Replaces each run of at least `min_run` entries of a report key at
+/// consecutive indices of one list with a single `root['a'][100:250]` entry:
+/// `{"count": ..., "first": ..., "last": ...}`, holding the entries at
+/// both ends, or the range path alone in paths-only reports.
+pub(crate) fn collapse_index_ranges(result: &mut Map<String, Value>, min_run: usize) {
+    for key in COLLAPSIBLE {
+        if let Some(entries) = result.get_mut(key) {
+            collapse_entries(entries, min_run);
+        }
+    }
+}
+
+fn collapse_entries(entries: &mut Value, min_run: usize) {
+    let (items, paths_only): (Vec<(String, Value)>, bool) = match entries.take() {
+        Value::Object(map) => (map.into_iter().collect(), false),
+        Value::Array(paths) => (
+            paths
+                .into_iter()
+                .filter_map(|path| match path {
+                    Value::String(path) => Some((path, Value::Null)),
+                    _ => None,
+                })
+                .collect(),
+            true,
+        ),
+        other => {
+            *entries = other;
+            return;
+        }
+    };
+
+    // Positions in `items` of the entries at each index of each list.
+    let mut lists: BTreeMap<String, BTreeMap<usize, usize>> = BTreeMap::new();
+    for (pos, (path, _)) in items.iter().enumerate() {
+        if let Some([parent @ .., PathSegment::Index(idx)]) = parse_path(path).as_deref() {
+            lists
+                .entry(format_path("root", parent))
+                .or_default()
+                .insert(*idx, pos);
+        }
+    }
+    // A run's entry takes the place of the first of its entries.
+    let mut ranges: HashMap<usize, (String, Value)> = HashMap::new();
+    let mut collapsed = HashSet::new();
+    for (parent, positions) in lists {
+        let indices: Vec<usize> = positions.keys().copied().collect();
+        let positions: Vec<usize> = positions.into_values().collect();
+        for run in consecutive_runs(&indices) {
+            if run.len() < min_run {
+                continue;
+            }
+            let path = format!(
+                "{}[{}:{}]",
+                parent,
+                indices[run.start],
+                indices[run.end - 1] + 1
+            );
+            let summary = json!({
+                "count": run.len(),
+                "first": items[positions[run.start]].1,
+                "last": items[positions[run.end - 1]].1,
+            });
+            let members = &positions[run];
+            collapsed.extend(members.iter().copied());
+            let first = members.iter().copied().min().unwrap_or_default();
+            ranges.insert(first, (path, summary));
+        }
+    }
+    let kept = items.into_iter().enumerate().filter_map(|(pos, item)| {
+        ranges
+            .remove(&pos)
+            .or_else(|| (!collapsed.contains(&pos)).then_some(item))
+    });
+
+    *entries = if paths_only {
+        Value::Array(kept.map(|(path, _)| Value::String(path)).collect())
+    } else {
+        Value::Object(kept.collect())
+    };
+}
+
+/// The runs of consecutive numbers in the sorted `indices`, as ranges of
+/// positions in `indices`.
+pub(crate) fn consecutive_runs(indices: &[usize]) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for pos in 1..=indices.len() {
+        if pos == indices.len() || indices[pos] != indices[pos - 1] + 1 {
+            runs.push(start..pos);
+            start = pos;
+        }
+    }
+    runs
+}
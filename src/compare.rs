@@ -0,0 +1,40 @@
+use crate::change::Change;
+use serde_json::Value;
+
+/// Custom equality for numbers and strings, such as semver-aware version
+/// strings or locale-aware text, registered with
+/// [`DeepDiffOptions::comparator`](crate::DeepDiffOptions::comparator) or
+/// [`DeepDiffOptions::comparator_for`](crate::DeepDiffOptions::comparator_for).
+///
+/// Closures `Fn(&Value, &Value) -> Option<bool>` implement it too.
+pub trait ApproxEq: Send + Sync {
+    /// Whether `t1` and `t2` are equal, or `None` to leave the decision to
+    /// the next comparator and finally to the built-in comparison. Both
+    /// values are of the same [`ValueType`](crate::ValueType), and tagged
+    /// values such as Decimals are passed as they are.
+    fn approx_eq(&self, t1: &Value, t2: &Value) -> Option<bool>;
+}
+
+impl<F> ApproxEq for F
+where
+    F: Fn(&Value, &Value) -> Option<bool> + Send + Sync,
+{
+    fn approx_eq(&self, t1: &Value, t2: &Value) -> Option<bool> {
+        self(t1, t2)
+    }
+}
+
+/// Takes over the comparison of the values at some paths or of some shapes,
+/// such as documents compared by a version field only or geometries compared
+/// by area, registered with
+/// [`DeepDiffOptions::custom_operator`](crate::DeepDiffOptions::custom_operator).
+pub trait CustomOperator: Send + Sync {
+    /// Whether this operator compares `t1` and `t2`, found at `path`
+    /// (rendered from `root`, such as `root['shapes'][0]`).
+    fn match_path(&self, path: &str, t1: &Value, t2: &Value) -> bool;
+
+    /// The changes between `t1` and `t2`, reported in place of the built-in
+    /// comparison's and nothing below them; an empty list when they are
+    /// equal, or `None` to leave them to the built-in comparison after all.
+    fn compare<'a>(&self, path: &str, t1: &'a Value, t2: &'a Value) -> Option<Vec<Change<'a>>>;
+}
@@ -0,0 +1,161 @@
+//! Minimal ISO 8601 parsing for the temporal values produced by the bindings
+//! (`datetime.isoformat()`, `date.isoformat()`, `time.isoformat()`).
+
+use serde_json::Value;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// A point on a timeline in microseconds, plus whether it carried a UTC offset.
+/// Naive values are placed on the timeline as if they were UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Instant {
+    pub(crate) micros: i64,
+    pub(crate) aware: bool,
+}
+
+/// Places a tagged temporal value (`datetime`, `date`, `time`, `timedelta`) on
+/// a microsecond timeline so tolerances can be applied uniformly.
+pub(crate) fn temporal_instant(type_name: &str, value: &Value) -> Option<Instant> {
+    match type_name {
+        "datetime" => parse_datetime(value.as_str()?),
+        "date" => Some(Instant {
+            micros: parse_date(value.as_str()?)? * MICROS_PER_DAY,
+            aware: false,
+        }),
+        "time" => parse_time(value.as_str()?),
+        "timedelta" => Some(Instant {
+            micros: value.as_i64()?,
+            aware: false,
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_datetime(value: &str) -> Option<Instant> {
+    let (date, time) = match value.find(['T', ' ']) {
+        Some(idx) => (&value[..idx], Some(&value[idx + 1..])),
+        None => (value, None),
+    };
+    let days = parse_date(date)?;
+    let time = match time {
+        Some(time) => parse_time(time)?,
+        None => Instant {
+            micros: 0,
+            aware: false,
+        },
+    };
+    Some(Instant {
+        micros: days * MICROS_PER_DAY + time.micros,
+        aware: time.aware,
+    })
+}
+
+/// Places an ISO 8601 timestamp (a date with a time part) on the timeline
+/// with its wall-clock time cut down to a multiple of `unit_micros`. The
+/// cut happens before any UTC offset applies, as truncating a Python
+/// `datetime` does.
+pub(crate) fn truncated_datetime(value: &str, unit_micros: i64) -> Option<Instant> {
+    let idx = value.find(['T', ' '])?;
+    let days = parse_date(&value[..idx])?;
+    let (clock, offset) = split_offset(&value[idx + 1..])?;
+    let local = days * MICROS_PER_DAY + parse_time(clock)?.micros;
+    Some(Instant {
+        micros: local.div_euclid(unit_micros) * unit_micros - offset.unwrap_or(0),
+        aware: offset.is_some(),
+    })
+}
+
+/// Days since 1970-01-01 for a `YYYY-MM-DD` date.
+pub(crate) fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.splitn(3, '-');
+    let year: i64 = parse_digits(parts.next()?, 4)?;
+    let month: i64 = parse_digits(parts.next()?, 2)?;
+    let day: i64 = parse_digits(parts.next()?, 2)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Microseconds since midnight for `HH:MM[:SS[.ffffff]][Z|±HH:MM]`, shifted to UTC
+/// when an offset is present.
+pub(crate) fn parse_time(value: &str) -> Option<Instant> {
+    let (clock, offset) = split_offset(value)?;
+    let mut parts = clock.splitn(3, ':');
+    let hours: i64 = parse_digits(parts.next()?, 2)?;
+    let minutes: i64 = parse_digits(parts.next()?, 2)?;
+    let (seconds, fraction) = match parts.next() {
+        Some(rest) => match rest.split_once('.') {
+            Some((secs, frac)) => (parse_digits(secs, 2)?, parse_fraction(frac)?),
+            None => (parse_digits(rest, 2)?, 0),
+        },
+        None => (0, 0),
+    };
+    if hours > 24 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let micros = ((hours * 60 + minutes) * 60 + seconds) * MICROS_PER_SECOND + fraction;
+    Some(Instant {
+        micros: micros - offset.unwrap_or(0),
+        aware: offset.is_some(),
+    })
+}
+
+fn split_offset(value: &str) -> Option<(&str, Option<i64>)> {
+    if let Some(clock) = value.strip_suffix('Z') {
+        return Some((clock, Some(0)));
+    }
+    let Some(idx) = value.rfind(['+', '-']) else {
+        return Some((value, None));
+    };
+    let (clock, offset) = value.split_at(idx);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let mut parts = offset[1..].split(':');
+    let hours: i64 = parse_digits(parts.next()?, 2)?;
+    let minutes: i64 = parts.next().map_or(Some(0), |m| parse_digits(m, 2))?;
+    let seconds: i64 = match parts.next() {
+        Some(rest) => {
+            let (secs, frac) = rest.split_once('.').unwrap_or((rest, ""));
+            parse_digits(secs, 2)? * MICROS_PER_SECOND
+                + if frac.is_empty() {
+                    0
+                } else {
+                    parse_fraction(frac)?
+                }
+        }
+        None => 0,
+    };
+    let micros = (hours * 3600 + minutes * 60) * MICROS_PER_SECOND + seconds;
+    Some((clock, Some(sign * micros)))
+}
+
+fn parse_digits(value: &str, width: usize) -> Option<i64> {
+    if value.len() != width || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn parse_fraction(value: &str) -> Option<i64> {
+    if value.is_empty() || value.len() > 9 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut padded = value.to_string();
+    padded.truncate(6);
+    while padded.len() < 6 {
+        padded.push('0');
+    }
+    padded.parse().ok()
+}
+
+// Howard Hinnant's days-from-civil algorithm.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,215 @@
+//! Exact decimal arithmetic for the few operations the engine needs on values
+//! that must not be rounded through `f64` (Python `Decimal`, big integers).
+
+use std::cmp::Ordering;
+
+const MAX_ALIGNMENT: i64 = 1024;
+
+/// `digits × 10^exponent`, with `digits` most-significant first and free of
+/// leading and trailing zeros. Zero has no digits and is never negative.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl Decimal {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (negative, rest) = match value.as_bytes().first()? {
+            b'-' => (true, &value[1..]),
+            b'+' => (false, &value[1..]),
+            _ => (false, value),
+        };
+        let (mantissa, exponent) = match rest.find(['e', 'E']) {
+            Some(idx) => (&rest[..idx], rest[idx + 1..].parse::<i64>().ok()?),
+            None => (rest, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return None;
+        }
+        let mut digits = Vec::with_capacity(int_part.len() + frac_part.len());
+        for byte in int_part.bytes().chain(frac_part.bytes()) {
+            if !byte.is_ascii_digit() {
+                return None;
+            }
+            digits.push(byte - b'0');
+        }
+        Some(Self::normalized(
+            negative,
+            digits,
+            exponent.checked_sub(frac_part.len() as i64)?,
+        ))
+    }
+
+    fn normalized(negative: bool, mut digits: Vec<u8>, mut exponent: i64) -> Self {
+        let leading = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..leading);
+        while digits.last() == Some(&0) {
+            digits.pop();
+            exponent += 1;
+        }
+        if digits.is_empty() {
+            return Self {
+                negative: false,
+                digits,
+                exponent: 0,
+            };
+        }
+        Self {
+            negative,
+            digits,
+            exponent,
+        }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub(crate) fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            ..self.clone()
+        }
+    }
+
+    /// Exponent of the most significant digit (`floor(log10(|self|))`).
+    fn magnitude(&self) -> i64 {
+        self.exponent + self.digits.len() as i64 - 1
+    }
+
+    /// Rounds half away from zero to `significant` digits.
+    pub(crate) fn round_significant(&self, significant: u32) -> Self {
+        let keep = significant.max(1) as usize;
+        if self.digits.len() <= keep {
+            return self.clone();
+        }
+        let mut digits = self.digits[..keep].to_vec();
+        let exponent = self.exponent + (self.digits.len() - keep) as i64;
+        if self.digits[keep] >= 5 {
+            let mut idx = keep;
+            loop {
+                if idx == 0 {
+                    digits.insert(0, 1);
+                    break;
+                }
+                idx -= 1;
+                if digits[idx] == 9 {
+                    digits[idx] = 0;
+                } else {
+                    digits[idx] += 1;
+                    break;
+                }
+            }
+        }
+        Self::normalized(self.negative, digits, exponent)
+    }
+
+    pub(crate) fn abs_diff(&self, other: &Self) -> Self {
+        // Aligning wildly different exponents would allocate a digit per power
+        // of ten; the smaller operand cannot matter to an f64 tolerance then.
+        if !self.is_zero()
+            && !other.is_zero()
+            && (self.exponent - other.exponent).abs() > MAX_ALIGNMENT
+        {
+            return if self.magnitude() >= other.magnitude() {
+                self.abs()
+            } else {
+                other.abs()
+            };
+        }
+        let exponent = self.exponent.min(other.exponent);
+        let a = self.scaled_digits(exponent);
+        let b = other.scaled_digits(exponent);
+        let digits = if self.negative != other.negative {
+            add_magnitudes(&a, &b)
+        } else {
+            match compare_magnitudes(&a, &b) {
+                Ordering::Less => sub_magnitudes(&b, &a),
+                _ => sub_magnitudes(&a, &b),
+            }
+        };
+        Self::normalized(false, digits, exponent)
+    }
+
+    pub(crate) fn to_f64(&self) -> f64 {
+        if self.is_zero() {
+            return 0.0;
+        }
+        // Only the leading digits can influence an f64.
+        let take = self.digits.len().min(20);
+        let mantissa: String = self.digits[..take]
+            .iter()
+            .map(|d| char::from(b'0' + d))
+            .collect();
+        let exponent = self.exponent + (self.digits.len() - take) as i64;
+        let sign = if self.negative { "-" } else { "" };
+        format!("{}{}e{}", sign, mantissa, exponent)
+            .parse()
+            .unwrap_or(f64::NAN)
+    }
+
+    /// Canonical text used for hashing and ordering-insensitive comparisons.
+    pub(crate) fn canonical(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let digits: String = self.digits.iter().map(|d| char::from(b'0' + d)).collect();
+        let sign = if self.negative { "-" } else { "" };
+        format!("{}{}e{}", sign, digits, self.exponent)
+    }
+
+    fn scaled_digits(&self, exponent: i64) -> Vec<u8> {
+        let mut digits = self.digits.clone();
+        digits.resize(digits.len() + (self.exponent - exponent) as usize, 0);
+        digits
+    }
+}
+
+fn compare_magnitudes(a: &[u8], b: &[u8]) -> Ordering {
+    let a = &a[a.iter().take_while(|d| **d == 0).count()..];
+    let b = &b[b.iter().take_while(|d| **d == 0).count()..];
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn add_magnitudes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    let mut ia = a.iter().rev();
+    let mut ib = b.iter().rev();
+    loop {
+        let (da, db) = (ia.next(), ib.next());
+        if da.is_none() && db.is_none() {
+            break;
+        }
+        let sum = da.copied().unwrap_or(0) + db.copied().unwrap_or(0) + carry;
+        out.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        out.push(carry);
+    }
+    out.reverse();
+    out
+}
+
+/// `a - b` for magnitudes with `a >= b`.
+fn sub_magnitudes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    let mut ib = b.iter().rev();
+    for da in a.iter().rev() {
+        let mut diff = *da as i8 - ib.next().copied().unwrap_or(0) as i8 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 10;
+            borrow = 1;
+        }
+        out.push(diff as u8);
+    }
+    out.reverse();
+    out
+}
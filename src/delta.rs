@@ -0,0 +1,504 @@
+use crate::change::{ChangeKind, Repetition};
+use crate::path::{format_path, get_value_at_path, parse_path, PathSegment};
+use crate::reverse::counterpart;
+use crate::tagged::{encode_key, object_attrs_mut, sequence_items_mut};
+use crate::DeepDiff;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+
+const CHANGED: [&str; 2] = ["values_changed", "type_changes"];
+const REMOVED: [&str; 3] = [
+    "dictionary_item_removed",
+    "attribute_removed",
+    "iterable_item_removed",
+];
+const ADDED: [&str; 3] = [
+    "dictionary_item_added",
+    "attribute_added",
+    "iterable_item_added",
+];
+
+/// A diff that can be applied to `t1` to produce `t2`, mirroring DeepDiff's
+/// `Delta`.
+///
+/// Changed values are stored as `{"old_value", "new_value"}` per path and
+/// added or removed items as their value per path, grouped by report type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delta {
+    diff: Map<String, Value>,
+    bidirectional: bool,
+    raise_errors: bool,
+}
+
+impl Delta {
+    /// Captures every change reported by `diff`, reading the affected values
+    /// from its two sides.
+    /// Repetition changes become additions or removals of the copies one
+    /// side has beyond the other, and moved items a removal and an addition.
+    /// Under [`DeepDiffOptions::align_lists`](crate::DeepDiffOptions::align_lists),
+    /// new values and added items are found at the indices of `t2`.
+    pub fn new(diff: &DeepDiff) -> Result<Self, DeltaError> {
+        let mut out = Map::new();
+        let Value::Object(result) = &diff.result else {
+            return Ok(Self::from_map(out));
+        };
+        for (report_type, entries) in result {
+            let kind = report_type.as_str();
+            if !(CHANGED.contains(&kind) || ADDED.contains(&kind) || REMOVED.contains(&kind)) {
+                continue;
+            }
+            let paths: Vec<&str> = match entries {
+                Value::Object(map) => map.keys().map(String::as_str).collect(),
+                Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let mut group = Map::new();
+            for path in paths {
+                let segments = parse_path(path).ok_or_else(|| unresolved(path))?;
+                let new_path = new_side_path(diff, path, kind);
+                let new_segments = parse_path(&new_path).ok_or_else(|| unresolved(path))?;
+                let old = get_value_at_path(&diff.t1, &segments).cloned();
+                let new = get_value_at_path(&diff.t2, &new_segments).cloned();
+                let entry = if CHANGED.contains(&report_type.as_str()) {
+                    let (Some(old), Some(new)) = (old, new) else {
+                        return Err(unresolved(path));
+                    };
+                    let mut change = Map::new();
+                    change.insert("old_value".to_string(), old);
+                    change.insert("new_value".to_string(), new);
+                    Value::Object(change)
+                } else if ADDED.contains(&report_type.as_str()) {
+                    new.ok_or_else(|| unresolved(path))?
+                } else if REMOVED.contains(&report_type.as_str()) {
+                    old.ok_or_else(|| unresolved(path))?
+                } else {
+                    continue;
+                };
+                let at = if ADDED.contains(&kind) {
+                    new_path
+                } else {
+                    path.to_string()
+                };
+                group.insert(at, entry);
+            }
+            if !group.is_empty() {
+                out.insert(report_type.clone(), Value::Object(group));
+            }
+        }
+        if let Some(Value::Object(repeated)) = result.get("repetition_change") {
+            for (path, entry) in repeated {
+                add_extra_copies(&mut out, diff, path, entry)?;
+            }
+        }
+        if let Some(Value::Object(moved)) = result.get("iterable_item_moved") {
+            for (path, entry) in moved {
+                add_move(&mut out, diff, path, entry)?;
+            }
+        }
+        Ok(Self::from_map(out))
+    }
+
+    /// Reads a delta serialized by [`Delta::to_value`].
+    pub fn from_value(value: Value) -> Result<Self, DeltaError> {
+        let Value::Object(map) = value else {
+            return Err(DeltaError::Invalid("expected a JSON object".to_string()));
+        };
+        for (report_type, entries) in &map {
+            let known = CHANGED
+                .iter()
+                .chain(&REMOVED)
+                .chain(&ADDED)
+                .any(|name| name == report_type);
+            if !known || !entries.is_object() {
+                return Err(DeltaError::Invalid(format!(
+                    "unexpected entry '{}'",
+                    report_type
+                )));
+            }
+        }
+        Ok(Self::from_map(map))
+    }
+
+    fn from_map(diff: Map<String, Value>) -> Self {
+        Self {
+            diff,
+            bidirectional: false,
+            raise_errors: false,
+        }
+    }
+
+    /// Keeps the old values so the delta can be reverted and checks them
+    /// before each change is applied.
+    pub fn bidirectional(mut self, value: bool) -> Self {
+        self.bidirectional = value;
+        self
+    }
+
+    /// Fails on the first change that cannot be applied instead of skipping it.
+    pub fn raise_errors(mut self, value: bool) -> Self {
+        self.raise_errors = value;
+        self
+    }
+
+    /// The serialized delta; old values of changes are only kept when
+    /// bidirectional.
+    pub fn to_value(&self) -> Value {
+        let mut out = self.diff.clone();
+        if !self.bidirectional {
+            for report_type in CHANGED {
+                if let Some(Value::Object(group)) = out.get_mut(report_type) {
+                    for change in group.values_mut() {
+                        if let Value::Object(change) = change {
+                            change.remove("old_value");
+                        }
+                    }
+                }
+            }
+        }
+        Value::Object(out)
+    }
+
+    /// Applies the delta to `value`, turning `t1` into `t2`.
+    pub fn apply(&self, value: &Value) -> Result<Value, DeltaError> {
+        apply_changes(&self.diff, value, self.bidirectional, self.raise_errors)
+    }
+
+    /// Applies the delta backwards, turning `t2` into `t1`. Requires a
+    /// bidirectional delta.
+    pub fn revert(&self, value: &Value) -> Result<Value, DeltaError> {
+        if !self.bidirectional {
+            return Err(DeltaError::NotBidirectional);
+        }
+        let mut inverse = Map::new();
+        for (report_type, entries) in &self.diff {
+            let (target, entries) = match report_type.as_str() {
+                name if CHANGED.contains(&name) => (name.to_string(), swap_changes(entries)?),
+                name => match REMOVED.iter().position(|removed| *removed == name) {
+                    Some(idx) => (ADDED[idx].to_string(), entries.clone()),
+                    None => {
+                        let idx = ADDED.iter().position(|added| *added == name);
+                        let idx = idx.expect("delta only holds known report types");
+                        (REMOVED[idx].to_string(), entries.clone())
+                    }
+                },
+            };
+            inverse.insert(target, entries);
+        }
+        apply_changes(&inverse, value, true, self.raise_errors)
+    }
+}
+
+/// `path`, reported with the indices of `t1`, with the indices of the same
+/// items in `t2` where aligned lists pair items across indices.
+fn new_side_path(diff: &DeepDiff, path: &str, report_type: &str) -> String {
+    match ChangeKind::from_key(report_type) {
+        Some(kind) if diff.options.align_lists => {
+            counterpart(path, kind, &diff.t1, &diff.t2, &diff.options)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Files a moved item as removed from its old position and added at its
+/// new one.
+fn add_move(
+    out: &mut Map<String, Value>,
+    diff: &DeepDiff,
+    path: &str,
+    entry: &Value,
+) -> Result<(), DeltaError> {
+    let new_path = entry["new_path"].as_str().ok_or_else(|| unresolved(path))?;
+    let new_path = new_side_path(diff, new_path, "iterable_item_moved");
+    for (report_type, side, at) in [
+        ("iterable_item_removed", &diff.t1, path),
+        ("iterable_item_added", &diff.t2, new_path.as_str()),
+    ] {
+        let segments = parse_path(at).ok_or_else(|| unresolved(at))?;
+        let value = get_value_at_path(side, &segments)
+            .cloned()
+            .ok_or_else(|| unresolved(at))?;
+        if let Value::Object(group) = out
+            .entry(report_type)
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            group.insert(at.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+/// Files the copies a repetition change reports beyond those both sides
+/// share as added or removed items.
+fn add_extra_copies(
+    out: &mut Map<String, Value>,
+    diff: &DeepDiff,
+    path: &str,
+    entry: &Value,
+) -> Result<(), DeltaError> {
+    let segments = parse_path(path).ok_or_else(|| unresolved(path))?;
+    let (Some((_, parent)), Some(repetition)) =
+        (segments.split_last(), Repetition::from_entry(entry))
+    else {
+        return Err(unresolved(path));
+    };
+    let (report_type, side, extra) = if repetition.new_repeat > repetition.old_repeat {
+        (
+            "iterable_item_added",
+            &diff.t2,
+            &repetition.new_indexes[repetition.old_repeat..],
+        )
+    } else {
+        (
+            "iterable_item_removed",
+            &diff.t1,
+            &repetition.old_indexes[repetition.new_repeat..],
+        )
+    };
+    let group = out
+        .entry(report_type)
+        .or_insert_with(|| Value::Object(Map::new()));
+    for &idx in extra {
+        let mut copy = parent.to_vec();
+        copy.push(PathSegment::Index(idx));
+        let path = format_path("root", &copy);
+        let value = get_value_at_path(side, &copy)
+            .cloned()
+            .ok_or_else(|| unresolved(&path))?;
+        if let Value::Object(group) = group {
+            group.insert(path, value);
+        }
+    }
+    Ok(())
+}
+
+fn swap_changes(entries: &Value) -> Result<Value, DeltaError> {
+    let mut out = Map::new();
+    for (path, change) in entries.as_object().into_iter().flatten() {
+        let old = change
+            .get("old_value")
+            .ok_or_else(|| DeltaError::Invalid(format!("missing old_value for {}", path)))?;
+        let new = change.get("new_value").cloned().unwrap_or(Value::Null);
+        let mut swapped = Map::new();
+        swapped.insert("old_value".to_string(), new);
+        swapped.insert("new_value".to_string(), old.clone());
+        out.insert(path.clone(), Value::Object(swapped));
+    }
+    Ok(Value::Object(out))
+}
+
+/// Applies changed values first, then removals from the deepest and highest
+/// index down, then additions in ascending order so list indices line up.
+fn apply_changes(
+    diff: &Map<String, Value>,
+    value: &Value,
+    verify: bool,
+    raise_errors: bool,
+) -> Result<Value, DeltaError> {
+    let mut out = value.clone();
+    let mut outcomes = Vec::new();
+    for report_type in CHANGED {
+        for (path, change) in entries(diff, report_type) {
+            let new = change.get("new_value").cloned().unwrap_or(Value::Null);
+            let expected = change.get("old_value").filter(|_| verify);
+            outcomes.push(set_at(&mut out, path, new, expected));
+        }
+    }
+    let mut removals: Vec<(Vec<PathSegment>, &str)> = Vec::new();
+    for report_type in REMOVED {
+        for (path, _) in entries(diff, report_type) {
+            match parse_path(path) {
+                Some(segments) => removals.push((segments, path)),
+                None => outcomes.push(Err(unresolved(path))),
+            }
+        }
+    }
+    removals.sort_by(|a, b| b.0.cmp(&a.0));
+    for (segments, path) in removals {
+        outcomes.push(remove_at(&mut out, &segments, path));
+    }
+    let mut additions: Vec<(Vec<PathSegment>, &str, &Value)> = Vec::new();
+    for report_type in ADDED {
+        for (path, item) in entries(diff, report_type) {
+            match parse_path(path) {
+                Some(segments) => additions.push((segments, path, item)),
+                None => outcomes.push(Err(unresolved(path))),
+            }
+        }
+    }
+    additions.sort_by(|a, b| a.0.cmp(&b.0));
+    for (segments, path, item) in additions {
+        outcomes.push(insert_at(&mut out, &segments, path, item.clone()));
+    }
+    if raise_errors {
+        outcomes.into_iter().collect::<Result<Vec<()>, _>>()?;
+    }
+    Ok(out)
+}
+
+fn entries<'a>(
+    diff: &'a Map<String, Value>,
+    report_type: &str,
+) -> impl Iterator<Item = (&'a String, &'a Value)> {
+    diff.get(report_type)
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+}
+
+fn set_at(
+    root: &mut Value,
+    path: &str,
+    new: Value,
+    expected: Option<&Value>,
+) -> Result<(), DeltaError> {
+    let segments = parse_path(path).ok_or_else(|| unresolved(path))?;
+    let (last, parents) = match segments.split_last() {
+        Some(split) => split,
+        None => {
+            check_expected(root, expected, path)?;
+            *root = new;
+            return Ok(());
+        }
+    };
+    let parent = value_at_mut(root, parents).ok_or_else(|| unresolved(path))?;
+    let slot = child_mut(parent, last).ok_or_else(|| unresolved(path))?;
+    check_expected(slot, expected, path)?;
+    *slot = new;
+    Ok(())
+}
+
+fn check_expected(current: &Value, expected: Option<&Value>, path: &str) -> Result<(), DeltaError> {
+    match expected {
+        Some(expected) if expected != current => Err(DeltaError::UnexpectedValue {
+            path: path.to_string(),
+            expected: Box::new(expected.clone()),
+            found: Box::new(current.clone()),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn remove_at(root: &mut Value, segments: &[PathSegment], path: &str) -> Result<(), DeltaError> {
+    let (last, parents) = segments.split_last().ok_or_else(|| unresolved(path))?;
+    let parent = value_at_mut(root, parents).ok_or_else(|| unresolved(path))?;
+    let removed = match last {
+        PathSegment::Key(key) => parent.as_object_mut().and_then(|map| map.remove(key)),
+        PathSegment::Attr(name) => object_attrs_mut(parent).and_then(|attrs| attrs.remove(name)),
+        PathSegment::Index(idx) => match sequence_items_mut(parent) {
+            Some(items) => (*idx < items.len()).then(|| items.remove(*idx)),
+            None => parent
+                .as_object_mut()
+                .and_then(|map| map.remove(&encode_key(&Value::from(*idx)))),
+        },
+    };
+    removed.map(|_| ()).ok_or_else(|| unresolved(path))
+}
+
+fn insert_at(
+    root: &mut Value,
+    segments: &[PathSegment],
+    path: &str,
+    item: Value,
+) -> Result<(), DeltaError> {
+    let (last, parents) = segments.split_last().ok_or_else(|| unresolved(path))?;
+    let parent = value_at_mut(root, parents).ok_or_else(|| unresolved(path))?;
+    match last {
+        PathSegment::Key(key) => {
+            let map = parent.as_object_mut().ok_or_else(|| unresolved(path))?;
+            map.insert(key.clone(), item);
+        }
+        PathSegment::Attr(name) => {
+            let attrs = object_attrs_mut(parent).ok_or_else(|| unresolved(path))?;
+            attrs.insert(name.clone(), item);
+        }
+        PathSegment::Index(idx) => match sequence_items_mut(parent) {
+            Some(items) => items.insert((*idx).min(items.len()), item),
+            None => {
+                let map = parent.as_object_mut().ok_or_else(|| unresolved(path))?;
+                map.insert(encode_key(&Value::from(*idx)), item);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Replaces the value at `path`, or adds it when its parent exists but the
+/// key, attribute, or index does not.
+pub(crate) fn put_at(root: &mut Value, path: &str, item: Value) -> Result<(), DeltaError> {
+    let segments = parse_path(path).ok_or_else(|| unresolved(path))?;
+    let Some((last, parents)) = segments.split_last() else {
+        *root = item;
+        return Ok(());
+    };
+    let parent = value_at_mut(root, parents).ok_or_else(|| unresolved(path))?;
+    match child_mut(parent, last) {
+        Some(slot) => *slot = item,
+        None => insert_at(root, &segments, path, item)?,
+    }
+    Ok(())
+}
+
+fn value_at_mut<'a>(root: &'a mut Value, segments: &[PathSegment]) -> Option<&'a mut Value> {
+    let mut current = root;
+    for segment in segments {
+        current = child_mut(current, segment)?;
+    }
+    Some(current)
+}
+
+fn child_mut<'a>(value: &'a mut Value, segment: &PathSegment) -> Option<&'a mut Value> {
+    match segment {
+        PathSegment::Key(key) => value.as_object_mut()?.get_mut(key),
+        PathSegment::Attr(name) => object_attrs_mut(value)?.get_mut(name),
+        PathSegment::Index(idx) => {
+            if sequence_items_mut(value).is_some() {
+                return sequence_items_mut(value)?.get_mut(*idx);
+            }
+            let key = encode_key(&Value::from(*idx));
+            value.as_object_mut()?.get_mut(&key)
+        }
+    }
+}
+
+fn unresolved(path: &str) -> DeltaError {
+    DeltaError::UnresolvedPath(path.to_string())
+}
+
+/// Error raised while building or applying a [`Delta`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeltaError {
+    /// The path does not exist in the value the delta is applied to.
+    UnresolvedPath(String),
+    /// A bidirectional delta found a different value than the one it replaces.
+    UnexpectedValue {
+        path: String,
+        expected: Box<Value>,
+        found: Box<Value>,
+    },
+    /// Reverting requires a delta built with `bidirectional(true)`.
+    NotBidirectional,
+    /// The serialized delta is malformed.
+    Invalid(String),
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnresolvedPath(path) => write!(f, "Unable to resolve path {}", path),
+            Self::UnexpectedValue {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected the old value for {} to be {} but it is {}",
+                path, expected, found
+            ),
+            Self::NotBidirectional => f.write_str("Reverting a delta requires bidirectional=True"),
+            Self::Invalid(reason) => write!(f, "Invalid delta: {}", reason),
+        }
+    }
+}
+
+impl Error for DeltaError {}
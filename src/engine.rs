@@ -1,204 +1,1937 @@
-use crate::options::{DeepDiffOptions, ValueType};
+use crate::alias::{self, PathAlias};
+use crate::align::{align, Edit};
+use crate::change::{Change, ChangeKind, Repetition};
+use crate::chunks;
+use crate::datetime::{temporal_instant, truncated_datetime};
+use crate::decimal::Decimal;
+use crate::group;
+use crate::hash::{canonical_hash, BloomFilter, SubtreeHashes};
+use crate::numeric;
+use crate::options::{edit_distance, Annotator, DeepDiffOptions, Progress, ValueType, Visitor};
+use crate::path::{get_value_at_path, NodePath, PathSegment, Step};
+use crate::redact::Redaction;
+use crate::rules;
+use crate::sample::Sampling;
+use crate::schema;
+use crate::tagged::{as_enum_member, as_object, as_tagged, is_tagged, sequence_items};
 use indexmap::IndexMap;
-use serde_json::Value;
-use std::collections::{BTreeMap, HashMap};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::time::Instant;
 
+/// Number of visited nodes between clock reads when a progress logger is set.
+const PROGRESS_CHECK_INTERVAL: usize = 1024;
+
+/// Receives every change as the engine finds it, instead of the result maps.
+pub(crate) type ChangeStream<'s> = &'s mut dyn FnMut(Change<'_>);
+
+/// Ordered lists with at least this many items compared pairwise are split
+/// into chunks diffed on separate threads.
+const PARALLEL_MIN_ITEMS: usize = 1 << 15;
+
+/// Unordered lists with at least this many items are checked against a bloom
+/// filter of the other side before exact matching.
+const BLOOM_MIN_ITEMS: usize = 4096;
+
+/// Ordered lists needing more insertions and deletions than this to align
+/// are compared index by index instead, keeping alignment cheap.
+const ALIGN_MAX_EDITS: usize = 1024;
+
+/// Report entries collected so far, keyed by path, in the order they were
+/// found: document order.
 #[derive(Default)]
-pub(crate) struct DiffAccumulator {
-    values_changed: BTreeMap<String, Value>,
+struct Entries {
+    values_changed: IndexMap<String, Value>,
     dictionary_item_added: Vec<String>,
     dictionary_item_removed: Vec<String>,
-    iterable_item_added: BTreeMap<String, Value>,
-    iterable_item_removed: BTreeMap<String, Value>,
-    type_changes: BTreeMap<String, Value>,
+    attribute_added: Vec<String>,
+    attribute_removed: Vec<String>,
+    iterable_item_added: IndexMap<String, Value>,
+    iterable_item_removed: IndexMap<String, Value>,
+    iterable_item_moved: IndexMap<String, Value>,
+    repetition_change: IndexMap<String, Value>,
+    type_changes: IndexMap<String, Value>,
+    schema_violations: IndexMap<String, Value>,
+    annotations: IndexMap<String, Value>,
 }
 
-impl DiffAccumulator {
-    pub(crate) fn into_value(self, verbose_level: u8) -> Value {
-        let mut result = IndexMap::new();
+impl Entries {
+    fn extend(&mut self, other: Entries) {
+        self.values_changed.extend(other.values_changed);
+        self.dictionary_item_added
+            .extend(other.dictionary_item_added);
+        self.dictionary_item_removed
+            .extend(other.dictionary_item_removed);
+        self.attribute_added.extend(other.attribute_added);
+        self.attribute_removed.extend(other.attribute_removed);
+        self.iterable_item_added.extend(other.iterable_item_added);
+        self.iterable_item_removed
+            .extend(other.iterable_item_removed);
+        self.iterable_item_moved.extend(other.iterable_item_moved);
+        self.repetition_change.extend(other.repetition_change);
+        self.type_changes.extend(other.type_changes);
+        self.schema_violations.extend(other.schema_violations);
+        self.annotations.extend(other.annotations);
+    }
 
-        if !self.values_changed.is_empty() {
-            if verbose_level == 0 {
-                let mut paths: Vec<String> = self.values_changed.keys().cloned().collect();
-                paths.sort();
-                result.insert(
-                    "values_changed".to_string(),
-                    Value::Array(paths.into_iter().map(Value::String).collect()),
-                );
-            } else {
-                result.insert(
-                    "values_changed".to_string(),
-                    map_to_value(self.values_changed),
-                );
-            }
+    /// Orders the entries of each report key by path text, for `sort_paths`.
+    fn sort(&mut self) {
+        for map in [
+            &mut self.values_changed,
+            &mut self.iterable_item_added,
+            &mut self.iterable_item_removed,
+            &mut self.iterable_item_moved,
+            &mut self.repetition_change,
+            &mut self.type_changes,
+            &mut self.schema_violations,
+            &mut self.annotations,
+        ] {
+            map.sort_keys();
         }
-        if !self.dictionary_item_added.is_empty() {
-            let mut paths = self.dictionary_item_added;
+        for paths in [
+            &mut self.dictionary_item_added,
+            &mut self.dictionary_item_removed,
+            &mut self.attribute_added,
+            &mut self.attribute_removed,
+        ] {
             paths.sort();
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct DiffAccumulator<'s> {
+    entries: Entries,
+    visited: usize,
+    reported: usize,
+    progress: Option<ProgressClock>,
+    hashes: SubtreeHashes,
+    stream: Option<ChangeStream<'s>>,
+    budget: Option<usize>,
+    stored_bytes: usize,
+    /// Values are not kept, either at `verbose_level` 0 or once the report
+    /// outgrew `budget`.
+    paths_only: bool,
+    /// Set once the report outgrew `budget`.
+    truncated: bool,
+    /// `max_diffs`: the number of changes reported before the diff stops.
+    limit: Option<usize>,
+    /// Set once a change beyond `limit` was found and left out.
+    limit_reached: bool,
+    /// Set on the accumulators of chunk threads, which do not split further.
+    in_worker: bool,
+    visitor: Option<Visitor>,
+    annotator: Option<Annotator>,
+    /// Set once the visitor asked to stop; nothing is reported after it.
+    stopped: bool,
+    /// Only whether anything differs matters: the first change stops the
+    /// diff and nothing is kept.
+    probe: bool,
+    /// Masks values before anything else sees them.
+    redaction: Option<Redaction>,
+    /// The root label and path aliases of the paths shown to the visitor and
+    /// the stream, when they change any. Stored entries keep `root` until the
+    /// result is handed out.
+    labels: Option<(String, Vec<PathAlias>)>,
+    sampling: Option<Sampling>,
+    /// Containers only a sample of whose items were diffed.
+    sampled: usize,
+    /// Entries are handed out ordered by path text instead of as found.
+    sort_paths: bool,
+}
+
+fn redaction_of(options: &DeepDiffOptions) -> Option<Redaction> {
+    Some(options.redaction.clone()).filter(Redaction::is_active)
+}
+
+fn labels_of(options: &DeepDiffOptions) -> Option<(String, Vec<PathAlias>)> {
+    (options.root_label != "root" || !options.path_aliases.is_empty())
+        .then(|| (options.root_label.clone(), options.path_aliases.clone()))
+}
+
+struct ProgressClock {
+    started: Instant,
+    last_report: Instant,
+}
+
+impl<'s> DiffAccumulator<'s> {
+    pub(crate) fn new(options: &DeepDiffOptions) -> Self {
+        Self {
+            budget: options.max_report_bytes,
+            limit: options.max_diffs,
+            paths_only: options.verbose_level == 0,
+            visitor: options.visitor.clone(),
+            annotator: options.annotator.clone(),
+            redaction: redaction_of(options),
+            labels: labels_of(options),
+            sampling: options.sampling,
+            hashes: SubtreeHashes::new(options.hasher),
+            sort_paths: options.sort_paths,
+            ..Self::default()
+        }
+    }
+
+    /// Like [`DiffAccumulator::new`], reusing the hash cache of an earlier
+    /// run; see [`DiffAccumulator::take_hashes`].
+    pub(crate) fn with_hashes(options: &DeepDiffOptions, mut hashes: SubtreeHashes) -> Self {
+        hashes.clear(options.hasher);
+        Self {
+            hashes,
+            ..Self::new(options)
+        }
+    }
+
+    pub(crate) fn take_hashes(&mut self) -> SubtreeHashes {
+        std::mem::take(&mut self.hashes)
+    }
+
+    /// An accumulator that stops at the first change, for equality checks.
+    pub(crate) fn probe(options: &DeepDiffOptions) -> Self {
+        Self {
+            probe: true,
+            paths_only: true,
+            ..Self::new(options)
+        }
+    }
+
+    /// Whether a change was found.
+    pub(crate) fn found_change(&self) -> bool {
+        self.reported > 0
+    }
+
+    /// An accumulator that hands changes to `stream` and keeps none of them.
+    pub(crate) fn streaming(options: &DeepDiffOptions, stream: ChangeStream<'s>) -> Self {
+        Self {
+            stream: Some(stream),
+            visitor: options.visitor.clone(),
+            redaction: redaction_of(options),
+            labels: labels_of(options),
+            ..Self::default()
+        }
+    }
+
+    fn report(&mut self, mut change: Change<'_>) {
+        if self.stopped {
+            return;
+        }
+        if self.probe {
+            self.reported += 1;
+            self.stopped = true;
+            return;
+        }
+        if self.limit.is_some_and(|limit| self.reported >= limit) {
+            self.limit_reached = true;
+            self.stopped = true;
+            return;
+        }
+        if let Some(redaction) = &self.redaction {
+            for value in [&mut change.old_value, &mut change.new_value]
+                .into_iter()
+                .flatten()
+            {
+                redaction.redact(value.to_mut(), &change.path);
+            }
+        }
+        let shown = self.visitor.is_some() || self.stream.is_some() || self.annotator.is_some();
+        let labeled = match &self.labels {
+            Some((label, aliases)) if shown => {
+                let path = alias::display_path(&change.path, label, aliases);
+                let new_path = change
+                    .new_path
+                    .as_ref()
+                    .map(|new_path| alias::display_path(new_path, label, aliases));
+                Some((
+                    std::mem::replace(&mut change.path, path),
+                    std::mem::replace(&mut change.new_path, new_path),
+                ))
+            }
+            _ => None,
+        };
+        if let Some(visitor) = &self.visitor {
+            if !visitor.visit(&change) {
+                self.stopped = true;
+            }
+        }
+        self.reported += 1;
+        if let Some(stream) = self.stream.as_mut() {
+            stream(change);
+            return;
+        }
+        let annotation = self
+            .annotator
+            .as_ref()
+            .and_then(|annotator| (annotator.0)(&change));
+        if let Some((path, new_path)) = labeled {
+            change.path = path;
+            change.new_path = new_path;
+        }
+        if let Some(annotation) = annotation {
+            self.entries
+                .annotations
+                .insert(change.path.clone(), Value::Object(annotation));
+        }
+        let Change {
+            kind,
+            path,
+            old_type,
+            new_type,
+            mut old_value,
+            mut new_value,
+            summary,
+            expected_type,
+            magnitude,
+            new_path,
+            repetition,
+        } = change;
+        if let Some(budget) = self.budget.filter(|_| !self.paths_only) {
+            self.stored_bytes += path.len()
+                + old_value.as_deref().map_or(0, approx_bytes)
+                + new_value.as_deref().map_or(0, approx_bytes)
+                + summary.as_ref().map_or(0, approx_bytes);
+            if self.stored_bytes > budget {
+                self.truncated = true;
+                self.drop_values();
+            }
+        }
+        if self.paths_only {
+            old_value = None;
+            new_value = None;
+        }
+        let old_value = old_value.map(|value| value.into_owned());
+        let new_value = new_value.map(|value| value.into_owned());
+        match kind {
+            ChangeKind::ValuesChanged if self.paths_only => {
+                self.entries.values_changed.insert(path, Value::Null);
+            }
+            ChangeKind::ValuesChanged if summary.is_some() => {
+                self.entries
+                    .values_changed
+                    .insert(path, summary.unwrap_or_default());
+            }
+            ChangeKind::ValuesChanged => {
+                let mut entries = vec![
+                    ("old_value", old_value.unwrap_or_default()),
+                    ("new_value", new_value.unwrap_or_default()),
+                ];
+                entries.extend(magnitude.map(|score| ("magnitude", Value::from(score))));
+                self.entries.values_changed.insert(path, json_obj(entries));
+            }
+            ChangeKind::TypeChanged => {
+                let mut entries = vec![
+                    ("old_type", Value::from(old_type.unwrap_or_default())),
+                    ("new_type", Value::from(new_type.unwrap_or_default())),
+                ];
+                entries.extend(old_value.map(|value| ("old_value", value)));
+                entries.extend(new_value.map(|value| ("new_value", value)));
+                self.entries.type_changes.insert(path, json_obj(entries));
+            }
+            ChangeKind::DictionaryItemAdded => self.entries.dictionary_item_added.push(path),
+            ChangeKind::DictionaryItemRemoved => self.entries.dictionary_item_removed.push(path),
+            ChangeKind::AttributeAdded => self.entries.attribute_added.push(path),
+            ChangeKind::AttributeRemoved => self.entries.attribute_removed.push(path),
+            ChangeKind::IterableItemAdded => {
+                self.entries
+                    .iterable_item_added
+                    .insert(path, new_value.unwrap_or_default());
+            }
+            ChangeKind::IterableItemRemoved => {
+                self.entries
+                    .iterable_item_removed
+                    .insert(path, old_value.unwrap_or_default());
+            }
+            ChangeKind::IterableItemMoved => {
+                let entry = match (new_path, old_value) {
+                    (Some(new_path), Some(value)) => json_obj(vec![
+                        ("new_path", Value::String(new_path)),
+                        ("value", value),
+                    ]),
+                    _ => Value::Null,
+                };
+                self.entries.iterable_item_moved.insert(path, entry);
+            }
+            ChangeKind::RepetitionChange => {
+                let entry = match (repetition, old_value) {
+                    (Some(repetition), Some(value)) => repetition.to_entry(value),
+                    _ => Value::Null,
+                };
+                self.entries.repetition_change.insert(path, entry);
+            }
+            ChangeKind::SchemaViolation => {
+                let mut entries = vec![("expected_type", Value::from(expected_type))];
+                entries.extend(old_value.map(|value| ("old_value", value)));
+                entries.extend(new_value.map(|value| ("new_value", value)));
+                self.entries
+                    .schema_violations
+                    .insert(path, json_obj(entries));
+            }
+        }
+    }
+
+    /// Whether work can move to other threads: changes must reach a
+    /// stream or visitor in order, a byte budget or change limit depends on
+    /// everything reported before, and callbacks may need the Python GIL held
+    /// by the calling thread.
+    fn can_split(&self, options: &DeepDiffOptions) -> bool {
+        !self.in_worker
+            && !self.probe
+            && options.threads() > 1
+            && self.stream.is_none()
+            && self.visitor.is_none()
+            && self.budget.is_none()
+            && self.limit.is_none()
+            && options.exclude_obj_callback.is_none()
+            && options.exclude_obj_callback_strict.is_none()
+            && options.annotator.is_none()
+            && options.progress_logger.is_none()
+    }
+
+    /// Switches to paths-only reporting, releasing the values kept so far.
+    fn drop_values(&mut self) {
+        self.paths_only = true;
+        for value in self
+            .entries
+            .values_changed
+            .values_mut()
+            .chain(self.entries.iterable_item_added.values_mut())
+            .chain(self.entries.iterable_item_removed.values_mut())
+            .chain(self.entries.iterable_item_moved.values_mut())
+            .chain(self.entries.repetition_change.values_mut())
+        {
+            *value = Value::Null;
+        }
+        for entry in self.entries.type_changes.values_mut() {
+            if let Value::Object(entry) = entry {
+                entry.remove("old_value");
+                entry.remove("new_value");
+            }
+        }
+    }
+
+    fn tick(&mut self, options: &DeepDiffOptions) {
+        self.visited += 1;
+        let Some(logger) = &options.progress_logger else {
+            return;
+        };
+        if self.progress.is_none() {
+            let now = Instant::now();
+            self.progress = Some(ProgressClock {
+                started: now,
+                last_report: now,
+            });
+        }
+        if !self.visited.is_multiple_of(PROGRESS_CHECK_INTERVAL) {
+            return;
+        }
+        let diffs_found = self.reported;
+        let Some(clock) = self.progress.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        if now.duration_since(clock.last_report) < options.log_frequency {
+            return;
+        }
+        clock.last_report = now;
+        let progress = Progress {
+            elapsed: now.duration_since(clock.started),
+            items_processed: self.visited,
+            diffs_found,
+        };
+        (logger.0)(&progress);
+    }
+
+    pub(crate) fn into_value(mut self) -> Value {
+        if self.sort_paths {
+            self.entries.sort();
+        }
+        let mut result = IndexMap::new();
+        let items = if self.paths_only {
+            keys_to_value
+        } else {
+            map_to_value
+        };
+
+        if !self.entries.values_changed.is_empty() {
+            result.insert(
+                "values_changed".to_string(),
+                items(self.entries.values_changed),
+            );
+        }
+        if !self.entries.dictionary_item_added.is_empty() {
+            let paths = self.entries.dictionary_item_added;
             result.insert(
                 "dictionary_item_added".to_string(),
                 Value::Array(paths.into_iter().map(Value::String).collect()),
             );
         }
-        if !self.dictionary_item_removed.is_empty() {
-            let mut paths = self.dictionary_item_removed;
-            paths.sort();
-            result.insert(
-                "dictionary_item_removed".to_string(),
-                Value::Array(paths.into_iter().map(Value::String).collect()),
+        if !self.entries.dictionary_item_removed.is_empty() {
+            let paths = self.entries.dictionary_item_removed;
+            result.insert(
+                "dictionary_item_removed".to_string(),
+                Value::Array(paths.into_iter().map(Value::String).collect()),
+            );
+        }
+        if !self.entries.attribute_added.is_empty() {
+            let paths = self.entries.attribute_added;
+            result.insert(
+                "attribute_added".to_string(),
+                Value::Array(paths.into_iter().map(Value::String).collect()),
+            );
+        }
+        if !self.entries.attribute_removed.is_empty() {
+            let paths = self.entries.attribute_removed;
+            result.insert(
+                "attribute_removed".to_string(),
+                Value::Array(paths.into_iter().map(Value::String).collect()),
+            );
+        }
+        if !self.entries.iterable_item_added.is_empty() {
+            result.insert(
+                "iterable_item_added".to_string(),
+                items(self.entries.iterable_item_added),
+            );
+        }
+        if !self.entries.iterable_item_removed.is_empty() {
+            result.insert(
+                "iterable_item_removed".to_string(),
+                items(self.entries.iterable_item_removed),
+            );
+        }
+        if !self.entries.iterable_item_moved.is_empty() {
+            result.insert(
+                "iterable_item_moved".to_string(),
+                items(self.entries.iterable_item_moved),
+            );
+        }
+        if !self.entries.repetition_change.is_empty() {
+            result.insert(
+                "repetition_change".to_string(),
+                items(self.entries.repetition_change),
+            );
+        }
+        if !self.entries.type_changes.is_empty() {
+            result.insert(
+                "type_changes".to_string(),
+                map_to_value(self.entries.type_changes),
+            );
+        }
+        if !self.entries.schema_violations.is_empty() {
+            result.insert(
+                "schema_violations".to_string(),
+                map_to_value(self.entries.schema_violations),
+            );
+        }
+        if !self.entries.annotations.is_empty() {
+            result.insert(
+                "annotations".to_string(),
+                map_to_value(self.entries.annotations),
+            );
+        }
+        if self.truncated {
+            result.insert("report_truncated".to_string(), Value::Bool(true));
+        }
+        if self.limit_reached {
+            result.insert("max_diffs_reached".to_string(), Value::Bool(true));
+        }
+        if let Some(sampling) = self.sampling.filter(|_| self.sampled > 0) {
+            result.insert(
+                "sampling".to_string(),
+                serde_json::json!({
+                    "rate": sampling.rate,
+                    "threshold": sampling.threshold,
+                    "seed": sampling.seed,
+                    "sampled_containers": self.sampled,
+                }),
+            );
+        }
+
+        Value::Object(result.into_iter().collect())
+    }
+}
+
+/// Orders the entries of each report key of `result` by path text, for
+/// `sort_paths` once paths were rewritten.
+pub(crate) fn sort_report(result: &mut Map<String, Value>) {
+    for entries in result.values_mut() {
+        match entries {
+            Value::Object(map) => map.sort_keys(),
+            Value::Array(paths) => paths.sort_by(|a, b| a.as_str().cmp(&b.as_str())),
+            _ => {}
+        }
+    }
+}
+
+fn map_to_value(map: IndexMap<String, Value>) -> Value {
+    Value::Object(map.into_iter().collect())
+}
+
+/// The paths of `map`, for reports that do not carry values.
+fn keys_to_value(map: IndexMap<String, Value>) -> Value {
+    Value::Array(map.into_keys().map(Value::String).collect())
+}
+
+/// Rough size of `value` once serialized, for the report budget.
+fn approx_bytes(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) => 5,
+        Value::Number(_) => 8,
+        Value::String(s) => s.len() + 2,
+        Value::Array(items) => items.iter().map(approx_bytes).sum::<usize>() + items.len() + 2,
+        Value::Object(map) => {
+            map.iter()
+                .map(|(key, item)| key.len() + 4 + approx_bytes(item))
+                .sum::<usize>()
+                + 2
+        }
+    }
+}
+
+/// Entry point for diffing two whole documents. Identical documents return
+/// before the walk, without hashing their subtrees or checking any option
+/// per node, unless sampling, which must not read them whole.
+pub(crate) fn diff_documents<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let grouped = group::grouped(t1, t2, options);
+    let (t1, t2) = match &grouped {
+        Some((t1, t2)) => (t1, t2),
+        None => (t1, t2),
+    };
+    if let Some(schema) = &options.schema {
+        if let Some(resolved) = schema::subschema(schema, &[]) {
+            let mut path = NodePath::default();
+            report_schema_violations(
+                Some(t1),
+                Some(t2),
+                schema,
+                resolved,
+                &mut path,
+                options,
+                acc,
+            );
+        }
+    }
+    if options.sampling.is_none()
+        && (std::ptr::eq(t1, t2) || t1 == t2)
+        && (options.ignore_nan_inequality || !contains_nan(t1))
+    {
+        return;
+    }
+    diff_values(t1, t2, &mut NodePath::default(), options, acc);
+}
+
+/// Diffs only the subtrees at `region`, which must exist on both sides,
+/// reporting what a full diff would report under it.
+pub(crate) fn diff_region<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    region: &'a [PathSegment],
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    if let Some((mut path, v1, v2)) = descend(t1, t2, region, options) {
+        diff_values(v1, v2, &mut path, options, acc);
+    }
+}
+
+/// The nodes at `region` on both sides and their path, or `None` when the
+/// region is missing on either side or lies under an excluded node.
+fn descend<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    region: &'a [PathSegment],
+    options: &DeepDiffOptions,
+) -> Option<(NodePath<'a>, &'a Value, &'a Value)> {
+    let mut path = NodePath::default();
+    let (mut v1, mut v2) = (t1, t2);
+    for segment in region {
+        if !path_allowed(&path, options) || excluded_by_value(Some(v1), Some(v2), &path, options) {
+            return None;
+        }
+        if let PathSegment::Key(key) | PathSegment::Attr(key) = segment {
+            if ignored_key(key, options) {
+                return None;
+            }
+        }
+        let step = std::slice::from_ref(segment);
+        (v1, v2) = (get_value_at_path(v1, step)?, get_value_at_path(v2, step)?);
+        path.push(Step::from(segment));
+    }
+    Some((path, v1, v2))
+}
+
+/// Containers of more nodes than this are split into parts when diffing in
+/// resumable steps.
+const PART_NODES: usize = 1 << 14;
+
+/// A part of a container split by [`split_region`]: one large item present
+/// on both sides, or a run of small ones by position.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Part {
+    Item(PathSegment),
+    Items(Range<usize>),
+}
+
+/// Splits a large dict or ordered list present at `region` on both sides:
+/// reports the items one side lacks and returns the others as parts to diff
+/// later with [`diff_region`] and [`diff_items`]. `None` where the node is
+/// diffed whole instead, and no parts where it is excluded.
+pub(crate) fn split_region<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    region: &'a [PathSegment],
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) -> Option<Vec<Part>> {
+    let Some((mut path, v1, v2)) = descend(t1, t2, region, options) else {
+        return Some(Vec::new());
+    };
+    if !path_allowed(&path, options) || excluded_by_value(Some(v1), Some(v2), &path, options) {
+        return Some(Vec::new());
+    }
+    if is_tagged(v1)
+        || is_tagged(v2)
+        || node_count(v1, PART_NODES).max(node_count(v2, PART_NODES)) < PART_NODES
+    {
+        return None;
+    }
+    let mut runs = Runs::default();
+    match (v1, v2) {
+        (Value::Object(map1), Value::Object(map2)) => {
+            for (pos, (key, item1)) in map1.iter().enumerate() {
+                if ignored_key(key, options) {
+                    continue;
+                }
+                match map2.get(key) {
+                    Some(item2) => runs.add(pos, PathSegment::Key(key.clone()), item1, item2),
+                    None => {
+                        path.push(Step::Key(key));
+                        if !excluded_by_value(Some(item1), None, &path, options) {
+                            acc.report(
+                                Change::new(ChangeKind::DictionaryItemRemoved, path.render())
+                                    .values(Some(item1), None),
+                            );
+                        }
+                        path.pop();
+                    }
+                }
+            }
+            runs.flush(map1.len());
+            for (key, item2) in map2 {
+                if !map1.contains_key(key) && !ignored_key(key, options) {
+                    path.push(Step::Key(key));
+                    if !excluded_by_value(None, Some(item2), &path, options) {
+                        acc.report(
+                            Change::new(ChangeKind::DictionaryItemAdded, path.render())
+                                .values(None, Some(item2)),
+                        );
+                    }
+                    path.pop();
+                }
+            }
+        }
+        (Value::Array(list1), Value::Array(list2)) => {
+            let min_len = list1.len().min(list2.len());
+            for idx in 0..min_len {
+                runs.add(idx, PathSegment::Index(idx), &list1[idx], &list2[idx]);
+            }
+            runs.flush(min_len);
+            for (idx, item) in list1.iter().enumerate().skip(min_len) {
+                path.push(Step::Index(idx));
+                if !excluded_by_value(Some(item), None, &path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemRemoved, path.render())
+                            .values(Some(item), None),
+                    );
+                }
+                path.pop();
+            }
+            for (idx, item) in list2.iter().enumerate().skip(min_len) {
+                path.push(Step::Index(idx));
+                if !excluded_by_value(None, Some(item), &path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemAdded, path.render())
+                            .values(None, Some(item)),
+                    );
+                }
+                path.pop();
+            }
+        }
+        _ => return None,
+    }
+    Some(runs.parts)
+}
+
+/// Diffs the items at positions `items` of the dict or list at `region`,
+/// a part returned by [`split_region`].
+pub(crate) fn diff_items<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    region: &'a [PathSegment],
+    items: Range<usize>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let Some((mut path, v1, v2)) = descend(t1, t2, region, options) else {
+        return;
+    };
+    match (v1, v2) {
+        (Value::Object(map1), Value::Object(map2)) => {
+            for (key, item1) in map1.iter().skip(items.start).take(items.len()) {
+                if let Some(item2) = map2.get(key) {
+                    path.push(Step::Key(key));
+                    diff_values(item1, item2, &mut path, options, acc);
+                    path.pop();
+                }
+            }
+        }
+        (Value::Array(list1), Value::Array(list2)) => {
+            for idx in items {
+                if let (Some(item1), Some(item2)) = (list1.get(idx), list2.get(idx)) {
+                    path.push(Step::Index(idx));
+                    diff_values(item1, item2, &mut path, options, acc);
+                    path.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Groups the items of a split container into parts of about
+/// [`PART_NODES`] nodes.
+#[derive(Default)]
+struct Runs {
+    parts: Vec<Part>,
+    start: Option<usize>,
+    nodes: usize,
+}
+
+impl Runs {
+    fn add(&mut self, pos: usize, segment: PathSegment, item1: &Value, item2: &Value) {
+        let nodes = node_count(item1, PART_NODES).max(node_count(item2, PART_NODES));
+        if nodes >= PART_NODES {
+            self.flush(pos);
+            self.parts.push(Part::Item(segment));
+            return;
+        }
+        self.start.get_or_insert(pos);
+        self.nodes += nodes;
+        if self.nodes >= PART_NODES {
+            self.flush(pos + 1);
+        }
+    }
+
+    fn flush(&mut self, end: usize) {
+        if let Some(start) = self.start.take() {
+            self.parts.push(Part::Items(start..end));
+        }
+        self.nodes = 0;
+    }
+}
+
+/// Number of nodes in `value`, counting no further than `limit`.
+fn node_count(value: &Value, limit: usize) -> usize {
+    let mut count = 1;
+    let children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Array(items) => Box::new(items.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        _ => return count,
+    };
+    for child in children {
+        if count >= limit {
+            break;
+        }
+        count += node_count(child, limit - count);
+    }
+    count
+}
+
+/// Whether `value` holds a NaN float, the one value unequal to itself.
+fn contains_nan(value: &Value) -> bool {
+    if let Some(("float", inner)) = as_tagged(value) {
+        return inner.as_str() == Some("nan");
+    }
+    match value {
+        Value::Array(items) => items.iter().any(contains_nan),
+        Value::Object(map) => map.values().any(contains_nan),
+        _ => false,
+    }
+}
+
+pub(crate) fn diff_values<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    if acc.stopped {
+        return;
+    }
+    acc.tick(options);
+
+    if !path_allowed(path, options) {
+        return;
+    }
+
+    if excluded_by_value(Some(t1), Some(t2), path, options) {
+        return;
+    }
+
+    if !options.operators.is_empty() && compared_by_operator(t1, t2, path, options, acc) {
+        return;
+    }
+
+    if schema_skips(t1, t2, path, options) {
+        return;
+    }
+
+    if !options.path_rules.is_empty() {
+        if let Some(scoped) = rules::scoped(options, &path.render()) {
+            diff_values(t1, t2, path, &scoped, acc);
+            return;
+        }
+    }
+
+    // Containers with differing structural hashes cannot be equal, which
+    // spares the deep comparison at every level of a changed subtree.
+    // Unordered lists are matched item by item below, which reports nothing
+    // for equal lists without hashing their items twice.
+    let unordered_lists =
+        options.ignore_order && matches!((t1, t2), (Value::Array(_), Value::Array(_)));
+    // Rules further down may compare stricter than these options, so
+    // containers under rules are only skipped when they are identical.
+    let container = t1.is_object() || t1.is_array();
+    let under_rules = !options.path_rules.is_empty() && container;
+    // Under sampling, containers are never compared whole, which would read
+    // every item of the sampled containers within them.
+    let sampling = sampled(t1, t2, options);
+    if options.sampling.is_some() && container {
+        acc.sampled += usize::from(sampling.is_some());
+    } else if under_rules {
+        if t1 == t2 {
+            return;
+        }
+    } else if !unordered_lists
+        && (!hashable_pair(t1, t2, options) || acc.hashes.get(t1) == acc.hashes.get(t2))
+        && values_equal(t1, t2, options)
+    {
+        return;
+    }
+
+    if !options.include_keys.is_empty() && !within_included(path, options) {
+        diff_toward_included(t1, t2, path, options, acc);
+        return;
+    }
+
+    match (as_object(t1), as_object(t2)) {
+        (None, None) => {}
+        (Some((class1, attrs1)), Some((class2, attrs2))) if class1 == class2 => {
+            diff_attributes(attrs1, attrs2, path, options, acc);
+            return;
+        }
+        _ => {
+            report_leaf_change(t1, t2, path, options, acc);
+            return;
+        }
+    }
+
+    if let (Some((type1, frame1)), Some((type2, frame2))) = (as_tagged(t1), as_tagged(t2)) {
+        if type1 == type2 && matches!(type1, "DataFrame" | "Table") {
+            diff_frames(frame1, frame2, path, options, acc);
+            return;
+        }
+    }
+
+    if is_tagged(t1) || is_tagged(t2) {
+        match (sequence_items(t1), sequence_items(t2)) {
+            (Some(list1), Some(list2)) if types_compatible(t1, t2, options) => {
+                diff_arrays(list1, list2, path, options, acc);
+            }
+            _ => report_leaf_change(t1, t2, path, options, acc),
+        }
+        return;
+    }
+
+    match (t1, t2) {
+        (Value::Object(map1), Value::Object(map2)) => {
+            let keeps = |key: &str| {
+                !ignored_key(key, options)
+                    && sampling.is_none_or(|sampling| sampling.keeps_key(key))
+            };
+            if let Some(threshold) = options.threshold_to_diff_deeper {
+                let shared = map1
+                    .keys()
+                    .filter(|key| keeps(key) && map2.contains_key(*key))
+                    .count();
+                let union = map1.keys().filter(|key| keeps(key)).count()
+                    + map2
+                        .keys()
+                        .filter(|key| keeps(key) && !map1.contains_key(*key))
+                        .count();
+                if union > 1 && (shared as f64) < threshold * union as f64 {
+                    acc.report(
+                        Change::new(ChangeKind::ValuesChanged, path.render())
+                            .values(Some(t1), Some(t2)),
+                    );
+                    return;
+                }
+            }
+            for (key, value1) in map1.iter().filter(|(key, _)| keeps(key)) {
+                path.push(Step::Key(key));
+                if let Some(value2) = map2.get(key) {
+                    diff_values(value1, value2, path, options, acc);
+                } else if !excluded_by_value(Some(value1), None, path, options)
+                    && !schema_ignores_key(path, options)
+                {
+                    acc.report(
+                        Change::new(ChangeKind::DictionaryItemRemoved, path.render())
+                            .values(Some(value1), None),
+                    );
+                }
+                path.pop();
+            }
+            for (key, value2) in map2.iter().filter(|(key, _)| keeps(key)) {
+                if !map1.contains_key(key) {
+                    path.push(Step::Key(key));
+                    if !excluded_by_value(None, Some(value2), path, options)
+                        && !schema_ignores_key(path, options)
+                    {
+                        acc.report(
+                            Change::new(ChangeKind::DictionaryItemAdded, path.render())
+                                .values(None, Some(value2)),
+                        );
+                    }
+                    path.pop();
+                }
+            }
+        }
+        (Value::Array(list1), Value::Array(list2)) => match sampling {
+            Some(sampling) => diff_sampled_items(list1, list2, sampling, path, options, acc),
+            None => diff_arrays(list1, list2, path, options, acc),
+        },
+        _ => report_leaf_change(t1, t2, path, options, acc),
+    }
+}
+
+/// Reports the changes the first matching custom operator finds between
+/// `t1` and `t2`; false when none takes them over.
+fn compared_by_operator(
+    t1: &Value,
+    t2: &Value,
+    path: &NodePath,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) -> bool {
+    let rendered = path.render();
+    let Some(changes) = options
+        .operators
+        .iter()
+        .find(|operator| operator.0.match_path(&rendered, t1, t2))
+        .and_then(|operator| operator.0.compare(&rendered, t1, t2))
+    else {
+        return false;
+    };
+    for change in changes {
+        acc.report(change);
+    }
+    true
+}
+
+/// The sampling settings when `t1` and `t2` are plain dicts or ordered
+/// lists large enough to be sampled.
+fn sampled(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> Option<Sampling> {
+    let (len1, len2) = match (t1, t2) {
+        _ if is_tagged(t1) || is_tagged(t2) => return None,
+        (Value::Object(map1), Value::Object(map2)) => (map1.len(), map2.len()),
+        (Value::Array(list1), Value::Array(list2)) if !options.ignore_order => {
+            (list1.len(), list2.len())
+        }
+        _ => return None,
+    };
+    options
+        .sampling
+        .filter(|sampling| sampling.applies(len1, len2))
+}
+
+/// Diffs the items of two ordered lists at the indices `sampling` keeps,
+/// reporting kept indices past the end of either list as added or removed.
+fn diff_sampled_items<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    sampling: Sampling,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let len = list1.len().max(list2.len());
+    for idx in (0..len).filter(|idx| sampling.keeps_index(*idx)) {
+        path.push(Step::Index(idx));
+        match (list1.get(idx), list2.get(idx)) {
+            (Some(item1), Some(item2)) => diff_values(item1, item2, path, options, acc),
+            (Some(item), None) => {
+                if !excluded_by_value(Some(item), None, path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemRemoved, path.render())
+                            .values(Some(item), None),
+                    );
+                }
+            }
+            (None, Some(item)) => {
+                if !excluded_by_value(None, Some(item), path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemAdded, path.render())
+                            .values(None, Some(item)),
+                    );
+                }
+            }
+            (None, None) => {}
+        }
+        path.pop();
+    }
+}
+
+/// Whether `values_equal` reduces to plain `==` for this pair of containers,
+/// so comparing structural hashes first is sound.
+fn hashable_pair(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
+    if is_tagged(t1) || is_tagged(t2) {
+        return false;
+    }
+    match (t1, t2) {
+        (Value::Object(_), Value::Object(_)) => true,
+        (Value::Array(_), Value::Array(_)) => !options.ignore_order,
+        _ => false,
+    }
+}
+
+fn diff_attributes<'a>(
+    attrs1: &'a serde_json::Map<String, Value>,
+    attrs2: &'a serde_json::Map<String, Value>,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    for (name, value1) in attrs1 {
+        if ignored_key(name, options) {
+            continue;
+        }
+        path.push(Step::Attr(name));
+        if let Some(value2) = attrs2.get(name) {
+            diff_values(value1, value2, path, options, acc);
+        } else if !excluded_by_value(Some(value1), None, path, options) {
+            acc.report(
+                Change::new(ChangeKind::AttributeRemoved, path.render()).values(Some(value1), None),
+            );
+        }
+        path.pop();
+    }
+    for (name, value2) in attrs2 {
+        if attrs1.contains_key(name) || ignored_key(name, options) {
+            continue;
+        }
+        path.push(Step::Attr(name));
+        if !excluded_by_value(None, Some(value2), path, options) {
+            acc.report(
+                Change::new(ChangeKind::AttributeAdded, path.render()).values(None, Some(value2)),
+            );
+        }
+        path.pop();
+    }
+}
+
+/// Compares two tagged DataFrames (or Arrow tables) aligned on their index: columns are reported
+/// at `root['col']`, rows at `root.loc[label]`, and cells at
+/// `root[(label, 'col')]`.
+fn diff_frames<'a>(
+    frame1: &'a Value,
+    frame2: &'a Value,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let (Some(f1), Some(f2)) = (Frame::parse(frame1), Frame::parse(frame2)) else {
+        report_leaf_change(frame1, frame2, path, options, acc);
+        return;
+    };
+
+    // Columns and rows are filtered by path and value like dict keys and
+    // list items; cells go through `diff_values`, which filters its own.
+    let reported = |path: &NodePath, t1: Option<&Value>, t2: Option<&Value>| {
+        path_allowed(path, options) && !excluded_by_value(t1, t2, path, options)
+    };
+    for (column, dtype1) in f1.dtypes {
+        path.push(Step::Key(column));
+        let (cells1, cells2) = (f1.columns.get(column), f2.columns.get(column));
+        match f2.dtypes.get(column) {
+            None if reported(path, cells1, None) => acc.report(Change::new(
+                ChangeKind::DictionaryItemRemoved,
+                path.render(),
+            )),
+            Some(dtype2) if dtype1 != dtype2 && reported(path, cells1, cells2) => {
+                acc.report(Change::new(ChangeKind::TypeChanged, path.render()).types(
+                    dtype1.as_str().unwrap_or_default(),
+                    dtype2.as_str().unwrap_or_default(),
+                ));
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+    for column in f2.dtypes.keys() {
+        if !f1.dtypes.contains_key(column) {
+            path.push(Step::Key(column));
+            if reported(path, None, f2.columns.get(column)) {
+                acc.report(Change::new(ChangeKind::DictionaryItemAdded, path.render()));
+            }
+            path.pop();
+        }
+    }
+
+    let rows2: HashMap<&str, usize> = f2.rows().collect();
+    let rows1: HashMap<&str, usize> = f1.rows().collect();
+    for (label, row1) in f1.rows() {
+        let Some(&row2) = rows2.get(label) else {
+            let row = f1.row_value(row1);
+            path.push(Step::Row(label));
+            if reported(path, Some(&row), None) {
+                let mut change = Change::new(ChangeKind::IterableItemRemoved, path.render());
+                change.old_value = Some(Cow::Owned(row));
+                acc.report(change);
+            }
+            path.pop();
+            continue;
+        };
+        for (column, cells1) in f1.columns {
+            let Some(cells2) = f2.columns.get(column).and_then(Value::as_array) else {
+                continue;
+            };
+            let (Some(cell1), Some(cell2)) = (
+                cells1.as_array().and_then(|cells| cells.get(row1)),
+                cells2.get(row2),
+            ) else {
+                continue;
+            };
+            // A column whose dtype changed (int64 -> float64) is already
+            // reported once; equal numbers are not repeated per cell.
+            if cell1.as_f64().is_some() && cell1.as_f64() == cell2.as_f64() {
+                continue;
+            }
+            path.push(Step::Cell(label, column));
+            diff_values(cell1, cell2, path, options, acc);
+            path.pop();
+        }
+    }
+    for (label, row2) in f2.rows() {
+        if !rows1.contains_key(label) {
+            let row = f2.row_value(row2);
+            path.push(Step::Row(label));
+            if reported(path, None, Some(&row)) {
+                let mut change = Change::new(ChangeKind::IterableItemAdded, path.render());
+                change.new_value = Some(Cow::Owned(row));
+                acc.report(change);
+            }
+            path.pop();
+        }
+    }
+}
+
+/// Borrowed view of the tagged DataFrame layout produced by the bindings:
+/// `{"dtypes": {col: dtype}, "index": [label], "columns": {col: [cell]}}`.
+struct Frame<'a> {
+    dtypes: &'a serde_json::Map<String, Value>,
+    index: &'a [Value],
+    columns: &'a serde_json::Map<String, Value>,
+}
+
+impl<'a> Frame<'a> {
+    fn parse(value: &'a Value) -> Option<Self> {
+        Some(Self {
+            dtypes: value.get("dtypes")?.as_object()?,
+            index: value.get("index")?.as_array()?,
+            columns: value.get("columns")?.as_object()?,
+        })
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (&'a str, usize)> + 'a {
+        let index = self.index;
+        index
+            .iter()
+            .enumerate()
+            .filter_map(|(row, label)| Some((label.as_str()?, row)))
+    }
+
+    fn row_value(&self, row: usize) -> Value {
+        let cells = self
+            .columns
+            .iter()
+            .filter_map(|(column, cells)| Some((column.clone(), cells.get(row)?.clone())))
+            .collect();
+        Value::Object(cells)
+    }
+}
+
+fn diff_arrays<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    if options.ignore_order {
+        diff_arrays_ignore_order(list1, list2, path, options, acc);
+        return;
+    }
+    if let Some(key) = options
+        .match_by
+        .as_deref()
+        .filter(|key| keyed(list1, key) && keyed(list2, key))
+    {
+        diff_arrays_by_key(list1, list2, key, path, options, acc);
+        return;
+    }
+    if options.align_lists && diff_arrays_aligned(list1, list2, path, options, acc) {
+        return;
+    }
+    diff_arrays_by_index(list1, list2, path, options, acc);
+}
+
+/// Compares items at the same index, then reports the tail of the longer
+/// list as removed or added.
+fn diff_arrays_by_index<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let min_len = list1.len().min(list2.len());
+    let (head1, head2) = (&list1[..min_len], &list2[..min_len]);
+    match numeric::unequal_indices(head1, head2, options) {
+        Some(unequal) => {
+            acc.visited += min_len - unequal.len();
+            for idx in unequal {
+                path.push(Step::Index(idx));
+                diff_values(&list1[idx], &list2[idx], path, options, acc);
+                path.pop();
+            }
+        }
+        None if min_len >= PARALLEL_MIN_ITEMS && acc.can_split(options) => {
+            diff_items_parallel(head1, head2, path, options, acc);
+        }
+        None => {
+            for idx in 0..min_len {
+                path.push(Step::Index(idx));
+                diff_values(&list1[idx], &list2[idx], path, options, acc);
+                path.pop();
+            }
+        }
+    }
+    for (idx, item) in list1.iter().enumerate().skip(min_len) {
+        path.push(Step::Index(idx));
+        if !excluded_by_value(Some(item), None, path, options) {
+            acc.report(
+                Change::new(ChangeKind::IterableItemRemoved, path.render())
+                    .values(Some(item), None),
+            );
+        }
+        path.pop();
+    }
+    for (idx, item) in list2.iter().enumerate().skip(min_len) {
+        path.push(Step::Index(idx));
+        if !excluded_by_value(None, Some(item), path, options) {
+            acc.report(
+                Change::new(ChangeKind::IterableItemAdded, path.render()).values(None, Some(item)),
+            );
+        }
+        path.pop();
+    }
+}
+
+/// How two aligned ordered lists pair their items, for `align_lists`.
+#[derive(Clone, Copy)]
+pub(crate) enum Aligned {
+    /// Items diffed against each other: kept by the alignment, or removed
+    /// and added between the same two kept items.
+    Paired(usize, usize),
+    /// An item removed from one position and added unchanged at another.
+    Moved(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Pairs the items of two ordered lists along a shortest edit script
+/// between them. Removed items found unchanged among the added ones are
+/// moved, the first removed taking the first added. Between two kept items,
+/// the rest of the removed and added items pair up in order, and any left
+/// over stay removed or added. `None` when the lists take more than
+/// `ALIGN_MAX_EDITS` edits to align.
+pub(crate) fn align_items(
+    list1: &[Value],
+    list2: &[Value],
+    options: &DeepDiffOptions,
+) -> Option<Vec<Aligned>> {
+    let hashes = |list: &[Value]| -> Vec<u64> {
+        list.iter()
+            .map(|item| canonical_hash(item, options))
+            .collect()
+    };
+    let (hashes1, hashes2) = (hashes(list1), hashes(list2));
+    let edits = align(&hashes1, &hashes2, ALIGN_MAX_EDITS)?;
+
+    let mut removed: HashMap<u64, VecDeque<usize>> = HashMap::new();
+    for edit in &edits {
+        if let Edit::Delete(idx) = *edit {
+            removed.entry(hashes1[idx]).or_default().push_back(idx);
+        }
+    }
+    let mut moves: HashMap<usize, usize> = HashMap::new();
+    for edit in &edits {
+        if let Edit::Insert(idx) = *edit {
+            let source = removed.get_mut(&hashes2[idx]).and_then(VecDeque::pop_front);
+            if let Some(source) = source {
+                moves.insert(source, idx);
+            }
+        }
+    }
+    let moved_to: HashSet<usize> = moves.values().copied().collect();
+
+    let mut steps = Vec::with_capacity(edits.len());
+    let (mut deleted, mut inserted) = (Vec::new(), Vec::new());
+    for edit in edits.into_iter().map(Some).chain([None]) {
+        match edit {
+            Some(Edit::Delete(idx)) => match moves.get(&idx) {
+                Some(&target) => steps.push(Aligned::Moved(idx, target)),
+                None => deleted.push(idx),
+            },
+            Some(Edit::Insert(idx)) => {
+                if !moved_to.contains(&idx) {
+                    inserted.push(idx);
+                }
+            }
+            Some(Edit::Keep(..)) | None => {
+                steps.extend(
+                    deleted
+                        .iter()
+                        .zip(&inserted)
+                        .map(|(&idx1, &idx2)| Aligned::Paired(idx1, idx2)),
+                );
+                steps.extend(
+                    deleted
+                        .iter()
+                        .skip(inserted.len())
+                        .map(|&idx| Aligned::Removed(idx)),
+                );
+                steps.extend(
+                    inserted
+                        .iter()
+                        .skip(deleted.len())
+                        .map(|&idx| Aligned::Added(idx)),
+                );
+                deleted.clear();
+                inserted.clear();
+                if let Some(Edit::Keep(idx1, idx2)) = edit {
+                    steps.push(Aligned::Paired(idx1, idx2));
+                }
+            }
+        }
+    }
+    Some(steps)
+}
+
+/// Diffs two ordered lists as [`align_items`] pairs them, paired items at
+/// their index in `list1`. Returns `false`, having reported nothing, when
+/// the lists are too far apart to align.
+fn diff_arrays_aligned<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) -> bool {
+    let Some(steps) = align_items(list1, list2, options) else {
+        return false;
+    };
+    for step in steps {
+        match step {
+            Aligned::Paired(idx1, idx2) => {
+                path.push(Step::Index(idx1));
+                diff_values(&list1[idx1], &list2[idx2], path, options, acc);
+                path.pop();
+            }
+            Aligned::Moved(idx1, idx2) => {
+                let new_path = path.render_child(Step::Index(idx2));
+                let (item1, item2) = (&list1[idx1], &list2[idx2]);
+                path.push(Step::Index(idx1));
+                if !excluded_by_value(Some(item1), Some(item2), path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemMoved, path.render())
+                            .values(Some(item1), Some(item2))
+                            .new_path(new_path),
+                    );
+                }
+                path.pop();
+            }
+            Aligned::Removed(idx) => {
+                path.push(Step::Index(idx));
+                if !excluded_by_value(Some(&list1[idx]), None, path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemRemoved, path.render())
+                            .values(Some(&list1[idx]), None),
+                    );
+                }
+                path.pop();
+            }
+            Aligned::Added(idx) => {
+                path.push(Step::Index(idx));
+                if !excluded_by_value(None, Some(&list2[idx]), path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemAdded, path.render())
+                            .values(None, Some(&list2[idx])),
+                    );
+                }
+                path.pop();
+            }
+        }
+    }
+    true
+}
+
+/// Whether every item of `list` is a dict holding `key`.
+pub(crate) fn keyed(list: &[Value], key: &str) -> bool {
+    list.iter()
+        .all(|item| item.as_object().is_some_and(|map| map.contains_key(key)))
+}
+
+/// Diffs two lists of dicts pairing the items whose `key` holds equal values,
+/// the first unpaired item of `list2` with that value taking each item of
+/// `list1`. Paired items are diffed at their index in `list1`.
+fn diff_arrays_by_key<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    key: &str,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let mut unpaired: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, item) in list2.iter().enumerate().rev() {
+        unpaired
+            .entry(canonical_hash(&item[key], options))
+            .or_default()
+            .push(idx);
+    }
+    let mut paired = vec![false; list2.len()];
+    for (idx, item) in list1.iter().enumerate() {
+        path.push(Step::Index(idx));
+        let candidates = unpaired.get_mut(&canonical_hash(&item[key], options));
+        match candidates.and_then(|candidates| candidates.pop()) {
+            Some(other) => {
+                paired[other] = true;
+                diff_values(item, &list2[other], path, options, acc);
+            }
+            None => {
+                if !excluded_by_value(Some(item), None, path, options) {
+                    acc.report(
+                        Change::new(ChangeKind::IterableItemRemoved, path.render())
+                            .values(Some(item), None),
+                    );
+                }
+            }
+        }
+        path.pop();
+    }
+    for (idx, item) in list2.iter().enumerate() {
+        if paired[idx] {
+            continue;
+        }
+        path.push(Step::Index(idx));
+        if !excluded_by_value(None, Some(item), path, options) {
+            acc.report(
+                Change::new(ChangeKind::IterableItemAdded, path.render()).values(None, Some(item)),
+            );
+        }
+        path.pop();
+    }
+}
+
+/// Diffs the paired items of two long ordered lists in contiguous chunks,
+/// one thread each. Every chunk renders absolute indices, so its entries
+/// merge into `acc` as they are.
+fn diff_items_parallel<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    path: &NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let chunk_len = list1.len().div_ceil(options.threads()).max(1);
+    let parts: Vec<(Entries, usize, usize, usize)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..list1.len())
+            .step_by(chunk_len)
+            .map(|start| {
+                let mut path = path.clone();
+                scope.spawn(move || {
+                    let mut part = DiffAccumulator::new(options);
+                    part.in_worker = true;
+                    for idx in start..(start + chunk_len).min(list1.len()) {
+                        path.push(Step::Index(idx));
+                        diff_values(&list1[idx], &list2[idx], &mut path, options, &mut part);
+                        path.pop();
+                    }
+                    (part.entries, part.visited, part.reported, part.sampled)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    for (entries, visited, reported, sampled) in parts {
+        acc.entries.extend(entries);
+        acc.visited += visited;
+        acc.reported += reported;
+        acc.sampled += sampled;
+    }
+}
+
+fn report_leaf_change(
+    t1: &Value,
+    t2: &Value,
+    path: &NodePath,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let change = if types_compatible(t1, t2, options) {
+        if let (Value::String(s1), Value::String(s2), Some(threshold)) =
+            (t1, t2, options.large_string_threshold)
+        {
+            if s1.len() > threshold && s2.len() > threshold && !acc.paths_only {
+                acc.report(
+                    Change::new(ChangeKind::ValuesChanged, path.render())
+                        .summary(chunks::summary(s1, s2)),
+                );
+                return;
+            }
+        }
+        Change::new(ChangeKind::ValuesChanged, path.render()).magnitude(
+            options
+                .magnitude_scores
+                .then(|| magnitude(t1, t2))
+                .flatten(),
+        )
+    } else {
+        let names = options.type_names;
+        let (old_type, new_type) = match (enum_class(t1), enum_class(t2)) {
+            // Enums named alike in different modules are told apart by module.
+            (Some(class1), Some(class2)) if type_name(t1) == type_name(t2) => (class1, class2),
+            _ => (type_name(t1), type_name(t2)),
+        };
+        Change::new(ChangeKind::TypeChanged, path.render())
+            .types(names.name(old_type), names.name(new_type))
+    };
+    acc.report(change.values(Some(t1), Some(t2)));
+}
+
+fn diff_arrays_ignore_order<'a>(
+    list1: &'a [Value],
+    list2: &'a [Value],
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let map2 = indices_by_hash(list2, options);
+    // Items of a long list that cannot be in `list2` are removed outright,
+    // keeping them out of the table matched below.
+    let filter = (list1.len() >= BLOOM_MIN_ITEMS).then(|| BloomFilter::new(map2.keys()));
+    let mut map1: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut removed = Vec::new();
+    for (idx, item) in list1.iter().enumerate() {
+        let hash = canonical_hash(item, options);
+        if filter
+            .as_ref()
+            .is_some_and(|filter| !filter.might_contain(hash))
+        {
+            removed.push(idx);
+        } else {
+            map1.entry(hash).or_default().push(idx);
+        }
+    }
+
+    // With `report_repetition`, extra copies of an item on one side are a
+    // repetition change rather than additions or removals.
+    let mut repeated = Vec::new();
+    for (key, indices1) in &map1 {
+        match map2.get(key) {
+            Some(indices2) if options.report_repetition => {
+                if indices1.len() != indices2.len() {
+                    repeated.push((indices1, indices2));
+                }
+            }
+            matched => removed.extend(indices1.iter().skip(matched.map_or(0, Vec::len))),
+        }
+    }
+    let mut added = Vec::new();
+    for (key, indices2) in &map2 {
+        match map1.get(key) {
+            Some(_) if options.report_repetition => {}
+            matched => added.extend(indices2.iter().skip(matched.map_or(0, Vec::len))),
+        }
+    }
+    // The tables are unordered; report in index order.
+    removed.sort_unstable();
+    added.sort_unstable();
+    repeated.sort_unstable_by_key(|(indices1, _)| indices1[0]);
+
+    if options.string_similarity.is_some() || options.cutoff_distance_for_pairs.is_some() {
+        for (idx1, idx2) in pair_similar_items(list1, list2, &mut removed, &mut added, options) {
+            path.push(Step::Index(idx1));
+            diff_values(&list1[idx1], &list2[idx2], path, options, acc);
+            path.pop();
+        }
+    }
+
+    for (indices1, indices2) in repeated {
+        let (idx1, idx2) = (indices1[0], indices2[0]);
+        path.push(Step::Index(idx1));
+        if !excluded_by_value(Some(&list1[idx1]), Some(&list2[idx2]), path, options) {
+            let repetition = Repetition {
+                old_repeat: indices1.len(),
+                new_repeat: indices2.len(),
+                old_indexes: indices1.clone(),
+                new_indexes: indices2.clone(),
+            };
+            acc.report(
+                Change::new(ChangeKind::RepetitionChange, path.render())
+                    .values(Some(&list1[idx1]), Some(&list2[idx2]))
+                    .repetition(repetition),
             );
         }
-        if !self.iterable_item_added.is_empty() {
-            result.insert(
-                "iterable_item_added".to_string(),
-                map_to_value(self.iterable_item_added),
+        path.pop();
+    }
+    for idx in removed {
+        path.push(Step::Index(idx));
+        if !excluded_by_value(Some(&list1[idx]), None, path, options) {
+            acc.report(
+                Change::new(ChangeKind::IterableItemRemoved, path.render())
+                    .values(Some(&list1[idx]), None),
             );
         }
-        if !self.iterable_item_removed.is_empty() {
-            result.insert(
-                "iterable_item_removed".to_string(),
-                map_to_value(self.iterable_item_removed),
+        path.pop();
+    }
+    for idx in added {
+        path.push(Step::Index(idx));
+        if !excluded_by_value(None, Some(&list2[idx]), path, options) {
+            acc.report(
+                Change::new(ChangeKind::IterableItemAdded, path.render())
+                    .values(None, Some(&list2[idx])),
             );
         }
-        if !self.type_changes.is_empty() {
-            result.insert("type_changes".to_string(), map_to_value(self.type_changes));
-        }
-
-        Value::Object(result.into_iter().collect())
+        path.pop();
     }
 }
 
-fn map_to_value(map: BTreeMap<String, Value>) -> Value {
-    Value::Object(map.into_iter().collect())
-}
-
-pub(crate) fn diff_values(
-    t1: &Value,
-    t2: &Value,
-    path: &str,
+/// Pairs the `removed` items of `list1` with the `added` items of `list2`
+/// that are close enough to be one item changed, most similar first, taking
+/// the paired indices out of both: strings at least `string_similarity`
+/// similar, and dicts or objects within `cutoff_distance_for_pairs` of each
+/// other. `cutoff_intersection_for_pairs` and `max_passes` bound the search.
+fn pair_similar_items(
+    list1: &[Value],
+    list2: &[Value],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
     options: &DeepDiffOptions,
-    acc: &mut DiffAccumulator,
-) {
-    if !path_allowed(path, options) {
-        return;
-    }
-
-    if values_equal(t1, t2, options) {
-        return;
+) -> Vec<(usize, usize)> {
+    if let Some(cutoff) = options.cutoff_intersection_for_pairs {
+        let unmatched = (removed.len() + added.len()) as f64;
+        if unmatched > cutoff * (list1.len() + list2.len()) as f64 {
+            return Vec::new();
+        }
     }
+    // Field hashes of each record, computed once for all the pairs it is in.
+    let (fields1, fields2) = match options.cutoff_distance_for_pairs {
+        Some(_) => (
+            field_hashes(list1, removed, options),
+            field_hashes(list2, added, options),
+        ),
+        None => (HashMap::new(), HashMap::new()),
+    };
 
-    match (t1, t2) {
-        (Value::Object(map1), Value::Object(map2)) => {
-            for (key, value1) in map1 {
-                if let Some(value2) = map2.get(key) {
-                    let child_path = format!("{}['{}']", path, key);
-                    diff_values(value1, value2, &child_path, options, acc);
-                } else {
-                    let child_path = format!("{}['{}']", path, key);
-                    acc.dictionary_item_removed.push(child_path);
-                }
+    let mut candidates = Vec::new();
+    let mut passes = options.max_passes.unwrap_or(usize::MAX);
+    'search: for &idx1 in removed.iter() {
+        for &idx2 in added.iter() {
+            if passes == 0 {
+                break 'search;
             }
-            for key in map2.keys() {
-                if !map1.contains_key(key) {
-                    let child_path = format!("{}['{}']", path, key);
-                    acc.dictionary_item_added.push(child_path);
-                }
-            }
-        }
-        (Value::Array(list1), Value::Array(list2)) => {
-            if options.ignore_order {
-                diff_arrays_ignore_order(list1, list2, path, options, acc);
-            } else {
-                let min_len = list1.len().min(list2.len());
-                for idx in 0..min_len {
-                    let child_path = format!("{}[{}]", path, idx);
-                    diff_values(&list1[idx], &list2[idx], &child_path, options, acc);
-                }
-                if list1.len() > list2.len() {
-                    for (idx, item) in list1.iter().enumerate().skip(min_len) {
-                        let child_path = format!("{}[{}]", path, idx);
-                        acc.iterable_item_removed.insert(child_path, item.clone());
+            passes -= 1;
+            let similarity = match (&list1[idx1], &list2[idx2]) {
+                (Value::String(s1), Value::String(s2)) => options
+                    .string_similarity
+                    .and_then(|threshold| string_similarity(s1, s2, threshold)),
+                _ => match (fields1.get(&idx1), fields2.get(&idx2)) {
+                    (Some(a), Some(b)) => {
+                        let distance = field_distance(a, b);
+                        options
+                            .cutoff_distance_for_pairs
+                            .filter(|cutoff| distance <= *cutoff)
+                            .map(|_| 1.0 - distance)
                     }
-                }
-                if list2.len() > list1.len() {
-                    for (idx, item) in list2.iter().enumerate().skip(min_len) {
-                        let child_path = format!("{}[{}]", path, idx);
-                        acc.iterable_item_added.insert(child_path, item.clone());
-                    }
-                }
+                    _ => None,
+                },
+            };
+            if let Some(similarity) = similarity {
+                candidates.push((similarity, idx1, idx2));
             }
         }
-        _ => {
-            if types_compatible(t1, t2, options) {
-                acc.values_changed
-                    .insert(path.to_string(), json_obj(old_new_value(t1, t2)));
-            } else {
-                acc.type_changes
-                    .insert(path.to_string(), json_obj(type_change_value(t1, t2)));
-            }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    let (mut paired1, mut paired2) = (HashSet::new(), HashSet::new());
+    let mut pairs = Vec::new();
+    for (_, idx1, idx2) in candidates {
+        if !paired1.contains(&idx1) && !paired2.contains(&idx2) {
+            paired1.insert(idx1);
+            paired2.insert(idx2);
+            pairs.push((idx1, idx2));
         }
     }
+    removed.retain(|idx| !paired1.contains(idx));
+    added.retain(|idx| !paired2.contains(idx));
+    pairs
 }
 
-fn diff_arrays_ignore_order(
-    list1: &[Value],
-    list2: &[Value],
-    path: &str,
-    _options: &DeepDiffOptions,
-    acc: &mut DiffAccumulator,
-) {
-    let mut map1: HashMap<String, Vec<usize>> = HashMap::new();
-    let mut map2: HashMap<String, Vec<usize>> = HashMap::new();
-
-    for (idx, item) in list1.iter().enumerate() {
-        let key = canonical_string(item);
-        map1.entry(key).or_default().push(idx);
+/// Similarity of two strings, 1 minus their edit distance over the longer
+/// length, when at least `threshold`.
+fn string_similarity(s1: &str, s2: &str, threshold: f64) -> Option<f64> {
+    let (len1, len2) = (s1.chars().count(), s2.chars().count());
+    let longest = len1.max(len2);
+    // The length difference alone bounds the similarity.
+    if longest == 0 || (len1.min(len2) as f64) < threshold * longest as f64 {
+        return None;
     }
-    for (idx, item) in list2.iter().enumerate() {
-        let key = canonical_string(item);
-        map2.entry(key).or_default().push(idx);
+    let similarity = 1.0 - edit_distance(s1, s2) as f64 / longest as f64;
+    (similarity >= threshold).then_some(similarity)
+}
+
+/// The fields of a dict or of an object's attributes; `None` for other
+/// values, including tagged scalars such as dates.
+fn record_fields(value: &Value) -> Option<&Map<String, Value>> {
+    match as_object(value) {
+        Some((_, attrs)) => Some(attrs),
+        None if !is_tagged(value) => value.as_object(),
+        None => None,
     }
+}
 
-    for (key, indices1) in &map1 {
-        let indices2 = map2.get(key).cloned().unwrap_or_default();
-        if indices1.len() > indices2.len() {
-            for idx in indices1[indices2.len()..].iter().copied() {
-                let child_path = format!("{}[{}]", path, idx);
-                acc.iterable_item_removed
-                    .insert(child_path, list1[idx].clone());
-            }
-        }
+/// Hashes of the fields of the records among `indices` of `list`.
+fn field_hashes<'v>(
+    list: &'v [Value],
+    indices: &[usize],
+    options: &DeepDiffOptions,
+) -> HashMap<usize, HashMap<&'v str, u64>> {
+    indices
+        .iter()
+        .filter_map(|&idx| {
+            let fields = record_fields(&list[idx])?;
+            let hashes = fields
+                .iter()
+                .map(|(key, value)| (key.as_str(), canonical_hash(value, options)))
+                .collect();
+            Some((idx, hashes))
+        })
+        .collect()
+}
+
+/// Share of the keys of two records that are missing from one of them or
+/// hold different values, from the hashes of their fields.
+fn field_distance(fields1: &HashMap<&str, u64>, fields2: &HashMap<&str, u64>) -> f64 {
+    let changed = fields1
+        .iter()
+        .filter(|(key, hash)| fields2.get(*key) != Some(hash))
+        .count();
+    let only2 = fields2
+        .keys()
+        .filter(|key| !fields1.contains_key(*key))
+        .count();
+    let union = fields1.len() + only2;
+    if union == 0 {
+        return 0.0;
     }
+    (changed + only2) as f64 / union as f64
+}
 
-    for (key, indices2) in &map2 {
-        let indices1 = map1.get(key).cloned().unwrap_or_default();
-        if indices2.len() > indices1.len() {
-            for idx in indices2[indices1.len()..].iter().copied() {
-                let child_path = format!("{}[{}]", path, idx);
-                acc.iterable_item_added
-                    .insert(child_path, list2[idx].clone());
-            }
-        }
+/// Indices of the items of `list`, grouped by their `canonical_hash` so each
+/// item is hashed once however many items it is matched against.
+fn indices_by_hash(list: &[Value], options: &DeepDiffOptions) -> HashMap<u64, Vec<usize>> {
+    let mut indices: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (idx, item) in list.iter().enumerate() {
+        indices
+            .entry(canonical_hash(item, options))
+            .or_default()
+            .push(idx);
     }
+    indices
 }
 
 fn values_equal(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
+    if let Some(equal) = custom_scalar_equal(t1, t2, options) {
+        return equal;
+    }
+    match (as_tagged(t1), as_tagged(t2)) {
+        (None, None) => {}
+        (Some((type1, inner1)), Some((type2, inner2))) if type1 == type2 => {
+            return tagged_equal(type1, inner1, inner2, options);
+        }
+        _ => {
+            if options.ignore_string_type_changes {
+                if let (Some(a), Some(b)) = (string_text(t1), string_text(t2)) {
+                    return a == b;
+                }
+            }
+            if options.ignore_uuid_types {
+                if let (Some(a), Some(b)) = (uuid_text(t1), uuid_text(t2)) {
+                    return normalize_uuid(a) == normalize_uuid(b);
+                }
+            }
+            if is_integer(t1) && is_integer(t2) {
+                if let (Some(a), Some(b)) = (as_decimal(t1), as_decimal(t2)) {
+                    return decimals_equal(&a, &b, options);
+                }
+            }
+            if options.ignore_numeric_type_changes && is_numeric(t1) && is_numeric(t2) {
+                if let (Some(a), Some(b)) = (as_decimal(t1), as_decimal(t2)) {
+                    return decimals_equal(&a, &b, options);
+                }
+            }
+            return false;
+        }
+    }
     match (t1, t2) {
         (Value::Number(n1), Value::Number(n2)) => numbers_equal(n1, n2, options),
-        (Value::String(s1), Value::String(s2)) => s1 == s2,
+        (Value::String(s1), Value::String(s2)) => s1 == s2 || timestamps_equal(s1, s2, options),
         (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
         (Value::Null, Value::Null) => true,
         (Value::Array(a1), Value::Array(a2)) => {
             if options.ignore_order {
-                let mut counts1: HashMap<String, usize> = HashMap::new();
-                let mut counts2: HashMap<String, usize> = HashMap::new();
+                let mut counts: HashMap<u64, isize> = HashMap::new();
                 for item in a1 {
-                    *counts1.entry(canonical_string(item)).or_insert(0) += 1;
+                    *counts.entry(canonical_hash(item, options)).or_insert(0) += 1;
                 }
                 for item in a2 {
-                    *counts2.entry(canonical_string(item)).or_insert(0) += 1;
+                    *counts.entry(canonical_hash(item, options)).or_insert(0) -= 1;
                 }
-                counts1 == counts2
+                a1.len() == a2.len() && counts.values().all(|count| *count == 0)
             } else {
                 a1 == a2
             }
@@ -208,6 +1941,91 @@ fn values_equal(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
     }
 }
 
+/// The verdict of the first registered comparator with one for two numbers
+/// or two strings, typed comparators before global ones.
+fn custom_scalar_equal(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> Option<bool> {
+    if options.comparators.is_empty() {
+        return None;
+    }
+    let scalar_type = value_type(t1);
+    if !matches!(scalar_type, ValueType::Number | ValueType::String)
+        || value_type(t2) != scalar_type
+    {
+        return None;
+    }
+    let typed = options
+        .comparators
+        .iter()
+        .filter(|comparator| comparator.value_type == Some(scalar_type));
+    let global = options
+        .comparators
+        .iter()
+        .filter(|comparator| comparator.value_type.is_none());
+    typed
+        .chain(global)
+        .find_map(|comparator| comparator.inner.approx_eq(t1, t2))
+}
+
+fn tagged_equal(type_name: &str, v1: &Value, v2: &Value, options: &DeepDiffOptions) -> bool {
+    if type_name == "bytes" {
+        return v1.get("text") == v2.get("text");
+    }
+    if type_name == "enum" {
+        return v1 == v2;
+    }
+    if type_name == "float" {
+        // NaN is unequal to everything, itself included, unless asked.
+        return v1 == v2 && (options.ignore_nan_inequality || v1.as_str() != Some("nan"));
+    }
+    if type_name == "Decimal" || type_name == "int" {
+        if let (Value::String(s1), Value::String(s2)) = (v1, v2) {
+            if let (Some(a), Some(b)) = (Decimal::parse(s1), Decimal::parse(s2)) {
+                return decimals_equal(&a, &b, options);
+            }
+        }
+        return v1 == v2;
+    }
+    if let (Some(a), Some(b)) = (
+        comparable_instant(type_name, v1, options),
+        comparable_instant(type_name, v2, options),
+    ) {
+        if a.aware != b.aware {
+            return false;
+        }
+        let epsilon = options.datetime_epsilon.unwrap_or(0.0) * 1e6;
+        return (a.micros - b.micros).unsigned_abs() as f64 <= epsilon;
+    }
+    values_equal(v1, v2, options)
+}
+
+/// A tagged temporal value on the timeline, truncated when
+/// `truncate_datetime` applies to it.
+fn comparable_instant(
+    type_name: &str,
+    value: &Value,
+    options: &DeepDiffOptions,
+) -> Option<crate::datetime::Instant> {
+    match options.truncate_datetime {
+        Some(unit) if type_name == "datetime" => truncated_datetime(value.as_str()?, unit.micros()),
+        _ => temporal_instant(type_name, value),
+    }
+}
+
+/// Whether two ISO 8601 timestamp strings fall in the same
+/// `truncate_datetime` unit.
+fn timestamps_equal(s1: &str, s2: &str, options: &DeepDiffOptions) -> bool {
+    let Some(unit) = options.truncate_datetime else {
+        return false;
+    };
+    match (
+        truncated_datetime(s1, unit.micros()),
+        truncated_datetime(s2, unit.micros()),
+    ) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
 fn numbers_equal(
     n1: &serde_json::Number,
     n2: &serde_json::Number,
@@ -217,6 +2035,9 @@ fn numbers_equal(
     let f2 = n2.as_f64();
 
     if let (Some(a), Some(b)) = (f1, f2) {
+        if let Some(equal) = formatted_equal(a, b, options) {
+            return equal;
+        }
         if options.ignore_numeric_type_changes && (a - b).abs() <= f64::EPSILON {
             return true;
         }
@@ -228,6 +2049,9 @@ fn numbers_equal(
                 return true;
             }
         }
+        if options.use_log_scale && log_scale_equal(a, b, options) {
+            return true;
+        }
         if let Some(sig) = options.significant_digits {
             if a == 0.0 || b == 0.0 {
                 let threshold = 10f64.powi(-(sig as i32));
@@ -239,9 +2063,151 @@ fn numbers_equal(
         }
     }
 
+    // By value rather than by lexeme: with `raw-numbers`, `1.10` and `1.1`
+    // are different numbers to `==`.
+    if n1.is_f64() && n2.is_f64() {
+        return f1 == f2;
+    }
     n1 == n2
 }
 
+/// Whether two numbers read the same once formatted, when
+/// `number_to_string_func` or `number_format_notation` decides.
+fn formatted_equal(a: f64, b: f64, options: &DeepDiffOptions) -> Option<bool> {
+    let notation = options.number_format_notation;
+    if let Some(format) = &options.number_to_string {
+        let digits = options.significant_digits;
+        let notation = notation.unwrap_or_default();
+        return Some((format.0)(a, digits, notation) == (format.0)(b, digits, notation));
+    }
+    let digits = options.significant_digits?;
+    let notation = notation?;
+    Some(notation.format(a, digits) == notation.format(b, digits))
+}
+
+/// Whether `a` and `b` have the same sign and base-10 logarithms of their
+/// magnitudes within `log_scale_similarity_threshold`; zero only matches
+/// zero.
+fn log_scale_equal(a: f64, b: f64, options: &DeepDiffOptions) -> bool {
+    if a == 0.0 || b == 0.0 || a.is_sign_negative() != b.is_sign_negative() {
+        return a == b;
+    }
+    (a.abs().log10() - b.abs().log10()).abs() <= options.log_scale_similarity_threshold
+}
+
+/// `|a - b| / max(|a|, |b|)` capped at 1, or `None` unless both values are
+/// numbers. NaN and changes to or from infinity score 1.
+fn magnitude(t1: &Value, t2: &Value) -> Option<f64> {
+    let (a, b) = (numeric_value(t1)?, numeric_value(t2)?);
+    let scale = a.abs().max(b.abs());
+    if scale == 0.0 {
+        return Some(0.0);
+    }
+    let score = (a - b).abs() / scale;
+    Some(if score.is_nan() { 1.0 } else { score.min(1.0) })
+}
+
+/// A plain number, or a tagged `Decimal`, `int` or `float`, as an `f64`.
+fn numeric_value(value: &Value) -> Option<f64> {
+    match (as_tagged(value), value) {
+        (Some(("Decimal" | "int", Value::String(text))), _) => Some(Decimal::parse(text)?.to_f64()),
+        (Some(("float", Value::String(text))), _) => text.parse().ok(),
+        (None, Value::Number(number)) => number.as_f64(),
+        _ => None,
+    }
+}
+
+fn decimals_equal(a: &Decimal, b: &Decimal, options: &DeepDiffOptions) -> bool {
+    if a == b {
+        return true;
+    }
+    if let Some(equal) = formatted_equal(a.to_f64(), b.to_f64(), options) {
+        return equal;
+    }
+    let atol = options.atol.or(options.math_epsilon).unwrap_or(0.0);
+    let rtol = options.rtol.unwrap_or(0.0);
+    if atol > 0.0 || rtol > 0.0 {
+        let diff = a.abs_diff(b).to_f64();
+        let tol = atol.max(rtol * a.abs().to_f64().max(b.abs().to_f64()));
+        if diff <= tol {
+            return true;
+        }
+    }
+    if options.use_log_scale && log_scale_equal(a.to_f64(), b.to_f64(), options) {
+        return true;
+    }
+    if let Some(sig) = options.significant_digits {
+        if a.is_zero() || b.is_zero() {
+            let threshold = 10f64.powi(-(sig as i32));
+            return a.abs_diff(b).to_f64() <= threshold;
+        }
+        return a.round_significant(sig) == b.round_significant(sig);
+    }
+    false
+}
+
+/// Text of a plain string or of decoded tagged `bytes`.
+fn string_text(value: &Value) -> Option<&str> {
+    match (as_tagged(value), value) {
+        (Some(("bytes", inner)), _) => inner.get("text")?.as_str(),
+        (None, Value::String(text)) => Some(text),
+        _ => None,
+    }
+}
+
+/// Text of a tagged `UUID` or of a plain string, the two sides
+/// `ignore_uuid_types` treats as interchangeable.
+fn uuid_text(value: &Value) -> Option<&str> {
+    match (as_tagged(value), value) {
+        (Some(("UUID", inner)), _) => inner.as_str(),
+        (None, Value::String(text)) => Some(text),
+        _ => None,
+    }
+}
+
+fn normalize_uuid(text: &str) -> String {
+    let text = text.trim();
+    let text = text.strip_prefix("urn:uuid:").unwrap_or(text);
+    text.chars()
+        .filter(|c| !matches!(c, '-' | '{' | '}'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_numeric(value: &Value) -> bool {
+    match as_tagged(value) {
+        Some((type_name, _)) => matches!(type_name, "Decimal" | "int" | "float"),
+        None => value.is_number(),
+    }
+}
+
+/// Plain numbers plus the tagged `int` and non-finite `float` values that
+/// stand in for numbers JSON cannot hold.
+fn is_plain_number(value: &Value) -> bool {
+    match as_tagged(value) {
+        Some((type_name, _)) => type_name == "int" || type_name == "float",
+        None => value.is_number(),
+    }
+}
+
+/// Plain integers and the tagged `int` carrying integers beyond 64 bits.
+fn is_integer(value: &Value) -> bool {
+    match (as_tagged(value), value) {
+        (Some((type_name, _)), _) => type_name == "int",
+        (None, Value::Number(number)) => number.is_i64() || number.is_u64(),
+        _ => false,
+    }
+}
+
+/// Exact decimal view of plain numbers and tagged `Decimal` and `int` values.
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match (as_tagged(value), value) {
+        (Some(("Decimal" | "int", Value::String(text))), _) => Decimal::parse(text),
+        (None, Value::Number(number)) => Decimal::parse(&number.to_string()),
+        _ => None,
+    }
+}
+
 fn round_significant(value: f64, digits: u32) -> f64 {
     if value == 0.0 {
         return 0.0;
@@ -253,6 +2219,20 @@ fn round_significant(value: f64, digits: u32) -> f64 {
 }
 
 fn types_compatible(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
+    let tag1 = as_tagged(t1).map(|(type_name, _)| type_name);
+    let tag2 = as_tagged(t2).map(|(type_name, _)| type_name);
+    if tag1.is_some() || tag2.is_some() {
+        return (tag1 == tag2 && enum_class(t1) == enum_class(t2))
+            || (is_plain_number(t1) && is_plain_number(t2))
+            || (options.ignore_numeric_type_changes && is_numeric(t1) && is_numeric(t2))
+            || (options.ignore_string_type_changes
+                && string_text(t1).is_some()
+                && string_text(t2).is_some())
+            || (options.ignore_uuid_types && uuid_text(t1).is_some() && uuid_text(t2).is_some())
+            || (sequence_items(t1).is_some()
+                && sequence_items(t2).is_some()
+                && in_same_type_group(value_type(t1), value_type(t2), options));
+    }
     if matches!(
         (t1, t2),
         (Value::Number(_), Value::Number(_))
@@ -267,16 +2247,31 @@ fn types_compatible(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
     }
     let vt1 = value_type(t1);
     let vt2 = value_type(t2);
-    if vt1 == vt2 {
-        return true;
-    }
+    vt1 == vt2 || in_same_type_group(vt1, vt2, options)
+}
+
+fn in_same_type_group(vt1: ValueType, vt2: ValueType, options: &DeepDiffOptions) -> bool {
     options
         .ignore_type_in_groups
         .iter()
         .any(|group| group.contains(&vt1) && group.contains(&vt2))
 }
 
+/// The `module.qualname` of an `Enum` member's class; two classes sharing a
+/// name in different modules are different types.
+fn enum_class(value: &Value) -> Option<&str> {
+    as_enum_member(value).map(|(class, _, _)| class)
+}
+
 fn value_type(value: &Value) -> ValueType {
+    if let Some((type_name, inner)) = as_tagged(value) {
+        return match type_name {
+            "Decimal" | "int" | "float" => ValueType::Number,
+            "bytes" => ValueType::String,
+            "tuple" => ValueType::Tuple,
+            _ => value_type(inner),
+        };
+    }
     match value {
         Value::Number(_) => ValueType::Number,
         Value::String(_) => ValueType::String,
@@ -287,9 +2282,18 @@ fn value_type(value: &Value) -> ValueType {
     }
 }
 
-fn type_name(value: &Value) -> &'static str {
+fn type_name(value: &Value) -> &str {
+    if let Some((_, qualname, _)) = as_enum_member(value) {
+        return qualname.rsplit('.').next().unwrap_or(qualname);
+    }
+    if let Some((type_name, _)) = as_tagged(value) {
+        return type_name;
+    }
+    if let Some((class_name, _)) = as_object(value) {
+        return class_name;
+    }
     match value {
-        Value::Null => "null",
+        Value::Null => "NoneType",
         Value::Bool(_) => "bool",
         Value::Number(n) => {
             if n.is_i64() || n.is_u64() {
@@ -304,19 +2308,6 @@ fn type_name(value: &Value) -> &'static str {
     }
 }
 
-fn type_change_value(t1: &Value, t2: &Value) -> Vec<(&'static str, Value)> {
-    vec![
-        ("old_type", Value::String(type_name(t1).to_string())),
-        ("new_type", Value::String(type_name(t2).to_string())),
-        ("old_value", t1.clone()),
-        ("new_value", t2.clone()),
-    ]
-}
-
-fn old_new_value(t1: &Value, t2: &Value) -> Vec<(&'static str, Value)> {
-    vec![("old_value", t1.clone()), ("new_value", t2.clone())]
-}
-
 fn json_obj(entries: Vec<(&'static str, Value)>) -> Value {
     Value::Object(
         entries
@@ -326,7 +2317,17 @@ fn json_obj(entries: Vec<(&'static str, Value)>) -> Value {
     )
 }
 
+#[cfg(feature = "python")]
 pub(crate) fn canonical_string(value: &Value) -> String {
+    if let Some(decimal) = as_tagged(value)
+        .filter(|(type_name, _)| *type_name == "Decimal")
+        .and_then(|_| as_decimal(value))
+    {
+        return format!("decimal:{}", decimal.canonical());
+    }
+    if let Some(("int", Value::String(digits))) = as_tagged(value) {
+        return format!("num:{}", digits);
+    }
     match value {
         Value::Null => "null".to_string(),
         Value::Bool(b) => format!("bool:{}", b),
@@ -351,12 +2352,259 @@ pub(crate) fn canonical_string(value: &Value) -> String {
     }
 }
 
-fn path_allowed(path: &str, options: &DeepDiffOptions) -> bool {
+/// Whether the node is left out by its values: either side is of a type in
+/// `exclude_types` or matches the non-strict callback, or both sides are
+/// present and match the strict callback.
+fn excluded_by_value(
+    t1: Option<&Value>,
+    t2: Option<&Value>,
+    path: &NodePath,
+    options: &DeepDiffOptions,
+) -> bool {
+    if !options.exclude_types.is_empty()
+        && t1
+            .into_iter()
+            .chain(t2)
+            .any(|value| options.exclude_types.contains(&value_type(value)))
+    {
+        return true;
+    }
+    if options.exclude_obj_callback.is_none() && options.exclude_obj_callback_strict.is_none() {
+        return false;
+    }
+    let path = &path.render();
+    if let Some(callback) = &options.exclude_obj_callback {
+        if t1
+            .into_iter()
+            .chain(t2)
+            .any(|value| (callback.0)(value, path))
+        {
+            return true;
+        }
+    }
+    if let Some(callback) = &options.exclude_obj_callback_strict {
+        if let (Some(v1), Some(v2)) = (t1, t2) {
+            if (callback.0)(v1, path) && (callback.0)(v2, path) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Whether the schema leaves the node at `path` out of the comparison: its
+/// key is deprecated or undeclared, or its numbers differ by no more than
+/// the tolerance for their `format`.
+fn schema_skips(t1: &Value, t2: &Value, path: &NodePath, options: &DeepDiffOptions) -> bool {
+    let Some(root) = &options.schema else {
+        return false;
+    };
+    if schema_ignores_key(path, options) {
+        return true;
+    }
+    let (Some(a), Some(b)) = (t1.as_f64(), t2.as_f64()) else {
+        return false;
+    };
+    schema::subschema(root, path.steps())
+        .and_then(|schema| schema::format_tolerance(schema, options))
+        .is_some_and(|tolerance| (a - b).abs() <= tolerance)
+}
+
+/// Whether `path` ends in a dict key the schema leaves out of the comparison.
+fn schema_ignores_key(path: &NodePath, options: &DeepDiffOptions) -> bool {
+    let Some(root) = &options.schema else {
+        return false;
+    };
+    let Some((Step::Key(key), parent)) = path.steps().split_last() else {
+        return false;
+    };
+    schema::subschema(root, parent).is_some_and(|schema| schema::ignores_key(root, schema, key))
+}
+
+/// Reports the values under `path` whose type `schema` does not allow, on
+/// either side, whether or not they changed.
+fn report_schema_violations<'a>(
+    t1: Option<&'a Value>,
+    t2: Option<&'a Value>,
+    root: &Value,
+    schema: &Value,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    if acc.stopped || !path_allowed(path, options) {
+        return;
+    }
+    let violation1 = t1.and_then(|value| schema::type_violation(schema, value));
+    let violation2 = t2.and_then(|value| schema::type_violation(schema, value));
+    if let Some(expected) = violation1.clone().or(violation2.clone()) {
+        acc.report(
+            Change::new(ChangeKind::SchemaViolation, path.render())
+                .expected_type(expected)
+                .values(
+                    t1.filter(|_| violation1.is_some()),
+                    t2.filter(|_| violation2.is_some()),
+                ),
+        );
+    }
+    let maps = (
+        t1.filter(|value| !is_tagged(value))
+            .and_then(Value::as_object),
+        t2.filter(|value| !is_tagged(value))
+            .and_then(Value::as_object),
+    );
+    if maps.0.is_some() || maps.1.is_some() {
+        let keys1 = maps.0.into_iter().flat_map(|map| map.keys());
+        let keys2 = maps
+            .1
+            .into_iter()
+            .flat_map(|map| map.keys())
+            .filter(|key| !maps.0.is_some_and(|map| map.contains_key(*key)));
+        for key in keys1.chain(keys2) {
+            if schema::ignores_key(root, schema, key) {
+                continue;
+            }
+            let step = Step::Key(key);
+            if let Some(child) = schema::child(root, schema, &step) {
+                path.push(step);
+                let (child1, child2) = (
+                    maps.0.and_then(|map| map.get(key)),
+                    maps.1.and_then(|map| map.get(key)),
+                );
+                report_schema_violations(child1, child2, root, child, path, options, acc);
+                path.pop();
+            }
+        }
+        return;
+    }
+    let lists = (
+        t1.and_then(|value| value.as_array()),
+        t2.and_then(|value| value.as_array()),
+    );
+    let len = lists.0.map_or(0, Vec::len).max(lists.1.map_or(0, Vec::len));
+    for idx in 0..len {
+        let step = Step::Index(idx);
+        if let Some(child) = schema::child(root, schema, &step) {
+            path.push(step);
+            let (child1, child2) = (
+                lists.0.and_then(|list| list.get(idx)),
+                lists.1.and_then(|list| list.get(idx)),
+            );
+            report_schema_violations(child1, child2, root, child, path, options, acc);
+            path.pop();
+        }
+    }
+}
+
+/// Whether `path` passes through a dict key or attribute named in
+/// `include_keys`, below which everything is compared.
+fn within_included(path: &NodePath, options: &DeepDiffOptions) -> bool {
+    path.steps().iter().any(|step| match step {
+        Step::Key(key) | Step::Attr(key) => options.include_keys.contains(*key),
+        _ => false,
+    })
+}
+
+/// Walks `t1` and `t2` outside the keys named in `include_keys`, looking for
+/// them: dict entries and attributes present on both sides are followed,
+/// lists by position, and only named keys added or removed are reported on
+/// the way.
+fn diff_toward_included<'a>(
+    t1: &'a Value,
+    t2: &'a Value,
+    path: &mut NodePath<'a>,
+    options: &DeepDiffOptions,
+    acc: &mut DiffAccumulator,
+) {
+    let (map1, map2, attributes) = match ((as_object(t1), as_object(t2)), (t1, t2)) {
+        ((Some((class1, attrs1)), Some((class2, attrs2))), _) if class1 == class2 => {
+            (attrs1, attrs2, true)
+        }
+        ((None, None), (Value::Object(map1), Value::Object(map2)))
+            if !is_tagged(t1) && !is_tagged(t2) =>
+        {
+            (map1, map2, false)
+        }
+        _ => {
+            if let (Some(list1), Some(list2)) = (sequence_items(t1), sequence_items(t2)) {
+                for (idx, (item1, item2)) in list1.iter().zip(list2).enumerate() {
+                    path.push(Step::Index(idx));
+                    diff_values(item1, item2, path, options, acc);
+                    path.pop();
+                }
+            }
+            return;
+        }
+    };
+    let step = |key| {
+        if attributes {
+            Step::Attr(key)
+        } else {
+            Step::Key(key)
+        }
+    };
+    let (removed, added) = if attributes {
+        (ChangeKind::AttributeRemoved, ChangeKind::AttributeAdded)
+    } else {
+        (
+            ChangeKind::DictionaryItemRemoved,
+            ChangeKind::DictionaryItemAdded,
+        )
+    };
+    for (key, value1) in map1.iter().filter(|(key, _)| !ignored_key(key, options)) {
+        path.push(step(key));
+        match map2.get(key) {
+            Some(value2) => diff_values(value1, value2, path, options, acc),
+            None if options.include_keys.contains(key)
+                && !excluded_by_value(Some(value1), None, path, options) =>
+            {
+                acc.report(Change::new(removed, path.render()).values(Some(value1), None));
+            }
+            None => {}
+        }
+        path.pop();
+    }
+    for (key, value2) in map2 {
+        if !map1.contains_key(key)
+            && options.include_keys.contains(key)
+            && !ignored_key(key, options)
+        {
+            path.push(step(key));
+            if !excluded_by_value(None, Some(value2), path, options) {
+                acc.report(Change::new(added, path.render()).values(None, Some(value2)));
+            }
+            path.pop();
+        }
+    }
+}
+
+/// Whether `key` is a dict key or attribute name listed in `ignore_keys`.
+fn ignored_key(key: &str, options: &DeepDiffOptions) -> bool {
+    !options.ignore_keys.is_empty() && options.ignore_keys.contains(key)
+}
+
+fn path_allowed(path: &NodePath, options: &DeepDiffOptions) -> bool {
+    if options.exclude_paths.is_empty()
+        && options.exclude_regex_paths.is_empty()
+        && options.ignored_patterns.is_empty()
+        && options.include_paths.is_empty()
+    {
+        return true;
+    }
+    let path = &path.render();
     for exclude in &options.exclude_paths {
         if path == exclude || path.starts_with(exclude) {
             return false;
         }
     }
+    if options
+        .exclude_regex_paths
+        .iter()
+        .chain(&options.ignored_patterns)
+        .any(|pattern| pattern.is_match(path))
+    {
+        return false;
+    }
     if options.include_paths.is_empty() {
         return true;
     }
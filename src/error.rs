@@ -0,0 +1,54 @@
+use crate::delta::DeltaError;
+use crate::options::OptionsError;
+use std::error::Error;
+use std::fmt;
+
+/// Any error turbodiff reports, for callers that handle them in one place.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TurboDiffError {
+    /// Invalid or conflicting options, from [`crate::DeepDiffOptions::build`]
+    /// and the `try_` constructors.
+    Options(OptionsError),
+    /// A delta could not be built, read, or applied.
+    Delta(DeltaError),
+    /// The text is not a path like `root['key'][0].attr`.
+    InvalidPath(String),
+    /// A severity rule that does not read as `<pattern> => <severity>`.
+    InvalidRule(String),
+    /// A checkpoint that does not resume the inputs and options given.
+    InvalidCheckpoint(String),
+}
+
+impl fmt::Display for TurboDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Options(err) => write!(f, "invalid options: {}", err),
+            Self::Delta(err) => err.fmt(f),
+            Self::InvalidPath(path) => write!(f, "invalid path: '{}'", path),
+            Self::InvalidRule(rule) => write!(f, "invalid rule: '{}'", rule),
+            Self::InvalidCheckpoint(reason) => write!(f, "invalid checkpoint: {}", reason),
+        }
+    }
+}
+
+impl Error for TurboDiffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Options(err) => Some(err),
+            Self::Delta(err) => Some(err),
+            Self::InvalidPath(_) | Self::InvalidRule(_) | Self::InvalidCheckpoint(_) => None,
+        }
+    }
+}
+
+impl From<OptionsError> for TurboDiffError {
+    fn from(err: OptionsError) -> Self {
+        Self::Options(err)
+    }
+}
+
+impl From<DeltaError> for TurboDiffError {
+    fn from(err: DeltaError) -> Self {
+        Self::Delta(err)
+    }
+}
@@ -0,0 +1,264 @@
+//! C API for embedding turbodiff in other languages: diff two JSON documents
+//! and get the result dict back as JSON. `include/turbodiff.h` declares these
+//! functions; regenerate it with
+//! `cbindgen --config cbindgen.toml --output include/turbodiff.h`.
+
+use crate::{diff_result, DeepDiffOptions, OptionsError, Rules, Sampling};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Diffs the JSON documents `t1` and `t2`, both NUL-terminated UTF-8.
+///
+/// `options` is NULL or a JSON object of the Python keyword options that
+/// apply to JSON input, such as `{"ignore_order": true}`. Returns the result
+/// dict as JSON, to be released with `turbodiff_string_free`, or NULL on
+/// error, with the message available from `turbodiff_last_error`. A panic
+/// inside the diff is caught and reported the same way.
+///
+/// # Safety
+///
+/// `t1` and `t2` must be valid NUL-terminated strings; `options` must be one
+/// too, or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn turbodiff_diff_json(
+    t1: *const c_char,
+    t2: *const c_char,
+    options: *const c_char,
+) -> *mut c_char {
+    // A panic must not unwind across the C boundary; report it as an error.
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let t1 = parse_document(t1, "t1")?;
+        let t2 = parse_document(t2, "t2")?;
+        let options = if options.is_null() {
+            DeepDiffOptions::default()
+        } else {
+            options_from_json(&parse_document(options, "options")?)?
+        };
+        let result = diff_result(&t1, &t2, &options);
+        CString::new(result.to_string()).map_err(|err| err.to_string())
+    }))
+    .unwrap_or_else(|payload| Err(panic_message(payload.as_ref())));
+    match outcome {
+        Ok(result) => {
+            set_last_error(None);
+            result.into_raw()
+        }
+        Err(message) => {
+            set_last_error(Some(message));
+            ptr::null_mut()
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    let detail = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("turbodiff panicked: {}", detail)
+}
+
+/// Releases a string returned by `turbodiff_diff_json`. NULL is ignored.
+///
+/// # Safety
+///
+/// `value` must come from `turbodiff_diff_json` and not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn turbodiff_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// The message of the last failed call on this thread, or NULL. The string
+/// stays valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn turbodiff_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+fn set_last_error(message: Option<String>) {
+    let message =
+        message.map(|message| CString::new(message.replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = message);
+}
+
+unsafe fn parse_document(text: *const c_char, name: &str) -> Result<Value, String> {
+    if text.is_null() {
+        return Err(format!("{} is NULL", name));
+    }
+    let text = CStr::from_ptr(text)
+        .to_str()
+        .map_err(|err| format!("{} is not UTF-8: {}", name, err))?;
+    serde_json::from_str(text).map_err(|err| format!("{} is not valid JSON: {}", name, err))
+}
+
+fn options_from_json(value: &Value) -> Result<DeepDiffOptions, String> {
+    let Value::Object(map) = value else {
+        return Err("options must be a JSON object".to_string());
+    };
+    let mut options = DeepDiffOptions::default();
+    for (key, value) in map {
+        let invalid = || format!("invalid value for option '{}': {}", key, value);
+        let flag = || value.as_bool().ok_or_else(invalid);
+        let number = || match value {
+            Value::Null => Ok(None),
+            _ => value.as_f64().map(Some).ok_or_else(invalid),
+        };
+        let count = || match value {
+            Value::Null => Ok(None),
+            _ => value
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .map(Some)
+                .ok_or_else(invalid),
+        };
+        let strings = || match value {
+            Value::String(text) => Ok(vec![text.clone()]),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+                .collect(),
+            _ => Err(invalid()),
+        };
+        options = match key.as_str() {
+            "ignore_order" => options.ignore_order(flag()?),
+            "ignore_numeric_type_changes" => options.ignore_numeric_type_changes(flag()?),
+            "ignore_string_type_changes" => options.ignore_string_type_changes(flag()?),
+            "ignore_uuid_types" => options.ignore_uuid_types(flag()?),
+            "ignore_nan_inequality" => options.ignore_nan_inequality(flag()?),
+            "significant_digits" => options.significant_digits(
+                count()?
+                    .map(|digits| u32::try_from(digits).map_err(|_| invalid()))
+                    .transpose()?,
+            ),
+            "number_format_notation" => match value {
+                Value::Null => options.number_format_notation(None),
+                Value::String(notation) => options.number_format_notation(Some(
+                    notation
+                        .parse()
+                        .map_err(|err: OptionsError| err.to_string())?,
+                )),
+                _ => return Err(invalid()),
+            },
+            "math_epsilon" => options.math_epsilon(number()?),
+            "use_log_scale" => options.use_log_scale(flag()?),
+            "log_scale_similarity_threshold" => {
+                options.log_scale_similarity_threshold(value.as_f64().ok_or_else(invalid)?)
+            }
+            "atol" => options.atol(number()?),
+            "rtol" => options.rtol(number()?),
+            "string_similarity" => options.string_similarity(number()?),
+            "cutoff_distance_for_pairs" => options.cutoff_distance_for_pairs(number()?),
+            "cutoff_intersection_for_pairs" => options.cutoff_intersection_for_pairs(number()?),
+            "max_passes" => options.max_passes(count()?),
+            "threshold_to_diff_deeper" => options.threshold_to_diff_deeper(number()?),
+            "report_repetition" => options.report_repetition(flag()?),
+            "align_lists" => options.align_lists(flag()?),
+            "include_paths" => options.include_paths(strings()?),
+            "exclude_paths" => options.exclude_paths(strings()?),
+            "ignore_keys" => options.ignore_keys(strings()?),
+            "include_keys" => options.include_keys(strings()?),
+            "exclude_regex_paths" => options.exclude_regex_paths(strings()?),
+            "schema" => options.schema(Some(value.clone()).filter(|schema| !schema.is_null())),
+            "format_tolerances" => {
+                let Value::Object(tolerances) = value else {
+                    return Err(invalid());
+                };
+                for (format, tolerance) in tolerances {
+                    options = options
+                        .format_tolerance(format.clone(), tolerance.as_f64().ok_or_else(invalid)?);
+                }
+                options
+            }
+            "rules" => {
+                options.rules(Rules::from_value(value.clone()).map_err(|err| err.to_string())?)
+            }
+            "sampling" => match value {
+                Value::Null => options.sampling(None),
+                Value::Number(rate) => {
+                    options.sampling(Some(Sampling::new(rate.as_f64().ok_or_else(invalid)?)))
+                }
+                _ => options.sampling(Some(
+                    serde_json::from_value(value.clone()).map_err(|err| err.to_string())?,
+                )),
+            },
+            "match_by" => match value {
+                Value::Null => options.match_by(None),
+                Value::String(key) => options.match_by(Some(key.clone())),
+                _ => return Err(invalid()),
+            },
+            "truncate_datetime" => match value {
+                Value::Null => options.truncate_datetime(None),
+                Value::String(unit) => options.truncate_datetime(Some(
+                    unit.parse().map_err(|err: OptionsError| err.to_string())?,
+                )),
+                _ => return Err(invalid()),
+            },
+            "group_by" => match value {
+                Value::Null => options.group_by(None),
+                Value::String(field) => options.group_by(Some(field.clone())),
+                _ => return Err(invalid()),
+            },
+            "redact_paths" => options.redact_paths(strings()?),
+            "redact_keys" => options.redact_keys(strings()?),
+            "redact_values" => options.redact_values(strings()?),
+            "exclude_types" => options.exclude_types(
+                strings()?
+                    .iter()
+                    .map(|name| name.parse())
+                    .collect::<Result<_, OptionsError>>()
+                    .map_err(|err| err.to_string())?,
+            ),
+            "verbose_level" => {
+                let level = value.as_u64().and_then(|n| u8::try_from(n).ok());
+                options.verbose_level(level.ok_or_else(invalid)?)
+            }
+            "type_names" => options.type_names(
+                value
+                    .as_str()
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|err: OptionsError| err.to_string())?,
+            ),
+            "hasher" => options.hasher(
+                value
+                    .as_str()
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|err: OptionsError| err.to_string())?,
+            ),
+            "root_label" => options.root_label(value.as_str().ok_or_else(invalid)?),
+            "path_aliases" => {
+                let mut options = options;
+                for (pattern, label) in value.as_object().ok_or_else(invalid)? {
+                    options = options.path_alias(pattern, label.as_str().ok_or_else(invalid)?);
+                }
+                options
+            }
+            "max_report_bytes" => options.max_report_bytes(count()?),
+            "max_diffs" => options.max_diffs(count()?),
+            "get_deep_distance" => options.get_deep_distance(flag()?),
+            "large_string_threshold" => options.large_string_threshold(count()?),
+            "magnitude_scores" => options.magnitude_scores(flag()?),
+            "collapse_index_ranges" => options.collapse_index_ranges(count()?),
+            "sort_paths" => options.sort_paths(flag()?),
+            "num_threads" => options.num_threads(count()?),
+            _ => return Err(format!("unsupported option '{}'", key)),
+        };
+    }
+    options.validate().map_err(|err| err.to_string())?;
+    Ok(options)
+}
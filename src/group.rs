@@ -0,0 +1,57 @@
+//! Re-keying top-level lists of records by one of their fields, for
+//! `group_by`.
+
+use crate::options::DeepDiffOptions;
+use crate::tagged::{as_object, encode_key, is_tagged};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// `t1` and `t2` grouped by the `group_by` field, when both group; `None`
+/// otherwise, leaving both sides as they are.
+pub(crate) fn grouped(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> Option<(Value, Value)> {
+    let field = options.group_by.as_deref()?;
+    Some((group_records(t1, field)?, group_records(t2, field)?))
+}
+
+/// [`grouped`] for shared sides, handing back the sides unchanged when they
+/// do not group.
+pub(crate) fn grouped_sides(
+    t1: Arc<Value>,
+    t2: Arc<Value>,
+    options: &DeepDiffOptions,
+) -> (Arc<Value>, Arc<Value>) {
+    match grouped(&t1, &t2, options) {
+        Some((g1, g2)) => (Arc::new(g1), Arc::new(g2)),
+        None => (t1, t2),
+    }
+}
+
+/// A list of dicts as a dict of the same dicts keyed by their `field`, which
+/// each drops. Non-string field values are keys of their own type, as in
+/// `root[1]`. `None` unless every item is a dict holding `field` with a
+/// string, number, or bool no other item holds.
+pub(crate) fn group_records(value: &Value, field: &str) -> Option<Value> {
+    let Value::Array(items) = value else {
+        return None;
+    };
+    let mut groups = Map::with_capacity(items.len());
+    for item in items {
+        let Value::Object(record) = item else {
+            return None;
+        };
+        if is_tagged(item) || as_object(item).is_some() {
+            return None;
+        }
+        let key = match record.get(field)? {
+            Value::String(key) => key.clone(),
+            key @ (Value::Number(_) | Value::Bool(_)) => encode_key(key),
+            _ => return None,
+        };
+        let mut record = record.clone();
+        record.shift_remove(field);
+        if groups.insert(key, Value::Object(record)).is_some() {
+            return None;
+        }
+    }
+    Some(Value::Object(groups))
+}
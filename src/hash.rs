@@ -0,0 +1,428 @@
+//! Structural hashes of JSON subtrees, computed once per container and cached
+//! by address so the engine can tell differing subtrees apart without a deep
+//! comparison at every level.
+
+use crate::datetime::truncated_datetime;
+use crate::decimal::Decimal;
+use crate::digest::{Sha256, Xxh64};
+use crate::options::{DeepDiffOptions, OptionsError};
+use crate::tagged::as_tagged;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::str::FromStr;
+
+/// The hash function behind subtree hashes, the matching of `ignore_order`
+/// and `match_by` items, and [`deep_hash`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// The multiply-rotate hash rustc uses: the fastest, for hashes that
+    /// stay within one process.
+    #[default]
+    Fx,
+    /// XXH64: fast and well mixed, for large inputs with many similar
+    /// items.
+    XxHash64,
+    /// SHA-256: slow, but collision resistant, for hashes that are
+    /// persisted or compared across trust boundaries.
+    Sha256,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = OptionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "fx" => Ok(Self::Fx),
+            "xxhash" => Ok(Self::XxHash64),
+            "sha256" => Ok(Self::Sha256),
+            _ => Err(OptionsError::invalid(
+                "hasher",
+                format!("must be 'fx', 'xxhash', or 'sha256', got '{}'", text),
+            )),
+        }
+    }
+}
+
+/// Subtrees with fewer nodes than this are rehashed on demand instead of
+/// cached; for them a lookup costs about as much as the hash itself.
+const CACHED_SUBTREE_NODES: usize = 32;
+
+/// Hashes of the larger containers of the two values being diffed, keyed by
+/// address. Equal subtrees hash equally; differing hashes prove the subtrees
+/// differ.
+#[derive(Default)]
+pub(crate) struct SubtreeHashes {
+    cache: HashMap<usize, u64, BuildHasherDefault<FxHasher>>,
+    algorithm: HashAlgorithm,
+}
+
+impl SubtreeHashes {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            ..Self::default()
+        }
+    }
+
+    /// Forgets every cached hash but keeps the allocation, for reuse on
+    /// other values hashed with `algorithm`.
+    pub(crate) fn clear(&mut self, algorithm: HashAlgorithm) {
+        self.cache.clear();
+        self.algorithm = algorithm;
+    }
+
+    /// Hash of `value`, filling the cache for the large containers below it
+    /// on first use. The values must outlive the cache, which holds for a
+    /// single diff run.
+    pub(crate) fn get(&mut self, value: &Value) -> u64 {
+        match self.algorithm {
+            HashAlgorithm::Fx => self.hash::<FxHasher>(value).0,
+            HashAlgorithm::XxHash64 => self.hash::<Xxh64>(value).0,
+            HashAlgorithm::Sha256 => self.hash::<Sha256>(value).0,
+        }
+    }
+
+    /// Hash and node count of `value`.
+    fn hash<H: Hasher + Default>(&mut self, value: &Value) -> (u64, usize) {
+        let mut hasher = H::default();
+        let mut nodes = 1;
+        match value {
+            Value::Null => hasher.write_u8(0),
+            Value::Bool(b) => hasher.write_u8(1 + u8::from(*b)),
+            Value::Number(n) => {
+                hasher.write_u8(3);
+                match (n.as_u64(), n.as_i64()) {
+                    (Some(u), _) => hasher.write_u64(u),
+                    (_, Some(i)) => hasher.write_i64(i),
+                    // Adding zero folds -0.0 into 0.0, which `==` treats as equal.
+                    _ => hasher.write_u64((n.as_f64().unwrap_or(0.0) + 0.0).to_bits() ^ 1),
+                }
+            }
+            Value::String(s) => {
+                hasher.write_u8(4);
+                hasher.write(s.as_bytes());
+            }
+            Value::Array(_) | Value::Object(_) => {
+                let key = value as *const Value as usize;
+                if let Some(hash) = self.cache.get(&key) {
+                    return (*hash, CACHED_SUBTREE_NODES);
+                }
+                if let Value::Array(items) = value {
+                    hasher.write_u8(5);
+                    hasher.write_usize(items.len());
+                    for item in items {
+                        let (hash, count) = self.hash::<H>(item);
+                        hasher.write_u64(hash);
+                        nodes += count;
+                    }
+                } else if let Value::Object(map) = value {
+                    // Entries are summed so the hash does not depend on key
+                    // order, which map equality ignores.
+                    let mut entries = 0u64;
+                    for (name, item) in map {
+                        let (hash, count) = self.hash::<H>(item);
+                        let mut entry = H::default();
+                        entry.write(name.as_bytes());
+                        entry.write_u64(hash);
+                        entries = entries.wrapping_add(entry.finish());
+                        nodes += count;
+                    }
+                    hasher.write_u8(6);
+                    hasher.write_usize(map.len());
+                    hasher.write_u64(entries);
+                }
+                if nodes >= CACHED_SUBTREE_NODES {
+                    self.cache.insert(key, hasher.finish());
+                }
+            }
+        }
+        (hasher.finish(), nodes)
+    }
+}
+
+/// Hash of `value` under the equivalence `canonical_string` spells out: equal
+/// for values `ignore_order` treats as the same item, such as a `Decimal` and
+/// its canonical form, and built without formatting any text. Entries under
+/// the `ignore_keys` of `options` are left out.
+pub(crate) fn canonical_hash(value: &Value, options: &DeepDiffOptions) -> u64 {
+    match options.hasher {
+        HashAlgorithm::Fx => hash_canonical::<FxHasher>(value, options),
+        HashAlgorithm::XxHash64 => hash_canonical::<Xxh64>(value, options),
+        HashAlgorithm::Sha256 => hash_canonical::<Sha256>(value, options),
+    }
+}
+
+fn hash_canonical<H: Hasher + Default>(value: &Value, options: &DeepDiffOptions) -> u64 {
+    let mut hasher = H::default();
+    let truncated = |text: &str| {
+        let unit = options.truncate_datetime?;
+        truncated_datetime(text, unit.micros())
+    };
+    match as_tagged(value) {
+        Some(("Decimal", Value::String(text))) => {
+            if let Some(decimal) = Decimal::parse(text) {
+                hasher.write_u8(7);
+                hasher.write(decimal.canonical().as_bytes());
+                return hasher.finish();
+            }
+        }
+        // Tagged ints only carry integers beyond 64 bits, but any that fit
+        // must still match the plain number.
+        Some(("int", Value::String(digits))) => {
+            match digits.parse::<i128>() {
+                Ok(int) => write_i128(&mut hasher, int),
+                Err(_) => {
+                    hasher.write_u8(4);
+                    hasher.write(digits.as_bytes());
+                }
+            }
+            return hasher.finish();
+        }
+        Some(("datetime", Value::String(text))) => {
+            if let Some(instant) = truncated(text) {
+                hasher.write_u8(11);
+                hasher.write_i64(instant.micros);
+                hasher.write_u8(u8::from(instant.aware));
+                return hasher.finish();
+            }
+        }
+        _ => {}
+    }
+    match value {
+        Value::Null => hasher.write_u8(0),
+        Value::Bool(b) => hasher.write_u8(1 + u8::from(*b)),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => write_i128(&mut hasher, i128::from(u)),
+            (_, Some(i)) => write_i128(&mut hasher, i128::from(i)),
+            _ => {
+                hasher.write_u8(5);
+                hasher.write_u64(n.as_f64().unwrap_or(0.0).to_bits());
+            }
+        },
+        Value::String(s) => match truncated(s) {
+            Some(instant) => {
+                hasher.write_u8(10);
+                hasher.write_i64(instant.micros);
+                hasher.write_u8(u8::from(instant.aware));
+            }
+            None => {
+                hasher.write_u8(6);
+                hasher.write(s.as_bytes());
+            }
+        },
+        Value::Array(items) => {
+            hasher.write_u8(8);
+            hasher.write_usize(items.len());
+            for item in items {
+                hasher.write_u64(hash_canonical::<H>(item, options));
+            }
+        }
+        Value::Object(map) => {
+            let ignored = &options.ignore_keys;
+            let (mut entries, mut len) = (0u64, 0);
+            for (name, item) in map {
+                if !ignored.is_empty() && ignored.contains(name) {
+                    continue;
+                }
+                let mut entry = H::default();
+                entry.write(name.as_bytes());
+                entry.write_u64(hash_canonical::<H>(item, options));
+                entries = entries.wrapping_add(entry.finish());
+                len += 1;
+            }
+            hasher.write_u8(9);
+            hasher.write_usize(len);
+            hasher.write_u64(entries);
+        }
+    }
+    hasher.finish()
+}
+
+fn write_i128(hasher: &mut impl Hasher, value: i128) {
+    hasher.write_u8(3);
+    hasher.write_u64(value as u64);
+    hasher.write_u64((value >> 64) as u64);
+}
+
+/// A content hash of `value` as hex: 64 digits with
+/// [`HashAlgorithm::Sha256`], 16 otherwise. Equal for values that compare
+/// equal under `ignore_order` item matching, and built from a length-prefixed
+/// encoding of the whole value, so with SHA-256 it is as collision resistant
+/// as the digest.
+pub fn deep_hash(value: &Value, algorithm: HashAlgorithm) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes.iter().fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{:02x}", byte);
+            out
+        })
+    };
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::default();
+            write_canonical(&mut hasher, value);
+            hex(&hasher.digest())
+        }
+        HashAlgorithm::XxHash64 => {
+            let mut hasher = Xxh64::default();
+            write_canonical(&mut hasher, value);
+            format!("{:016x}", hasher.finish())
+        }
+        HashAlgorithm::Fx => {
+            let mut hasher = FxHasher::default();
+            write_canonical(&mut hasher, value);
+            format!("{:016x}", hasher.finish())
+        }
+    }
+}
+
+/// Writes `value` in full, in the terms of [`canonical_hash`] but without
+/// hashing subtrees on their own. Dict entries follow the map's sorted key
+/// order.
+fn write_canonical(hasher: &mut impl Hasher, value: &Value) {
+    match as_tagged(value) {
+        Some(("Decimal", Value::String(text))) => {
+            if let Some(decimal) = Decimal::parse(text) {
+                let canonical = decimal.canonical();
+                hasher.write_u8(7);
+                hasher.write_usize(canonical.len());
+                hasher.write(canonical.as_bytes());
+                return;
+            }
+        }
+        Some(("int", Value::String(digits))) => {
+            match digits.parse::<i128>() {
+                Ok(int) => write_i128(hasher, int),
+                Err(_) => {
+                    hasher.write_u8(4);
+                    hasher.write_usize(digits.len());
+                    hasher.write(digits.as_bytes());
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+    match value {
+        Value::Null => hasher.write_u8(0),
+        Value::Bool(b) => hasher.write_u8(1 + u8::from(*b)),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => write_i128(hasher, i128::from(u)),
+            (_, Some(i)) => write_i128(hasher, i128::from(i)),
+            _ => {
+                hasher.write_u8(5);
+                hasher.write_u64(n.as_f64().unwrap_or(0.0).to_bits());
+            }
+        },
+        Value::String(s) => {
+            hasher.write_u8(6);
+            hasher.write_usize(s.len());
+            hasher.write(s.as_bytes());
+        }
+        Value::Array(items) => {
+            hasher.write_u8(8);
+            hasher.write_usize(items.len());
+            for item in items {
+                write_canonical(hasher, item);
+            }
+        }
+        Value::Object(map) => {
+            hasher.write_u8(9);
+            hasher.write_usize(map.len());
+            // Maps keep document order; the digest must not depend on it.
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_unstable_by_key(|(name, _)| *name);
+            for (name, item) in entries {
+                hasher.write_usize(name.len());
+                hasher.write(name.as_bytes());
+                write_canonical(hasher, item);
+            }
+        }
+    }
+}
+
+/// Set of hashes that answers "definitely absent" without a table lookup,
+/// at one byte per hash and a few percent false positives.
+pub(crate) struct BloomFilter {
+    words: Vec<u64>,
+    mask: u64,
+}
+
+impl BloomFilter {
+    pub(crate) fn new<'h>(hashes: impl ExactSizeIterator<Item = &'h u64>) -> Self {
+        let bits = (hashes.len() * 8).next_power_of_two().max(64);
+        let mut filter = Self {
+            words: vec![0; bits / 64],
+            mask: bits as u64 - 1,
+        };
+        for hash in hashes {
+            for bit in filter.probes(*hash) {
+                filter.words[(bit / 64) as usize] |= 1 << (bit % 64);
+            }
+        }
+        filter
+    }
+
+    /// `false` only if `hash` was never added.
+    pub(crate) fn might_contain(&self, hash: u64) -> bool {
+        self.probes(hash)
+            .iter()
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Two bit positions taken from the high bits of remixed hashes, since
+    /// the low bits of an Fx hash are poorly mixed.
+    fn probes(&self, hash: u64) -> [u64; 2] {
+        let first = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let second = hash.wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        [(first >> 32) & self.mask, (second >> 32) & self.mask]
+    }
+}
+
+/// The multiply-rotate hash used by rustc: far cheaper than SipHash and good
+/// enough for telling subtrees apart, since equal hashes are always
+/// confirmed by a full comparison.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct FxHasher {
+    hash: u64,
+}
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl FxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+        let rest = chunks.remainder();
+        let mut word = [0u8; 8];
+        word[..rest.len()].copy_from_slice(rest);
+        self.add(u64::from_le_bytes(word) ^ ((rest.len() as u64) << 59));
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.add(u64::from(value));
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.add(value);
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
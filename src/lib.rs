@@ -1,19 +1,105 @@
+mod alias;
+mod align;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod change;
+mod checkpoint;
+mod chunks;
+mod collapse;
+mod compare;
+mod datetime;
+mod decimal;
+mod delta;
+mod digest;
 mod engine;
+mod error;
+mod group;
+mod hash;
+mod multi;
+mod numeric;
 mod options;
+mod path;
 mod pretty;
+mod redact;
+mod rediff;
+mod report;
+mod reverse;
+mod rules;
+mod sample;
+mod schema;
+mod severity;
+mod stats;
+mod tagged;
 
+#[cfg(feature = "ffi")]
+pub mod ffi;
 #[cfg(feature = "python")]
 mod python;
 
-pub use options::{DeepDiffOptions, PrettyOptions, ValueType};
+pub use change::{Change, ChangeKind, Repetition};
+pub use checkpoint::{Checkpoint, DiffJob};
+pub use compare::{ApproxEq, CustomOperator};
+pub use delta::{Delta, DeltaError};
+pub use error::TurboDiffError;
+pub use hash::{deep_hash, HashAlgorithm};
+pub use multi::{compare_many, Divergence, MultiDiff, ValueGroup};
+pub use options::{
+    DatetimeUnit, DeepDiffOptions, NumberFormat, OptionsError, PrettyOptions, Progress, TypeNames,
+    ValueType,
+};
+pub use path::{Path, PathSegment};
+pub use report::{
+    ChangedRange, DiffReport, ItemChange, MovedItem, RepetitionChange, SamplingReport,
+    SchemaViolation, StringSummary, TypeChange, ValueChange,
+};
+pub use rules::{Rule, Rules};
+pub use sample::Sampling;
+pub use severity::{ClassifiedChange, ClassifiedReport, Severity, SeverityRules};
+pub use stats::ChangeStats;
+pub use tagged::float_value;
 
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct DeepDiff {
     result: Value,
-    t1: Value,
-    t2: Value,
+    t1: Arc<Value>,
+    t2: Arc<Value>,
+    options: DeepDiffOptions,
+}
+
+/// The pretty output without colors: compact paths by default, the full tree
+/// with `{:#}`.
+impl fmt::Display for DeepDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = PrettyOptions {
+            compact: !f.alternate(),
+            no_color: true,
+            ..PrettyOptions::default()
+        };
+        f.write_str(&self.pretty(options))
+    }
+}
+
+/// Both directions of a diff: `forward` reports `t1` against `t2`, and
+/// `backward` the same changes seen from `t2`, where what `t1` gained is
+/// removed instead of added.
+#[derive(Clone, Debug)]
+pub struct SymmetricDiff {
+    pub forward: DeepDiff,
+    pub backward: DeepDiff,
+}
+
+/// One of the two inputs of a diff.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Side {
+    T1,
+    T2,
 }
 
 impl DeepDiff {
@@ -22,31 +108,492 @@ impl DeepDiff {
     }
 
     pub fn with_options(t1: Value, t2: Value, options: DeepDiffOptions) -> Self {
-        let mut acc = engine::DiffAccumulator::default();
-        engine::diff_values(&t1, &t2, "root", &options, &mut acc);
+        Self::with_shared(Arc::new(t1), Arc::new(t2), options)
+    }
+
+    /// Diffs values the caller also keeps, sharing them instead of requiring
+    /// owned copies; `pretty()` reads the values through the shared handles.
+    pub fn with_shared(t1: Arc<Value>, t2: Arc<Value>, options: DeepDiffOptions) -> Self {
+        let (t1, t2) = group::grouped_sides(t1, t2, &options);
         Self {
-            result: acc.into_value(options.verbose_level),
+            result: canonical_result(&t1, &t2, &options),
             t1,
             t2,
+            options,
+        }
+    }
+
+    /// Diffs `t1` against `t2` and `t2` against `t1` in one traversal: the
+    /// backward report is the [inverted](DeepDiff::invert) forward one.
+    pub fn symmetric(t1: Value, t2: Value, options: DeepDiffOptions) -> SymmetricDiff {
+        let forward = Self::with_options(t1, t2, options);
+        let backward = forward.invert();
+        SymmetricDiff { forward, backward }
+    }
+
+    /// Like [`DeepDiff::with_options`], but rejects invalid or conflicting
+    /// options instead of diffing with them.
+    pub fn try_with_options(
+        t1: Value,
+        t2: Value,
+        options: DeepDiffOptions,
+    ) -> Result<Self, TurboDiffError> {
+        Ok(Self::with_options(t1, t2, options.build()?))
+    }
+
+    /// Diffs every `(t1, t2)` pair with the same options, spreading the pairs
+    /// over `num_threads` threads that each reuse one [`DiffEngine`]. Results
+    /// come back in the order of `pairs`.
+    pub fn diff_many(pairs: Vec<(Value, Value)>, options: DeepDiffOptions) -> Vec<Self> {
+        let threads = options.threads().min(pairs.len()).max(1);
+        // Each thread already has a pair to itself; splitting a pair's lists
+        // further would only oversubscribe the cores.
+        let worker_options = if threads > 1 {
+            options.clone().num_threads(Some(1))
+        } else {
+            options.clone()
+        };
+        let chunk_len = pairs.len().div_ceil(threads).max(1);
+        let results: Vec<Value> = std::thread::scope(|scope| {
+            let handles: Vec<_> = pairs
+                .chunks(chunk_len)
+                .map(|chunk| {
+                    let options = worker_options.clone();
+                    scope.spawn(move || {
+                        let mut engine = DiffEngine::new(options);
+                        chunk
+                            .iter()
+                            .map(|(t1, t2)| engine.canonical_diff(t1, t2))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+        pairs
+            .into_iter()
+            .zip(results)
+            .map(|((t1, t2), result)| {
+                let (t1, t2) = group::grouped_sides(Arc::new(t1), Arc::new(t2), &options);
+                Self {
+                    result,
+                    t1,
+                    t2,
+                    options: options.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Replaces, or adds, the value at `path` in one side and updates the
+    /// result by re-diffing only the subtree around it. Sides shared through
+    /// [`DeepDiff::with_shared`] are copied before they are changed. `path`
+    /// is a [`Path`] or its text, starting with `root` or the root label.
+    pub fn update<P: ToString>(
+        &mut self,
+        side: Side,
+        path: P,
+        value: Value,
+    ) -> Result<(), DeltaError> {
+        let path = path::unlabel(&path.to_string(), &self.options.root_label).into_owned();
+        let target = match side {
+            Side::T1 => Arc::make_mut(&mut self.t1),
+            Side::T2 => Arc::make_mut(&mut self.t2),
+        };
+        delta::put_at(target, &path, value)?;
+        let changed = path::parse_path(&path).unwrap_or_default();
+        rediff::rediff(
+            &mut self.result,
+            &self.t1,
+            &self.t2,
+            &changed,
+            &self.options,
+        );
+        Ok(())
+    }
+
+    /// The diff of `t2` against `t1`, derived from this one without diffing
+    /// again: old and new values swap, additions become removals and back,
+    /// and changes inside lists matched by key move to the indices of their
+    /// items in `t2`.
+    pub fn invert(&self) -> Self {
+        Self {
+            result: reverse::reversed_result(&self.result, &self.t1, &self.t2, &self.options),
+            t1: Arc::clone(&self.t2),
+            t2: Arc::clone(&self.t1),
+            options: self.options.clone(),
         }
     }
 
     pub fn to_value(&self) -> Value {
-        self.result.clone()
+        let mut result = with_labels(self.result.clone(), &self.options);
+        if self.options.get_deep_distance {
+            if let Value::Object(map) = &mut result {
+                map.insert(
+                    "deep_distance".to_string(),
+                    Value::from(self.get_deep_distance()),
+                );
+            }
+        }
+        result
+    }
+
+    /// How far apart the two documents are, from 0 when equal to 1 when they
+    /// have nothing in common: the elements the changes touch over the
+    /// elements below the roots of both documents, like DeepDiff's
+    /// `deep_distance`. A changed value counts once per side, and an added or
+    /// removed one with every element in it.
+    pub fn get_deep_distance(&self) -> f64 {
+        stats::deep_distance(&self.result, &self.t1, &self.t2)
     }
 
     pub fn to_dict(&self) -> Value {
-        self.result.clone()
+        self.to_value()
+    }
+
+    /// The result as typed entries instead of a `serde_json::Value`.
+    pub fn report(&self) -> DiffReport {
+        DiffReport::from_value(&self.to_value())
+    }
+
+    /// Each change with the severity `rules` give it, and the overall verdict.
+    /// Rule patterns start with `root` whatever the root label.
+    pub fn classify(&self, rules: &SeverityRules) -> ClassifiedReport {
+        let mut report = rules.classify(&self.result);
+        for change in &mut report.changes {
+            change.path = self.options.display_path(&change.path);
+        }
+        report
+    }
+
+    /// Classifies the changes, old side against new, as backward compatible
+    /// or breaking with [`SeverityRules::api_compat`].
+    pub fn api_compat(&self) -> ClassifiedReport {
+        self.classify(&SeverityRules::api_compat())
+    }
+
+    /// Whether the report outgrew `max_report_bytes` and keeps paths only.
+    pub fn is_truncated(&self) -> bool {
+        self.result.get("report_truncated") == Some(&Value::Bool(true))
+    }
+
+    /// Whether the diff stopped at `max_diffs` changes with more left.
+    pub fn reached_max_diffs(&self) -> bool {
+        self.result.get("max_diffs_reached") == Some(&Value::Bool(true))
+    }
+
+    /// The sampling settings when only a sample of some containers was
+    /// diffed, so that an empty diff means "probably unchanged".
+    pub fn sampling(&self) -> Option<SamplingReport> {
+        serde_json::from_value(self.result.get("sampling")?.clone()).ok()
+    }
+
+    /// The fields the `annotate` callback attached to changes, by reported
+    /// path.
+    pub fn annotations(&self) -> BTreeMap<String, Map<String, Value>> {
+        let Some(Value::Object(annotations)) = self.result.get("annotations") else {
+            return BTreeMap::new();
+        };
+        annotations
+            .iter()
+            .filter_map(|(path, fields)| {
+                Some((self.options.display_path(path), fields.as_object()?.clone()))
+            })
+            .collect()
     }
 
     pub fn pretty(&self, options: PrettyOptions) -> String {
-        pretty::render_pretty(&self.result, &self.t1, &self.t2, options)
+        let (t1, t2) = self.shown_sides();
+        pretty::render_pretty(&self.result, &t1, &t2, &self.options, options)
+    }
+
+    /// The inputs as renderings of the result may show them, with the
+    /// values the options redact masked.
+    pub(crate) fn shown_sides(&self) -> (Cow<'_, Value>, Cow<'_, Value>) {
+        let redaction = &self.options.redaction;
+        if !redaction.is_active() {
+            return (Cow::Borrowed(&*self.t1), Cow::Borrowed(&*self.t2));
+        }
+        let shown = |side: &Value| {
+            let mut side = side.clone();
+            redaction.redact(&mut side, "root");
+            Cow::Owned(side)
+        };
+        (shown(&self.t1), shown(&self.t2))
+    }
+
+    /// Whether the inputs compare equal under the options.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of report keys with changes, like `len()` of the Python dict.
+    pub fn len(&self) -> usize {
+        self.entry_counts().count()
     }
 
-    #[cfg(feature = "python")]
-    pub(crate) fn is_empty(&self) -> bool {
-        matches!(&self.result, Value::Object(map) if map.is_empty())
+    /// Number of changes across all report keys.
+    pub fn change_count(&self) -> usize {
+        self.entry_counts().map(|(_, count)| count).sum()
+    }
+
+    /// Number of changes per kind, leaving out kinds without any.
+    pub fn count_by_kind(&self) -> HashMap<ChangeKind, usize> {
+        self.entry_counts().collect()
+    }
+
+    /// Every change of the result, by kind in [`ChangeKind::ALL`] order and
+    /// by path within a kind, built as it is reached.
+    pub fn changes(&self) -> impl Iterator<Item = Change<'_>> {
+        change::result_entries(&self.result).map(|entry| self.labeled_change(entry))
+    }
+
+    /// The changes from `offset` on, at most `limit` of them, in the order of
+    /// [`DeepDiff::changes`]: one page of a result too large to hand out at
+    /// once. Pages past the end are empty.
+    pub fn changes_page(&self, offset: usize, limit: usize) -> Vec<Change<'_>> {
+        change::result_entries(&self.result)
+            .skip(offset)
+            .take(limit)
+            .map(|entry| self.labeled_change(entry))
+            .collect()
+    }
+
+    fn labeled_change<'a>(
+        &self,
+        (kind, path, entry): (ChangeKind, &str, Option<&'a Value>),
+    ) -> Change<'a> {
+        Change::from_entry(kind, self.options.display_path(path), entry)
+    }
+
+    /// Change counts by path depth and by the first `prefix_depth` steps of
+    /// their paths; merge the stats of many diffs with [`ChangeStats::merge`].
+    pub fn change_stats(&self, prefix_depth: usize) -> ChangeStats {
+        ChangeStats::of(&self.result, prefix_depth, &self.options.root_label)
+    }
+
+    fn entry_counts(&self) -> impl Iterator<Item = (ChangeKind, usize)> + '_ {
+        self.result
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, entries)| {
+                let count = match entries {
+                    Value::Object(map) => map.len(),
+                    Value::Array(paths) => paths.len(),
+                    _ => 0,
+                };
+                Some((ChangeKind::from_key(key)?, count)).filter(|_| count > 0)
+            })
+    }
+}
+
+/// Diffs many pairs of documents with the same options, keeping the caches
+/// and buffers of one run for the next instead of allocating them per diff.
+pub struct DiffEngine {
+    options: DeepDiffOptions,
+    hashes: hash::SubtreeHashes,
+}
+
+impl DiffEngine {
+    pub fn new(options: DeepDiffOptions) -> Self {
+        Self {
+            options,
+            hashes: hash::SubtreeHashes::default(),
+        }
+    }
+
+    /// Like [`DiffEngine::new`], but rejects invalid or conflicting options.
+    pub fn try_new(options: DeepDiffOptions) -> Result<Self, TurboDiffError> {
+        Ok(Self::new(options.build()?))
+    }
+
+    pub fn options(&self) -> &DeepDiffOptions {
+        &self.options
+    }
+
+    /// The result dict of diffing `t1` against `t2`.
+    pub fn diff(&mut self, t1: &Value, t2: &Value) -> Value {
+        let result = self.canonical_diff(t1, t2);
+        with_labels(result, &self.options)
+    }
+
+    /// Like [`DiffEngine::diff`], with paths starting with `root`.
+    fn canonical_diff(&mut self, t1: &Value, t2: &Value) -> Value {
+        let hashes = std::mem::take(&mut self.hashes);
+        let mut acc = engine::DiffAccumulator::with_hashes(&self.options, hashes);
+        engine::diff_documents(t1, t2, &self.options, &mut acc);
+        self.hashes = acc.take_hashes();
+        acc.into_value()
+    }
+}
+
+/// Whether `t1` and `t2` compare equal under `options`, with every
+/// tolerance, exclusion, and `ignore_order` applied, stopping at the first
+/// difference instead of building a report.
+pub fn deep_equal(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> bool {
+    let mut acc = engine::DiffAccumulator::probe(options);
+    engine::diff_documents(t1, t2, options, &mut acc);
+    !acc.found_change()
+}
+
+/// Diffs `t1` against `t2`, handing each change to `on_change` as it is found
+/// instead of building the result dict; changes borrow their values from the
+/// inputs.
+pub fn for_each_change<F>(t1: &Value, t2: &Value, options: &DeepDiffOptions, mut on_change: F)
+where
+    F: FnMut(Change<'_>),
+{
+    let mut acc = engine::DiffAccumulator::streaming(options, &mut on_change);
+    engine::diff_documents(t1, t2, options, &mut acc);
+}
+
+/// Diffs `t1` against `t2`, writing each change to `writer` as one line of
+/// JSON (JSON Lines) as soon as it is found, so the report is never held in
+/// memory. Returns the number of changes written.
+///
+/// Writes are not buffered here; wrap files and sockets in a
+/// `std::io::BufWriter`. The first write error stops the output and is
+/// returned.
+pub fn write_changes<W: io::Write>(
+    t1: &Value,
+    t2: &Value,
+    options: &DeepDiffOptions,
+    mut writer: W,
+) -> io::Result<usize> {
+    let mut written = 0;
+    let mut failure = None;
+    for_each_change(t1, t2, options, |change| {
+        if failure.is_some() {
+            return;
+        }
+        let line = serde_json::to_writer(&mut writer, &change)
+            .map_err(io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"));
+        match line {
+            Ok(()) => written += 1,
+            Err(err) => failure = Some(err),
+        }
+    });
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Asserts that two `serde_json::Value`s compare equal, optionally under
+/// [`DeepDiffOptions`], panicking with the pretty diff (no colors) instead of
+/// both values when they do not.
+///
+/// ```
+/// use serde_json::json;
+/// use turbodiff::{assert_deep_eq, DeepDiffOptions};
+///
+/// assert_deep_eq!(json!({"a": [1, 2]}), json!({"a": [1, 2]}));
+/// assert_deep_eq!(
+///     json!([1, 2]),
+///     json!([2, 1]),
+///     DeepDiffOptions::default().ignore_order(true)
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_deep_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_deep_eq!($left, $right, $crate::DeepDiffOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        if let Some(diff) = $crate::deep_eq_failure(&$left, &$right, $options) {
+            panic!("assertion `left deep-equals right` failed\n{}", diff);
+        }
+    };
+    ($left:expr, $right:expr, $options:expr, $($arg:tt)+) => {
+        if let Some(diff) = $crate::deep_eq_failure(&$left, &$right, $options) {
+            panic!(
+                "assertion `left deep-equals right` failed: {}\n{}",
+                format_args!($($arg)+),
+                diff
+            );
+        }
+    };
+}
+
+/// The pretty diff of `left` against `right` unless they compare equal;
+/// used by [`assert_deep_eq!`].
+#[doc(hidden)]
+pub fn deep_eq_failure(left: &Value, right: &Value, options: DeepDiffOptions) -> Option<String> {
+    let diff = DeepDiff::with_options(left.clone(), right.clone(), options);
+    (!diff.is_empty() || diff.is_truncated() || diff.reached_max_diffs())
+        .then(|| format!("{:#}", diff))
+}
+
+/// The result dict of diffing `t1` against `t2`, without keeping either side.
+pub(crate) fn diff_result(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> Value {
+    with_labels(canonical_result(t1, t2, options), options)
+}
+
+/// The result dict with paths starting with `root`, as kept by [`DeepDiff`]
+/// whatever the root label.
+pub(crate) fn canonical_result(t1: &Value, t2: &Value, options: &DeepDiffOptions) -> Value {
+    let mut acc = engine::DiffAccumulator::new(options);
+    engine::diff_documents(t1, t2, options, &mut acc);
+    acc.into_value()
+}
+
+/// `result` with its paths as the options report them: starting with the
+/// root label instead of `root`, or through a path alias, and with runs of
+/// list items collapsed into ranges.
+fn with_labels(result: Value, options: &DeepDiffOptions) -> Value {
+    let Value::Object(mut map) = result else {
+        return result;
+    };
+    if let Some(min_run) = options.collapse_index_ranges {
+        collapse::collapse_index_ranges(&mut map, min_run);
+        if options.sort_paths {
+            engine::sort_report(&mut map);
+        }
+    }
+    if options.root_label == "root" && options.path_aliases.is_empty() {
+        return Value::Object(map);
+    }
+    let relabel_entries = |entries| match entries {
+        Value::Object(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(path, mut entry)| {
+                    if let Some(Value::String(new_path)) = entry.get_mut("new_path") {
+                        *new_path = options.display_path(new_path);
+                    }
+                    (options.display_path(&path), entry)
+                })
+                .collect(),
+        ),
+        Value::Array(paths) => Value::Array(
+            paths
+                .into_iter()
+                .map(|path| match path {
+                    Value::String(path) => Value::String(options.display_path(&path)),
+                    other => other,
+                })
+                .collect(),
+        ),
+        other => other,
+    };
+    let mut map: Map<String, Value> = map
+        .into_iter()
+        .map(|(key, entries)| (key, relabel_entries(entries)))
+        .collect();
+    if options.sort_paths {
+        engine::sort_report(&mut map);
     }
+    Value::Object(map)
 }
 
 #[cfg(feature = "python")]
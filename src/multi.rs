@@ -0,0 +1,215 @@
+use crate::change::ChangeKind;
+use crate::group::group_records;
+use crate::options::DeepDiffOptions;
+use crate::path::{format_path, get_value_at_path, parse_path, PathSegment};
+use crate::rediff::document_position;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Where several documents diverge: each path at which they do not all
+/// agree, with the documents grouped by the value they hold there.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MultiDiff {
+    /// Number of documents compared.
+    pub documents: usize,
+    /// One entry per diverging path, in document order, or sorted under
+    /// [`DeepDiffOptions::sort_paths`].
+    pub divergences: Vec<Divergence>,
+}
+
+/// The documents at one diverging path.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Divergence {
+    pub path: String,
+    /// Groups of documents that agree at the path, in the order of their
+    /// first document; the group holding document 0 comes first.
+    pub groups: Vec<ValueGroup>,
+}
+
+/// Documents that agree at a path, by their position among the inputs.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValueGroup {
+    pub documents: Vec<usize>,
+    /// The value of the first of `documents`; `None` where they lack the
+    /// path.
+    pub value: Option<Value>,
+}
+
+impl MultiDiff {
+    /// Whether all the documents compare equal.
+    pub fn is_empty(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// The divergence at `path`, as reported.
+    pub fn get(&self, path: &str) -> Option<&Divergence> {
+        self.divergences.iter().find(|d| d.path == path)
+    }
+}
+
+impl Divergence {
+    /// The documents outside the largest group, the ones that disagree with
+    /// the majority; ties go to the group holding the earliest document.
+    pub fn outliers(&self) -> Vec<usize> {
+        let majority = self
+            .groups
+            .iter()
+            .enumerate()
+            .max_by(|(a_pos, a), (b_pos, b)| {
+                a.documents
+                    .len()
+                    .cmp(&b.documents.len())
+                    .then(b_pos.cmp(a_pos))
+            })
+            .map(|(pos, _)| pos);
+        let mut outliers: Vec<usize> = self
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(pos, _)| Some(*pos) != majority)
+            .flat_map(|(_, group)| group.documents.iter().copied())
+            .collect();
+        outliers.sort_unstable();
+        outliers
+    }
+}
+
+/// Compares all of `documents` at once: diffs each against the first, takes
+/// every path any of those diffs reports, and groups the documents by the
+/// value they hold there. Documents are diffed against the first of each
+/// group only, on first need, instead of pairwise. Two documents agree at a
+/// path when their diff under `options` reports nothing at, above, or below
+/// it.
+///
+/// Where `ignore_order`, `align_lists` or `match_by` pair list items across
+/// indices, item positions differ between documents, so changes inside a
+/// list are reported at the list. With `group_by`, documents are grouped
+/// only when all of them group.
+pub fn compare_many(documents: &[Value], options: &DeepDiffOptions) -> MultiDiff {
+    let grouped: Option<Vec<Value>> = options.group_by.as_deref().and_then(|field| {
+        documents
+            .iter()
+            .map(|document| group_records(document, field))
+            .collect()
+    });
+    let ungrouped;
+    let (documents, options) = match &grouped {
+        Some(grouped) => (grouped.as_slice(), options),
+        None if options.group_by.is_some() => {
+            ungrouped = options.clone().group_by(None);
+            (documents, &ungrouped)
+        }
+        None => (documents, options),
+    };
+    let mut diffs = PairDiffs {
+        documents,
+        options,
+        items_move: options.ignore_order || options.align_lists || options.match_by.is_some(),
+        paths: HashMap::new(),
+    };
+
+    // Each path with the first document reporting it, to place it by.
+    let mut paths: Vec<(Vec<PathSegment>, usize)> = Vec::new();
+    let mut seen = HashSet::new();
+    for other in 1..documents.len() {
+        for segments in diffs.changed_paths(0, other).to_vec() {
+            if seen.insert(segments.clone()) {
+                paths.push((segments, other));
+            }
+        }
+    }
+
+    let mut divergences: Vec<(Vec<usize>, Divergence)> = Vec::new();
+    for (segments, first) in paths {
+        let groups = diffs.group_at(&segments);
+        if groups.len() < 2 {
+            continue;
+        }
+        let path = format_path("root", &segments);
+        let position = document_position(&path, &documents[0], &documents[first]);
+        let divergence = Divergence {
+            path: options.display_path(&path),
+            groups,
+        };
+        divergences.push((position, divergence));
+    }
+    if options.sort_paths {
+        divergences.sort_by(|(_, a), (_, b)| a.path.cmp(&b.path));
+    } else {
+        divergences.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    MultiDiff {
+        documents: documents.len(),
+        divergences: divergences.into_iter().map(|(_, d)| d).collect(),
+    }
+}
+
+/// The paths reported between pairs of documents, diffed on first use.
+struct PairDiffs<'a> {
+    documents: &'a [Value],
+    options: &'a DeepDiffOptions,
+    items_move: bool,
+    paths: HashMap<(usize, usize), Vec<Vec<PathSegment>>>,
+}
+
+impl PairDiffs<'_> {
+    fn changed_paths(&mut self, t1: usize, t2: usize) -> &[Vec<PathSegment>] {
+        let (documents, options, items_move) = (self.documents, self.options, self.items_move);
+        self.paths.entry((t1, t2)).or_insert_with(|| {
+            let result = crate::canonical_result(&documents[t1], &documents[t2], options);
+            let mut paths = Vec::new();
+            for kind in ChangeKind::ALL {
+                let reported: Vec<&str> = match result.get(kind.as_str()) {
+                    Some(Value::Object(map)) => map.keys().map(String::as_str).collect(),
+                    Some(Value::Array(paths)) => paths.iter().filter_map(Value::as_str).collect(),
+                    _ => continue,
+                };
+                for path in reported {
+                    let Some(mut segments) = parse_path(path) else {
+                        continue;
+                    };
+                    if items_move {
+                        if let Some(idx) = segments
+                            .iter()
+                            .position(|segment| matches!(segment, PathSegment::Index(_)))
+                        {
+                            segments.truncate(idx);
+                        }
+                    }
+                    if !paths.contains(&segments) {
+                        paths.push(segments);
+                    }
+                }
+            }
+            paths
+        })
+    }
+
+    /// Whether documents `t1` and `t2` agree at `path`.
+    fn agree_at(&mut self, t1: usize, t2: usize, path: &[PathSegment]) -> bool {
+        !self
+            .changed_paths(t1, t2)
+            .iter()
+            .any(|changed| changed.starts_with(path) || path.starts_with(changed))
+    }
+
+    /// Groups the documents by their value at `path`, comparing each
+    /// document against the first of every group so far.
+    fn group_at(&mut self, path: &[PathSegment]) -> Vec<ValueGroup> {
+        let mut groups: Vec<ValueGroup> = Vec::new();
+        for doc in 0..self.documents.len() {
+            match groups
+                .iter()
+                .position(|group| self.agree_at(group.documents[0], doc, path))
+            {
+                Some(pos) => groups[pos].documents.push(doc),
+                None => groups.push(ValueGroup {
+                    documents: vec![doc],
+                    value: get_value_at_path(&self.documents[doc], path).cloned(),
+                }),
+            }
+        }
+        groups
+    }
+}
@@ -0,0 +1,85 @@
+//! Fast comparison of long arrays of plain numbers, compared lane by lane in
+//! fixed-size chunks the compiler turns into SIMD instructions, so only the
+//! indices that differ go through the general engine.
+
+use crate::options::DeepDiffOptions;
+use serde_json::Value;
+
+/// Number of elements compared per chunk; eight f64 lanes fill an AVX-512
+/// register or two AVX2 ones.
+const LANES: usize = 8;
+
+/// Arrays shorter than this are compared element by element, where
+/// extracting the numbers would cost more than it saves.
+const MIN_FAST_LEN: usize = 32;
+
+/// Indices of the equally long `list1` and `list2` whose numbers may differ,
+/// or `None` when the arrays are too short or not both all integers or all
+/// floats. Every index left out is equal under the options' tolerances.
+pub(crate) fn unequal_indices(
+    list1: &[Value],
+    list2: &[Value],
+    options: &DeepDiffOptions,
+) -> Option<Vec<usize>> {
+    if list1.len() < MIN_FAST_LEN
+        || list1.len() != list2.len()
+        || !options.comparators.is_empty()
+        || !options.operators.is_empty()
+        || !options.path_rules.is_empty()
+        || options.number_to_string.is_some()
+        || (options.number_format_notation.is_some() && options.significant_digits.is_some())
+    {
+        return None;
+    }
+    if let (Some(ints1), Some(ints2)) = (integers(list1), integers(list2)) {
+        return Some(unequal_lanes(&ints1, &ints2, |a, b| a == b));
+    }
+    let (floats1, floats2) = (floats(list1)?, floats(list2)?);
+    let mut atol = options.atol.or(options.math_epsilon).unwrap_or(0.0);
+    if options.ignore_numeric_type_changes {
+        atol = atol.max(f64::EPSILON);
+    }
+    let rtol = options.rtol.unwrap_or(0.0);
+    Some(unequal_lanes(&floats1, &floats2, |a, b| {
+        a == b || (a - b).abs() <= atol.max(rtol * a.abs().max(b.abs()))
+    }))
+}
+
+fn integers(list: &[Value]) -> Option<Vec<i64>> {
+    list.iter()
+        .map(|item| match item {
+            Value::Number(n) if !n.is_f64() => n.as_i64(),
+            _ => None,
+        })
+        .collect()
+}
+
+fn floats(list: &[Value]) -> Option<Vec<f64>> {
+    list.iter()
+        .map(|item| match item {
+            Value::Number(n) if n.is_f64() => n.as_f64(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks whole chunks without branching per element and only scans the
+/// chunks that contain a mismatch.
+fn unequal_lanes<T: Copy>(a: &[T], b: &[T], equal: impl Fn(T, T) -> bool) -> Vec<usize> {
+    let mut unequal = Vec::new();
+    for (chunk, (lanes1, lanes2)) in a.chunks(LANES).zip(b.chunks(LANES)).enumerate() {
+        let all_equal = lanes1
+            .iter()
+            .zip(lanes2)
+            .fold(true, |acc, (x, y)| acc & equal(*x, *y));
+        if all_equal {
+            continue;
+        }
+        for (lane, (x, y)) in lanes1.iter().zip(lanes2).enumerate() {
+            if !equal(*x, *y) {
+                unequal.push(chunk * LANES + lane);
+            }
+        }
+    }
+    unequal
+}
@@ -1,32 +1,149 @@
+use crate::alias::{self, PathAlias};
+use crate::change::Change;
+use crate::compare::{ApproxEq, CustomOperator};
+use crate::error::TurboDiffError;
+use crate::hash::HashAlgorithm;
+use crate::path::{is_well_formed, with_expanded_indices};
+use crate::redact::Redaction;
+use crate::rules::{self, PathRule, Rules};
+use crate::sample::Sampling;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 #[derive(Clone, Debug)]
 pub struct DeepDiffOptions {
     pub(crate) ignore_order: bool,
+    pub(crate) string_similarity: Option<f64>,
+    pub(crate) cutoff_distance_for_pairs: Option<f64>,
+    pub(crate) cutoff_intersection_for_pairs: Option<f64>,
+    pub(crate) max_passes: Option<usize>,
+    pub(crate) threshold_to_diff_deeper: Option<f64>,
+    pub(crate) report_repetition: bool,
+    pub(crate) align_lists: bool,
     pub(crate) ignore_numeric_type_changes: bool,
     pub(crate) ignore_string_type_changes: bool,
+    pub(crate) ignore_uuid_types: bool,
+    pub(crate) ignore_nan_inequality: bool,
     pub(crate) significant_digits: Option<u32>,
+    pub(crate) number_format_notation: Option<NumberFormat>,
+    pub(crate) number_to_string: Option<NumberFormatter>,
     pub(crate) math_epsilon: Option<f64>,
+    pub(crate) use_log_scale: bool,
+    pub(crate) log_scale_similarity_threshold: f64,
     pub(crate) atol: Option<f64>,
     pub(crate) rtol: Option<f64>,
+    pub(crate) datetime_epsilon: Option<f64>,
+    pub(crate) truncate_datetime: Option<DatetimeUnit>,
     pub(crate) include_paths: Vec<String>,
     pub(crate) exclude_paths: Vec<String>,
+    pub(crate) exclude_regex_paths: Vec<Regex>,
+    pub(crate) ignore_keys: HashSet<String>,
+    pub(crate) include_keys: HashSet<String>,
+    pub(crate) ignored_patterns: Vec<Regex>,
+    /// The first pattern given to a builder that did not compile, for
+    /// [`DeepDiffOptions::validate`] to report.
+    pub(crate) invalid_pattern: Option<OptionsError>,
     pub(crate) verbose_level: u8,
+    pub(crate) root_label: String,
+    pub(crate) path_aliases: Vec<PathAlias>,
+    pub(crate) type_names: TypeNames,
     pub(crate) ignore_type_in_groups: Vec<Vec<ValueType>>,
+    pub(crate) exclude_types: Vec<ValueType>,
+    pub(crate) exclude_obj_callback: Option<ObjCallback>,
+    pub(crate) exclude_obj_callback_strict: Option<ObjCallback>,
+    pub(crate) progress_logger: Option<ProgressLogger>,
+    pub(crate) log_frequency: Duration,
+    pub(crate) max_report_bytes: Option<usize>,
+    pub(crate) max_diffs: Option<usize>,
+    pub(crate) get_deep_distance: bool,
+    pub(crate) large_string_threshold: Option<usize>,
+    pub(crate) magnitude_scores: bool,
+    pub(crate) collapse_index_ranges: Option<usize>,
+    pub(crate) sort_paths: bool,
+    pub(crate) num_threads: Option<usize>,
+    pub(crate) visitor: Option<Visitor>,
+    pub(crate) annotator: Option<Annotator>,
+    pub(crate) comparators: Vec<Comparator>,
+    pub(crate) operators: Vec<Operator>,
+    pub(crate) redaction: Redaction,
+    pub(crate) schema: Option<Arc<Value>>,
+    pub(crate) format_tolerances: Vec<(String, f64)>,
+    pub(crate) match_by: Option<String>,
+    pub(crate) group_by: Option<String>,
+    pub(crate) path_rules: Vec<PathRule>,
+    pub(crate) sampling: Option<Sampling>,
+    pub(crate) hasher: HashAlgorithm,
 }
 
 impl Default for DeepDiffOptions {
     fn default() -> Self {
         Self {
             ignore_order: false,
+            string_similarity: None,
+            cutoff_distance_for_pairs: None,
+            cutoff_intersection_for_pairs: None,
+            max_passes: None,
+            threshold_to_diff_deeper: None,
+            report_repetition: false,
+            align_lists: false,
             ignore_numeric_type_changes: false,
             ignore_string_type_changes: false,
+            ignore_uuid_types: false,
+            ignore_nan_inequality: false,
             significant_digits: None,
+            number_format_notation: None,
+            number_to_string: None,
             math_epsilon: None,
+            use_log_scale: false,
+            log_scale_similarity_threshold: 0.1,
             atol: None,
             rtol: None,
+            datetime_epsilon: None,
+            truncate_datetime: None,
             include_paths: Vec::new(),
             exclude_paths: Vec::new(),
+            exclude_regex_paths: Vec::new(),
+            ignore_keys: HashSet::new(),
+            include_keys: HashSet::new(),
+            ignored_patterns: Vec::new(),
+            invalid_pattern: None,
             verbose_level: 1,
+            root_label: "root".to_string(),
+            path_aliases: Vec::new(),
+            type_names: TypeNames::Legacy,
             ignore_type_in_groups: Vec::new(),
+            exclude_types: Vec::new(),
+            exclude_obj_callback: None,
+            exclude_obj_callback_strict: None,
+            progress_logger: None,
+            log_frequency: Duration::ZERO,
+            max_report_bytes: None,
+            max_diffs: None,
+            get_deep_distance: false,
+            large_string_threshold: None,
+            magnitude_scores: false,
+            collapse_index_ranges: None,
+            sort_paths: false,
+            num_threads: None,
+            visitor: None,
+            annotator: None,
+            comparators: Vec::new(),
+            operators: Vec::new(),
+            redaction: Redaction::default(),
+            schema: None,
+            format_tolerances: Vec::new(),
+            match_by: None,
+            group_by: None,
+            path_rules: Vec::new(),
+            sampling: None,
+            hasher: HashAlgorithm::Fx,
         }
     }
 }
@@ -37,6 +154,67 @@ impl DeepDiffOptions {
         self
     }
 
+    /// Under `ignore_order`, pairs strings left unmatched on both sides when
+    /// they are at least this similar (1 minus their edit distance over the
+    /// longer length) and reports them as `values_changed`, closest first.
+    pub fn string_similarity(mut self, value: Option<f64>) -> Self {
+        self.string_similarity = value;
+        self
+    }
+
+    /// Under `ignore_order`, pairs dicts and objects left unmatched on both
+    /// sides when at most this share of their keys differ, missing from one
+    /// or holding another value, and reports the changes inside them at the
+    /// old index, closest first, like DeepDiff's `cutoff_distance_for_pairs`.
+    pub fn cutoff_distance_for_pairs(mut self, value: Option<f64>) -> Self {
+        self.cutoff_distance_for_pairs = value;
+        self
+    }
+
+    /// Skips the pairing of `string_similarity` and
+    /// `cutoff_distance_for_pairs` when more than this share of the items of
+    /// both lists is left unmatched, where the lists have little in common
+    /// and pairing would mostly compare unrelated items.
+    pub fn cutoff_intersection_for_pairs(mut self, value: Option<f64>) -> Self {
+        self.cutoff_intersection_for_pairs = value;
+        self
+    }
+
+    /// Caps the pairs of unmatched items compared when pairing similar ones
+    /// under `ignore_order`; past it, only the pairs found so far are used.
+    pub fn max_passes(mut self, value: Option<usize>) -> Self {
+        self.max_passes = value;
+        self
+    }
+
+    /// Reports two dicts that share less than this share of their keys as
+    /// one `values_changed` of the whole dict instead of diffing them key
+    /// by key, like DeepDiff's `threshold_to_diff_deeper`.
+    pub fn threshold_to_diff_deeper(mut self, value: Option<f64>) -> Self {
+        self.threshold_to_diff_deeper = value;
+        self
+    }
+
+    /// Under `ignore_order`, reports an item found a different number of
+    /// times on each side under `repetition_change`, with both counts and
+    /// the indices of its copies, instead of adding or removing the extra
+    /// copies.
+    pub fn report_repetition(mut self, value: bool) -> Self {
+        self.report_repetition = value;
+        self
+    }
+
+    /// Aligns ordered lists on their longest common run of equal items
+    /// before comparing them, so an inserted or removed item is reported as
+    /// such instead of as a change at every later index, and an item found
+    /// unchanged elsewhere is reported under `iterable_item_moved`. Items
+    /// left unmatched between two aligned ones are compared pairwise. Lists
+    /// too different to align cheaply are compared index by index.
+    pub fn align_lists(mut self, value: bool) -> Self {
+        self.align_lists = value;
+        self
+    }
+
     pub fn ignore_numeric_type_changes(mut self, value: bool) -> Self {
         self.ignore_numeric_type_changes = value;
         self
@@ -47,16 +225,65 @@ impl DeepDiffOptions {
         self
     }
 
+    /// Treat a UUID and its string form as the same type.
+    pub fn ignore_uuid_types(mut self, value: bool) -> Self {
+        self.ignore_uuid_types = value;
+        self
+    }
+
+    /// Treat NaN as equal to NaN, as DeepDiff's `ignore_nan_inequality`
+    /// does. NaN passes through [`float_value`](crate::float_value).
+    pub fn ignore_nan_inequality(mut self, value: bool) -> Self {
+        self.ignore_nan_inequality = value;
+        self
+    }
+
     pub fn significant_digits(mut self, value: Option<u32>) -> Self {
         self.significant_digits = value;
         self
     }
 
+    /// Compares numbers by their text in this notation with
+    /// `significant_digits` digits after the point, as DeepDiff's
+    /// `number_format_notation` does, instead of rounding them to that many
+    /// significant digits. Has no effect without `significant_digits`.
+    pub fn number_format_notation(mut self, notation: Option<NumberFormat>) -> Self {
+        self.number_format_notation = notation;
+        self
+    }
+
+    /// Compares numbers by the text `format` gives them, called with each
+    /// number, `significant_digits`, and the notation (fixed by default),
+    /// like DeepDiff's `number_to_string_func`. Numbers are equal when their
+    /// texts are, whatever the tolerances.
+    pub fn number_to_string_func<F>(mut self, format: F) -> Self
+    where
+        F: Fn(f64, Option<u32>, NumberFormat) -> String + Send + Sync + 'static,
+    {
+        self.number_to_string = Some(NumberFormatter(Arc::new(format)));
+        self
+    }
+
     pub fn math_epsilon(mut self, value: Option<f64>) -> Self {
         self.math_epsilon = value;
         self
     }
 
+    /// Treats numbers of the same sign as equal when their base-10
+    /// logarithms differ by at most `log_scale_similarity_threshold`, so
+    /// values spanning orders of magnitude are compared by ratio.
+    pub fn use_log_scale(mut self, value: bool) -> Self {
+        self.use_log_scale = value;
+        self
+    }
+
+    /// The largest difference of logarithms `use_log_scale` treats as equal;
+    /// 0.1 by default, about a 26% ratio.
+    pub fn log_scale_similarity_threshold(mut self, value: f64) -> Self {
+        self.log_scale_similarity_threshold = value;
+        self
+    }
+
     pub fn atol(mut self, value: Option<f64>) -> Self {
         self.atol = value;
         self
@@ -67,13 +294,186 @@ impl DeepDiffOptions {
         self
     }
 
-    pub fn include_paths(mut self, paths: Vec<String>) -> Self {
-        self.include_paths = paths;
+    /// Tolerance in seconds for datetime, date, time, and timedelta values.
+    pub fn datetime_epsilon(mut self, value: Option<f64>) -> Self {
+        self.datetime_epsilon = value;
+        self
+    }
+
+    /// Truncates datetimes, including ISO 8601 timestamp strings, to `unit`
+    /// before comparing them.
+    pub fn truncate_datetime(mut self, unit: Option<DatetimeUnit>) -> Self {
+        self.truncate_datetime = unit;
+        self
+    }
+
+    /// Takes [`Path`](crate::Path)s or their text; paths may use tuple
+    /// indices (`root[(2, 17)]`) for nested sequences.
+    pub fn include_paths<P: ToString>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.include_paths =
+            with_expanded_indices(paths.into_iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    /// Takes [`Path`](crate::Path)s or their text; paths may use tuple
+    /// indices (`root[(2, 17)]`) for nested sequences.
+    pub fn exclude_paths<P: ToString>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.exclude_paths =
+            with_expanded_indices(paths.into_iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    /// Skips paths matching any of the regular expressions `patterns`.
+    pub fn exclude_regex_paths<P: AsRef<str>>(
+        mut self,
+        patterns: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.exclude_regex_paths = self.compile_patterns("exclude_regex_paths", patterns);
+        self
+    }
+
+    /// Skips dict keys and object attributes with these names at any depth,
+    /// such as `updated_at` or `etag`, including when matching the items of
+    /// `ignore_order` lists.
+    pub fn ignore_keys<K: ToString>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.ignore_keys = keys.into_iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    /// Compares only dict keys and object attributes with these names, such
+    /// as `price` and `quantity`, at any depth, and everything below them.
+    /// The rest of the documents is walked only to reach them: entries on
+    /// both sides are followed, lists by position, and no other change is
+    /// reported there.
+    pub fn include_keys<K: ToString>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.include_keys = keys.into_iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    /// Compares the documents under a JSON Schema: properties marked
+    /// `deprecated`, and keys of objects that declare `properties` without
+    /// them, are left out; values of a type the schema does not allow are
+    /// reported under `schema_violations`. Local `$ref`s are followed.
+    pub fn schema(mut self, schema: Option<Value>) -> Self {
+        self.schema = schema.map(Arc::new);
+        self
+    }
+
+    /// Treats numbers whose schema has this `format` (such as `"float"`) as
+    /// equal when they differ by at most `tolerance`.
+    pub fn format_tolerance(mut self, format: impl Into<String>, tolerance: f64) -> Self {
+        self.format_tolerances.push((format.into(), tolerance));
+        self
+    }
+
+    /// Pairs the items of lists of objects by the value of their `key`
+    /// instead of by position, reporting changed items at their old index,
+    /// removed ones at their old index, and added ones at their new index.
+    /// Lists where an item lacks the key are compared as usual.
+    pub fn match_by(mut self, key: Option<String>) -> Self {
+        self.match_by = key;
+        self
+    }
+
+    /// Turns top-level lists of dicts into dicts of the same dicts keyed by
+    /// their `field`, which each drops, before diffing, so records are
+    /// reported as `root['<id>']['name']` instead of by index. Applies when
+    /// both sides are such lists and no two records on a side share a value
+    /// of `field`; `t1` and `t2` of the diff are then the grouped dicts.
+    pub fn group_by(mut self, field: Option<String>) -> Self {
+        self.group_by = field;
+        self
+    }
+
+    /// Applies a [`Rules`] document: subtrees of ignored rules are excluded,
+    /// those of redacted rules are masked, and the comparison settings of a
+    /// rule replace these options' own at and under the paths it matches.
+    pub fn rules(mut self, rules: Rules) -> Self {
+        for rule in rules.rules {
+            if rule.ignore {
+                self.ignored_patterns.push(rules::subtree_regex(&rule.path));
+            }
+            if rule.redact {
+                self.redaction
+                    .patterns
+                    .push(rules::subtree_regex(&rule.path));
+            }
+            self.path_rules.push(PathRule::new(rule));
+        }
+        self
+    }
+
+    /// Reports values at or under these paths as `"***"`, still reporting
+    /// that they changed. Takes [`Path`](crate::Path)s or their text.
+    pub fn redact_paths<P: ToString>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.redaction.paths =
+            with_expanded_indices(paths.into_iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    /// Reports values under dict keys or attributes matching any of
+    /// `patterns` as `"***"`.
+    pub fn redact_keys<P: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = P>) -> Self {
+        self.redaction.keys = self.compile_patterns("redact_keys", patterns);
+        self
+    }
+
+    /// Reports strings matching any of `patterns` as `"***"`.
+    pub fn redact_values<P: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = P>) -> Self {
+        self.redaction.values = self.compile_patterns("redact_values", patterns);
+        self
+    }
+
+    /// Compiles the regular expressions given to `option`, leaving out those
+    /// that do not compile and keeping the first such error for
+    /// [`DeepDiffOptions::validate`].
+    fn compile_patterns<P: AsRef<str>>(
+        &mut self,
+        option: &'static str,
+        patterns: impl IntoIterator<Item = P>,
+    ) -> Vec<Regex> {
+        patterns
+            .into_iter()
+            .filter_map(|pattern| match Regex::new(pattern.as_ref()) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    self.invalid_pattern.get_or_insert_with(|| {
+                        OptionsError::invalid(option, format!("has an invalid pattern: {}", err))
+                    });
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Starts reported paths with `label`, such as a file name or resource
+    /// id, instead of `root`: in result keys, streamed changes, and the
+    /// pretty output. Paths given to options keep starting with `root`;
+    /// [`Path::parse_with_root`](crate::Path::parse_with_root) reads
+    /// labeled paths back.
+    pub fn root_label(mut self, label: impl Into<String>) -> Self {
+        self.root_label = label.into();
         self
     }
 
-    pub fn exclude_paths(mut self, paths: Vec<String>) -> Self {
-        self.exclude_paths = paths;
+    /// Shows reported paths matching `pattern`, and those under them, with
+    /// `label` in place of the matched part, in results, streamed changes,
+    /// and pretty output: `root['spec']['containers'][*]` aliased as
+    /// `container[*]` reports `container[0]['image']`. `[*]` matches any one
+    /// key or index, and each `[*]` of the label repeats the step its
+    /// counterpart matched. The first matching alias wins, and aliased paths
+    /// do not start with the root label.
+    pub fn path_alias(mut self, pattern: impl AsRef<str>, label: impl AsRef<str>) -> Self {
+        self.path_aliases
+            .push(PathAlias::new(pattern.as_ref(), label.as_ref()));
+        self
+    }
+
+    /// The vocabulary of the type names in `type_changes` and the pretty
+    /// output: Python's (`str`, `NoneType`, `dict`) or JSON's (`string`,
+    /// `null`, `object`). By default Python's, with `null` for `None`.
+    pub fn type_names(mut self, names: TypeNames) -> Self {
+        self.type_names = names;
         self
     }
 
@@ -86,6 +486,540 @@ impl DeepDiffOptions {
         self.ignore_type_in_groups = groups;
         self
     }
+
+    /// Skips every node whose value, on either side, is of one of these
+    /// types, with everything under it: no change is reported at or below
+    /// it, nor its addition or removal.
+    pub fn exclude_types(mut self, types: Vec<ValueType>) -> Self {
+        self.exclude_types = types;
+        self
+    }
+
+    /// Skips every node for whose value, on either side, `callback` returns
+    /// true, with everything under it. It is called with the value and its
+    /// path, so values can be excluded by their content, such as any dict
+    /// holding `"internal": true`.
+    pub fn exclude_obj_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Value, &str) -> bool + Send + Sync + 'static,
+    {
+        self.exclude_obj_callback = Some(ObjCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Like [`DeepDiffOptions::exclude_obj_callback`], skipping a node only
+    /// when `callback` returns true for its values on both sides.
+    pub fn exclude_obj_callback_strict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Value, &str) -> bool + Send + Sync + 'static,
+    {
+        self.exclude_obj_callback_strict = Some(ObjCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Diffs only a deterministic sample of the items of large lists and
+    /// dicts; see [`Sampling`]. A sampled diff may miss changes, so an empty
+    /// one means "probably unchanged".
+    pub fn sampling(mut self, sampling: Option<Sampling>) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// The hash function matching items under `ignore_order` and `match_by`
+    /// and fingerprinting the inputs of a [`DiffJob`](crate::DiffJob)
+    /// checkpoint. The diff is the same under every hasher, barring
+    /// collisions.
+    pub fn hasher(mut self, hasher: HashAlgorithm) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Once the reported values take roughly this many bytes, the report
+    /// drops them and keeps paths only, marked with `report_truncated`.
+    pub fn max_report_bytes(mut self, value: Option<usize>) -> Self {
+        self.max_report_bytes = value;
+        self
+    }
+
+    /// Stops the diff once this many changes were reported, marking the
+    /// result with `max_diffs_reached` when more were left.
+    pub fn max_diffs(mut self, value: Option<usize>) -> Self {
+        self.max_diffs = value;
+        self
+    }
+
+    /// Adds the [deep distance](crate::DeepDiff::get_deep_distance) of the
+    /// two documents to the result, under `deep_distance`.
+    pub fn get_deep_distance(mut self, value: bool) -> Self {
+        self.get_deep_distance = value;
+        self
+    }
+
+    /// Strings longer than this many bytes on both sides are compared by
+    /// chunk hashes and reported as their lengths and changed byte ranges
+    /// instead of both values.
+    pub fn large_string_threshold(mut self, value: Option<usize>) -> Self {
+        self.large_string_threshold = value;
+        self
+    }
+
+    /// Orders the entries of each report key by their path text, so that
+    /// `root[10]` comes before `root[2]`, instead of in document order.
+    pub fn sort_paths(mut self, value: bool) -> Self {
+        self.sort_paths = value;
+        self
+    }
+
+    /// Reports runs of at least this many changed, added or removed items at
+    /// consecutive indices of a list as one `root[100:250]` entry with a
+    /// count and the entries at both ends, in the result and `pretty()`.
+    pub fn collapse_index_ranges(mut self, min_run: Option<usize>) -> Self {
+        self.collapse_index_ranges = min_run;
+        self
+    }
+
+    /// Scores each numeric `values_changed` entry with its `magnitude`: the
+    /// change relative to the larger of the two values, capped at 1.
+    pub fn magnitude_scores(mut self, value: bool) -> Self {
+        self.magnitude_scores = value;
+        self
+    }
+
+    /// Caps the threads used to diff long ordered lists. `None` uses every
+    /// available core; `Some(1)` keeps the whole diff on the calling thread.
+    pub fn num_threads(mut self, value: Option<usize>) -> Self {
+        self.num_threads = value;
+        self
+    }
+
+    /// Calls `visitor` with each change as it is found, before it enters the
+    /// report. Returning `ControlFlow::Break(())` stops the diff after that
+    /// change, leaving the report with the changes found so far.
+    pub fn visitor<F>(mut self, visitor: F) -> Self
+    where
+        F: FnMut(&Change<'_>) -> ControlFlow<()> + Send + 'static,
+    {
+        self.visitor = Some(Visitor(Arc::new(Mutex::new(visitor))));
+        self
+    }
+
+    /// Calls `annotate` with each change as it is found. The fields it
+    /// returns, such as an owner team, a docs link, or a severity, are kept
+    /// under `annotations` in the result, keyed by the change's path, and
+    /// shown below the change in the pretty output.
+    pub fn annotate<F>(mut self, annotate: F) -> Self
+    where
+        F: Fn(&Change<'_>) -> Option<Map<String, Value>> + Send + Sync + 'static,
+    {
+        self.annotator = Some(Annotator(Arc::new(annotate)));
+        self
+    }
+
+    /// Decides equality of numbers and strings with `comparator` before the
+    /// built-in comparison. Comparators registered for a type with
+    /// [`DeepDiffOptions::comparator_for`] are asked first. Lists diffed with
+    /// `ignore_order` still pair their items by hash.
+    pub fn comparator(mut self, comparator: impl ApproxEq + 'static) -> Self {
+        self.comparators.push(Comparator {
+            value_type: None,
+            inner: Arc::new(comparator),
+        });
+        self
+    }
+
+    /// Like [`DeepDiffOptions::comparator`], for values of `value_type` only;
+    /// types other than `Number` and `String` never reach a comparator.
+    pub fn comparator_for(
+        mut self,
+        value_type: ValueType,
+        comparator: impl ApproxEq + 'static,
+    ) -> Self {
+        self.comparators.push(Comparator {
+            value_type: Some(value_type),
+            inner: Arc::new(comparator),
+        });
+        self
+    }
+
+    /// Hands the values `operator` matches to it instead of comparing them,
+    /// reporting the changes it returns. Operators are asked in the order
+    /// they were registered, and the first match decides. Lists diffed with
+    /// `ignore_order` still pair their items by hash.
+    pub fn custom_operator(mut self, operator: impl CustomOperator + 'static) -> Self {
+        self.operators.push(Operator(Arc::new(operator)));
+        self
+    }
+
+    /// Calls `logger` at most once per `frequency` while the diff is running.
+    pub fn progress_logger<F>(mut self, frequency: Duration, logger: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress_logger = Some(ProgressLogger(Arc::new(logger)));
+        self.log_frequency = frequency;
+        self
+    }
+
+    /// Checks option values and combinations the engine cannot honor, such as
+    /// negative tolerances or `significant_digits` combined with `atol`.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if let Some(err) = &self.invalid_pattern {
+            return Err(err.clone());
+        }
+        if self.significant_digits.is_some() {
+            for (name, value) in [
+                ("math_epsilon", self.math_epsilon),
+                ("atol", self.atol),
+                ("rtol", self.rtol),
+            ] {
+                if value.is_some() {
+                    return Err(OptionsError::Conflict("significant_digits", name));
+                }
+            }
+        }
+        for (name, value) in [
+            ("math_epsilon", self.math_epsilon),
+            ("atol", self.atol),
+            ("rtol", self.rtol),
+            ("datetime_epsilon", self.datetime_epsilon),
+            (
+                "log_scale_similarity_threshold",
+                Some(self.log_scale_similarity_threshold),
+            ),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v < 0.0) {
+                return Err(OptionsError::invalid(name, "must be a non-negative number"));
+            }
+        }
+        if self
+            .collapse_index_ranges
+            .is_some_and(|min_run| min_run < 2)
+        {
+            return Err(OptionsError::invalid(
+                "collapse_index_ranges",
+                "runs must be at least 2 items long",
+            ));
+        }
+        if self
+            .string_similarity
+            .is_some_and(|v| !(0.0..=1.0).contains(&v))
+        {
+            return Err(OptionsError::invalid(
+                "string_similarity",
+                "must be between 0 and 1",
+            ));
+        }
+        if self
+            .cutoff_distance_for_pairs
+            .is_some_and(|v| !(0.0..=1.0).contains(&v))
+        {
+            return Err(OptionsError::invalid(
+                "cutoff_distance_for_pairs",
+                "must be between 0 and 1",
+            ));
+        }
+        if self
+            .cutoff_intersection_for_pairs
+            .is_some_and(|v| !(0.0..=1.0).contains(&v))
+        {
+            return Err(OptionsError::invalid(
+                "cutoff_intersection_for_pairs",
+                "must be between 0 and 1",
+            ));
+        }
+        if self
+            .threshold_to_diff_deeper
+            .is_some_and(|v| !(0.0..=1.0).contains(&v))
+        {
+            return Err(OptionsError::invalid(
+                "threshold_to_diff_deeper",
+                "must be between 0 and 1",
+            ));
+        }
+        if let Some((format, _)) = self
+            .format_tolerances
+            .iter()
+            .find(|(_, tolerance)| !tolerance.is_finite() || *tolerance < 0.0)
+        {
+            return Err(OptionsError::invalid(
+                "format_tolerance",
+                format!(
+                    "the tolerance for '{}' must be a non-negative number",
+                    format
+                ),
+            ));
+        }
+        if let Some(rule) = self
+            .path_rules
+            .iter()
+            .find(|rule| !rules::is_pattern(&rule.source))
+        {
+            return Err(OptionsError::invalid(
+                "rules",
+                format!(
+                    "paths must be patterns like root['key'][*].attr, got '{}'",
+                    rule.source
+                ),
+            ));
+        }
+        if let Some(rule) = self.path_rules.iter().find(|rule| !rule.tolerances_valid()) {
+            return Err(OptionsError::invalid(
+                "rules",
+                format!(
+                    "the tolerances for '{}' must be non-negative numbers",
+                    rule.source
+                ),
+            ));
+        }
+        if self.sampling.is_some_and(|sampling| !sampling.rate_valid()) {
+            return Err(OptionsError::invalid(
+                "sampling",
+                "the rate must be greater than 0 and at most 1",
+            ));
+        }
+        if self.num_threads == Some(0) {
+            return Err(OptionsError::invalid("num_threads", "must be at least 1"));
+        }
+        if self.root_label.is_empty() || self.root_label.starts_with(['[', '.']) {
+            return Err(OptionsError::invalid(
+                "root_label",
+                "must be non-empty and not start with '[' or '.'",
+            ));
+        }
+        if let Some(alias) = self
+            .path_aliases
+            .iter()
+            .find(|alias| !rules::is_pattern(&alias.source))
+        {
+            return Err(OptionsError::invalid(
+                "path_aliases",
+                format!(
+                    "patterns must be like root['key'][*].attr, got '{}'",
+                    alias.source
+                ),
+            ));
+        }
+        if self.verbose_level > 2 {
+            return Err(OptionsError::invalid("verbose_level", "must be 0, 1, or 2"));
+        }
+        for (name, paths) in [
+            ("include_paths", &self.include_paths),
+            ("exclude_paths", &self.exclude_paths),
+            ("redact_paths", &self.redaction.paths),
+        ] {
+            if let Some(path) = paths.iter().find(|path| !path.starts_with("root")) {
+                return Err(OptionsError::invalid(
+                    name,
+                    format!("entries must start with 'root', got '{}'", path),
+                ));
+            }
+            if let Some(path) = paths.iter().find(|path| !is_well_formed(path)) {
+                return Err(OptionsError::invalid(
+                    name,
+                    format!(
+                        "entries must be paths like root['key'][0].attr, got '{}'",
+                        path
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Final step of the builder: the options, once [`DeepDiffOptions::validate`]
+    /// accepts them.
+    pub fn build(self) -> Result<Self, TurboDiffError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Threads a parallel step may use: `num_threads`, or every available
+    /// core.
+    pub(crate) fn threads(&self) -> usize {
+        self.num_threads
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from))
+    }
+
+    /// `path`, rendered from `root`, as reported: through the first path
+    /// alias matching it, or else starting with the root label.
+    pub(crate) fn display_path(&self, path: &str) -> String {
+        alias::display_path(path, &self.root_label, &self.path_aliases)
+    }
+
+    /// Whether the changes under a node depend on that node alone, so a
+    /// subtree can be diffed on its own. Not so where unordered, aligned and
+    /// keyed lists pair items across indices, path rules scope options by
+    /// where a subtree sits, custom operators or `threshold_to_diff_deeper`
+    /// may take over a node above the subtree, truncated, capped and sampled
+    /// reports depend on the whole walk, or schema violations are found by a
+    /// pass over both documents.
+    pub(crate) fn region_local(&self) -> bool {
+        !self.ignore_order
+            && self.match_by.is_none()
+            && self.max_report_bytes.is_none()
+            && self.max_diffs.is_none()
+            && self.schema.is_none()
+            && self.path_rules.is_empty()
+            && self.sampling.is_none()
+            && self.include_keys.is_empty()
+            && !self.align_lists
+            && self.operators.is_empty()
+            && self.threshold_to_diff_deeper.is_none()
+    }
+}
+
+/// Error returned by [`DeepDiffOptions::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    /// The option has a value outside its accepted range.
+    Invalid {
+        option: &'static str,
+        reason: String,
+    },
+    /// The two options cannot be used together.
+    Conflict(&'static str, &'static str),
+}
+
+impl OptionsError {
+    pub(crate) fn invalid(option: &'static str, reason: impl Into<String>) -> Self {
+        Self::Invalid {
+            option,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { option, reason } => write!(f, "{} {}", option, reason),
+            Self::Conflict(a, b) => write!(f, "{} and {} cannot be used together", a, b),
+        }
+    }
+}
+
+impl Error for OptionsError {}
+
+/// The candidate closest to `name` by edit distance, if it is close enough to
+/// be a plausible typo.
+#[cfg(feature = "python")]
+pub(crate) fn closest_match<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(2);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between `a` and `b`, in chars.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+type NumberFormatFn = dyn Fn(f64, Option<u32>, NumberFormat) -> String + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct NumberFormatter(pub(crate) Arc<NumberFormatFn>);
+
+impl fmt::Debug for NumberFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NumberFormatter(..)")
+    }
+}
+
+type ObjPredicate = dyn Fn(&Value, &str) -> bool + Send + Sync;
+
+/// Predicate invoked with a value and its path; returning `true` excludes it.
+#[derive(Clone)]
+pub(crate) struct ObjCallback(pub(crate) Arc<ObjPredicate>);
+
+impl fmt::Debug for ObjCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ObjCallback(..)")
+    }
+}
+
+type VisitorFn = dyn FnMut(&Change<'_>) -> ControlFlow<()> + Send;
+
+#[derive(Clone)]
+pub(crate) struct Visitor(pub(crate) Arc<Mutex<VisitorFn>>);
+
+impl Visitor {
+    /// Whether the diff should go on after `change`.
+    pub(crate) fn visit(&self, change: &Change<'_>) -> bool {
+        match self.0.lock() {
+            Ok(mut visitor) => visitor(change).is_continue(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Debug for Visitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Visitor(..)")
+    }
+}
+
+type AnnotateFn = dyn Fn(&Change<'_>) -> Option<Map<String, Value>> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct Annotator(pub(crate) Arc<AnnotateFn>);
+
+impl fmt::Debug for Annotator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Annotator(..)")
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Comparator {
+    pub(crate) value_type: Option<ValueType>,
+    pub(crate) inner: Arc<dyn ApproxEq>,
+}
+
+impl fmt::Debug for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Comparator({:?}, ..)", self.value_type)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Operator(pub(crate) Arc<dyn CustomOperator>);
+
+impl fmt::Debug for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Operator(..)")
+    }
+}
+
+/// Snapshot passed to the progress logger during long diffs.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub elapsed: Duration,
+    pub items_processed: usize,
+    pub diffs_found: usize,
+}
+
+#[derive(Clone)]
+pub(crate) struct ProgressLogger(pub(crate) Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl fmt::Debug for ProgressLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressLogger(..)")
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -109,12 +1043,172 @@ impl Default for PrettyOptions {
     }
 }
 
+/// The names `type_changes` and the pretty output give value types.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TypeNames {
+    /// Python's, except `null` for `None`, as turbodiff has always reported
+    /// them.
+    #[default]
+    Legacy,
+    /// Python's: `int`, `float`, `str`, `bool`, `NoneType`, `list`, `dict`.
+    Python,
+    /// JSON Schema's: `integer`, `number`, `string`, `boolean`, `null`,
+    /// `array`, `object`. Types without a JSON counterpart, such as
+    /// `datetime` or class names, keep their Python names.
+    Json,
+}
+
+impl TypeNames {
+    /// The name of the type Python calls `python_name` in this vocabulary.
+    pub fn name(self, python_name: &str) -> &str {
+        match self {
+            Self::Legacy if python_name == "NoneType" => "null",
+            Self::Legacy | Self::Python => python_name,
+            Self::Json => match python_name {
+                "int" => "integer",
+                "float" | "Decimal" => "number",
+                "str" => "string",
+                "bool" => "boolean",
+                "NoneType" => "null",
+                "list" | "tuple" => "array",
+                "dict" => "object",
+                other => other,
+            },
+        }
+    }
+}
+
+impl FromStr for TypeNames {
+    type Err = OptionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "python" => Ok(Self::Python),
+            "json" => Ok(Self::Json),
+            _ => Err(OptionsError::invalid(
+                "type_names",
+                format!("must be 'python' or 'json', got '{}'", text),
+            )),
+        }
+    }
+}
+
+/// The kinds of value `exclude_types`, `ignore_type_in_groups`, and
+/// `comparator_for` select by. More may be added, so matches need a wildcard
+/// arm.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ValueType {
     Number,
     String,
     Bool,
     Null,
     Array,
+    Tuple,
     Object,
 }
+
+impl FromStr for ValueType {
+    type Err = OptionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "number" => Ok(Self::Number),
+            "string" => Ok(Self::String),
+            "bool" => Ok(Self::Bool),
+            "null" => Ok(Self::Null),
+            "array" => Ok(Self::Array),
+            "tuple" => Ok(Self::Tuple),
+            "object" => Ok(Self::Object),
+            _ => Err(OptionsError::invalid(
+                "exclude_types",
+                format!(
+                    "must name number, string, bool, null, array, tuple or object, got '{}'",
+                    text
+                ),
+            )),
+        }
+    }
+}
+
+/// The granularity `truncate_datetime` cuts timestamps down to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatetimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl DatetimeUnit {
+    pub(crate) fn micros(self) -> i64 {
+        match self {
+            Self::Second => 1_000_000,
+            Self::Minute => 60_000_000,
+            Self::Hour => 3_600_000_000,
+            Self::Day => 86_400_000_000,
+        }
+    }
+}
+
+impl FromStr for DatetimeUnit {
+    type Err = OptionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "second" => Ok(Self::Second),
+            "minute" => Ok(Self::Minute),
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            _ => Err(OptionsError::invalid(
+                "truncate_datetime",
+                format!(
+                    "must be 'second', 'minute', 'hour' or 'day', got '{}'",
+                    text
+                ),
+            )),
+        }
+    }
+}
+
+/// The notation numbers are written in to compare them, with
+/// `number_format_notation`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Fixed-point, `"f"`: `1234.57` with 2 digits.
+    #[default]
+    Fixed,
+    /// Scientific, `"e"`: `1.23e3` with 2 digits.
+    Scientific,
+}
+
+impl NumberFormat {
+    /// `number` in this notation with `digits` digits after the point;
+    /// zero never carries a sign.
+    pub fn format(self, number: f64, digits: u32) -> String {
+        let digits = digits as usize;
+        let text = match self {
+            Self::Fixed => format!("{:.*}", digits, number),
+            Self::Scientific => format!("{:.*e}", digits, number),
+        };
+        match text.strip_prefix('-') {
+            Some(unsigned) if unsigned.parse::<f64>() == Ok(0.0) => unsigned.to_string(),
+            _ => text,
+        }
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = OptionsError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "f" => Ok(Self::Fixed),
+            "e" => Ok(Self::Scientific),
+            _ => Err(OptionsError::invalid(
+                "number_format_notation",
+                format!("must be 'f' or 'e', got '{}'", text),
+            )),
+        }
+    }
+}
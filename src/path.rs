@@ -0,0 +1,518 @@
+use crate::error::TurboDiffError;
+use crate::pretty::format_value;
+use crate::tagged::{as_object, decode_key, encode_key, sequence_items, tagged};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// One step of a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    /// A dict key: `['name']`. Keys that are not strings, such as `[1]` or
+    /// `[(1, 2)]`, are held in the encoded form the engine uses for them.
+    Key(String),
+    /// A list index: `[0]`.
+    Index(usize),
+    /// An object attribute: `.name`.
+    Attr(String),
+}
+
+/// A path in DeepDiff's `root['key'][0].attr` notation, as reported in
+/// results and accepted by options and deltas.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path(Vec<PathSegment>);
+
+impl Path {
+    /// The root of a document.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parses `root['key'][0].attr`; `None` unless all of `path` is a path.
+    pub fn parse(path: &str) -> Option<Self> {
+        match parse_segments(path) {
+            Some((segments, end)) if end == path.len() => Some(Self(segments)),
+            _ => None,
+        }
+    }
+
+    /// Like [`Path::parse`], for paths starting with `label` in place of
+    /// `root`, as reported under [`DeepDiffOptions::root_label`].
+    ///
+    /// [`DeepDiffOptions::root_label`]: crate::DeepDiffOptions::root_label
+    pub fn parse_with_root(path: &str, label: &str) -> Option<Self> {
+        Self::parse(&unlabel(path, label))
+    }
+
+    /// The path as text, starting with `label` in place of `root`.
+    pub fn to_string_with_root(&self, label: &str) -> String {
+        format_path(label, &self.0)
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// The path one step up, or `None` at the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Self(parent.to_vec()))
+    }
+
+    /// The path one step down, through `segment`.
+    pub fn child(&self, segment: PathSegment) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(segment);
+        Self(segments)
+    }
+
+    /// Whether `prefix` is this path or one of its ancestors.
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// The value this path points to inside `root`.
+    pub fn resolve<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        get_value_at_path(root, &self.0)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_path("root", &self.0))
+    }
+}
+
+impl FromStr for Path {
+    type Err = TurboDiffError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::parse(path).ok_or_else(|| TurboDiffError::InvalidPath(path.to_string()))
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+}
+
+pub(crate) fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
+    parse_segments(path).map(|(segments, _)| segments)
+}
+
+/// `path`, rendered from `root`, starting with `label` instead.
+pub(crate) fn relabel(path: &str, label: &str) -> String {
+    match path.strip_prefix("root") {
+        Some(rest) => format!("{}{}", label, rest),
+        None => path.to_string(),
+    }
+}
+
+/// `path`, starting with `label`, rendered from `root` instead; other paths
+/// are returned as they are.
+pub(crate) fn unlabel<'p>(path: &'p str, label: &str) -> Cow<'p, str> {
+    match path.strip_prefix(label) {
+        Some(rest) if label != "root" && (rest.is_empty() || rest.starts_with(['[', '.'])) => {
+            Cow::Owned(format!("root{}", rest))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Whether all of `path` parses, not just a leading part of it.
+pub(crate) fn is_well_formed(path: &str) -> bool {
+    Path::parse(path).is_some()
+}
+
+/// Segments of the leading part of `path` that parses, and where it ends.
+fn parse_segments(path: &str) -> Option<(Vec<PathSegment>, usize)> {
+    if !path.starts_with("root") {
+        return None;
+    }
+    let mut segments = Vec::new();
+    let mut i = 4;
+    while i < path.len() {
+        if let Some((key, len)) = quoted_key(&path[i..]) {
+            segments.push(PathSegment::Key(key));
+            i += len;
+        } else if path[i..].starts_with("['") {
+            // Paths written by hand with quotes left unescaped, such as
+            // `root['it's']`, end at the first `']`.
+            i += 2;
+            let end = path[i..].find("']")?;
+            let key = &path[i..i + end];
+            segments.push(PathSegment::Key(key.to_string()));
+            i += end + 2;
+        } else if path.as_bytes().get(i) == Some(&b'[') {
+            i += 1;
+            let end = find_top_level(&path[i..], ']')?;
+            let inner = &path[i..i + end];
+            match inner.parse::<usize>() {
+                Ok(idx) => segments.push(PathSegment::Index(idx)),
+                Err(_) => segments.push(PathSegment::Key(encode_key(&parse_literal(inner)?))),
+            }
+            i += end + 1;
+        } else if path.as_bytes().get(i) == Some(&b'.') {
+            i += 1;
+            let end = path[i..].find(['[', '.']).unwrap_or(path.len() - i);
+            segments.push(PathSegment::Attr(path[i..i + end].to_string()));
+            i += end;
+        } else {
+            break;
+        }
+    }
+    Some((segments, i.min(path.len())))
+}
+
+/// A `['key']` or `["key"]` step at the start of `path`, unescaped, and its
+/// length.
+fn quoted_key(path: &str) -> Option<(String, usize)> {
+    let rest = path.strip_prefix('[')?;
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let mut escaped = false;
+    let (close, _) = rest.char_indices().skip(1).find(|&(_, ch)| {
+        let closes = !escaped && ch == quote;
+        escaped = !escaped && ch == '\\';
+        closes
+    })?;
+    if !rest[close + 1..].starts_with(']') {
+        return None;
+    }
+    let key = unquote(&rest[..close + 1])?;
+    Some((key, close + 3))
+}
+
+pub(crate) fn get_value_at_path<'a>(
+    root: &'a Value,
+    segments: &[PathSegment],
+) -> Option<&'a Value> {
+    let mut current = root;
+    for segment in segments {
+        match (segment, current) {
+            (PathSegment::Key(key), Value::Object(map)) if map.contains_key(key) => {
+                current = map.get(key)?;
+            }
+            (PathSegment::Index(idx), _) if sequence_items(current).is_some() => {
+                current = sequence_items(current)?.get(*idx)?;
+            }
+            // `root[1]` addresses an integer dict key as well as a list index.
+            (PathSegment::Index(idx), Value::Object(map)) => {
+                current = map.get(&encode_key(&Value::from(*idx)))?;
+            }
+            (PathSegment::Index(_), _) => return None,
+            (PathSegment::Attr(name), _) => {
+                current = as_object(current)?.1.get(name)?;
+            }
+            // `root[(2, 17)]` addresses nested sequences like `root[2][17]`.
+            (PathSegment::Key(key), _) => {
+                for idx in multi_index(key)? {
+                    current = sequence_items(current)?.get(idx)?;
+                }
+            }
+        }
+    }
+    Some(current)
+}
+
+/// Path segment for a map key: `['name']` for strings, `[repr]` for keys of
+/// other types such as `[1]` or `[(1, 2)]`.
+pub(crate) fn key_segment(key: &str) -> String {
+    format!("[{}]", key_repr(key))
+}
+
+/// Python repr of a map key: `'name'` for strings, `1` or `(1, 2)` otherwise.
+pub(crate) fn key_repr(key: &str) -> String {
+    match decode_key(key) {
+        Some(value) => format_value(&value),
+        None => quote_str(key),
+    }
+}
+
+/// `value` as a Python string literal, the way `repr` writes it: in single
+/// quotes unless only double quotes avoid escaping, with backslashes, the
+/// quote, and control characters escaped, so any key parses back intact.
+pub(crate) fn quote_str(value: &str) -> String {
+    let quote = if value.contains('\'') && !value.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push(quote);
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ if ch == quote => {
+                out.push('\\');
+                out.push(ch);
+            }
+            _ if ch.is_control() => push_escaped_char(&mut out, ch),
+            _ => out.push(ch),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// `ch` as a `\xNN` or `\uNNNN` escape.
+pub(crate) fn push_escaped_char(out: &mut String, ch: char) {
+    let code = u32::from(ch);
+    if code <= 0xff {
+        out.push_str(&format!("\\x{:02x}", code));
+    } else {
+        out.push_str(&format!("\\u{:04x}", code));
+    }
+}
+
+/// Adds the `root[2][17]` spelling of every path written with a tuple index
+/// such as `root[(2, 17)]`, so both match the nested paths the engine reports.
+pub(crate) fn with_expanded_indices(paths: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(paths.len());
+    for path in paths {
+        if let Some(expanded) = expand_indices(&path) {
+            out.push(expanded);
+        }
+        out.push(path);
+    }
+    out
+}
+
+fn expand_indices(path: &str) -> Option<String> {
+    let segments = parse_path(path)?;
+    if format_path("root", &segments) != path {
+        return None;
+    }
+    let mut expanded = Vec::with_capacity(segments.len());
+    let mut changed = false;
+    for segment in segments {
+        match &segment {
+            PathSegment::Key(key) => match multi_index(key) {
+                Some(indices) => {
+                    expanded.extend(indices.into_iter().map(PathSegment::Index));
+                    changed = true;
+                }
+                None => expanded.push(segment),
+            },
+            _ => expanded.push(segment),
+        }
+    }
+    changed.then(|| format_path("root", &expanded))
+}
+
+/// Indices of a tuple key made only of non-negative integers.
+fn multi_index(key: &str) -> Option<Vec<usize>> {
+    let key = decode_key(key)?;
+    let items = sequence_items(&key)?;
+    if items.is_empty() {
+        return None;
+    }
+    items
+        .iter()
+        .map(|item| item.as_u64().and_then(|idx| usize::try_from(idx).ok()))
+        .collect()
+}
+
+pub(crate) fn format_path(root: &str, segments: &[PathSegment]) -> String {
+    let mut out = root.to_string();
+    for segment in segments {
+        match segment {
+            PathSegment::Key(key) => out.push_str(&key_segment(key)),
+            PathSegment::Attr(name) => {
+                out.push('.');
+                out.push_str(name);
+            }
+            PathSegment::Index(idx) => {
+                out.push('[');
+                out.push_str(&idx.to_string());
+                out.push(']');
+            }
+        }
+    }
+    out
+}
+
+/// Offset of the first `target` outside nested tuples, lists, and quoted
+/// strings.
+fn find_top_level(value: &str, target: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (idx, ch) in value.char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        match ch {
+            '\'' | '"' => quote = Some(ch),
+            _ if ch == target && depth == 0 => return Some(idx),
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses the Python literals that can appear as dict keys in a path: numbers,
+/// strings, `True`/`False`/`None`, and tuples of those.
+fn parse_literal(value: &str) -> Option<Value> {
+    let value = value.trim();
+    match value {
+        "None" => return Some(Value::Null),
+        "True" => return Some(Value::Bool(true)),
+        "False" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(inner) = value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        let mut items = Vec::new();
+        for item in split_top_level(inner) {
+            items.push(parse_literal(item)?);
+        }
+        return Some(tagged("tuple", Value::Array(items)));
+    }
+    if let Some(text) = unquote(value) {
+        return Some(Value::String(text));
+    }
+    if let Ok(int) = value.parse::<i64>() {
+        return Some(Value::from(int));
+    }
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|f| serde_json::Number::from_f64(f).map(Value::Number))
+}
+
+/// Splits tuple contents on top-level commas, dropping a trailing one.
+fn split_top_level(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut rest = value;
+    while !rest.trim().is_empty() {
+        let end = find_top_level(rest, ',').unwrap_or(rest.len());
+        items.push(&rest[..end]);
+        rest = rest.get(end + 1..).unwrap_or("");
+    }
+    items
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let quote = value.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let inner = value.strip_prefix(quote)?.strip_suffix(quote)?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            hex @ ('x' | 'u' | 'U') => {
+                let digits = match hex {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let code: String = chars.by_ref().take(digits).collect();
+                if code.len() != digits {
+                    return None;
+                }
+                out.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+            }
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+/// One step of the path to the node being diffed, borrowing keys from the
+/// values so that descending allocates nothing.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+    Attr(&'a str),
+    /// DataFrame row: `.loc['label']`.
+    Row(&'a str),
+    /// DataFrame cell: `[('label', 'col')]`.
+    Cell(&'a str, &'a str),
+}
+
+impl<'a> From<&'a PathSegment> for Step<'a> {
+    fn from(segment: &'a PathSegment) -> Self {
+        match segment {
+            PathSegment::Key(key) => Step::Key(key),
+            PathSegment::Index(idx) => Step::Index(*idx),
+            PathSegment::Attr(name) => Step::Attr(name),
+        }
+    }
+}
+
+/// Stack of steps from the root to the node being diffed. Paths are only
+/// rendered to strings when a report entry or a path filter needs them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodePath<'a> {
+    steps: Vec<Step<'a>>,
+}
+
+impl<'a> NodePath<'a> {
+    pub(crate) fn push(&mut self, step: Step<'a>) {
+        self.steps.push(step);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.steps.pop();
+    }
+
+    pub(crate) fn steps(&self) -> &[Step<'a>] {
+        &self.steps
+    }
+
+    /// The path in DeepDiff's `root['key'][0]` notation.
+    pub(crate) fn render(&self) -> String {
+        let mut out = String::from("root");
+        for step in &self.steps {
+            match step {
+                Step::Key(key) => out.push_str(&key_segment(key)),
+                Step::Index(idx) => {
+                    out.push('[');
+                    out.push_str(&idx.to_string());
+                    out.push(']');
+                }
+                Step::Attr(name) => {
+                    out.push('.');
+                    out.push_str(name);
+                }
+                Step::Row(label) => {
+                    out.push_str(".loc");
+                    out.push_str(&key_segment(label));
+                }
+                Step::Cell(label, column) => {
+                    out.push_str(&format!("[({}, {})]", key_repr(label), key_repr(column)));
+                }
+            }
+        }
+        out
+    }
+
+    /// The path of the child reached by `step`, without descending into it.
+    pub(crate) fn render_child(&mut self, step: Step<'a>) -> String {
+        self.push(step);
+        let rendered = self.render();
+        self.pop();
+        rendered
+    }
+}
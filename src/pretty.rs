@@ -1,12 +1,12 @@
-use crate::options::PrettyOptions;
-use serde_json::Value;
-use std::collections::HashSet;
-
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-enum PathSegment {
-    Key(String),
-    Index(usize),
-}
+use crate::collapse::consecutive_runs;
+use crate::options::{DeepDiffOptions, PrettyOptions};
+use crate::path::{
+    format_path, get_value_at_path, key_segment, parse_path, push_escaped_char, PathSegment,
+};
+use crate::reverse::counterpart;
+use crate::tagged::{as_enum_member, as_object, as_tagged, decode_key, is_tagged, sequence_items};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Clone, Debug)]
 struct ChangeEntry {
@@ -32,6 +32,44 @@ enum ChangeKind {
     Removed {
         value: Option<Value>,
     },
+    /// An item found unchanged at `new_path` of the new list, with
+    /// `align_lists`.
+    Moved {
+        value: Value,
+        new_path: String,
+    },
+    /// An item found `old` times in the old list and `new` times in the new
+    /// one, with `report_repetition`.
+    Repeated {
+        value: Value,
+        old: usize,
+        new: usize,
+    },
+    /// `count` items at consecutive indices ending before `end` that all
+    /// changed the same way, with `collapse_index_ranges`.
+    Range {
+        end: usize,
+        count: usize,
+        change: RangeChange,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum RangeChange {
+    Changed,
+    Added,
+    Removed,
+}
+
+impl ChangeKind {
+    fn range_change(&self) -> Option<RangeChange> {
+        match self {
+            ChangeKind::ValueChanged { .. } => Some(RangeChange::Changed),
+            ChangeKind::Added { .. } => Some(RangeChange::Added),
+            ChangeKind::Removed { .. } => Some(RangeChange::Removed),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -87,22 +125,35 @@ pub(crate) fn render_pretty(
     result: &Value,
     t1: &Value,
     t2: &Value,
+    labels: &DeepDiffOptions,
     options: PrettyOptions,
 ) -> String {
-    let mut changes = collect_changes(result, t1, t2);
+    let root_label = labels.root_label.as_str();
+    let annotations = result.get("annotations").and_then(Value::as_object);
+    let mut changes = collect_changes(result, t1, t2, labels);
     if changes.is_empty() {
         return String::new();
     }
+    if let Some(min_run) = labels.collapse_index_ranges {
+        changes = collapse_runs(changes, min_run);
+    }
 
     if options.path_header {
-        changes.sort_by(|a, b| {
-            format_compact_path(&a.segments).cmp(&format_compact_path(&b.segments))
-        });
+        changes.sort_by_cached_key(|change| format_compact_path(&change.segments, labels));
         let mut lines = Vec::new();
         for change in changes {
-            let path = format_compact_path(&change.segments);
-            lines.push(path);
+            let path = format_compact_path(&change.segments, labels);
+            lines.push(range_label(path, &change.segments, &change.kind));
             append_change_lines(&mut lines, 0, &[], false, &change.kind, &options);
+            append_annotation_lines(
+                &mut lines,
+                0,
+                &[],
+                false,
+                &change.segments,
+                annotations,
+                &options,
+            );
         }
         return lines.join("\n");
     }
@@ -110,19 +161,43 @@ pub(crate) fn render_pretty(
     let tree = build_tree(changes);
     let mut lines = Vec::new();
     if let Some(change) = &tree.change {
-        lines.push("root".to_string());
+        lines.push(root_label.to_string());
         append_change_lines(&mut lines, 0, &[], false, change, &options);
+        append_annotation_lines(&mut lines, 0, &[], false, &[], annotations, &options);
     }
     let env = RenderEnv {
         t1,
         t2,
         options: &options,
+        labels,
+        annotations,
     };
     render_children(&tree, 0, &[], &[], &env, &mut lines);
     lines.join("\n")
 }
 
-fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
+/// Where the new side of the change at `path` is in `t2`: at the same path,
+/// except through lists aligned by `align_lists`, where it may sit at other
+/// indices.
+fn new_side(
+    path: &str,
+    report_type: &str,
+    t1: &Value,
+    t2: &Value,
+    options: &DeepDiffOptions,
+) -> Option<Vec<PathSegment>> {
+    match crate::change::ChangeKind::from_key(report_type) {
+        Some(kind) if options.align_lists => parse_path(&counterpart(path, kind, t1, t2, options)),
+        _ => parse_path(path),
+    }
+}
+
+fn collect_changes(
+    result: &Value,
+    t1: &Value,
+    t2: &Value,
+    options: &DeepDiffOptions,
+) -> Vec<ChangeEntry> {
     let mut changes = Vec::new();
     let Value::Object(map) = result else {
         return changes;
@@ -135,7 +210,8 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
                     .cloned()
                     .or_else(|| entry.get("old_value").cloned())
                     .unwrap_or(Value::Null);
-                let new = get_value_at_path(t2, &segments)
+                let new = new_side(path, "values_changed", t1, t2, options)
+                    .and_then(|at| get_value_at_path(t2, &at))
                     .cloned()
                     .or_else(|| entry.get("new_value").cloned())
                     .unwrap_or(Value::Null);
@@ -152,7 +228,8 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
                     let old = get_value_at_path(t1, &segments)
                         .cloned()
                         .unwrap_or(Value::Null);
-                    let new = get_value_at_path(t2, &segments)
+                    let new = new_side(path, "values_changed", t1, t2, options)
+                        .and_then(|at| get_value_at_path(t2, &at))
                         .cloned()
                         .unwrap_or(Value::Null);
                     changes.push(ChangeEntry {
@@ -181,7 +258,8 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
                     .cloned()
                     .or_else(|| entry.get("old_value").cloned())
                     .unwrap_or(Value::Null);
-                let new = get_value_at_path(t2, &segments)
+                let new = new_side(path, "type_changes", t1, t2, options)
+                    .and_then(|at| get_value_at_path(t2, &at))
                     .cloned()
                     .or_else(|| entry.get("new_value").cloned())
                     .unwrap_or(Value::Null);
@@ -198,11 +276,16 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
         }
     }
 
-    if let Some(Value::Array(added)) = map.get("dictionary_item_added") {
+    for report_type in ["dictionary_item_added", "attribute_added"] {
+        let Some(Value::Array(added)) = map.get(report_type) else {
+            continue;
+        };
         for path in added {
             if let Value::String(path) = path {
                 if let Some(segments) = parse_path(path) {
-                    let value = get_value_at_path(t2, &segments).cloned();
+                    let value = new_side(path, report_type, t1, t2, options)
+                        .and_then(|at| get_value_at_path(t2, &at))
+                        .cloned();
                     changes.push(ChangeEntry {
                         segments,
                         kind: ChangeKind::Added { value },
@@ -212,7 +295,10 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
         }
     }
 
-    if let Some(Value::Array(removed)) = map.get("dictionary_item_removed") {
+    for report_type in ["dictionary_item_removed", "attribute_removed"] {
+        let Some(Value::Array(removed)) = map.get(report_type) else {
+            continue;
+        };
         for path in removed {
             if let Value::String(path) = path {
                 if let Some(segments) = parse_path(path) {
@@ -229,7 +315,8 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
     if let Some(Value::Object(added)) = map.get("iterable_item_added") {
         for (path, value) in added {
             if let Some(segments) = parse_path(path) {
-                let value = get_value_at_path(t2, &segments)
+                let value = new_side(path, "iterable_item_added", t1, t2, options)
+                    .and_then(|at| get_value_at_path(t2, &at))
                     .cloned()
                     .or_else(|| Some(value.clone()));
                 changes.push(ChangeEntry {
@@ -254,86 +341,150 @@ fn collect_changes(result: &Value, t1: &Value, t2: &Value) -> Vec<ChangeEntry> {
         }
     }
 
+    if let Some(Value::Object(moved)) = map.get("iterable_item_moved") {
+        for (path, entry) in moved {
+            let new_path = entry.get("new_path").and_then(Value::as_str);
+            if let (Some(segments), Some(new_path)) = (parse_path(path), new_path) {
+                let new_path = new_side(new_path, "iterable_item_moved", t1, t2, options)
+                    .map(|at| format_compact_path(&at, options))
+                    .unwrap_or_else(|| new_path.to_string());
+                changes.push(ChangeEntry {
+                    segments,
+                    kind: ChangeKind::Moved {
+                        value: entry.get("value").cloned().unwrap_or(Value::Null),
+                        new_path,
+                    },
+                });
+            }
+        }
+    }
+
+    if let Some(Value::Object(repeated)) = map.get("repetition_change") {
+        for (path, entry) in repeated {
+            if let Some(segments) = parse_path(path) {
+                let count = |name: &str| entry.get(name).and_then(Value::as_u64).unwrap_or(0);
+                changes.push(ChangeEntry {
+                    segments,
+                    kind: ChangeKind::Repeated {
+                        value: entry.get("value").cloned().unwrap_or(Value::Null),
+                        old: count("old_repeat") as usize,
+                        new: count("new_repeat") as usize,
+                    },
+                });
+            }
+        }
+    }
+
     changes
 }
 
-fn build_tree(changes: Vec<ChangeEntry>) -> PrettyNode {
-    let mut root = PrettyNode::root();
+/// Replaces each run of at least `min_run` changes of one kind at
+/// consecutive indices of one list with a single range change.
+fn collapse_runs(changes: Vec<ChangeEntry>, min_run: usize) -> Vec<ChangeEntry> {
+    type Run = BTreeMap<usize, ChangeEntry>;
+    let mut lists: BTreeMap<(Vec<PathSegment>, RangeChange), Run> = BTreeMap::new();
+    let mut kept = Vec::new();
     for change in changes {
-        root.add_change(change.segments, change.kind);
+        match (change.segments.split_last(), change.kind.range_change()) {
+            (Some((PathSegment::Index(idx), parent)), Some(range_change)) => {
+                let idx = *idx;
+                lists
+                    .entry((parent.to_vec(), range_change))
+                    .or_default()
+                    .insert(idx, change);
+            }
+            _ => kept.push(change),
+        }
     }
-    root
+    for ((parent, range_change), items) in lists {
+        let indices: Vec<usize> = items.keys().copied().collect();
+        let mut items = items.into_values();
+        for run in consecutive_runs(&indices) {
+            let run_items: Vec<ChangeEntry> = items.by_ref().take(run.len()).collect();
+            if run.len() < min_run {
+                kept.extend(run_items);
+                continue;
+            }
+            let mut segments = parent.clone();
+            segments.push(PathSegment::Index(indices[run.start]));
+            kept.push(ChangeEntry {
+                segments,
+                kind: ChangeKind::Range {
+                    end: indices[run.end - 1] + 1,
+                    count: run.len(),
+                    change: range_change,
+                },
+            });
+        }
+    }
+    kept
 }
 
-fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
-    if !path.starts_with("root") {
-        return None;
-    }
-    let mut segments = Vec::new();
-    let mut i = 4;
-    while i < path.len() {
-        if path[i..].starts_with("['") {
-            i += 2;
-            let end = path[i..].find("']")?;
-            let key = &path[i..i + end];
-            segments.push(PathSegment::Key(key.to_string()));
-            i += end + 2;
-        } else if path.as_bytes().get(i) == Some(&b'[') {
-            i += 1;
-            let end = path[i..].find(']')?;
-            let idx = path[i..i + end].parse::<usize>().ok()?;
-            segments.push(PathSegment::Index(idx));
-            i += end + 1;
-        } else {
-            break;
+/// `label`, ending in the first index of a range change, with the whole
+/// range in place of that index.
+fn range_label(label: String, segments: &[PathSegment], change: &ChangeKind) -> String {
+    match (change, segments.last()) {
+        (ChangeKind::Range { end, .. }, Some(PathSegment::Index(start))) => {
+            let index = format_index_label(*start);
+            let prefix = label.strip_suffix(&index).unwrap_or(&label);
+            format!("{}[{}:{}]", prefix, start, end)
         }
+        _ => label,
     }
-    Some(segments)
 }
 
-fn get_value_at_path<'a>(root: &'a Value, segments: &[PathSegment]) -> Option<&'a Value> {
-    let mut current = root;
-    for segment in segments {
-        match (segment, current) {
-            (PathSegment::Key(key), Value::Object(map)) => {
-                current = map.get(key)?;
-            }
-            (PathSegment::Index(idx), Value::Array(list)) => {
-                current = list.get(*idx)?;
-            }
-            _ => return None,
-        }
+fn build_tree(changes: Vec<ChangeEntry>) -> PrettyNode {
+    let mut root = PrettyNode::root();
+    for change in changes {
+        root.add_change(change.segments, change.kind);
     }
-    Some(current)
+    root
 }
 
-fn format_compact_path(segments: &[PathSegment]) -> String {
+fn format_compact_path(segments: &[PathSegment], labels: &DeepDiffOptions) -> String {
+    if let Some((label, rest)) = aliased(segments, labels) {
+        return format!("{}{}", label, format_compact_segments(&segments[rest..], 1));
+    }
     if segments.is_empty() {
-        return "root".to_string();
+        return labels.root_label.clone();
     }
-    format_compact_segments(segments)
+    format_compact_segments(segments, 0)
 }
 
-fn format_compact_segments(segments: &[PathSegment]) -> String {
+/// The label of the first path alias matching `segments` or one of their
+/// prefixes, and where the steps after the matched prefix start.
+fn aliased(segments: &[PathSegment], labels: &DeepDiffOptions) -> Option<(String, usize)> {
+    if labels.path_aliases.is_empty() {
+        return None;
+    }
+    let prefixes: Vec<String> = (0..=segments.len())
+        .map(|end| format_path("root", &segments[..end]))
+        .collect();
+    labels.path_aliases.iter().find_map(|alias| {
+        prefixes.iter().enumerate().find_map(|(end, prefix)| {
+            let (label, rest) = alias.split(prefix)?;
+            rest.is_empty().then_some((label, end))
+        })
+    })
+}
+
+/// `segments` in the compact notation, as if `depth` steps came before them.
+fn format_compact_segments(segments: &[PathSegment], depth: usize) -> String {
     let mut out = String::new();
     for (idx, segment) in segments.iter().enumerate() {
+        let idx = idx + depth;
         match segment {
+            PathSegment::Key(key) if decode_key(key).is_some() => {
+                out.push_str(&key_segment(key));
+            }
             PathSegment::Key(key) => {
-                if idx == 0 {
-                    if is_simple_identifier(key) {
-                        out.push_str(key);
-                    } else {
-                        out.push_str("['");
-                        out.push_str(key);
-                        out.push_str("']");
+                if is_simple_identifier(key) {
+                    if idx > 0 {
+                        out.push('.');
                     }
-                } else if is_simple_identifier(key) {
-                    out.push('.');
                     out.push_str(key);
                 } else {
-                    out.push_str("['");
-                    out.push_str(key);
-                    out.push_str("']");
+                    out.push_str(&key_segment(key));
                 }
             }
             PathSegment::Index(i) => {
@@ -341,6 +492,12 @@ fn format_compact_segments(segments: &[PathSegment]) -> String {
                 out.push_str(&i.to_string());
                 out.push(']');
             }
+            PathSegment::Attr(name) => {
+                if idx > 0 {
+                    out.push('.');
+                }
+                out.push_str(name);
+            }
         }
     }
     out
@@ -359,8 +516,12 @@ fn is_simple_identifier(value: &str) -> bool {
 
 fn format_segment_label(segment: &PathSegment) -> String {
     match segment {
-        PathSegment::Key(key) => key.to_string(),
+        PathSegment::Key(key) => match decode_key(key) {
+            Some(value) => format_value(&value),
+            None => key.to_string(),
+        },
         PathSegment::Index(i) => format_index_label(*i),
+        PathSegment::Attr(name) => name.to_string(),
     }
 }
 
@@ -368,7 +529,20 @@ fn format_index_label(index: usize) -> String {
     format!("[{}]", index)
 }
 
-fn format_value(value: &Value) -> String {
+pub(crate) fn format_value(value: &Value) -> String {
+    if let Some((_, qualname, member)) = as_enum_member(value) {
+        return format!("{}.{}", qualname, member);
+    }
+    if let Some((type_name, inner)) = as_tagged(value) {
+        return format_tagged_value(type_name, inner);
+    }
+    if let Some((class_name, attrs)) = as_object(value) {
+        let parts: Vec<String> = attrs
+            .iter()
+            .map(|(name, v)| format!("{}={}", name, format_value(v)))
+            .collect();
+        return format!("{}({})", class_name, parts.join(", "));
+    }
     match value {
         Value::Null => "None".to_string(),
         Value::Bool(b) => {
@@ -394,6 +568,41 @@ fn format_value(value: &Value) -> String {
     }
 }
 
+fn format_tagged_value(type_name: &str, inner: &Value) -> String {
+    match (type_name, inner) {
+        ("timedelta", Value::Number(micros)) => {
+            let seconds = micros.as_f64().unwrap_or(0.0) / 1e6;
+            format!("timedelta(seconds={})", seconds)
+        }
+        ("cycle", _) => "...".to_string(),
+        ("int" | "float", Value::String(text)) => text.clone(),
+        ("DataFrame" | "Table", Value::Object(frame)) => {
+            let rows = frame
+                .get("index")
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            let columns = frame
+                .get("dtypes")
+                .and_then(Value::as_object)
+                .map_or(0, |c| c.len());
+            format!("{}(shape=({}, {}))", type_name, rows, columns)
+        }
+        ("tuple", Value::Array(items)) => {
+            let inner: Vec<String> = items.iter().map(format_value).collect();
+            if inner.len() == 1 {
+                format!("({},)", inner[0])
+            } else {
+                format!("({})", inner.join(", "))
+            }
+        }
+        ("bytes", Value::Object(map)) => {
+            let text = map.get("text").and_then(Value::as_str).unwrap_or_default();
+            format!("b'{}'", escape_string(text))
+        }
+        _ => format!("{}({})", type_name, format_value(inner)),
+    }
+}
+
 fn escape_string(value: &str) -> String {
     let mut out = String::new();
     for ch in value.chars() {
@@ -403,6 +612,7 @@ fn escape_string(value: &str) -> String {
             '\n' => out.push_str("\\n"),
             '\r' => out.push_str("\\r"),
             '\t' => out.push_str("\\t"),
+            _ if ch.is_control() => push_escaped_char(&mut out, ch),
             _ => out.push(ch),
         }
     }
@@ -419,6 +629,8 @@ struct RenderEnv<'a> {
     t1: &'a Value,
     t2: &'a Value,
     options: &'a PrettyOptions,
+    labels: &'a DeepDiffOptions,
+    annotations: Option<&'a Map<String, Value>>,
 }
 
 fn render_children(
@@ -436,7 +648,7 @@ fn render_children(
     let v1 = get_value_at_path(env.t1, path);
     let v2 = get_value_at_path(env.t2, path);
 
-    if let Some(keys) = object_keys_union(v1, v2) {
+    if let Some(keys) = object_keys_union(v1, v2, env.labels.sort_paths) {
         let mut changed = HashSet::new();
         for child in &node.children {
             if let Some(PathSegment::Key(key)) = &child.segment {
@@ -467,9 +679,14 @@ fn render_children(
 
     if let Some(len) = array_length_union(v1, v2) {
         let mut changed_indices = HashSet::new();
+        let mut collapsed = HashSet::new();
         for child in &node.children {
             if let Some(PathSegment::Index(idx)) = child.segment.as_ref() {
                 changed_indices.insert(*idx);
+                if let Some(ChangeKind::Range { end, .. }) = &child.change {
+                    changed_indices.insert(end - 1);
+                    collapsed.extend(idx + 1..*end);
+                }
             }
         }
 
@@ -492,6 +709,9 @@ fn render_children(
         let mut seen = false;
         let mut pending_ellipsis = false;
         for idx in 0..len {
+            if collapsed.contains(&idx) {
+                continue;
+            }
             if display_indices.contains(&idx) {
                 if pending_ellipsis {
                     entries.push(RenderEntry::Ellipsis);
@@ -556,7 +776,7 @@ fn render_node(
     env: &RenderEnv<'_>,
     lines: &mut Vec<String>,
 ) {
-    let (label, node_ref, node_path) = if env.options.compact {
+    let (mut label, node_ref, node_path) = if env.options.compact {
         compress_node(node, parent_path)
     } else {
         let segment = node
@@ -567,11 +787,29 @@ fn render_node(
         next_path.push(segment.clone());
         (format_segment_label(segment), node, next_path)
     };
+    // A node at a path an alias names exactly is labeled with the alias.
+    if let Some((alias, end)) = aliased(&node_path, env.labels) {
+        if end == node_path.len() {
+            label = alias;
+        }
+    }
 
+    if let Some(change) = &node_ref.change {
+        label = range_label(label, &node_path, change);
+    }
     lines.push(format_node_line(depth, branches, is_last, &label));
 
     if let Some(change) = &node_ref.change {
         append_change_lines(lines, depth, branches, !is_last, change, env.options);
+        append_annotation_lines(
+            lines,
+            depth,
+            branches,
+            !is_last,
+            &node_path,
+            env.annotations,
+            env.options,
+        );
     }
 
     let mut child_branches = branches.to_vec();
@@ -614,7 +852,7 @@ fn compress_node<'a>(
         }
     }
 
-    (format_compact_segments(&parts), current, path)
+    (format_compact_segments(&parts, 0), current, path)
 }
 
 fn format_node_line(depth: usize, branches: &[bool], is_last: bool, label: &str) -> String {
@@ -697,6 +935,70 @@ fn append_change_lines(
                 colorize(&format!("- {}", rendered), "31", !options.no_color)
             ));
         }
+        ChangeKind::Moved { value, new_path } => {
+            lines.push(format!(
+                "{}{}",
+                indent,
+                colorize(
+                    &format!("~ {} moved to {}", format_value(value), new_path),
+                    "33",
+                    !options.no_color
+                )
+            ));
+        }
+        ChangeKind::Repeated { value, old, new } => {
+            lines.push(format!(
+                "{}{}",
+                indent,
+                colorize(
+                    &format!("~ {} repeated {} → {} times", format_value(value), old, new),
+                    "33",
+                    !options.no_color
+                )
+            ));
+        }
+        ChangeKind::Range { count, change, .. } => {
+            let (line, color) = match change {
+                RangeChange::Changed => (format!("~ {} values changed", count), "33"),
+                RangeChange::Added => (format!("+ {} items", count), "32"),
+                RangeChange::Removed => (format!("- {} items", count), "31"),
+            };
+            lines.push(format!(
+                "{}{}",
+                indent,
+                colorize(&line, color, !options.no_color)
+            ));
+        }
+    }
+}
+
+/// Renders the fields the `annotate` callback attached to the change at
+/// `path` as `# field: value` lines under it.
+fn append_annotation_lines(
+    lines: &mut Vec<String>,
+    depth: usize,
+    branches: &[bool],
+    node_has_more: bool,
+    path: &[PathSegment],
+    annotations: Option<&Map<String, Value>>,
+    options: &PrettyOptions,
+) {
+    let Some(Value::Object(fields)) =
+        annotations.and_then(|annotations| annotations.get(&format_path("root", path)))
+    else {
+        return;
+    };
+    let indent = branch_indent(depth, branches, node_has_more);
+    for (field, value) in fields {
+        let value = match value {
+            Value::String(text) => text.clone(),
+            other => format_value(other),
+        };
+        lines.push(format!(
+            "{}{}",
+            indent,
+            colorize(&format!("# {}: {}", field, value), "90", !options.no_color)
+        ));
     }
 }
 
@@ -733,22 +1035,20 @@ fn colorize(text: &str, code: &str, enabled: bool) -> String {
     }
 }
 
-fn object_keys_union(v1: Option<&Value>, v2: Option<&Value>) -> Option<Vec<String>> {
+/// The keys of the new dict in document order, then those only the old one
+/// has; each group sorted with `sorted`.
+fn object_keys_union(v1: Option<&Value>, v2: Option<&Value>, sorted: bool) -> Option<Vec<String>> {
     let mut keys = Vec::new();
     let mut seen = HashSet::new();
 
-    if let Some(Value::Object(map)) = v2 {
-        for key in map.keys() {
-            if seen.insert(key.clone()) {
-                keys.push(key.clone());
-            }
+    for map in [v2, v1].into_iter().flatten().filter_map(plain_map) {
+        let mut group: Vec<&String> = map.keys().filter(|key| !seen.contains(*key)).collect();
+        if sorted {
+            group.sort();
         }
-    }
-    if let Some(Value::Object(map)) = v1 {
-        for key in map.keys() {
-            if seen.insert(key.clone()) {
-                keys.push(key.clone());
-            }
+        for key in group {
+            seen.insert(key.clone());
+            keys.push(key.clone());
         }
     }
 
@@ -759,15 +1059,18 @@ fn object_keys_union(v1: Option<&Value>, v2: Option<&Value>) -> Option<Vec<Strin
     }
 }
 
+/// Dict entries of `value`, excluding the maps used to encode tagged values
+/// and objects.
+fn plain_map(value: &Value) -> Option<&serde_json::Map<String, Value>> {
+    match value {
+        Value::Object(map) if !is_tagged(value) && as_object(value).is_none() => Some(map),
+        _ => None,
+    }
+}
+
 fn array_length_union(v1: Option<&Value>, v2: Option<&Value>) -> Option<usize> {
-    let len1 = match v1 {
-        Some(Value::Array(list)) => list.len(),
-        _ => 0,
-    };
-    let len2 = match v2 {
-        Some(Value::Array(list)) => list.len(),
-        _ => 0,
-    };
+    let len1 = v1.and_then(sequence_items).map_or(0, Vec::len);
+    let len2 = v2.and_then(sequence_items).map_or(0, Vec::len);
     let len = len1.max(len2);
     if len == 0 {
         None
@@ -1,14 +1,42 @@
 use crate::engine::canonical_string;
-use crate::options::{DeepDiffOptions, PrettyOptions, ValueType};
-use crate::DeepDiff;
-use pyo3::exceptions::{PyTypeError, PyValueError};
+use crate::options::{
+    closest_match, Annotator, DeepDiffOptions, NumberFormat, NumberFormatter, ObjCallback,
+    OptionsError, PrettyOptions, ValueType,
+};
+use crate::path::{format_path, get_value_at_path, parse_path, PathSegment};
+use crate::reverse::counterpart;
+use crate::tagged::{as_object, as_tagged, decode_key, encode_key, float_value, object, tagged};
+use crate::{
+    diff_result, for_each_change, Change, ChangeKind, Checkpoint, ClassifiedReport, DeepDiff,
+    Delta, DiffJob, Rules, Sampling, SeverityRules, TurboDiffError,
+};
+#[cfg(feature = "numpy")]
+use numpy::PyArrayMethods;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyUserWarning, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyAny, PyBytes, PyDict, PyFrozenSet, PyList, PySet, PyTuple, PyType};
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{
+    PyAny, PyBool, PyBytes, PyDict, PyFloat, PyFrozenSet, PyIterator, PyList, PyLong, PySet,
+    PyString, PyTuple, PyType,
+};
+use pyo3::{PyTraverseError, PyVisit};
 use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Text,
+    Tree,
+}
 
 #[pyclass(name = "DeepDiff")]
 struct PyDeepDiff {
     inner: DeepDiff,
+    view: View,
 }
 
 #[pymethods]
@@ -20,20 +48,188 @@ impl PyDeepDiff {
         t2: &Bound<'_, PyAny>,
         kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Self> {
-        let t1_val = value_from_py(t1)?;
-        let t2_val = value_from_py(t2)?;
-        let options = options_from_kwargs(kwargs)?;
-        Ok(Self {
-            inner: DeepDiff::with_options(t1_val, t2_val, options),
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+        let (t1_val, t2_val) = values_from_py(t1, t2, &convert)?;
+        let inner = DeepDiff::with_options(t1_val, t2_val, options);
+        errors.take()?;
+        Ok(Self { inner, view })
+    }
+
+    /// Diffs two JSON documents given as `str` or `bytes`, parsing them in Rust
+    /// instead of converting Python objects.
+    #[staticmethod]
+    #[pyo3(signature = (t1_json, t2_json, **kwargs))]
+    fn from_json(
+        py: Python<'_>,
+        t1_json: &Bound<'_, PyAny>,
+        t2_json: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, _) = options_from_kwargs(kwargs, &errors)?;
+        let t1_val = json_from_py(py, t1_json)?;
+        let t2_val = json_from_py(py, t2_json)?;
+        let inner = DeepDiff::with_options(t1_val, t2_val, options);
+        errors.take()?;
+        Ok(Self { inner, view })
+    }
+
+    /// Converts `t1` and `t2` on the calling thread and diffs them on a worker
+    /// thread without holding the GIL. Returns a `concurrent.futures.Future`
+    /// resolving to the `DeepDiff`.
+    #[staticmethod]
+    #[pyo3(signature = (t1, t2, **kwargs))]
+    fn compute_async(
+        py: Python<'_>,
+        t1: &Bound<'_, PyAny>,
+        t2: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+        let (t1_val, t2_val) = values_from_py(t1, t2, &convert)?;
+        let future = py
+            .import_bound("concurrent.futures")?
+            .getattr("Future")?
+            .call0()?;
+        future.call_method0("set_running_or_notify_cancel")?;
+        let future = future.unbind();
+        let handle = future.clone_ref(py);
+        std::thread::Builder::new()
+            .name("turbodiff-compute".to_string())
+            .spawn(move || {
+                let inner = DeepDiff::with_options(t1_val, t2_val, options);
+                Python::with_gil(|py| {
+                    let outcome = errors
+                        .take()
+                        .and_then(|()| Py::new(py, Self { inner, view }));
+                    let settled = match outcome {
+                        Ok(diff) => handle.call_method1(py, "set_result", (diff,)),
+                        Err(err) => handle.call_method1(py, "set_exception", (err,)),
+                    };
+                    if let Err(err) = settled {
+                        err.write_unraisable_bound(py, None);
+                    }
+                });
+            })
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(future)
+    }
+
+    /// Diffs `t1` against `t2` and `t2` against `t1` in one traversal;
+    /// returns the two `DeepDiff`s in that order.
+    #[staticmethod]
+    #[pyo3(signature = (t1, t2, **kwargs))]
+    fn symmetric(
+        py: Python<'_>,
+        t1: &Bound<'_, PyAny>,
+        t2: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<(Self, Self)> {
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+        let (t1_val, t2_val) = values_from_py(t1, t2, &convert)?;
+        let both = py.allow_threads(|| DeepDiff::symmetric(t1_val, t2_val, options));
+        errors.take()?;
+        Ok((
+            Self {
+                inner: both.forward,
+                view,
+            },
+            Self {
+                inner: both.backward,
+                view,
+            },
+        ))
+    }
+
+    /// Diffs every `(t1, t2)` pair in `pairs` with the same options, parsed
+    /// once. The pairs are converted under the GIL and diffed across threads
+    /// without it; the `DeepDiff`s come back in the order of `pairs`.
+    #[staticmethod]
+    #[pyo3(signature = (pairs, **kwargs))]
+    fn diff_many(
+        py: Python<'_>,
+        pairs: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Vec<Self>> {
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+        let mut values = Vec::new();
+        for pair in pairs.iter()? {
+            let (t1, t2): (Bound<'_, PyAny>, Bound<'_, PyAny>) = pair?.extract()?;
+            values.push(values_from_py(&t1, &t2, &convert)?);
+        }
+        let diffs = py.allow_threads(|| DeepDiff::diff_many(values, options));
+        errors.take()?;
+        Ok(diffs
+            .into_iter()
+            .map(|inner| Self { inner, view })
+            .collect())
+    }
+
+    /// Change counts by path depth and by path prefix:
+    /// `{"by_depth": {depth: n}, "by_prefix": {path: n}, "total": n}`.
+    #[pyo3(signature = (prefix_depth = 1))]
+    fn change_stats(&self, py: Python<'_>, prefix_depth: usize) -> PyResult<PyObject> {
+        let stats = self.inner.change_stats(prefix_depth);
+        let dict = PyDict::new_bound(py);
+        dict.set_item("by_depth", stats.by_depth.into_py(py))?;
+        dict.set_item("by_prefix", stats.by_prefix.into_py(py))?;
+        dict.set_item("total", stats.total)?;
+        Ok(dict.into_py(py))
+    }
+
+    /// The changes from `offset` on, at most `limit` of them, as dicts of
+    /// `kind`, `path`, and the values and types the result holds.
+    fn changes_page(&self, py: Python<'_>, offset: usize, limit: usize) -> PyResult<PyObject> {
+        changes_to_py(py, &self.inner.changes_page(offset, limit))
+    }
+
+    /// Iterates over the changes in lists of at most `chunk_size`, in the
+    /// order of `changes_page`.
+    #[pyo3(signature = (chunk_size = 1000))]
+    fn iter_changes(slf: Py<Self>, chunk_size: usize) -> PyResult<PyChangePages> {
+        if chunk_size == 0 {
+            return Err(PyValueError::new_err("chunk_size must be at least 1"));
+        }
+        Ok(PyChangePages {
+            diff: slf,
+            offset: 0,
+            chunk_size,
         })
     }
 
+    /// The diff of `t2` against `t1`, derived from this one without diffing
+    /// again.
+    fn invert(&self) -> Self {
+        Self {
+            inner: self.inner.invert(),
+            view: self.view,
+        }
+    }
+
     fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
-        value_to_py(py, &self.inner.to_value())
+        match self.view {
+            View::Text => value_to_py(py, &self.inner.to_value()),
+            View::Tree => tree_to_py(py, &self.inner),
+        }
+    }
+
+    fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<PyObject> {
+        let dict = self.to_dict(py)?;
+        let item = dict.bind(py).get_item(key)?;
+        Ok(item.unbind())
     }
 
     fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
-        let value = value_to_py(py, &self.inner.to_value())?;
+        let value = self.to_dict(py)?;
         Ok(format!("DeepDiff({})", value.bind(py).repr()?))
     }
 
@@ -42,87 +238,1154 @@ impl PyDeepDiff {
     }
 
     fn __len__(&self) -> usize {
-        match &self.inner.to_value() {
-            Value::Object(map) => map.len(),
-            _ => 0,
-        }
+        self.inner.len()
+            + usize::from(self.inner.is_truncated())
+            + usize::from(self.inner.reached_max_diffs())
+            + usize::from(self.inner.sampling().is_some())
+            + usize::from(!self.inner.annotations().is_empty())
+            + usize::from(self.inner.options.get_deep_distance)
     }
 
-    #[pyo3(signature = (*, compact = false, max_depth = 5, context = 0, no_color = false, path_header = false))]
-    fn pretty(
+    /// Classifies each change by severity: `rules` is rules text, one
+    /// `pattern => severity` per line, or a mapping of patterns to
+    /// severities. Returns `{"changes": [...], "verdict": ...}`.
+    #[pyo3(signature = (rules, *, default = "info"))]
+    fn classify(
         &self,
+        py: Python<'_>,
+        rules: &Bound<'_, PyAny>,
+        default: &str,
+    ) -> PyResult<PyObject> {
+        let text = format!("* => {}\n{}", default, rules_text(rules)?);
+        let rules =
+            SeverityRules::parse(&text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        classified_to_py(py, self.inner.classify(&rules))
+    }
+
+    /// Classifies the changes, old against new, as backward compatible or
+    /// breaking; `rules`, as for `classify`, take precedence over the
+    /// built-in ones. Adds `"compatible": bool` to the `classify` result.
+    #[pyo3(signature = (rules = None))]
+    fn api_compat(&self, py: Python<'_>, rules: Option<&Bound<'_, PyAny>>) -> PyResult<PyObject> {
+        let text = match rules {
+            Some(rules) => rules_text(rules)?,
+            None => String::new(),
+        };
+        let rules = SeverityRules::api_compat()
+            .read(&text)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        classified_to_py(py, self.inner.classify(&rules))
+    }
+
+    /// The `values_changed` entries scored by `magnitude_scores` with a
+    /// `magnitude` of at least `min_magnitude`, largest first.
+    #[pyo3(signature = (min_magnitude = 0.0))]
+    fn by_magnitude(&self, py: Python<'_>, min_magnitude: f64) -> PyResult<PyObject> {
+        let report = self.inner.report();
+        let changes = serde_json::to_value(report.by_magnitude(min_magnitude))
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        value_to_py(py, &changes)
+    }
+
+    /// Renders the diff as a tree (the default) or, with `style="deepdiff"`,
+    /// as DeepDiff's one sentence per change, each line starting with `prefix`.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (*, compact = false, max_depth = 5, context = 0, no_color = false, path_header = false, style = "tree", prefix = None))]
+    fn pretty(
+        slf: &Bound<'_, Self>,
         compact: bool,
         max_depth: usize,
         context: usize,
         no_color: bool,
         path_header: bool,
+        style: &str,
+        prefix: Option<&Bound<'_, PyAny>>,
     ) -> PyResult<String> {
-        Ok(self.inner.pretty(PrettyOptions {
-            compact,
-            max_depth,
-            context,
-            no_color,
-            path_header,
-        }))
+        match style {
+            "tree" => Ok(slf.borrow().inner.pretty(PrettyOptions {
+                compact,
+                max_depth,
+                context,
+                no_color,
+                path_header,
+            })),
+            "deepdiff" => {
+                let prefix = match prefix {
+                    Some(prefix) if prefix.is_callable() => {
+                        let kwargs = PyDict::new_bound(slf.py());
+                        kwargs.set_item("diff", slf)?;
+                        prefix.call((), Some(&kwargs))?.str()?.extract()?
+                    }
+                    Some(prefix) if !prefix.is_none() => prefix.str()?.extract()?,
+                    _ => String::new(),
+                };
+                let lines = deepdiff_sentences(slf.py(), &slf.borrow().inner)?;
+                Ok(lines
+                    .iter()
+                    .map(|line| format!("{}{}", prefix, line))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            other => Err(PyValueError::new_err(format!(
+                "Unsupported style: {} (expected 'tree' or 'deepdiff')",
+                other
+            ))),
+        }
+    }
+}
+
+/// Severity rules text from rules text or a mapping of patterns to
+/// severities.
+fn rules_text(rules: &Bound<'_, PyAny>) -> PyResult<String> {
+    let Ok(mapping) = rules.downcast::<PyDict>() else {
+        return rules.extract();
+    };
+    let mut text = String::new();
+    for (pattern, severity) in mapping.iter() {
+        let pattern: String = pattern.extract()?;
+        let severity: String = severity.extract()?;
+        text.push_str(&format!("{} => {}\n", pattern, severity));
+    }
+    Ok(text)
+}
+
+/// A rules document given as a dict or list, or as the path of a JSON or
+/// YAML file (YAML needs PyYAML).
+fn rules_document(value: &Bound<'_, PyAny>) -> PyResult<Rules> {
+    let invalid = |err: TurboDiffError| PyValueError::new_err(err.to_string());
+    if value.downcast::<PyDict>().is_ok() || value.downcast::<PyList>().is_ok() {
+        return Rules::from_value(value_from_py(value, &ConvertOptions::default())?)
+            .map_err(invalid);
+    }
+    let path: PathBuf = value.extract().map_err(|_| {
+        PyTypeError::new_err("rules must be a dict, a list, or the path of a JSON or YAML file")
+    })?;
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| PyValueError::new_err(format!("{}: {}", path.display(), err)))?;
+    let yaml = path
+        .extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml");
+    if !yaml {
+        return Rules::from_json(&text).map_err(invalid);
+    }
+    let loaded = value
+        .py()
+        .import_bound("yaml")?
+        .call_method1("safe_load", (text,))?;
+    Rules::from_value(value_from_py(&loaded, &ConvertOptions::default())?).map_err(invalid)
+}
+
+/// `sampling`: `None`, a rate, or a dict of `rate`, `threshold`, and `seed`.
+fn sampling_option(value: &Bound<'_, PyAny>) -> PyResult<Option<Sampling>> {
+    if value.is_none() {
+        return Ok(None);
+    }
+    if let Ok(rate) = value.extract::<f64>() {
+        return Ok(Some(Sampling::new(rate)));
+    }
+    let settings = value_from_py(value, &ConvertOptions::default())?;
+    serde_json::from_value(settings)
+        .map(Some)
+        .map_err(|err| PyValueError::new_err(format!("invalid sampling: {}", err)))
+}
+
+fn classified_to_py(py: Python<'_>, report: ClassifiedReport) -> PyResult<PyObject> {
+    let compatible = report.is_compatible();
+    let mut value =
+        serde_json::to_value(report).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    if let Value::Object(map) = &mut value {
+        map.insert("compatible".to_string(), Value::Bool(compatible));
     }
+    value_to_py(py, &value)
+}
+
+/// DeepDiff's `pretty()` sentences, one per change, with report types in
+/// alphabetical order.
+fn deepdiff_sentences(py: Python<'_>, diff: &DeepDiff) -> PyResult<Vec<String>> {
+    let Value::Object(result) = &diff.result else {
+        return Ok(Vec::new());
+    };
+    let mut report_types: Vec<&String> = result
+        .keys()
+        .filter(|key| ChangeKind::from_key(key).is_some())
+        .collect();
+    report_types.sort();
+    let (shown_t1, shown_t2) = diff.shown_sides();
+    let mut lines = Vec::new();
+    for report_type in report_types {
+        let paths: Vec<&str> = match &result[report_type.as_str()] {
+            Value::Object(map) => map.keys().map(String::as_str).collect(),
+            Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for path in paths {
+            let shown_path = diff.options.display_path(path);
+            let new_path = match ChangeKind::from_key(report_type) {
+                Some(kind) if diff.options.align_lists => {
+                    counterpart(path, kind, &diff.t1, &diff.t2, &diff.options)
+                }
+                _ => path.to_string(),
+            };
+            let side = |root: &Value, path: &str| -> PyResult<Bound<'_, PyAny>> {
+                let value =
+                    parse_path(path).and_then(|segments| get_value_at_path(root, &segments));
+                match value {
+                    Some(value) => Ok(value_to_py(py, value)?.into_bound(py)),
+                    None => Ok(not_present(py)?.into_bound(py)),
+                }
+            };
+            let (t1, t2) = (side(&shown_t1, path)?, side(&shown_t2, &new_path)?);
+            let type_t1: String = t1.get_type().getattr("__name__")?.extract()?;
+            let type_t2: String = t2.get_type().getattr("__name__")?.extract()?;
+            let text = |value: &Bound<'_, PyAny>, type_name: &str| -> PyResult<String> {
+                let text: String = value.str()?.extract()?;
+                Ok(if type_name == "str" {
+                    format!("\"{}\"", text)
+                } else {
+                    text
+                })
+            };
+            let line = match report_type.as_str() {
+                "type_changes" => format!(
+                    "Type of {} changed from {} to {} and value changed from {} to {}.",
+                    shown_path,
+                    type_t1,
+                    type_t2,
+                    text(&t1, &type_t1)?,
+                    text(&t2, &type_t2)?
+                ),
+                "values_changed" => format!(
+                    "Value of {} changed from {} to {}.",
+                    shown_path,
+                    text(&t1, &type_t1)?,
+                    text(&t2, &type_t2)?
+                ),
+                "dictionary_item_added" => format!("Item {} added to dictionary.", shown_path),
+                "dictionary_item_removed" => {
+                    format!("Item {} removed from dictionary.", shown_path)
+                }
+                "iterable_item_added" => format!("Item {} added to iterable.", shown_path),
+                "iterable_item_removed" => format!("Item {} removed from iterable.", shown_path),
+                "iterable_item_moved" => {
+                    let moved_to = result[report_type.as_str()][path]["new_path"]
+                        .as_str()
+                        .map(|new_path| diff.options.display_path(new_path))
+                        .unwrap_or_default();
+                    format!("Item {} moved to {}.", shown_path, moved_to)
+                }
+                "repetition_change" => format!("Repetition change for item {}.", shown_path),
+                "attribute_added" => format!("Attribute {} added.", shown_path),
+                "attribute_removed" => format!("Attribute {} removed.", shown_path),
+                "schema_violations" => {
+                    let expected = result[report_type.as_str()][path]["expected_type"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string();
+                    format!(
+                        "Value of {} is not of schema type {}.",
+                        shown_path, expected
+                    )
+                }
+                _ => String::new(),
+            };
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+/// The changes of a diff one page at a time, as returned by
+/// `DeepDiff.iter_changes`.
+#[pyclass(name = "ChangePages")]
+struct PyChangePages {
+    diff: Py<PyDeepDiff>,
+    offset: usize,
+    chunk_size: usize,
+}
+
+#[pymethods]
+impl PyChangePages {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let diff = self.diff.borrow(py);
+        let page = diff.inner.changes_page(self.offset, self.chunk_size);
+        if page.is_empty() {
+            return Ok(None);
+        }
+        self.offset += page.len();
+        changes_to_py(py, &page).map(Some)
+    }
+}
+
+fn changes_to_py(py: Python<'_>, changes: &[Change<'_>]) -> PyResult<PyObject> {
+    let list = PyList::empty_bound(py);
+    for change in changes {
+        let value =
+            serde_json::to_value(change).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        list.append(value_to_py(py, &value)?)?;
+    }
+    Ok(list.into_py(py))
+}
+
+/// Placeholder for the missing side of an added or removed item in the tree view.
+#[pyclass(name = "NotPresent", frozen)]
+struct PyNotPresent;
+
+#[pymethods]
+impl PyNotPresent {
+    fn __repr__(&self) -> &'static str {
+        "not present"
+    }
+
+    fn __bool__(&self) -> bool {
+        false
+    }
+}
+
+static NOT_PRESENT: GILOnceCell<Py<PyNotPresent>> = GILOnceCell::new();
+
+fn not_present(py: Python<'_>) -> PyResult<PyObject> {
+    let obj = NOT_PRESENT.get_or_try_init(py, || Py::new(py, PyNotPresent))?;
+    Ok(obj.clone_ref(py).into_any())
+}
+
+/// One step on the path to a change, as returned by `view="tree"`.
+#[pyclass(name = "DiffLevel")]
+struct PyDiffLevel {
+    #[pyo3(get)]
+    t1: PyObject,
+    #[pyo3(get)]
+    t2: PyObject,
+    #[pyo3(get)]
+    report_type: Option<String>,
+    #[pyo3(get)]
+    up: Option<Py<PyDiffLevel>>,
+    #[pyo3(get)]
+    down: Option<Py<PyDiffLevel>>,
+    segments: Vec<PathSegment>,
+}
+
+#[pymethods]
+impl PyDiffLevel {
+    #[pyo3(signature = (root = "root", output_format = "str"))]
+    fn path(&self, py: Python<'_>, root: &str, output_format: &str) -> PyResult<PyObject> {
+        match output_format {
+            "str" => Ok(format_path(root, &self.segments).into_py(py)),
+            "list" => {
+                let list = PyList::empty_bound(py);
+                for segment in &self.segments {
+                    match segment {
+                        PathSegment::Key(key) => match decode_key(key) {
+                            Some(key) => list.append(value_to_py(py, &key)?)?,
+                            None => list.append(key)?,
+                        },
+                        PathSegment::Index(idx) => list.append(idx)?,
+                        PathSegment::Attr(name) => list.append(name)?,
+                    }
+                }
+                Ok(list.into_py(py))
+            }
+            _ => Err(PyValueError::new_err(
+                "output_format must be 'str' or 'list'",
+            )),
+        }
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "<{} t1:{}, t2:{}>",
+            format_path("root", &self.segments),
+            self.t1.bind(py).repr()?,
+            self.t2.bind(py).repr()?
+        ))
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        visit.call(&self.t1)?;
+        visit.call(&self.t2)?;
+        if let Some(up) = &self.up {
+            visit.call(up)?;
+        }
+        if let Some(down) = &self.down {
+            visit.call(down)?;
+        }
+        Ok(())
+    }
+
+    fn __clear__(&mut self) {
+        self.up = None;
+        self.down = None;
+    }
+}
+
+/// Returns the result dict of `DeepDiff(t1, t2, **kwargs).to_dict()` without
+/// building a `DeepDiff` object.
+#[pyfunction]
+#[pyo3(signature = (t1, t2, **kwargs))]
+fn diff(
+    py: Python<'_>,
+    t1: &Bound<'_, PyAny>,
+    t2: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    if view_from_kwargs(kwargs)? == View::Tree {
+        return Err(PyValueError::new_err(
+            "diff() only supports view='text'; use DeepDiff for the tree view",
+        ));
+    }
+    let errors = CallbackError::default();
+    let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+    let (t1, t2) = values_from_py(t1, t2, &convert)?;
+    let result = diff_result(&t1, &t2, &options);
+    errors.take()?;
+    value_to_py(py, &result)
+}
+
+/// Whether `t1` and `t2` compare equal under the options, stopping at the
+/// first difference instead of building a report.
+#[pyfunction]
+#[pyo3(signature = (t1, t2, **kwargs))]
+fn deep_equal(
+    py: Python<'_>,
+    t1: &Bound<'_, PyAny>,
+    t2: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<bool> {
+    let errors = CallbackError::default();
+    let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+    let (t1, t2) = values_from_py(t1, t2, &convert)?;
+    let equal = py.allow_threads(|| crate::deep_equal(&t1, &t2, &options));
+    errors.take()?;
+    Ok(equal)
+}
+
+/// Compares all of `documents` at once, returning `{"documents": n,
+/// "divergences": [...]}` with, for each path where they do not all agree,
+/// the documents grouped by the value they hold there.
+#[pyfunction]
+#[pyo3(signature = (documents, **kwargs))]
+fn compare_many(
+    py: Python<'_>,
+    documents: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let errors = CallbackError::default();
+    let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+    let documents = documents
+        .iter()?
+        .map(|document| value_from_py(&document?, &convert))
+        .collect::<PyResult<Vec<Value>>>()?;
+    let report = py.allow_threads(|| crate::compare_many(&documents, &options));
+    errors.take()?;
+    let value =
+        serde_json::to_value(report).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    value_to_py(py, &value)
+}
+
+/// Content hash of `obj` as hex, stable across runs and platforms: a
+/// SHA-256 digest by default, or the 16-digit hash of `hasher="xxhash"` or
+/// `"fx"`.
+#[pyfunction]
+#[pyo3(signature = (obj, hasher="sha256"))]
+fn deep_hash(obj: &Bound<'_, PyAny>, hasher: &str) -> PyResult<String> {
+    let algorithm = hasher
+        .parse()
+        .map_err(|err: OptionsError| PyValueError::new_err(err.to_string()))?;
+    let value = value_from_py(obj, &ConvertOptions::default())?;
+    Ok(crate::deep_hash(&value, algorithm))
+}
+
+/// Diffs `t1` against `t2`, passing each change to `sink` as one line of JSON
+/// as soon as it is found. `sink` is a callable or an object with a `write`
+/// method taking `str`, such as a text file or `socket.makefile("w")`.
+/// Returns the number of lines written.
+#[pyfunction]
+#[pyo3(signature = (t1, t2, sink, **kwargs))]
+fn write_changes(
+    t1: &Bound<'_, PyAny>,
+    t2: &Bound<'_, PyAny>,
+    sink: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<usize> {
+    let write = if sink.hasattr("write")? {
+        sink.getattr("write")?
+    } else if sink.is_callable() {
+        sink.clone()
+    } else {
+        return Err(PyTypeError::new_err(
+            "sink must be callable or have a write() method",
+        ));
+    };
+    let errors = CallbackError::default();
+    let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+    let (t1, t2) = values_from_py(t1, t2, &convert)?;
+    let mut written = 0;
+    for_each_change(&t1, &t2, &options, |change| {
+        if errors.is_set() {
+            return;
+        }
+        let line = serde_json::to_string(&change)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+            .and_then(|line| write.call1((line + "\n",)));
+        match line {
+            Ok(_) => written += 1,
+            Err(err) => errors.record(err),
+        }
+    });
+    errors.take()?;
+    Ok(written)
+}
+
+pyo3::create_exception!(turbodiff, DeltaError, PyValueError);
+
+/// A diff run in steps, which can be saved to a checkpoint file between
+/// steps and resumed from it, so a preempted batch job does not start over.
+#[pyclass(name = "DiffJob")]
+struct PyDiffJob {
+    inner: Option<DiffJob>,
+    view: View,
+    errors: CallbackError,
+}
+
+#[pymethods]
+impl PyDiffJob {
+    /// Starts diffing `t1` against `t2`, or picks up where the checkpoint
+    /// saved at `resume_from` left off; the inputs and options must be the
+    /// ones the job started with.
+    #[new]
+    #[pyo3(signature = (t1, t2, *, resume_from = None, **kwargs))]
+    fn new(
+        t1: &Bound<'_, PyAny>,
+        t2: &Bound<'_, PyAny>,
+        resume_from: Option<PathBuf>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let view = view_from_kwargs(kwargs)?;
+        let errors = CallbackError::default();
+        let (options, convert) = options_from_kwargs(kwargs, &errors)?;
+        let (t1, t2) = values_from_py(t1, t2, &convert)?;
+        let inner = match resume_from {
+            Some(path) => DiffJob::resume(t1, t2, options, Checkpoint::load(path)?)
+                .map_err(|err| PyValueError::new_err(err.to_string()))?,
+            None => DiffJob::new(t1, t2, options),
+        };
+        Ok(Self {
+            inner: Some(inner),
+            view,
+            errors,
+        })
+    }
+
+    #[getter]
+    fn done(&self) -> bool {
+        self.inner.as_ref().is_none_or(DiffJob::is_done)
+    }
+
+    /// Diffs up to `parts` parts of the inputs; returns whether the diff is
+    /// done.
+    #[pyo3(signature = (parts = 1))]
+    fn step(&mut self, parts: usize) -> PyResult<bool> {
+        let done = self.job()?.step(parts);
+        self.errors.take()?;
+        Ok(done)
+    }
+
+    /// Diffs until done or `seconds` have passed; returns whether the diff
+    /// is done.
+    fn run_for(&mut self, seconds: f64) -> PyResult<bool> {
+        let budget = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| PyValueError::new_err("seconds must be a non-negative number"))?;
+        let done = self.job()?.run_for(budget);
+        self.errors.take()?;
+        Ok(done)
+    }
+
+    /// Writes the progress so far to `path`, replacing any earlier
+    /// checkpoint there only once the new one is complete.
+    fn save(&mut self, path: PathBuf) -> PyResult<()> {
+        Ok(self.job()?.checkpoint().save(path)?)
+    }
+
+    /// Diffs what is left and returns the `DeepDiff`.
+    fn finish(&mut self) -> PyResult<PyDeepDiff> {
+        let inner = self.inner.take().ok_or_else(finished)?.finish();
+        self.errors.take()?;
+        Ok(PyDeepDiff {
+            inner,
+            view: self.view,
+        })
+    }
+}
+
+impl PyDiffJob {
+    fn job(&mut self) -> PyResult<&mut DiffJob> {
+        self.inner.as_mut().ok_or_else(finished)
+    }
+}
+
+fn finished() -> PyErr {
+    PyRuntimeError::new_err("the job has already finished")
+}
+
+/// A diff that can be added to `t1` to produce `t2` (and, when bidirectional,
+/// subtracted from `t2` to produce `t1`).
+#[pyclass(name = "Delta")]
+struct PyDelta {
+    inner: Delta,
+}
+
+#[pymethods]
+impl PyDelta {
+    #[new]
+    #[pyo3(signature = (diff, *, bidirectional = false, raise_errors = false))]
+    fn new(diff: &Bound<'_, PyAny>, bidirectional: bool, raise_errors: bool) -> PyResult<Self> {
+        let inner = match diff.downcast::<PyDeepDiff>() {
+            Ok(diff) => Delta::new(&diff.borrow().inner).map_err(delta_error)?,
+            Err(_) => delta_from_dump(diff)?,
+        };
+        Ok(Self {
+            inner: inner
+                .bidirectional(bidirectional)
+                .raise_errors(raise_errors),
+        })
+    }
+
+    /// Reads a delta produced by `dumps()`.
+    #[staticmethod]
+    #[pyo3(signature = (data, *, bidirectional = false, raise_errors = false))]
+    fn loads(data: &Bound<'_, PyAny>, bidirectional: bool, raise_errors: bool) -> PyResult<Self> {
+        Ok(Self {
+            inner: delta_from_dump(data)?
+                .bidirectional(bidirectional)
+                .raise_errors(raise_errors),
+        })
+    }
+
+    /// Serializes the delta as JSON bytes.
+    fn dumps(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let data = serde_json::to_vec(&self.inner.to_value())
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(PyBytes::new_bound(py, &data).into_py(py))
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        value_to_py(py, &self.inner.to_value())
+    }
+
+    fn __add__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let value = value_from_py(other, &ConvertOptions::default())?;
+        value_to_py(py, &self.inner.apply(&value).map_err(delta_error)?)
+    }
+
+    fn __radd__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        self.__add__(py, other)
+    }
+
+    fn __rsub__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let value = value_from_py(other, &ConvertOptions::default())?;
+        value_to_py(py, &self.inner.revert(&value).map_err(delta_error)?)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let value = self.to_dict(py)?;
+        Ok(format!("Delta({})", value.bind(py).repr()?))
+    }
+}
+
+fn delta_from_dump(data: &Bound<'_, PyAny>) -> PyResult<Delta> {
+    let parsed = if let Ok(bytes) = data.downcast::<PyBytes>() {
+        serde_json::from_slice(bytes.as_bytes())
+    } else if let Ok(text) = data.extract::<String>() {
+        serde_json::from_str(&text)
+    } else {
+        return Err(PyTypeError::new_err(
+            "Delta expects a DeepDiff or the output of Delta.dumps()",
+        ));
+    };
+    let value = parsed.map_err(|err| DeltaError::new_err(err.to_string()))?;
+    Delta::from_value(value).map_err(delta_error)
+}
+
+fn delta_error(err: crate::DeltaError) -> PyErr {
+    DeltaError::new_err(err.to_string())
 }
 
 pub(crate) fn register_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyDeepDiff>()?;
+    m.add_class::<PyDelta>()?;
+    m.add_class::<PyDiffLevel>()?;
+    m.add_class::<PyChangePages>()?;
+    m.add_class::<PyDiffJob>()?;
+    m.add_function(wrap_pyfunction!(diff, m)?)?;
+    m.add_function(wrap_pyfunction!(deep_equal, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_many, m)?)?;
+    m.add_function(wrap_pyfunction!(deep_hash, m)?)?;
+    m.add_function(wrap_pyfunction!(write_changes, m)?)?;
+    m.add("DeltaError", m.py().get_type_bound::<DeltaError>())?;
+    m.add("notpresent", not_present(m.py())?)?;
     Ok(())
 }
 
-fn options_from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<DeepDiffOptions> {
-    let mut options = DeepDiffOptions::default();
+fn view_from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<View> {
+    let Some(value) = kwargs
+        .map(|kwargs| kwargs.get_item("view"))
+        .transpose()?
+        .flatten()
+    else {
+        return Ok(View::Text);
+    };
+    match value.extract::<String>()?.as_str() {
+        "text" => Ok(View::Text),
+        "tree" => Ok(View::Tree),
+        other => Err(PyValueError::new_err(format!(
+            "Unsupported view: {} (expected 'text' or 'tree')",
+            other
+        ))),
+    }
+}
+
+fn tree_to_py(py: Python<'_>, diff: &DeepDiff) -> PyResult<PyObject> {
+    let dict = PyDict::new_bound(py);
+    let Value::Object(result) = &diff.result else {
+        return Ok(dict.into_py(py));
+    };
+    let (t1, t2) = diff.shown_sides();
+    for (report_type, entries) in result {
+        if report_type == "sampling" || report_type == "max_diffs_reached" {
+            dict.set_item(report_type, value_to_py(py, entries)?)?;
+            continue;
+        }
+        if report_type == "annotations" {
+            let labeled: serde_json::Map<String, Value> = entries
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(path, fields)| (diff.options.display_path(path), fields.clone()))
+                .collect();
+            dict.set_item(report_type, value_to_py(py, &Value::Object(labeled))?)?;
+            continue;
+        }
+        let paths: Vec<&str> = match entries {
+            Value::Object(map) => map.keys().map(String::as_str).collect(),
+            Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let levels = PyList::empty_bound(py);
+        for path in paths {
+            if let Some(segments) = parse_path(path) {
+                levels.append(diff_level_chain(py, (&t1, &t2), report_type, segments)?)?;
+            }
+        }
+        dict.set_item(report_type, levels)?;
+    }
+    if diff.options.get_deep_distance {
+        dict.set_item("deep_distance", diff.get_deep_distance())?;
+    }
+    Ok(dict.into_py(py))
+}
+
+/// Builds the `up`/`down` linked levels from root to the changed node and
+/// returns the leaf.
+fn diff_level_chain(
+    py: Python<'_>,
+    (t1, t2): (&Value, &Value),
+    report_type: &str,
+    segments: Vec<PathSegment>,
+) -> PyResult<Py<PyDiffLevel>> {
+    let side = |root: &Value, depth: usize| -> PyResult<PyObject> {
+        match get_value_at_path(root, &segments[..depth]) {
+            Some(value) => value_to_py(py, value),
+            None => not_present(py),
+        }
+    };
+
+    let mut parent: Option<Py<PyDiffLevel>> = None;
+    for depth in 0..=segments.len() {
+        let level = Py::new(
+            py,
+            PyDiffLevel {
+                t1: side(t1, depth)?,
+                t2: side(t2, depth)?,
+                report_type: (depth == segments.len()).then(|| report_type.to_string()),
+                up: parent.as_ref().map(|up| up.clone_ref(py)),
+                down: None,
+                segments: segments[..depth].to_vec(),
+            },
+        )?;
+        if let Some(up) = &parent {
+            up.borrow_mut(py).down = Some(level.clone_ref(py));
+        }
+        parent = Some(level);
+    }
+    Ok(parent.expect("chain always has a root level"))
+}
+
+/// Parses a JSON document passed as `str`, `bytes`, or `bytearray`.
+fn json_from_py(py: Python<'_>, document: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let parsed = if let Ok(bytes) = document.downcast::<PyBytes>() {
+        let bytes = bytes.as_bytes();
+        py.allow_threads(|| serde_json::from_slice(bytes))
+    } else if let Ok(text) = document.extract::<String>() {
+        py.allow_threads(|| serde_json::from_str(&text))
+    } else if let Ok(bytes) = document.extract::<Vec<u8>>() {
+        py.allow_threads(|| serde_json::from_slice(&bytes))
+    } else {
+        return Err(PyTypeError::new_err(
+            "from_json expects str or bytes containing a JSON document",
+        ));
+    };
+    parsed.map_err(|err| PyValueError::new_err(format!("Invalid JSON: {}", err)))
+}
+
+/// First exception raised by a Python callback while the engine was running.
+#[derive(Clone, Default)]
+struct CallbackError(Arc<Mutex<Option<PyErr>>>);
+
+impl CallbackError {
+    fn is_set(&self) -> bool {
+        self.0.lock().map(|slot| slot.is_some()).unwrap_or(true)
+    }
+
+    fn record(&self, err: PyErr) {
+        if let Ok(mut slot) = self.0.lock() {
+            if slot.is_none() {
+                *slot = Some(err);
+            }
+        }
+    }
+
+    fn take(&self) -> PyResult<()> {
+        match self.0.lock().ok().and_then(|mut slot| slot.take()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+fn obj_callback(callable: &Bound<'_, PyAny>, errors: &CallbackError) -> PyResult<ObjCallback> {
+    if !callable.is_callable() {
+        return Err(PyTypeError::new_err("Expected a callable"));
+    }
+    let callable = callable.clone().unbind();
+    let errors = errors.clone();
+    Ok(ObjCallback(Arc::new(move |value, path| {
+        if errors.is_set() {
+            return false;
+        }
+        Python::with_gil(|py| {
+            let result = value_to_py(py, value)
+                .and_then(|obj| callable.call1(py, (obj, path)))
+                .and_then(|res| res.bind(py).is_truthy());
+            result.unwrap_or_else(|err| {
+                errors.record(err);
+                false
+            })
+        })
+    })))
+}
+
+/// `number_to_string_func`: called with a number, `significant_digits`, and
+/// `"f"` or `"e"`, returning the text the number is compared by.
+fn number_formatter(
+    callable: &Bound<'_, PyAny>,
+    errors: &CallbackError,
+) -> PyResult<NumberFormatter> {
+    if !callable.is_callable() {
+        return Err(PyTypeError::new_err("Expected a callable"));
+    }
+    let callable = callable.clone().unbind();
+    let errors = errors.clone();
+    Ok(NumberFormatter(Arc::new(
+        move |number, digits, notation| {
+            if errors.is_set() {
+                return String::new();
+            }
+            let notation = match notation {
+                NumberFormat::Fixed => "f",
+                NumberFormat::Scientific => "e",
+            };
+            Python::with_gil(|py| {
+                let result = callable
+                    .call1(py, (number, digits, notation))
+                    .and_then(|text| text.extract::<String>(py));
+                result.unwrap_or_else(|err| {
+                    errors.record(err);
+                    String::new()
+                })
+            })
+        },
+    )))
+}
+
+/// `annotate`: called with each change as a dict, returning a dict of
+/// fields to attach to it or `None`.
+fn annotator(callable: &Bound<'_, PyAny>, errors: &CallbackError) -> PyResult<Annotator> {
+    if !callable.is_callable() {
+        return Err(PyTypeError::new_err("Expected a callable"));
+    }
+    let callable = callable.clone().unbind();
+    let errors = errors.clone();
+    Ok(Annotator(Arc::new(move |change| {
+        if errors.is_set() {
+            return None;
+        }
+        Python::with_gil(|py| {
+            let annotation = serde_json::to_value(change)
+                .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+                .and_then(|change| value_to_py(py, &change))
+                .and_then(|change| callable.call1(py, (change,)))
+                .and_then(|fields| {
+                    let fields = fields.bind(py);
+                    if fields.is_none() {
+                        return Ok(None);
+                    }
+                    if !fields.is_instance_of::<PyDict>() {
+                        return Err(PyTypeError::new_err("annotate must return a dict or None"));
+                    }
+                    match value_from_py(fields, &ConvertOptions::default())? {
+                        Value::Object(fields) => Ok(Some(fields)),
+                        _ => Ok(None),
+                    }
+                });
+            annotation.unwrap_or_else(|err| {
+                errors.record(err);
+                None
+            })
+        })
+    })))
+}
+
+/// Settings that change how Python objects are turned into `Value`s.
+struct ConvertOptions {
+    use_enum_value: bool,
+    encodings: Vec<String>,
+    ignore_encoding_errors: bool,
+    ignore_private_variables: bool,
+    /// Keyword arguments forwarded to `model_dump` (and pydantic v1 `dict`).
+    model_exclude_unset: bool,
+    model_exclude_defaults: bool,
+    model_exclude_none: bool,
+    model_include_computed_fields: bool,
+    /// Called with objects no other conversion applies to, like `json.dumps`.
+    default: Option<PyObject>,
+    /// Ids of the objects currently being converted, outermost first.
+    ancestors: RefCell<Vec<usize>>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            use_enum_value: false,
+            encodings: Vec::new(),
+            ignore_encoding_errors: false,
+            ignore_private_variables: true,
+            model_exclude_unset: false,
+            model_exclude_defaults: false,
+            model_exclude_none: false,
+            model_include_computed_fields: true,
+            default: None,
+            ancestors: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+fn options_from_kwargs(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    errors: &CallbackError,
+) -> PyResult<(DeepDiffOptions, ConvertOptions)> {
+    let mut options = DeepDiffOptions::default().num_threads(env_threads()?);
+    let mut convert = ConvertOptions::default();
+    let mut progress_logger: Option<PyObject> = None;
+    let mut log_frequency_in_sec = 0.0;
 
     if let Some(kwargs) = kwargs {
+        let ignore_unknown = match kwargs.get_item("ignore_unknown_kwargs")? {
+            Some(value) => extract_option::<bool>("ignore_unknown_kwargs", &value)?,
+            None => env_flag(IGNORE_UNKNOWN_KWARGS_ENV),
+        };
         for (key_any, value) in kwargs.iter() {
             let key: String = key_any.extract()?;
             match key {
                 key if key == "ignore_order" => {
-                    options = options.ignore_order(value.extract::<bool>()?);
+                    options = options.ignore_order(extract_option::<bool>(&key, &value)?);
                 }
                 key if key == "ignore_numeric_type_changes" => {
-                    options = options.ignore_numeric_type_changes(value.extract::<bool>()?);
+                    options =
+                        options.ignore_numeric_type_changes(extract_option::<bool>(&key, &value)?);
                 }
                 key if key == "ignore_string_type_changes" => {
-                    options = options.ignore_string_type_changes(value.extract::<bool>()?);
+                    options =
+                        options.ignore_string_type_changes(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "ignore_uuid_types" => {
+                    options = options.ignore_uuid_types(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "ignore_nan_inequality" => {
+                    options = options.ignore_nan_inequality(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "encodings" => {
+                    convert.encodings = if value.is_none() {
+                        Vec::new()
+                    } else {
+                        extract_string_list(&value)?
+                    };
+                }
+                key if key == "ignore_encoding_errors" => {
+                    convert.ignore_encoding_errors = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "ignore_private_variables" => {
+                    convert.ignore_private_variables = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "model_exclude_unset" => {
+                    convert.model_exclude_unset = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "model_exclude_defaults" => {
+                    convert.model_exclude_defaults = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "model_exclude_none" => {
+                    convert.model_exclude_none = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "model_include_computed_fields" => {
+                    convert.model_include_computed_fields = extract_option::<bool>(&key, &value)?;
+                }
+                key if key == "default" => {
+                    if !value.is_none() && !value.is_callable() {
+                        return Err(PyTypeError::new_err("default must be callable"));
+                    }
+                    convert.default = (!value.is_none()).then(|| value.clone().unbind());
+                }
+                key if key == "use_enum_value" => {
+                    convert.use_enum_value = extract_option::<bool>(&key, &value)?;
                 }
                 key if key == "significant_digits" => {
                     if value.is_none() {
                         options = options.significant_digits(None);
                     } else {
-                        options = options.significant_digits(Some(value.extract::<u32>()?));
+                        options =
+                            options.significant_digits(Some(extract_option::<u32>(&key, &value)?));
                     }
                 }
+                key if key == "number_format_notation" => {
+                    let notation = match extract_option::<Option<String>>(&key, &value)? {
+                        Some(notation) => {
+                            Some(notation.parse().map_err(|err: OptionsError| {
+                                PyValueError::new_err(err.to_string())
+                            })?)
+                        }
+                        None => None,
+                    };
+                    options = options.number_format_notation(notation);
+                }
+                key if key == "number_to_string_func" => {
+                    options.number_to_string = if value.is_none() {
+                        None
+                    } else {
+                        Some(number_formatter(&value, errors)?)
+                    };
+                }
+                key if key == "use_log_scale" => {
+                    options = options.use_log_scale(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "log_scale_similarity_threshold" => {
+                    options = options
+                        .log_scale_similarity_threshold(extract_option::<f64>(&key, &value)?);
+                }
                 key if key == "math_epsilon" => {
                     if value.is_none() {
                         options = options.math_epsilon(None);
                     } else {
-                        options = options.math_epsilon(Some(value.extract::<f64>()?));
+                        options = options.math_epsilon(Some(extract_option::<f64>(&key, &value)?));
                     }
                 }
                 key if key == "math_absilon" => {
                     if value.is_none() {
                         options = options.math_epsilon(None);
                     } else {
-                        options = options.math_epsilon(Some(value.extract::<f64>()?));
+                        options = options.math_epsilon(Some(extract_option::<f64>(&key, &value)?));
                     }
                 }
                 key if key == "atol" => {
                     if value.is_none() {
                         options = options.atol(None);
                     } else {
-                        options = options.atol(Some(value.extract::<f64>()?));
+                        options = options.atol(Some(extract_option::<f64>(&key, &value)?));
+                    }
+                }
+                key if key == "string_similarity" => {
+                    if value.is_none() {
+                        options = options.string_similarity(None);
+                    } else {
+                        options =
+                            options.string_similarity(Some(extract_option::<f64>(&key, &value)?));
+                    }
+                }
+                key if key == "report_repetition" => {
+                    options = options.report_repetition(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "align_lists" => {
+                    options = options.align_lists(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "cutoff_distance_for_pairs" => {
+                    if value.is_none() {
+                        options = options.cutoff_distance_for_pairs(None);
+                    } else {
+                        options = options
+                            .cutoff_distance_for_pairs(Some(extract_option::<f64>(&key, &value)?));
+                    }
+                }
+                key if key == "cutoff_intersection_for_pairs" => {
+                    if value.is_none() {
+                        options = options.cutoff_intersection_for_pairs(None);
+                    } else {
+                        options = options.cutoff_intersection_for_pairs(Some(
+                            extract_option::<f64>(&key, &value)?,
+                        ));
                     }
                 }
+                key if key == "threshold_to_diff_deeper" => {
+                    if value.is_none() {
+                        options = options.threshold_to_diff_deeper(None);
+                    } else {
+                        options = options
+                            .threshold_to_diff_deeper(Some(extract_option::<f64>(&key, &value)?));
+                    }
+                }
+                key if key == "max_passes" => {
+                    options = options.max_passes(extract_option::<Option<usize>>(&key, &value)?);
+                }
                 key if key == "rtol" => {
                     if value.is_none() {
                         options = options.rtol(None);
                     } else {
-                        options = options.rtol(Some(value.extract::<f64>()?));
+                        options = options.rtol(Some(extract_option::<f64>(&key, &value)?));
                     }
                 }
+                key if key == "datetime_epsilon" => {
+                    if value.is_none() {
+                        options = options.datetime_epsilon(None);
+                    } else if value
+                        .is_instance(py_classes(value.py())?.timedelta.bind(value.py()))?
+                    {
+                        let seconds = timedelta_micros(&value)? as f64 / 1e6;
+                        options = options.datetime_epsilon(Some(seconds));
+                    } else {
+                        options =
+                            options.datetime_epsilon(Some(extract_option::<f64>(&key, &value)?));
+                    }
+                }
+                key if key == "truncate_datetime" => {
+                    let unit =
+                        match extract_option::<Option<String>>(&key, &value)? {
+                            Some(unit) => Some(unit.parse().map_err(|err: OptionsError| {
+                                PyValueError::new_err(err.to_string())
+                            })?),
+                            None => None,
+                        };
+                    options = options.truncate_datetime(unit);
+                }
                 key if key == "include_paths" => {
                     let paths = extract_string_list(&value)?;
                     options = options.include_paths(paths);
@@ -131,8 +1394,158 @@ fn options_from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<DeepDiffO
                     let paths = extract_string_list(&value)?;
                     options = options.exclude_paths(paths);
                 }
-                key if key == "verbose_level" => {
-                    options = options.verbose_level(value.extract::<u8>()?);
+                key if key == "ignore_keys" => {
+                    options = options.ignore_keys(extract_string_list(&value)?);
+                }
+                key if key == "include_keys" => {
+                    options = options.include_keys(extract_string_list(&value)?);
+                }
+                key if key == "exclude_regex_paths" => {
+                    let patterns = extract_regex_list(&value)?;
+                    options = options.exclude_regex_paths(patterns);
+                }
+                key if key == "schema" => {
+                    options = if value.is_none() {
+                        options.schema(None)
+                    } else {
+                        options.schema(Some(value_from_py(&value, &ConvertOptions::default())?))
+                    };
+                }
+                key if key == "rules" => {
+                    options = options.rules(rules_document(&value)?);
+                }
+                key if key == "type_names" => {
+                    let names: String = extract_option(&key, &value)?;
+                    options = options.type_names(
+                        names
+                            .parse()
+                            .map_err(|err: OptionsError| PyValueError::new_err(err.to_string()))?,
+                    );
+                }
+                key if key == "path_aliases" => {
+                    let aliases: Vec<(String, String)> = value
+                        .downcast::<PyDict>()
+                        .map_err(|_| {
+                            PyTypeError::new_err("path_aliases must be a dict of str to str")
+                        })?
+                        .iter()
+                        .map(|(pattern, label)| Ok((pattern.extract()?, label.extract()?)))
+                        .collect::<PyResult<_>>()?;
+                    for (pattern, label) in aliases {
+                        options = options.path_alias(pattern, label);
+                    }
+                }
+                key if key == "hasher" => {
+                    let hasher: String = extract_option(&key, &value)?;
+                    options = options.hasher(
+                        hasher
+                            .parse()
+                            .map_err(|err: OptionsError| PyValueError::new_err(err.to_string()))?,
+                    );
+                }
+                key if key == "root_label" => {
+                    options = options.root_label(extract_option::<String>(&key, &value)?);
+                }
+                key if key == "sampling" => {
+                    options = options.sampling(sampling_option(&value)?);
+                }
+                key if key == "match_by" => {
+                    options = options.match_by(extract_option::<Option<String>>(&key, &value)?);
+                }
+                key if key == "group_by" => {
+                    options = options.group_by(extract_option::<Option<String>>(&key, &value)?);
+                }
+                key if key == "format_tolerances" => {
+                    let tolerances: HashMap<String, f64> = value.extract().map_err(|_| {
+                        PyTypeError::new_err("format_tolerances must be a dict of str to float")
+                    })?;
+                    for (format, tolerance) in tolerances {
+                        options = options.format_tolerance(format, tolerance);
+                    }
+                }
+                key if key == "redact_paths" => {
+                    let paths = extract_string_list(&value)?;
+                    options = options.redact_paths(paths);
+                }
+                key if key == "redact_keys" => {
+                    let patterns = extract_regex_list(&value)?;
+                    options = options.redact_keys(patterns);
+                }
+                key if key == "redact_values" => {
+                    let patterns = extract_regex_list(&value)?;
+                    options = options.redact_values(patterns);
+                }
+                key if key == "annotate" => {
+                    options.annotator = if value.is_none() {
+                        None
+                    } else {
+                        Some(annotator(&value, errors)?)
+                    };
+                }
+                key if key == "exclude_obj_callback" => {
+                    options.exclude_obj_callback = if value.is_none() {
+                        None
+                    } else {
+                        Some(obj_callback(&value, errors)?)
+                    };
+                }
+                key if key == "exclude_obj_callback_strict" => {
+                    options.exclude_obj_callback_strict = if value.is_none() {
+                        None
+                    } else {
+                        Some(obj_callback(&value, errors)?)
+                    };
+                }
+                key if key == "progress_logger" => {
+                    if !value.is_none() && !value.is_callable() {
+                        return Err(PyTypeError::new_err("progress_logger must be callable"));
+                    }
+                    progress_logger = (!value.is_none()).then(|| value.clone().unbind());
+                }
+                key if key == "log_frequency_in_sec" => {
+                    log_frequency_in_sec = if value.is_none() {
+                        0.0
+                    } else {
+                        extract_option::<f64>(&key, &value)?
+                    };
+                    if !log_frequency_in_sec.is_finite() || log_frequency_in_sec < 0.0 {
+                        return Err(PyValueError::new_err(
+                            "log_frequency_in_sec must be a non-negative number",
+                        ));
+                    }
+                }
+                key if key == "view" => {
+                    // Handled by the constructor; it does not affect the diff itself.
+                }
+                key if key == "verbose_level" => {
+                    options = options.verbose_level(extract_option::<u8>(&key, &value)?);
+                }
+                key if key == "get_deep_distance" => {
+                    options = options.get_deep_distance(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "max_diffs" => {
+                    options = options.max_diffs(extract_option::<Option<usize>>(&key, &value)?);
+                }
+                key if key == "max_report_bytes" => {
+                    options =
+                        options.max_report_bytes(extract_option::<Option<usize>>(&key, &value)?);
+                }
+                key if key == "num_threads" => {
+                    options = options.num_threads(extract_option::<Option<usize>>(&key, &value)?);
+                }
+                key if key == "sort_paths" => {
+                    options = options.sort_paths(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "collapse_index_ranges" => {
+                    options = options
+                        .collapse_index_ranges(extract_option::<Option<usize>>(&key, &value)?);
+                }
+                key if key == "magnitude_scores" => {
+                    options = options.magnitude_scores(extract_option::<bool>(&key, &value)?);
+                }
+                key if key == "large_string_threshold" => {
+                    options = options
+                        .large_string_threshold(extract_option::<Option<usize>>(&key, &value)?);
                 }
                 key if key == "ignore_type_in_groups" => {
                     let (groups, ignore_numeric, ignore_string) = extract_type_groups(&value)?;
@@ -144,17 +1557,189 @@ fn options_from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<DeepDiffO
                         options = options.ignore_string_type_changes(true);
                     }
                 }
-                _ => {
-                    return Err(PyValueError::new_err(format!(
-                        "Unsupported option: {}",
-                        key
-                    )));
+                key if key == "exclude_types" => {
+                    // Read as a single group of `ignore_type_in_groups`.
+                    let group = PyTuple::new_bound(value.py(), [&value]);
+                    let (mut groups, _, _) = extract_type_groups(&group).map_err(|_| {
+                        PyTypeError::new_err("exclude_types must be a list or tuple of types")
+                    })?;
+                    options = options.exclude_types(groups.pop().unwrap_or_default());
+                }
+                key if key == "ignore_unknown_kwargs" => {
+                    // Read before the loop so it applies to every option.
                 }
+                _ if ignore_unknown => {
+                    let message = format!("turbodiff ignores unsupported option '{}'", key);
+                    PyErr::warn_bound(
+                        kwargs.py(),
+                        &kwargs.py().get_type_bound::<PyUserWarning>(),
+                        &message,
+                        1,
+                    )?;
+                }
+                _ => return Err(unsupported_option(&key)),
             }
         }
     }
+    options
+        .validate()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    if log_frequency_in_sec > 0.0 {
+        let py = kwargs.map(|kwargs| kwargs.py());
+        let logger = match (progress_logger, py) {
+            (Some(logger), _) => logger,
+            (None, Some(py)) => py
+                .import_bound("logging")?
+                .call_method1("getLogger", ("turbodiff",))?
+                .getattr("info")?
+                .unbind(),
+            (None, None) => return Ok((options, convert)),
+        };
+        let errors = errors.clone();
+        options = options.progress_logger(
+            Duration::from_secs_f64(log_frequency_in_sec),
+            move |progress| {
+                if errors.is_set() {
+                    return;
+                }
+                let message = format!(
+                    "DeepDiff {} seconds in progress. Items #{}, Diff #{}",
+                    progress.elapsed.as_secs(),
+                    progress.items_processed,
+                    progress.diffs_found
+                );
+                Python::with_gil(|py| {
+                    if let Err(err) = logger.call1(py, (message,)) {
+                        errors.record(err);
+                    }
+                });
+            },
+        );
+    }
+
+    Ok((options, convert))
+}
+
+/// Keyword options accepted by `DeepDiff(...)`.
+const SUPPORTED_OPTIONS: &[&str] = &[
+    "ignore_order",
+    "ignore_numeric_type_changes",
+    "ignore_string_type_changes",
+    "ignore_uuid_types",
+    "ignore_nan_inequality",
+    "use_enum_value",
+    "encodings",
+    "ignore_encoding_errors",
+    "ignore_private_variables",
+    "model_exclude_unset",
+    "model_exclude_defaults",
+    "model_exclude_none",
+    "model_include_computed_fields",
+    "default",
+    "ignore_type_in_groups",
+    "exclude_types",
+    "significant_digits",
+    "number_format_notation",
+    "number_to_string_func",
+    "math_epsilon",
+    "use_log_scale",
+    "log_scale_similarity_threshold",
+    "atol",
+    "rtol",
+    "datetime_epsilon",
+    "truncate_datetime",
+    "string_similarity",
+    "cutoff_distance_for_pairs",
+    "cutoff_intersection_for_pairs",
+    "max_passes",
+    "threshold_to_diff_deeper",
+    "report_repetition",
+    "align_lists",
+    "include_paths",
+    "exclude_paths",
+    "exclude_regex_paths",
+    "ignore_keys",
+    "include_keys",
+    "schema",
+    "format_tolerances",
+    "rules",
+    "match_by",
+    "group_by",
+    "sampling",
+    "redact_paths",
+    "redact_keys",
+    "redact_values",
+    "exclude_obj_callback",
+    "exclude_obj_callback_strict",
+    "annotate",
+    "progress_logger",
+    "log_frequency_in_sec",
+    "verbose_level",
+    "root_label",
+    "path_aliases",
+    "type_names",
+    "hasher",
+    "max_report_bytes",
+    "max_diffs",
+    "get_deep_distance",
+    "large_string_threshold",
+    "magnitude_scores",
+    "collapse_index_ranges",
+    "sort_paths",
+    "num_threads",
+    "view",
+    "ignore_unknown_kwargs",
+];
+
+/// Environment variable that turns on `ignore_unknown_kwargs` by default.
+const IGNORE_UNKNOWN_KWARGS_ENV: &str = "TURBODIFF_IGNORE_UNKNOWN_KWARGS";
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// Environment variable capping the threads of the parallel diff when
+/// `num_threads` is not passed.
+const THREADS_ENV: &str = "TURBODIFF_THREADS";
+
+fn env_threads() -> PyResult<Option<usize>> {
+    let Ok(value) = std::env::var(THREADS_ENV) else {
+        return Ok(None);
+    };
+    match value.trim().parse::<usize>() {
+        Ok(threads) if threads > 0 => Ok(Some(threads)),
+        _ => Err(PyValueError::new_err(format!(
+            "{} must be a positive integer, got '{}'",
+            THREADS_ENV, value
+        ))),
+    }
+}
+
+fn unsupported_option(key: &str) -> PyErr {
+    let suggestion = closest_match(key, SUPPORTED_OPTIONS)
+        .map(|name| format!(" (did you mean '{}'?)", name))
+        .unwrap_or_default();
+    PyValueError::new_err(format!(
+        "Unsupported option: '{}'{}. Supported options: {}",
+        key,
+        suggestion,
+        SUPPORTED_OPTIONS.join(", ")
+    ))
+}
 
-    Ok(options)
+/// Extracts an option value, naming the option when it has the wrong type.
+fn extract_option<'py, T: FromPyObject<'py>>(key: &str, value: &Bound<'py, PyAny>) -> PyResult<T> {
+    value.extract::<T>().map_err(|err| {
+        let py = value.py();
+        let message = format!("Invalid value for {}: {}", key, err.value_bound(py));
+        PyErr::from_type_bound(err.get_type_bound(py), message)
+    })
 }
 
 fn extract_string_list(value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
@@ -173,6 +1758,68 @@ fn extract_string_list(value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
     }
 }
 
+fn extract_regex_list(value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    if value.is_none() {
+        return Ok(Vec::new());
+    }
+    if value.downcast::<PyString>().is_ok() || value.hasattr("pattern")? {
+        return Ok(vec![regex_source(value)?]);
+    }
+    let items = if let Ok(list) = value.downcast::<PyList>() {
+        list.iter().collect::<Vec<_>>()
+    } else if let Ok(tuple) = value.downcast::<PyTuple>() {
+        tuple.iter().collect::<Vec<_>>()
+    } else if let Ok(set) = value.downcast::<PySet>() {
+        set.iter().collect::<Vec<_>>()
+    } else if let Ok(set) = value.downcast::<PyFrozenSet>() {
+        set.iter().collect::<Vec<_>>()
+    } else {
+        return Err(PyTypeError::new_err(
+            "Expected a string, compiled pattern, or a list of either",
+        ));
+    };
+    items.iter().map(regex_source).collect()
+}
+
+/// The source of a pattern given as a string or a compiled `re.Pattern`,
+/// with the flags that have a Rust regex equivalent written inline.
+fn regex_source(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    let (pattern, flags) = if let Ok(pattern) = value.extract::<String>() {
+        (pattern, 0)
+    } else if value.hasattr("pattern")? {
+        let pattern: String = value
+            .getattr("pattern")?
+            .extract()
+            .map_err(|_| PyTypeError::new_err("Only str patterns are supported"))?;
+        let flags: i64 = value.getattr("flags")?.extract()?;
+        (pattern, flags)
+    } else {
+        return Err(PyTypeError::new_err(
+            "Expected a string or compiled pattern",
+        ));
+    };
+
+    // Carry the Python re flags that have a Rust regex equivalent.
+    let mut inline = String::new();
+    if flags & 2 != 0 {
+        inline.push('i');
+    }
+    if flags & 8 != 0 {
+        inline.push('m');
+    }
+    if flags & 16 != 0 {
+        inline.push('s');
+    }
+    if flags & 64 != 0 {
+        inline.push('x');
+    }
+    Ok(if inline.is_empty() {
+        pattern
+    } else {
+        format!("(?{}){}", inline, pattern)
+    })
+}
+
 fn extract_type_groups(value: &Bound<'_, PyAny>) -> PyResult<(Vec<Vec<ValueType>>, bool, bool)> {
     let groups_any = if let Ok(list) = value.downcast::<PyList>() {
         list.iter().collect::<Vec<_>>()
@@ -257,8 +1904,10 @@ fn extract_type_groups(value: &Bound<'_, PyAny>) -> PyResult<(Vec<Vec<ValueType>
                 ValueType::String
             } else if ty.is(&type_none) {
                 ValueType::Null
-            } else if ty.is(&type_list) || ty.is(&type_tuple) {
+            } else if ty.is(&type_list) {
                 ValueType::Array
+            } else if ty.is(&type_tuple) {
+                ValueType::Tuple
             } else if ty.is(&type_dict) {
                 ValueType::Object
             } else if {
@@ -336,10 +1985,30 @@ fn extract_type_groups(value: &Bound<'_, PyAny>) -> PyResult<(Vec<Vec<ValueType>
     Ok((groups, ignore_numeric, ignore_string))
 }
 
-fn value_from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+fn value_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
     if value.is_none() {
         return Ok(Value::Null);
     }
+    // Exact builtin scalars are by far the most common inputs; resolve them
+    // before the isinstance checks for richer types below.
+    if let Ok(b) = value.downcast_exact::<PyBool>() {
+        return Ok(Value::Bool(b.is_true()));
+    }
+    if let Ok(f) = value.downcast_exact::<PyFloat>() {
+        return float_to_value(f.value());
+    }
+    if value.is_exact_instance_of::<PyString>() {
+        return Ok(Value::String(value.extract()?));
+    }
+    if !value.is_exact_instance_of::<PyLong>() {
+        if let Some(member) = enum_from_py(value, convert)? {
+            return Ok(member);
+        }
+    }
+    #[cfg(feature = "numpy")]
+    if let Some(array) = ndarray_from_py(value)? {
+        return Ok(array);
+    }
     if let Ok(b) = value.extract::<bool>() {
         return Ok(Value::Bool(b));
     }
@@ -349,33 +2018,99 @@ fn value_from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
     if let Ok(u) = value.extract::<u64>() {
         return Ok(Value::Number(u.into()));
     }
+    // Integers beyond 64 bits keep their exact digits.
+    if value.is_instance_of::<PyLong>() {
+        let digits: String = value.str()?.extract()?;
+        return Ok(tagged("int", Value::String(digits)));
+    }
+    if let Ok(f) = value.downcast::<PyFloat>() {
+        return float_to_value(f.value());
+    }
+    // Decimal implements __float__, so it must be caught before the generic
+    // float extraction below would silently round it.
+    if value.is_instance(py_classes(value.py())?.decimal.bind(value.py()))? {
+        let text: String = value.str()?.extract()?;
+        return Ok(tagged("Decimal", Value::String(text)));
+    }
     if let Ok(f) = value.extract::<f64>() {
-        if let Some(num) = serde_json::Number::from_f64(f) {
-            return Ok(Value::Number(num));
-        }
-        return Err(PyValueError::new_err("Float value is not finite"));
+        return float_to_value(f);
     }
     if let Ok(s) = value.extract::<String>() {
         return Ok(Value::String(s));
     }
+    if value.downcast::<PyBytes>().is_ok() {
+        return bytes_from_py(value, convert);
+    }
+    // A container reached again while still converting it is a reference
+    // cycle; mark how many levels up it points instead of recursing forever.
+    let id = value.as_ptr() as usize;
+    let ancestors = convert.ancestors.borrow().len();
+    if let Some(depth) = convert.ancestors.borrow().iter().rposition(|a| *a == id) {
+        return Ok(tagged("cycle", Value::from(ancestors - depth)));
+    }
+    convert.ancestors.borrow_mut().push(id);
+    let result = object_from_py(value, convert);
+    convert.ancestors.borrow_mut().pop();
+    result
+}
+
+/// Converts both sides of a diff.
+fn values_from_py(
+    t1: &Bound<'_, PyAny>,
+    t2: &Bound<'_, PyAny>,
+    convert: &ConvertOptions,
+) -> PyResult<(Value, Value)> {
+    Ok((value_from_py(t1, convert)?, value_from_py(t2, convert)?))
+}
+
+/// The `exclude_*` flags enabled in `convert`, plus an `exclude` set naming
+/// the model's computed fields when those are turned off.
+fn model_dump_kwargs<'py>(
+    value: &Bound<'py, PyAny>,
+    convert: &ConvertOptions,
+) -> PyResult<Bound<'py, PyDict>> {
+    let py = value.py();
+    let kwargs = PyDict::new_bound(py);
+    for (name, enabled) in [
+        ("exclude_unset", convert.model_exclude_unset),
+        ("exclude_defaults", convert.model_exclude_defaults),
+        ("exclude_none", convert.model_exclude_none),
+    ] {
+        if enabled {
+            kwargs.set_item(name, true)?;
+        }
+    }
+    if !convert.model_include_computed_fields {
+        if let Ok(fields) = value.get_type().getattr("model_computed_fields") {
+            let names = fields.iter()?.collect::<PyResult<Vec<_>>>()?;
+            let names = PySet::new_bound(py, &names)?;
+            if !names.is_empty() {
+                kwargs.set_item("exclude", names)?;
+            }
+        }
+    }
+    Ok(kwargs)
+}
+
+fn object_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
     if let Ok(list) = value.downcast::<PyList>() {
         let mut items = Vec::with_capacity(list.len());
         for item in list.iter() {
-            items.push(value_from_py(&item)?);
+            items.push(value_from_py(&item, convert)?);
         }
         return Ok(Value::Array(items));
     }
     if let Ok(tuple) = value.downcast::<PyTuple>() {
         let mut items = Vec::with_capacity(tuple.len());
         for item in tuple.iter() {
-            items.push(value_from_py(&item)?);
+            items.push(value_from_py(&item, convert)?);
         }
-        return Ok(Value::Array(items));
+        return Ok(tagged("tuple", Value::Array(items)));
     }
     if let Ok(set) = value.downcast::<PySet>() {
         let mut items = Vec::with_capacity(set.len());
         for item in set.iter() {
-            items.push(value_from_py(&item)?);
+            items.push(value_from_py(&item, convert)?);
         }
         items.sort_by_key(canonical_string);
         return Ok(Value::Array(items));
@@ -383,7 +2118,7 @@ fn value_from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
     if let Ok(set) = value.downcast::<PyFrozenSet>() {
         let mut items = Vec::with_capacity(set.len());
         for item in set.iter() {
-            items.push(value_from_py(&item)?);
+            items.push(value_from_py(&item, convert)?);
         }
         items.sort_by_key(canonical_string);
         return Ok(Value::Array(items));
@@ -391,51 +2126,83 @@ fn value_from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
     if let Ok(dict) = value.downcast::<PyDict>() {
         let mut map = serde_json::Map::with_capacity(dict.len());
         for (k, v) in dict.iter() {
-            let key: String = match k.extract::<String>() {
+            let key = match k.extract::<String>() {
                 Ok(val) => val,
-                Err(_) => k
-                    .str()
-                    .and_then(|s| s.extract::<String>())
-                    .map_err(|_| PyTypeError::new_err("Unsupported dict key type for DeepDiff"))?,
+                Err(_) => encode_key(&value_from_py(&k, convert)?),
             };
-            map.insert(key, value_from_py(&v)?);
+            map.insert(key, value_from_py(&v, convert)?);
         }
         return Ok(Value::Object(map));
     }
-    if value
-        .get_type()
-        .getattr("__module__")?
-        .extract::<String>()?
-        .starts_with("pandas")
-    {
+    if let Some(temporal) = temporal_from_py(value)? {
+        return Ok(temporal);
+    }
+    if value.is_instance(py_classes(value.py())?.uuid.bind(value.py()))? {
+        let text: String = value.str()?.extract()?;
+        return Ok(tagged("UUID", Value::String(text)));
+    }
+    if value.hasattr("__json__")? {
+        let converted = value.call_method0("__json__")?;
+        return converted_from_py(value, &converted, convert);
+    }
+    if let Some(dataclass) = dataclass_from_py(value, convert)? {
+        return Ok(dataclass);
+    }
+    let module: String = value.get_type().getattr("__module__")?.extract()?;
+    if module.starts_with("polars") {
+        let class_name: String = value.get_type().getattr("__name__")?.extract()?;
+        match class_name.as_str() {
+            "DataFrame" => return polars_frame_from_py(value, convert),
+            "Series" => return polars_series_from_py(value, convert),
+            _ => {}
+        }
+    }
+    if module.starts_with("pyarrow") {
+        let class_name: String = value.get_type().getattr("__name__")?.extract()?;
+        match class_name.as_str() {
+            "Table" | "RecordBatch" => return arrow_table_from_py(value, convert),
+            _ if value.hasattr("to_pylist")? => return arrow_column_from_py(value, convert),
+            _ => {}
+        }
+    }
+    if module.starts_with("pandas") {
+        if value.get_type().getattr("__name__")?.extract::<String>()? == "DataFrame" {
+            return dataframe_from_py(value, convert);
+        }
         if let Ok(to_dict) = value.getattr("to_dict") {
             let py = value.py();
             let kwargs = PyDict::new_bound(py);
             kwargs.set_item("orient", "list")?;
             if let Ok(res) = to_dict.call((), Some(&kwargs)) {
-                return value_from_py(&res);
+                return value_from_py(&res, convert);
             }
             let res = to_dict.call0()?;
-            return value_from_py(&res);
+            return value_from_py(&res, convert);
         }
         if let Ok(to_numpy) = value.getattr("to_numpy") {
             let res = to_numpy.call0()?;
-            return value_from_py(&res);
+            return value_from_py(&res, convert);
+        }
+    }
+    if module != "numpy" && (value.hasattr("__array__")? || value.hasattr("__dlpack__")?) {
+        if let Some(array) = tensor_to_numpy(value)? {
+            return value_from_py(&array, convert);
         }
     }
     if value.hasattr("model_dump")? {
-        let py = value.py();
-        let kwargs = PyDict::new_bound(py);
-        kwargs.set_item("mode", "json")?;
-        if let Ok(dumped) = value.call_method("model_dump", (), Some(&kwargs)) {
-            return value_from_py(&dumped);
+        let kwargs = model_dump_kwargs(value, convert)?;
+        let json_kwargs = kwargs.copy()?;
+        json_kwargs.set_item("mode", "json")?;
+        if let Ok(dumped) = value.call_method("model_dump", (), Some(&json_kwargs)) {
+            return value_from_py(&dumped, convert);
         }
-        let dumped = value.call_method0("model_dump")?;
-        return value_from_py(&dumped);
+        let dumped = value.call_method("model_dump", (), Some(&kwargs))?;
+        return value_from_py(&dumped, convert);
     }
     if value.hasattr("dict")? {
-        let dumped = value.call_method0("dict")?;
-        return value_from_py(&dumped);
+        let kwargs = model_dump_kwargs(value, convert)?;
+        let dumped = value.call_method("dict", (), Some(&kwargs))?;
+        return value_from_py(&dumped, convert);
     }
     if value
         .get_type()
@@ -444,14 +2211,691 @@ fn value_from_py(value: &Bound<'_, PyAny>) -> PyResult<Value> {
         .starts_with("numpy")
     {
         if let Ok(tolist) = value.call_method0("tolist") {
-            return value_from_py(&tolist);
+            return value_from_py(&tolist, convert);
         }
     }
+    if let Ok(iter) = value.iter() {
+        return iterable_from_py(iter, convert);
+    }
+    if let Some(attributes) = attributes_from_py(value, convert)? {
+        return Ok(attributes);
+    }
+    if let Some(default) = &convert.default {
+        let converted = default.bind(value.py()).call1((value,))?;
+        return converted_from_py(value, &converted, convert);
+    }
 
     Err(PyTypeError::new_err("Unsupported Python type for DeepDiff"))
 }
 
+/// Converts the result of `__json__()` or `default`, which must be a different
+/// object than the one it stands in for.
+fn converted_from_py(
+    original: &Bound<'_, PyAny>,
+    converted: &Bound<'_, PyAny>,
+    convert: &ConvertOptions,
+) -> PyResult<Value> {
+    if converted.is(original) {
+        return Err(PyTypeError::new_err(format!(
+            "Converting {} returned the object itself",
+            original.get_type().name()?
+        )));
+    }
+    value_from_py(converted, convert)
+}
+
+/// Converts an arbitrary object through its `__dict__` and `__slots__`
+/// attributes. Returns `None` for objects that have neither.
+fn attributes_from_py(
+    value: &Bound<'_, PyAny>,
+    convert: &ConvertOptions,
+) -> PyResult<Option<Value>> {
+    let py = value.py();
+    let class = value.get_type();
+    let class_name: String = class.getattr("__name__")?.extract()?;
+    let mangled_prefix = format!("_{}__", class_name);
+    let is_private = |name: &str| {
+        convert.ignore_private_variables
+            && (name.starts_with("__") || name.starts_with(&mangled_prefix))
+    };
+
+    let mut attrs = serde_json::Map::new();
+    let mut has_attributes = false;
+    if let Ok(dict) = value.getattr("__dict__") {
+        if let Ok(dict) = dict.downcast::<PyDict>() {
+            has_attributes = true;
+            for (name, attr) in dict.iter() {
+                let name: String = name.extract()?;
+                if !is_private(&name) {
+                    attrs.insert(name, value_from_py(&attr, convert)?);
+                }
+            }
+        }
+    }
+    for base in class.getattr("__mro__")?.iter()? {
+        let Ok(slots) = base?.getattr("__slots__") else {
+            continue;
+        };
+        has_attributes = true;
+        let names: Vec<String> = match slots.extract::<String>() {
+            Ok(name) => vec![name],
+            Err(_) => slots
+                .iter()?
+                .map(|name| name?.extract())
+                .collect::<PyResult<_>>()?,
+        };
+        for name in names {
+            if name == "__dict__" || name == "__weakref__" || is_private(&name) {
+                continue;
+            }
+            // Unassigned slots raise AttributeError and are simply absent.
+            if let Ok(attr) = value.getattr(name.as_str()) {
+                attrs.insert(name, value_from_py(&attr, convert)?);
+            }
+        }
+    }
+    if !has_attributes {
+        return Ok(None);
+    }
+    register_object_class(py, &class_name, &class);
+    Ok(Some(object(&class_name, attrs)))
+}
+
+/// Upper bound on items drawn from a generic iterable, so an endless generator
+/// fails instead of exhausting memory.
+const MAX_ITERABLE_ITEMS: usize = 10_000_000;
+
+/// Consumes generators, mapping views, ranges, deques, and other iterables
+/// into a list.
+fn iterable_from_py(iter: Bound<'_, PyIterator>, convert: &ConvertOptions) -> PyResult<Value> {
+    let mut items = Vec::new();
+    for item in iter {
+        if items.len() == MAX_ITERABLE_ITEMS {
+            return Err(PyValueError::new_err(format!(
+                "Iterable yielded more than {} items",
+                MAX_ITERABLE_ITEMS
+            )));
+        }
+        items.push(value_from_py(&item?, convert)?);
+    }
+    Ok(Value::Array(items))
+}
+
+/// Python classes looked up once per interpreter and reused by the converters.
+struct PyClasses {
+    datetime: Py<PyType>,
+    date: Py<PyType>,
+    time: Py<PyType>,
+    timedelta: Py<PyType>,
+    decimal: Py<PyType>,
+    uuid: Py<PyType>,
+    enum_: Py<PyType>,
+}
+
+static PY_CLASSES: GILOnceCell<PyClasses> = GILOnceCell::new();
+
+fn py_classes(py: Python<'_>) -> PyResult<&'static PyClasses> {
+    PY_CLASSES.get_or_try_init(py, || {
+        let class = |module: &str, name: &str| -> PyResult<Py<PyType>> {
+            Ok(py
+                .import_bound(module)?
+                .getattr(name)?
+                .downcast_into::<PyType>()?
+                .unbind())
+        };
+        Ok(PyClasses {
+            datetime: class("datetime", "datetime")?,
+            date: class("datetime", "date")?,
+            time: class("datetime", "time")?,
+            timedelta: class("datetime", "timedelta")?,
+            decimal: class("decimal", "Decimal")?,
+            uuid: class("uuid", "UUID")?,
+            enum_: class("enum", "Enum")?,
+        })
+    })
+}
+
+fn temporal_from_py(value: &Bound<'_, PyAny>) -> PyResult<Option<Value>> {
+    let py = value.py();
+    let classes = py_classes(py)?;
+    // datetime is a subclass of date, so it has to be checked first.
+    for (class, type_name) in [
+        (&classes.datetime, "datetime"),
+        (&classes.date, "date"),
+        (&classes.time, "time"),
+    ] {
+        if value.is_instance(class.bind(py))? {
+            let iso: String = value.call_method0("isoformat")?.extract()?;
+            return Ok(Some(tagged(type_name, Value::String(iso))));
+        }
+    }
+    if value.is_instance(classes.timedelta.bind(py))? {
+        return Ok(Some(tagged(
+            "timedelta",
+            Value::Number(timedelta_micros(value)?.into()),
+        )));
+    }
+    Ok(None)
+}
+
+fn timedelta_micros(value: &Bound<'_, PyAny>) -> PyResult<i64> {
+    let days: i64 = value.getattr("days")?.extract()?;
+    let seconds: i64 = value.getattr("seconds")?.extract()?;
+    let micros: i64 = value.getattr("microseconds")?.extract()?;
+    Ok((days * 86_400 + seconds) * 1_000_000 + micros)
+}
+
+/// Decodes bytes with the first of `encodings` that succeeds (UTF-8 by default).
+/// The encoding is kept alongside the text so results can re-encode it.
+fn bytes_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    let default = ["utf-8".to_string()];
+    let encodings = if convert.encodings.is_empty() {
+        &default[..]
+    } else {
+        &convert.encodings[..]
+    };
+    let mut last_err = None;
+    for encoding in encodings {
+        match value.call_method1("decode", (encoding,)) {
+            Ok(text) => return Ok(bytes_value(text.extract()?, encoding)),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    if convert.ignore_encoding_errors {
+        let text = value.call_method1("decode", ("utf-8", "replace"))?;
+        return Ok(bytes_value(text.extract()?, "utf-8"));
+    }
+    Err(last_err.unwrap_or_else(|| PyValueError::new_err("No encodings to decode bytes with")))
+}
+
+fn bytes_value(text: String, encoding: &str) -> Value {
+    let mut inner = serde_json::Map::with_capacity(2);
+    inner.insert("text".to_string(), Value::String(text));
+    inner.insert("encoding".to_string(), Value::String(encoding.to_string()));
+    tagged("bytes", Value::Object(inner))
+}
+
+/// Converts a DataFrame column by column into the tagged layout the engine
+/// aligns on the index: `{"dtypes": .., "index": [label], "columns": {col: [cell]}}`.
+fn dataframe_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    let mut index = Vec::new();
+    for label in value.getattr("index")?.call_method0("tolist")?.iter()? {
+        index.push(Value::String(label_key(&label?, convert)?));
+    }
+    let mut dtypes = serde_json::Map::new();
+    let mut columns = serde_json::Map::new();
+    for column in value.getattr("columns")?.call_method0("tolist")?.iter()? {
+        let column = column?;
+        let key = label_key(&column, convert)?;
+        let series = value.get_item(&column)?;
+        let dtype: String = series.getattr("dtype")?.str()?.extract()?;
+        dtypes.insert(key.clone(), Value::String(dtype));
+        columns.insert(
+            key,
+            value_from_py(&series.call_method0("tolist")?, convert)?,
+        );
+    }
+    Ok(frame_value("DataFrame", "pandas", dtypes, index, columns))
+}
+
+/// Converts a polars DataFrame; polars has no index, so rows align by position.
+fn polars_frame_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    let height: usize = value.getattr("height")?.extract()?;
+    let index = (0..height)
+        .map(|row| Value::String(encode_key(&Value::from(row))))
+        .collect();
+    let mut dtypes = serde_json::Map::new();
+    let mut columns = serde_json::Map::new();
+    for name in value.getattr("columns")?.iter()? {
+        let name: String = name?.extract()?;
+        let series = value.call_method1("get_column", (name.as_str(),))?;
+        let dtype: String = series.getattr("dtype")?.str()?.extract()?;
+        dtypes.insert(name.clone(), Value::String(dtype));
+        columns.insert(name, polars_series_from_py(&series, convert)?);
+    }
+    Ok(frame_value("DataFrame", "polars", dtypes, index, columns))
+}
+
+/// Views a torch/TensorFlow/JAX tensor as a numpy array through `__array__`,
+/// falling back to DLPack. Torch tensors are detached and moved to the CPU
+/// first, since neither protocol works for tensors requiring gradients or
+/// living on an accelerator.
+fn tensor_to_numpy<'py>(value: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Ok(numpy) = value.py().import_bound("numpy") else {
+        return Ok(None);
+    };
+    let tensor = if value.hasattr("detach")? && value.hasattr("cpu")? {
+        value.call_method0("detach")?.call_method0("cpu")?
+    } else {
+        value.clone()
+    };
+    if let Ok(array) = numpy.call_method1("asarray", (&tensor,)) {
+        return Ok(Some(array));
+    }
+    if tensor.hasattr("__dlpack__")? {
+        if let Ok(array) = numpy.call_method1("from_dlpack", (&tensor,)) {
+            return Ok(Some(array));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads a polars Series through Arrow when the `arrow` feature is on and
+/// pyarrow is available, falling back to `to_list()`.
+fn polars_series_from_py(series: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    #[cfg(feature = "arrow")]
+    if let Ok(array) = series.call_method0("to_arrow") {
+        return arrow_column_from_py(&array, convert);
+    }
+    value_from_py(&series.call_method0("to_list")?, convert)
+}
+
+/// Converts a pyarrow Table or RecordBatch; fields become columns and rows
+/// align by position.
+fn arrow_table_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    let rows: usize = value.getattr("num_rows")?.extract()?;
+    let index = (0..rows)
+        .map(|row| Value::String(encode_key(&Value::from(row))))
+        .collect();
+    let mut dtypes = serde_json::Map::new();
+    let mut columns = serde_json::Map::new();
+    for field in value.getattr("schema")?.iter()? {
+        let field = field?;
+        let name: String = field.getattr("name")?.extract()?;
+        let dtype: String = field.getattr("type")?.str()?.extract()?;
+        let column = value.call_method1("column", (name.as_str(),))?;
+        dtypes.insert(name.clone(), Value::String(dtype));
+        columns.insert(name, arrow_column_from_py(&column, convert)?);
+    }
+    Ok(frame_value("Table", "pyarrow", dtypes, index, columns))
+}
+
+/// Converts a pyarrow Array or ChunkedArray to a list, reading primitive and
+/// string buffers directly when the `arrow` feature is on.
+fn arrow_column_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Value> {
+    #[cfg(feature = "arrow")]
+    {
+        let chunks = match value.getattr("chunks") {
+            Ok(chunks) => chunks.iter()?.collect::<PyResult<Vec<_>>>()?,
+            Err(_) => vec![value.clone()],
+        };
+        let mut items = Vec::new();
+        let mut native = true;
+        for chunk in &chunks {
+            let array = import_arrow_array(chunk).ok();
+            match array.and_then(|array| crate::arrow::array_values(array.as_ref())) {
+                Some(values) => items.extend(values),
+                None => {
+                    native = false;
+                    break;
+                }
+            }
+        }
+        if native {
+            return Ok(Value::Array(items));
+        }
+    }
+    value_from_py(&value.call_method0("to_pylist")?, convert)
+}
+
+/// Imports a pyarrow Array through the Arrow C data interface.
+#[cfg(feature = "arrow")]
+fn import_arrow_array(array: &Bound<'_, PyAny>) -> PyResult<arrow_array::ArrayRef> {
+    use arrow_array::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+
+    let mut ffi_array = FFI_ArrowArray::empty();
+    let mut ffi_schema = FFI_ArrowSchema::empty();
+    array.call_method1(
+        "_export_to_c",
+        (
+            std::ptr::addr_of_mut!(ffi_array) as usize,
+            std::ptr::addr_of_mut!(ffi_schema) as usize,
+        ),
+    )?;
+    // SAFETY: pyarrow has just filled both structs, and `from_ffi` takes
+    // ownership of the array's release callback.
+    let data = unsafe { from_ffi(ffi_array, &ffi_schema) }
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(arrow_array::make_array(data))
+}
+
+fn frame_value(
+    type_name: &str,
+    library: &str,
+    dtypes: serde_json::Map<String, Value>,
+    index: Vec<Value>,
+    columns: serde_json::Map<String, Value>,
+) -> Value {
+    let mut frame = serde_json::Map::with_capacity(4);
+    frame.insert("library".to_string(), Value::String(library.to_string()));
+    frame.insert("dtypes".to_string(), Value::Object(dtypes));
+    frame.insert("index".to_string(), Value::Array(index));
+    frame.insert("columns".to_string(), Value::Object(columns));
+    tagged(type_name, Value::Object(frame))
+}
+
+/// Map key for an index label or column name, encoding non-string labels.
+fn label_key(label: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<String> {
+    match label.extract::<String>() {
+        Ok(key) => Ok(key),
+        Err(_) => Ok(encode_key(&value_from_py(label, convert)?)),
+    }
+}
+
+fn key_to_py(py: Python<'_>, key: &str) -> PyResult<PyObject> {
+    match decode_key(key) {
+        Some(key) => value_to_py(py, &key),
+        None => Ok(key.into_py(py)),
+    }
+}
+
+fn dataframe_to_py(py: Python<'_>, frame: &serde_json::Map<String, Value>) -> PyResult<PyObject> {
+    let data = PyDict::new_bound(py);
+    if let Some(Value::Object(columns)) = frame.get("columns") {
+        for (column, cells) in columns {
+            data.set_item(key_to_py(py, column)?, value_to_py(py, cells)?)?;
+        }
+    }
+    match frame.get("library").and_then(Value::as_str) {
+        Some("polars") => {
+            let polars = py.import_bound("polars")?;
+            return Ok(polars.getattr("DataFrame")?.call1((data,))?.unbind());
+        }
+        Some("pyarrow") => {
+            let pyarrow = py.import_bound("pyarrow")?;
+            return Ok(pyarrow.getattr("table")?.call1((data,))?.unbind());
+        }
+        _ => {}
+    }
+    let index = PyList::empty_bound(py);
+    if let Some(Value::Array(labels)) = frame.get("index") {
+        for label in labels.iter().filter_map(Value::as_str) {
+            index.append(key_to_py(py, label)?)?;
+        }
+    }
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("index", index)?;
+    let frame_py = py
+        .import_bound("pandas")?
+        .getattr("DataFrame")?
+        .call((data,), Some(&kwargs))?;
+    if let Some(Value::Object(dtypes)) = frame.get("dtypes") {
+        let dtypes_py = PyDict::new_bound(py);
+        for (column, dtype) in dtypes {
+            dtypes_py.set_item(key_to_py(py, column)?, value_to_py(py, dtype)?)?;
+        }
+        if let Ok(cast) = frame_py.call_method1("astype", (dtypes_py,)) {
+            return Ok(cast.unbind());
+        }
+    }
+    Ok(frame_py.unbind())
+}
+
+/// Classes of objects converted attribute by attribute, keyed by class name, so
+/// results can rebuild instances.
+static OBJECT_CLASSES: GILOnceCell<Mutex<HashMap<String, PyObject>>> = GILOnceCell::new();
+
+/// Converts a dataclass instance through its fields, skipping those declared
+/// with `field(compare=False)`.
+fn dataclass_from_py(
+    value: &Bound<'_, PyAny>,
+    convert: &ConvertOptions,
+) -> PyResult<Option<Value>> {
+    let py = value.py();
+    let class = value.get_type();
+    if !class.hasattr("__dataclass_fields__")? || value.is_instance_of::<PyType>() {
+        return Ok(None);
+    }
+    let fields = py
+        .import_bound("dataclasses")?
+        .getattr("fields")?
+        .call1((value,))?;
+    let mut attrs = serde_json::Map::new();
+    for field in fields.iter()? {
+        let field = field?;
+        if !field.getattr("compare")?.is_truthy()? {
+            continue;
+        }
+        let name: String = field.getattr("name")?.extract()?;
+        let attr = value.getattr(name.as_str())?;
+        attrs.insert(name, value_from_py(&attr, convert)?);
+    }
+    let class_name: String = class.getattr("__name__")?.extract()?;
+    register_object_class(py, &class_name, &class);
+    Ok(Some(object(&class_name, attrs)))
+}
+
+fn register_object_class(py: Python<'_>, class_name: &str, class: &Bound<'_, PyType>) {
+    let classes = OBJECT_CLASSES.get_or_init(py, Default::default);
+    if let Ok(mut classes) = classes.lock() {
+        classes
+            .entry(class_name.to_string())
+            .or_insert_with(|| class.clone().into_any().unbind());
+    }
+}
+
+/// Rebuilds an object without calling `__init__`, falling back to a dict of its
+/// attributes when the class was never seen.
+fn object_to_py(
+    py: Python<'_>,
+    class_name: &str,
+    attrs: &serde_json::Map<String, Value>,
+) -> PyResult<PyObject> {
+    let class = OBJECT_CLASSES.get(py).and_then(|classes| {
+        classes
+            .lock()
+            .ok()?
+            .get(class_name)
+            .map(|c| c.clone_ref(py))
+    });
+    let Some(class) = class else {
+        return value_to_py(py, &Value::Object(attrs.clone()));
+    };
+    let class = class.bind(py);
+    let instance = class.call_method1("__new__", (class,))?;
+    let setattr = py
+        .import_bound("builtins")?
+        .getattr("object")?
+        .getattr("__setattr__")?;
+    for (name, attr) in attrs {
+        setattr.call1((&instance, name, value_to_py(py, attr)?))?;
+    }
+    Ok(instance.unbind())
+}
+
+fn enum_from_py(value: &Bound<'_, PyAny>, convert: &ConvertOptions) -> PyResult<Option<Value>> {
+    let py = value.py();
+    if !value.is_instance(py_classes(py)?.enum_.bind(py))? {
+        return Ok(None);
+    }
+    if convert.use_enum_value {
+        return value_from_py(&value.getattr("value")?, convert).map(Some);
+    }
+    let class = value.get_type();
+    let module: String = class.getattr("__module__")?.extract()?;
+    let qualname: String = class.getattr("__qualname__")?.extract()?;
+    let class_name = format!("{}.{}", module, qualname);
+    enum_classes(py)?.set_item(&class_name, &class)?;
+    let mut member = serde_json::Map::with_capacity(3);
+    member.insert("class".to_string(), Value::String(class_name));
+    member.insert("module".to_string(), Value::String(module));
+    member.insert(
+        "member".to_string(),
+        Value::String(value.getattr("name")?.extract()?),
+    );
+    Ok(Some(tagged("enum", Value::Object(member))))
+}
+
+/// Enum classes seen during conversion by `module.qualname`, held weakly, for
+/// those their module cannot hand back: classes made with the functional API
+/// or defined inside functions.
+static ENUM_CLASSES: GILOnceCell<PyObject> = GILOnceCell::new();
+
+fn enum_classes(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    let classes = ENUM_CLASSES.get_or_try_init(py, || {
+        Ok::<_, PyErr>(
+            py.import_bound("weakref")?
+                .getattr("WeakValueDictionary")?
+                .call0()?
+                .unbind(),
+        )
+    })?;
+    Ok(classes.bind(py))
+}
+
+/// Looks an `Enum` member back up by its class, imported again from its
+/// module or else found among the classes seen during conversion; `None`
+/// when neither has it.
+fn enum_member_to_py(py: Python<'_>, inner: &Value) -> Option<PyObject> {
+    let field = |key| inner.get(key).and_then(Value::as_str);
+    let (class_name, module, member) = (field("class")?, field("module")?, field("member")?);
+    let qualname = class_name.strip_prefix(module)?.strip_prefix('.')?;
+    let imported = py.import_bound(module).ok().and_then(|module| {
+        qualname
+            .split('.')
+            .try_fold(module.into_any(), |parent, part| parent.getattr(part).ok())
+    });
+    let class = match imported {
+        Some(class) => class,
+        None => enum_classes(py).ok()?.get_item(class_name).ok()?,
+    };
+    let member = class.getattr("__members__").ok()?.get_item(member).ok()?;
+    Some(member.unbind())
+}
+
+fn tagged_to_py(py: Python<'_>, type_name: &str, inner: &Value) -> PyResult<PyObject> {
+    let classes = py_classes(py)?;
+    match (type_name, inner) {
+        ("Decimal", Value::String(text)) => {
+            return Ok(classes.decimal.bind(py).call1((text,))?.unbind());
+        }
+        ("UUID", Value::String(text)) => {
+            return Ok(classes.uuid.bind(py).call1((text,))?.unbind());
+        }
+        ("float", Value::String(text)) => {
+            return Ok(py.get_type_bound::<PyFloat>().call1((text,))?.unbind());
+        }
+        ("int", Value::String(digits)) => {
+            return Ok(py.get_type_bound::<PyLong>().call1((digits,))?.unbind());
+        }
+        ("cycle", _) => return Ok(py.Ellipsis()),
+        ("DataFrame" | "Table", Value::Object(frame)) => return dataframe_to_py(py, frame),
+        ("tuple", Value::Array(items)) => {
+            let items = items
+                .iter()
+                .map(|item| value_to_py(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            return Ok(PyTuple::new_bound(py, items).into_any().unbind());
+        }
+        ("bytes", Value::Object(inner)) => {
+            if let (Some(Value::String(text)), Some(Value::String(encoding))) =
+                (inner.get("text"), inner.get("encoding"))
+            {
+                let text = PyString::new_bound(py, text);
+                return Ok(text.call_method1("encode", (encoding,))?.unbind());
+            }
+        }
+        _ => {}
+    }
+    let temporal = match type_name {
+        "datetime" => Some(&classes.datetime),
+        "date" => Some(&classes.date),
+        "time" => Some(&classes.time),
+        _ => None,
+    };
+    match (temporal, inner) {
+        (Some(class), Value::String(iso)) => {
+            return Ok(class
+                .bind(py)
+                .call_method1("fromisoformat", (iso,))?
+                .unbind());
+        }
+        (None, Value::Number(micros)) if type_name == "timedelta" => {
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("microseconds", micros.as_i64())?;
+            return Ok(classes.timedelta.bind(py).call((), Some(&kwargs))?.unbind());
+        }
+        _ => {}
+    }
+    if type_name == "enum" {
+        if let Some(member) = enum_member_to_py(py, inner) {
+            return Ok(member);
+        }
+    }
+    value_to_py(py, inner)
+}
+
+/// Reads numeric and boolean ndarrays straight from their buffers, skipping
+/// the per-element Python objects `tolist()` would allocate. Other dtypes
+/// return `None` and go through `tolist()`.
+#[cfg(feature = "numpy")]
+fn ndarray_from_py(value: &Bound<'_, PyAny>) -> PyResult<Option<Value>> {
+    // Only touch the NumPy C API for objects that come from numpy, so the
+    // extension keeps working when numpy is not installed.
+    let module = value.get_type().getattr("__module__")?;
+    if module.extract::<String>()? != "numpy" || value.downcast::<numpy::PyUntypedArray>().is_err()
+    {
+        return Ok(None);
+    }
+    macro_rules! try_dtype {
+        ($ty:ty, $leaf:expr) => {
+            if let Ok(array) = value.downcast::<numpy::PyArrayDyn<$ty>>() {
+                let array = array.readonly();
+                return nested_from_view(array.as_array(), &$leaf).map(Some);
+            }
+        };
+    }
+    try_dtype!(f64, float_to_value);
+    try_dtype!(f32, |x: f32| float_to_value(f64::from(x)));
+    try_dtype!(i64, |x: i64| Ok(Value::from(x)));
+    try_dtype!(i32, |x: i32| Ok(Value::from(x)));
+    try_dtype!(i16, |x: i16| Ok(Value::from(x)));
+    try_dtype!(i8, |x: i8| Ok(Value::from(x)));
+    try_dtype!(u64, |x: u64| Ok(Value::from(x)));
+    try_dtype!(u32, |x: u32| Ok(Value::from(x)));
+    try_dtype!(u16, |x: u16| Ok(Value::from(x)));
+    try_dtype!(u8, |x: u8| Ok(Value::from(x)));
+    try_dtype!(bool, |x: bool| Ok(Value::Bool(x)));
+    Ok(None)
+}
+
+/// Nests an n-dimensional view into lists the way `tolist()` does.
+#[cfg(feature = "numpy")]
+fn nested_from_view<T: Copy>(
+    view: numpy::ndarray::ArrayViewD<'_, T>,
+    leaf: &dyn Fn(T) -> PyResult<Value>,
+) -> PyResult<Value> {
+    if view.ndim() == 0 {
+        return match view.first() {
+            Some(item) => leaf(*item),
+            None => Ok(Value::Null),
+        };
+    }
+    if view.ndim() == 1 {
+        let items = view
+            .iter()
+            .map(|item| leaf(*item))
+            .collect::<PyResult<_>>()?;
+        return Ok(Value::Array(items));
+    }
+    let items = view
+        .outer_iter()
+        .map(|row| nested_from_view(row, leaf))
+        .collect::<PyResult<_>>()?;
+    Ok(Value::Array(items))
+}
+
+fn float_to_value(f: f64) -> PyResult<Value> {
+    Ok(float_value(f))
+}
+
 fn value_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    if let Some((type_name, inner)) = as_tagged(value) {
+        return tagged_to_py(py, type_name, inner);
+    }
+    if let Some((class_name, attrs)) = as_object(value) {
+        return object_to_py(py, class_name, attrs);
+    }
     match value {
         Value::Null => Ok(py.None()),
         Value::Bool(b) => Ok(b.into_py(py)),
@@ -477,7 +2921,10 @@ fn value_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
         Value::Object(obj) => {
             let dict = PyDict::new_bound(py);
             for (k, v) in obj {
-                dict.set_item(k, value_to_py(py, v)?)?;
+                match decode_key(k) {
+                    Some(key) => dict.set_item(value_to_py(py, &key)?, value_to_py(py, v)?)?,
+                    None => dict.set_item(k, value_to_py(py, v)?)?,
+                }
             }
             Ok(dict.into_py(py))
         }
@@ -0,0 +1,99 @@
+use crate::path::{key_segment, parse_path, PathSegment};
+use crate::tagged::{is_tagged, object_attrs_mut, sequence_items_mut};
+use regex::Regex;
+use serde_json::Value;
+
+/// What a redacted value is reported as.
+pub(crate) const MASK: &str = "***";
+
+/// Values to mask in reports: those under `paths`, those under dict keys or
+/// attributes matching `keys`, those at paths matching `patterns`, and strings
+/// matching `values`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Redaction {
+    pub(crate) paths: Vec<String>,
+    pub(crate) keys: Vec<Regex>,
+    pub(crate) patterns: Vec<Regex>,
+    pub(crate) values: Vec<Regex>,
+}
+
+impl Redaction {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.paths.is_empty()
+            || !self.keys.is_empty()
+            || !self.patterns.is_empty()
+            || !self.values.is_empty()
+    }
+
+    /// Masks the parts of `value`, found at `path`, that the redaction options
+    /// cover: all of it when `path` is under a redacted path or a redacted key,
+    /// otherwise the redacted paths, keys, and strings inside it.
+    pub(crate) fn redact(&self, value: &mut Value, path: &str) {
+        let under_redacted_key = parse_path(path).is_some_and(|segments| {
+            segments.iter().any(|segment| match segment {
+                PathSegment::Key(name) | PathSegment::Attr(name) => self.key_redacted(name),
+                PathSegment::Index(_) => false,
+            })
+        });
+        if under_redacted_key {
+            *value = Value::String(MASK.to_string());
+            return;
+        }
+        self.redact_within(value, &mut path.to_string());
+    }
+
+    fn redact_within(&self, value: &mut Value, path: &mut String) {
+        let path_redacted = self
+            .paths
+            .iter()
+            .any(|redacted| path == redacted || path.starts_with(redacted.as_str()))
+            || self.patterns.iter().any(|pattern| pattern.is_match(path));
+        let text_redacted = value
+            .as_str()
+            .is_some_and(|text| self.values.iter().any(|pattern| pattern.is_match(text)));
+        if path_redacted || text_redacted {
+            *value = Value::String(MASK.to_string());
+            return;
+        }
+        let len = path.len();
+        if let Some(attrs) = object_attrs_mut(value) {
+            for (name, attr) in attrs.iter_mut() {
+                if self.key_redacted(name) {
+                    *attr = Value::String(MASK.to_string());
+                    continue;
+                }
+                path.push('.');
+                path.push_str(name);
+                self.redact_within(attr, path);
+                path.truncate(len);
+            }
+            return;
+        }
+        if let Some(items) = sequence_items_mut(value) {
+            for (idx, item) in items.iter_mut().enumerate() {
+                path.push_str(&format!("[{}]", idx));
+                self.redact_within(item, path);
+                path.truncate(len);
+            }
+            return;
+        }
+        if is_tagged(value) {
+            return;
+        }
+        if let Value::Object(map) = value {
+            for (key, item) in map.iter_mut() {
+                if self.key_redacted(key) {
+                    *item = Value::String(MASK.to_string());
+                    continue;
+                }
+                path.push_str(&key_segment(key));
+                self.redact_within(item, path);
+                path.truncate(len);
+            }
+        }
+    }
+
+    fn key_redacted(&self, name: &str) -> bool {
+        self.keys.iter().any(|pattern| pattern.is_match(name))
+    }
+}
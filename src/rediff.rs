@@ -0,0 +1,193 @@
+//! Updating a diff result after one side changed at a known path, by
+//! re-diffing only the region around that path.
+
+use crate::engine::{self, DiffAccumulator};
+use crate::options::DeepDiffOptions;
+use crate::path::{format_path, get_value_at_path, parse_path, PathSegment};
+use crate::tagged::{as_object, is_tagged};
+use serde_json::{Map, Value};
+
+/// Brings `result` up to date after a change at `changed` in `t1` or `t2`.
+pub(crate) fn rediff(
+    result: &mut Value,
+    t1: &Value,
+    t2: &Value,
+    changed: &[PathSegment],
+    options: &DeepDiffOptions,
+) {
+    let Some(region) = region(result, t1, t2, changed, options) else {
+        *result = crate::diff_result(t1, t2, options);
+        return;
+    };
+    let region_path = format_path("root", region);
+    let Value::Object(report) = result else {
+        return;
+    };
+    for entries in report.values_mut() {
+        match entries {
+            Value::Object(map) => map.retain(|path, _| !is_within(path, &region_path)),
+            Value::Array(paths) => {
+                paths.retain(|path| !path.as_str().is_some_and(|p| is_within(p, &region_path)))
+            }
+            _ => {}
+        }
+    }
+
+    let mut acc = DiffAccumulator::new(options);
+    engine::diff_region(t1, t2, region, options, &mut acc);
+    if let Value::Object(partial) = acc.into_value() {
+        merge(report, partial, options);
+        if !options.sort_paths {
+            for entries in report.values_mut() {
+                in_document_order(entries, t1, t2);
+            }
+        }
+    }
+    report.retain(|_, entries| match entries {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(paths) => !paths.is_empty(),
+        _ => true,
+    });
+}
+
+/// The deepest prefix of `changed` present on both sides, or `None` when the
+/// change cannot be re-diffed in isolation: the options make changes depend
+/// on more than the changed subtree, tagged values such as DataFrames report
+/// paths of their own, and a change reported at an ancestor covers the whole
+/// region.
+fn region<'a>(
+    result: &Value,
+    t1: &Value,
+    t2: &Value,
+    changed: &'a [PathSegment],
+    options: &DeepDiffOptions,
+) -> Option<&'a [PathSegment]> {
+    if !options.region_local() {
+        return None;
+    }
+    let mut depth = 0;
+    while depth < changed.len() {
+        let prefix = &changed[..=depth];
+        match (get_value_at_path(t1, prefix), get_value_at_path(t2, prefix)) {
+            (Some(_), Some(_)) => depth += 1,
+            _ => break,
+        }
+    }
+    let region = &changed[..depth];
+    for end in 0..=depth {
+        let ancestor = &region[..end];
+        let (Some(v1), Some(v2)) = (
+            get_value_at_path(t1, ancestor),
+            get_value_at_path(t2, ancestor),
+        ) else {
+            return None;
+        };
+        if end < depth && (is_tagged(v1) || is_tagged(v2)) {
+            return None;
+        }
+    }
+    let region_path = format_path("root", region);
+    let reported_above =
+        report_paths(result).any(|path| path != region_path && is_within(&region_path, path));
+    (!reported_above).then_some(region)
+}
+
+fn report_paths(result: &Value) -> impl Iterator<Item = &str> {
+    result
+        .as_object()
+        .into_iter()
+        .flat_map(Map::values)
+        .flat_map(|entries| -> Box<dyn Iterator<Item = &str>> {
+            match entries {
+                Value::Object(map) => Box::new(map.keys().map(String::as_str)),
+                Value::Array(paths) => Box::new(paths.iter().filter_map(Value::as_str)),
+                _ => Box::new(std::iter::empty()),
+            }
+        })
+}
+
+/// Whether `path` is `ancestor` or lies below it.
+fn is_within(path: &str, ancestor: &str) -> bool {
+    match path.strip_prefix(ancestor) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['[', '.']),
+        None => false,
+    }
+}
+
+/// Adds the entries of `partial` to `report`, which holds none of their
+/// paths, after its own; with `sort_paths`, keeps them ordered by path.
+pub(crate) fn merge(
+    report: &mut Map<String, Value>,
+    partial: Map<String, Value>,
+    options: &DeepDiffOptions,
+) {
+    for (report_type, entries) in partial {
+        match (report.get_mut(&report_type), entries) {
+            (Some(Value::Object(existing)), Value::Object(added)) => {
+                existing.extend(added);
+                if options.sort_paths {
+                    existing.sort_keys();
+                }
+            }
+            (Some(Value::Array(existing)), Value::Array(added)) => {
+                existing.extend(added);
+                if options.sort_paths {
+                    existing.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                }
+            }
+            (_, entries) => {
+                report.insert(report_type, entries);
+            }
+        }
+    }
+}
+
+/// Reorders the entries of a report key by where their paths lie in the
+/// documents, as a full diff finds them.
+fn in_document_order(entries: &mut Value, t1: &Value, t2: &Value) {
+    match entries {
+        Value::Object(map) => {
+            let mut ordered: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            ordered.sort_by_cached_key(|(path, _)| document_position(path, t1, t2));
+            map.extend(ordered);
+        }
+        Value::Array(paths) => paths.sort_by_cached_key(|path| {
+            document_position(path.as_str().unwrap_or_default(), t1, t2)
+        }),
+        _ => {}
+    }
+}
+
+/// Position of each step of `path` among its siblings: the index of a list
+/// item, or the place of a key or attribute in `t1`, then in `t2` for those
+/// only `t2` has.
+pub(crate) fn document_position(path: &str, t1: &Value, t2: &Value) -> Vec<usize> {
+    let Some(segments) = parse_path(path) else {
+        return vec![usize::MAX];
+    };
+    let mut position = Vec::with_capacity(segments.len());
+    for (depth, segment) in segments.iter().enumerate() {
+        let parents = [t1, t2].map(|side| get_value_at_path(side, &segments[..depth]));
+        let place = match segment {
+            PathSegment::Index(idx) => *idx,
+            PathSegment::Key(name) | PathSegment::Attr(name) => {
+                let names = |parent: Option<&Value>| {
+                    let fields = match (segment, parent) {
+                        (PathSegment::Attr(_), Some(parent)) => as_object(parent).map(|(_, a)| a),
+                        (_, Some(parent)) => parent.as_object(),
+                        _ => None,
+                    };
+                    fields.map_or((None, 0), |fields| {
+                        (fields.keys().position(|key| key == name), fields.len())
+                    })
+                };
+                let ((in_t1, t1_len), (in_t2, _)) = (names(parents[0]), names(parents[1]));
+                in_t1
+                    .or_else(|| in_t2.map(|place| t1_len + place))
+                    .unwrap_or(usize::MAX)
+            }
+        };
+        position.push(place);
+    }
+    position
+}
@@ -0,0 +1,515 @@
+use crate::change::Repetition;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Typed form of the result dict, one list per report key in path order.
+///
+/// Values are absent from entries of paths-only reports (`verbose_level` 0
+/// or `max_report_bytes`). [`DiffReport::to_value`] gives back the result
+/// dict.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiffReport {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values_changed: Vec<ValueChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_changes: Vec<TypeChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dictionary_item_added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dictionary_item_removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attribute_added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attribute_removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iterable_item_added: Vec<ItemChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iterable_item_removed: Vec<ItemChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iterable_item_moved: Vec<MovedItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repetition_change: Vec<RepetitionChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schema_violations: Vec<SchemaViolation>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub report_truncated: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub max_diffs_reached: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingReport>,
+    /// Fields attached to changes by [`crate::DeepDiffOptions::annotate`],
+    /// by path.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, Map<String, Value>>,
+}
+
+/// The `sampling` block of a diff that compared only a sample of the items
+/// of `sampled_containers` large lists and dicts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SamplingReport {
+    pub rate: f64,
+    pub threshold: usize,
+    pub seed: u64,
+    pub sampled_containers: usize,
+}
+
+/// An entry of `values_changed`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValueChange {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Value>,
+    /// Set instead of the values for strings past `large_string_threshold`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<StringSummary>,
+    /// How large a numeric change is, from 0 to 1, with `magnitude_scores`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub magnitude: Option<f64>,
+}
+
+/// Lengths and changed byte ranges of two large strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StringSummary {
+    pub old_length: usize,
+    pub new_length: usize,
+    pub changed_ranges: Vec<ChangedRange>,
+}
+
+/// Bytes `old[0]..old[1]` of the old string replaced by `new[0]..new[1]`
+/// of the new one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedRange {
+    pub old: [usize; 2],
+    pub new: [usize; 2],
+}
+
+/// An entry of `type_changes`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeChange {
+    pub path: String,
+    pub old_type: String,
+    pub new_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Value>,
+}
+
+/// An entry of `schema_violations`: the values, on either side, whose type
+/// the schema does not allow.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub expected_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<Value>,
+}
+
+/// An entry of `iterable_item_added` or `iterable_item_removed`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemChange {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// An entry of `iterable_item_moved`: an item of the old list at `path`
+/// found unchanged at `new_path` in the new one. Only the path is set in
+/// paths-only reports.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovedItem {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// An entry of `repetition_change`: an item found `old_repeat` times in the
+/// old list, at `old_indexes`, and `new_repeat` times in the new one. Only
+/// the path is set in paths-only reports.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepetitionChange {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub old_repeat: usize,
+    #[serde(default)]
+    pub new_repeat: usize,
+    #[serde(default)]
+    pub old_indexes: Vec<usize>,
+    #[serde(default)]
+    pub new_indexes: Vec<usize>,
+}
+
+impl DiffReport {
+    /// Reads a result dict as produced by [`crate::DeepDiff::to_value`].
+    pub fn from_value(result: &Value) -> Self {
+        let mut report = Self::default();
+        let Value::Object(map) = result else {
+            return report;
+        };
+        for (key, entries) in map {
+            match key.as_str() {
+                "values_changed" => {
+                    report.values_changed = entries_of(entries)
+                        .map(|(path, entry)| value_change(path, entry))
+                        .collect();
+                }
+                "type_changes" => {
+                    report.type_changes = entries_of(entries)
+                        .map(|(path, entry)| TypeChange {
+                            path,
+                            old_type: type_name(entry, "old_type"),
+                            new_type: type_name(entry, "new_type"),
+                            old_value: field(entry, "old_value"),
+                            new_value: field(entry, "new_value"),
+                        })
+                        .collect();
+                }
+                "dictionary_item_added" => report.dictionary_item_added = paths_of(entries),
+                "dictionary_item_removed" => report.dictionary_item_removed = paths_of(entries),
+                "attribute_added" => report.attribute_added = paths_of(entries),
+                "attribute_removed" => report.attribute_removed = paths_of(entries),
+                "iterable_item_added" => report.iterable_item_added = items_of(entries),
+                "iterable_item_removed" => report.iterable_item_removed = items_of(entries),
+                "iterable_item_moved" => {
+                    report.iterable_item_moved = entries_of(entries)
+                        .map(|(path, entry)| MovedItem {
+                            path,
+                            new_path: field(entry, "new_path")
+                                .and_then(|path| path.as_str().map(str::to_string)),
+                            value: field(entry, "value"),
+                        })
+                        .collect();
+                }
+                "repetition_change" => {
+                    report.repetition_change = entries_of(entries)
+                        .map(|(path, entry)| {
+                            let Repetition {
+                                old_repeat,
+                                new_repeat,
+                                old_indexes,
+                                new_indexes,
+                            } = entry.and_then(Repetition::from_entry).unwrap_or_default();
+                            RepetitionChange {
+                                path,
+                                value: field(entry, "value"),
+                                old_repeat,
+                                new_repeat,
+                                old_indexes,
+                                new_indexes,
+                            }
+                        })
+                        .collect();
+                }
+                "schema_violations" => {
+                    report.schema_violations = entries_of(entries)
+                        .map(|(path, entry)| SchemaViolation {
+                            path,
+                            expected_type: type_name(entry, "expected_type"),
+                            old_value: field(entry, "old_value"),
+                            new_value: field(entry, "new_value"),
+                        })
+                        .collect();
+                }
+                "report_truncated" => report.report_truncated = entries == &Value::Bool(true),
+                "max_diffs_reached" => report.max_diffs_reached = entries == &Value::Bool(true),
+                "sampling" => report.sampling = SamplingReport::deserialize(entries).ok(),
+                "annotations" => {
+                    report.annotations = entries_of(entries)
+                        .filter_map(|(path, entry)| Some((path, entry?.as_object()?.clone())))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// The result dict, in the shape [`crate::DeepDiff::to_value`] returns.
+    pub fn to_value(&self) -> Value {
+        let mut result = Map::new();
+        if !self.values_changed.is_empty() {
+            let paths_only = self
+                .values_changed
+                .iter()
+                .all(|change| change.old_value.is_none() && change.summary.is_none());
+            let entries = if paths_only {
+                Value::Array(
+                    self.values_changed
+                        .iter()
+                        .map(|change| Value::String(change.path.clone()))
+                        .collect(),
+                )
+            } else {
+                let entries = self.values_changed.iter().map(|change| {
+                    let entry = match &change.summary {
+                        Some(summary) => serde_json::to_value(summary).unwrap_or_default(),
+                        None => {
+                            let mut entry = Map::new();
+                            entry.insert(
+                                "old_value".to_string(),
+                                change.old_value.clone().unwrap_or_default(),
+                            );
+                            entry.insert(
+                                "new_value".to_string(),
+                                change.new_value.clone().unwrap_or_default(),
+                            );
+                            if let Some(score) = change.magnitude {
+                                entry.insert("magnitude".to_string(), Value::from(score));
+                            }
+                            Value::Object(entry)
+                        }
+                    };
+                    (change.path.clone(), entry)
+                });
+                Value::Object(entries.collect())
+            };
+            result.insert("values_changed".to_string(), entries);
+        }
+        for (key, paths) in [
+            ("dictionary_item_added", &self.dictionary_item_added),
+            ("dictionary_item_removed", &self.dictionary_item_removed),
+            ("attribute_added", &self.attribute_added),
+            ("attribute_removed", &self.attribute_removed),
+        ] {
+            if !paths.is_empty() {
+                let paths = paths.iter().cloned().map(Value::String).collect();
+                result.insert(key.to_string(), Value::Array(paths));
+            }
+        }
+        for (key, items) in [
+            ("iterable_item_added", &self.iterable_item_added),
+            ("iterable_item_removed", &self.iterable_item_removed),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            let entries = if items.iter().all(|item| item.value.is_none()) {
+                Value::Array(
+                    items
+                        .iter()
+                        .map(|item| Value::String(item.path.clone()))
+                        .collect(),
+                )
+            } else {
+                Value::Object(
+                    items
+                        .iter()
+                        .map(|item| (item.path.clone(), item.value.clone().unwrap_or_default()))
+                        .collect(),
+                )
+            };
+            result.insert(key.to_string(), entries);
+        }
+        if !self.iterable_item_moved.is_empty() {
+            let moved = &self.iterable_item_moved;
+            let entries = if moved.iter().all(|item| item.value.is_none()) {
+                Value::Array(
+                    moved
+                        .iter()
+                        .map(|item| Value::String(item.path.clone()))
+                        .collect(),
+                )
+            } else {
+                Value::Object(
+                    moved
+                        .iter()
+                        .map(|item| {
+                            let mut entry = Map::new();
+                            entry.insert(
+                                "new_path".to_string(),
+                                Value::from(item.new_path.clone().unwrap_or_default()),
+                            );
+                            entry.insert(
+                                "value".to_string(),
+                                item.value.clone().unwrap_or_default(),
+                            );
+                            (item.path.clone(), Value::Object(entry))
+                        })
+                        .collect(),
+                )
+            };
+            result.insert("iterable_item_moved".to_string(), entries);
+        }
+        if !self.repetition_change.is_empty() {
+            let entries = if self
+                .repetition_change
+                .iter()
+                .all(|change| change.value.is_none())
+            {
+                Value::Array(
+                    self.repetition_change
+                        .iter()
+                        .map(|change| Value::String(change.path.clone()))
+                        .collect(),
+                )
+            } else {
+                Value::Object(
+                    self.repetition_change
+                        .iter()
+                        .map(|change| {
+                            let repetition = Repetition {
+                                old_repeat: change.old_repeat,
+                                new_repeat: change.new_repeat,
+                                old_indexes: change.old_indexes.clone(),
+                                new_indexes: change.new_indexes.clone(),
+                            };
+                            let value = change.value.clone().unwrap_or_default();
+                            (change.path.clone(), repetition.to_entry(value))
+                        })
+                        .collect(),
+                )
+            };
+            result.insert("repetition_change".to_string(), entries);
+        }
+        if !self.type_changes.is_empty() {
+            let entries = self.type_changes.iter().map(|change| {
+                let mut entry = Map::new();
+                entry.insert("old_type".to_string(), Value::from(change.old_type.clone()));
+                entry.insert("new_type".to_string(), Value::from(change.new_type.clone()));
+                if let Some(value) = &change.old_value {
+                    entry.insert("old_value".to_string(), value.clone());
+                }
+                if let Some(value) = &change.new_value {
+                    entry.insert("new_value".to_string(), value.clone());
+                }
+                (change.path.clone(), Value::Object(entry))
+            });
+            result.insert("type_changes".to_string(), Value::Object(entries.collect()));
+        }
+        if !self.schema_violations.is_empty() {
+            let entries = self.schema_violations.iter().map(|violation| {
+                let mut entry = Map::new();
+                entry.insert(
+                    "expected_type".to_string(),
+                    Value::from(violation.expected_type.clone()),
+                );
+                if let Some(value) = &violation.old_value {
+                    entry.insert("old_value".to_string(), value.clone());
+                }
+                if let Some(value) = &violation.new_value {
+                    entry.insert("new_value".to_string(), value.clone());
+                }
+                (violation.path.clone(), Value::Object(entry))
+            });
+            result.insert(
+                "schema_violations".to_string(),
+                Value::Object(entries.collect()),
+            );
+        }
+        if self.report_truncated {
+            result.insert("report_truncated".to_string(), Value::Bool(true));
+        }
+        if self.max_diffs_reached {
+            result.insert("max_diffs_reached".to_string(), Value::Bool(true));
+        }
+        if let Some(sampling) = &self.sampling {
+            result.insert(
+                "sampling".to_string(),
+                serde_json::to_value(sampling).unwrap_or_default(),
+            );
+        }
+        if !self.annotations.is_empty() {
+            let entries = self
+                .annotations
+                .iter()
+                .map(|(path, fields)| (path.clone(), Value::Object(fields.clone())));
+            result.insert("annotations".to_string(), Value::Object(entries.collect()));
+        }
+        Value::Object(result)
+    }
+
+    /// The scored `values_changed` entries with a `magnitude` of at least
+    /// `min`, largest first, so the most significant numeric drifts lead.
+    pub fn by_magnitude(&self, min: f64) -> Vec<&ValueChange> {
+        let mut changes: Vec<&ValueChange> = self
+            .values_changed
+            .iter()
+            .filter(|change| change.magnitude.is_some_and(|score| score >= min))
+            .collect();
+        changes.sort_by(|a, b| {
+            b.magnitude
+                .partial_cmp(&a.magnitude)
+                .unwrap_or(Ordering::Equal)
+        });
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values_changed.is_empty()
+            && self.type_changes.is_empty()
+            && self.dictionary_item_added.is_empty()
+            && self.dictionary_item_removed.is_empty()
+            && self.attribute_added.is_empty()
+            && self.attribute_removed.is_empty()
+            && self.iterable_item_added.is_empty()
+            && self.iterable_item_removed.is_empty()
+            && self.iterable_item_moved.is_empty()
+            && self.repetition_change.is_empty()
+            && self.schema_violations.is_empty()
+    }
+}
+
+/// `(path, entry)` pairs of a report key, whether it maps paths to entries
+/// or lists paths only.
+fn entries_of(entries: &Value) -> Box<dyn Iterator<Item = (String, Option<&Value>)> + '_> {
+    match entries {
+        Value::Object(map) => Box::new(map.iter().map(|(path, entry)| (path.clone(), Some(entry)))),
+        Value::Array(paths) => Box::new(
+            paths
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|path| (path.to_string(), None)),
+        ),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn paths_of(entries: &Value) -> Vec<String> {
+    entries_of(entries).map(|(path, _)| path).collect()
+}
+
+fn items_of(entries: &Value) -> Vec<ItemChange> {
+    entries_of(entries)
+        .map(|(path, value)| ItemChange {
+            path,
+            value: value.cloned(),
+        })
+        .collect()
+}
+
+fn value_change(path: String, entry: Option<&Value>) -> ValueChange {
+    let summary = entry
+        .filter(|entry| entry.get("changed_ranges").is_some())
+        .and_then(|entry| serde_json::from_value(entry.clone()).ok());
+    ValueChange {
+        path,
+        old_value: field(entry, "old_value"),
+        new_value: field(entry, "new_value"),
+        summary,
+        magnitude: entry
+            .and_then(|entry| entry.get("magnitude"))
+            .and_then(Value::as_f64),
+    }
+}
+
+fn field(entry: Option<&Value>, name: &str) -> Option<Value> {
+    entry?.get(name).cloned()
+}
+
+fn type_name(entry: Option<&Value>, name: &str) -> String {
+    entry
+        .and_then(|entry| entry.get(name))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
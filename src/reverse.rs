@@ -0,0 +1,235 @@
+//! Turning a result around: the report of `t2` against `t1` derived from
+//! the report of `t1` against `t2`.
+
+use crate::change::ChangeKind;
+use crate::engine::{self, keyed, Aligned};
+use crate::hash::canonical_hash;
+use crate::options::DeepDiffOptions;
+use crate::path::{format_path, get_value_at_path, parse_path, PathSegment};
+use crate::rules;
+use crate::tagged::sequence_items;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The result of diffing `t2` against `t1`, given the result of diffing `t1`
+/// against `t2` under `options`: additions and removals trade places, the
+/// old and new sides of each entry swap, and paths through lists matched by
+/// key move from the indices of paired items in `t1` to those in `t2`.
+/// Aligned lists may align differently the other way round, so under
+/// `align_lists` the sides are diffed again instead.
+pub(crate) fn reversed_result(
+    result: &Value,
+    t1: &Value,
+    t2: &Value,
+    options: &DeepDiffOptions,
+) -> Value {
+    let Value::Object(map) = result else {
+        return result.clone();
+    };
+    if options.align_lists {
+        return crate::canonical_result(t2, t1, options);
+    }
+    let pairs_by_key =
+        options.match_by.is_some() || options.path_rules.iter().any(|rule| rule.matches_by_key());
+    let mut reversed = Map::new();
+    for (key, entries) in map {
+        let Some(kind) = ChangeKind::from_key(key) else {
+            reversed.insert(key.clone(), entries.clone());
+            continue;
+        };
+        let path = |path: &str| {
+            if pairs_by_key {
+                counterpart(path, kind, t1, t2, options)
+            } else {
+                path.to_string()
+            }
+        };
+        let swaps_sides = matches!(
+            kind,
+            ChangeKind::ValuesChanged
+                | ChangeKind::TypeChanged
+                | ChangeKind::SchemaViolation
+                | ChangeKind::RepetitionChange
+        );
+        let entries = match entries {
+            Value::Object(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(at, entry)| {
+                        let at = match kind {
+                            ChangeKind::RepetitionChange => first_copy(&path(at), entry),
+                            _ => path(at),
+                        };
+                        let entry = if swaps_sides {
+                            swapped_entry(entry)
+                        } else {
+                            entry.clone()
+                        };
+                        (at, entry)
+                    })
+                    .collect(),
+            ),
+            Value::Array(paths) => Value::Array(
+                paths
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|at| Value::String(path(at)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        };
+        reversed.insert(kind.reversed().as_str().to_string(), entries);
+    }
+    if options.sort_paths {
+        engine::sort_report(&mut reversed);
+    }
+    Value::Object(reversed)
+}
+
+/// `path`, reported for a change of `kind` with the indices of paired items
+/// in `t1`, with the indices of the same items in `t2`. The last index of an
+/// added, removed or moved item already is that of the side holding it.
+pub(crate) fn counterpart(
+    path: &str,
+    kind: ChangeKind,
+    t1: &Value,
+    t2: &Value,
+    options: &DeepDiffOptions,
+) -> String {
+    let Some(segments) = parse_path(path) else {
+        return path.to_string();
+    };
+    let item_change = matches!(
+        kind,
+        ChangeKind::IterableItemAdded
+            | ChangeKind::IterableItemRemoved
+            | ChangeKind::IterableItemMoved
+    );
+    let mut options = Cow::Borrowed(options);
+    let (mut node1, mut node2) = (Some(t1), Some(t2));
+    let mut translated = Vec::with_capacity(segments.len());
+    for (depth, segment) in segments.iter().enumerate() {
+        if !options.path_rules.is_empty() {
+            if let Some(scoped) = rules::scoped(&options, &format_path("root", &segments[..depth]))
+            {
+                options = Cow::Owned(scoped);
+            }
+        }
+        let mut step = segment.clone();
+        let lists = node1
+            .and_then(sequence_items)
+            .zip(node2.and_then(sequence_items));
+        let last_item = item_change && depth + 1 == segments.len();
+        if let (PathSegment::Index(idx), Some((list1, list2)), false) = (segment, lists, last_item)
+        {
+            let key = options
+                .match_by
+                .as_deref()
+                .filter(|_| !options.ignore_order);
+            if let Some(key) = key.filter(|key| keyed(list1, key) && keyed(list2, key)) {
+                if let Some(other) = paired_index(list1, list2, *idx, key, &options) {
+                    step = PathSegment::Index(other);
+                }
+            } else if options.align_lists && !options.ignore_order {
+                if let Some(other) = aligned_index(list1, list2, *idx, &options) {
+                    step = PathSegment::Index(other);
+                }
+            }
+        }
+        node1 = node1.and_then(|node| get_value_at_path(node, std::slice::from_ref(segment)));
+        node2 = node2.and_then(|node| get_value_at_path(node, std::slice::from_ref(&step)));
+        translated.push(step);
+    }
+    format_path("root", &translated)
+}
+
+/// The index in `list2` of the item paired with `list1[idx]` by `key`: the
+/// nth item of `list1` with a key value pairs with the nth of `list2`.
+fn paired_index(
+    list1: &[Value],
+    list2: &[Value],
+    idx: usize,
+    key: &str,
+    options: &DeepDiffOptions,
+) -> Option<usize> {
+    let hash = canonical_hash(&list1.get(idx)?[key], options);
+    let occurrence = list1[..idx]
+        .iter()
+        .filter(|item| canonical_hash(&item[key], options) == hash)
+        .count();
+    list2
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| canonical_hash(&item[key], options) == hash)
+        .nth(occurrence)
+        .map(|(other, _)| other)
+}
+
+/// The index in `list2` of the item `list1[idx]` is diffed against or moved
+/// to when the two lists are aligned.
+fn aligned_index(
+    list1: &[Value],
+    list2: &[Value],
+    idx: usize,
+    options: &DeepDiffOptions,
+) -> Option<usize> {
+    engine::align_items(list1, list2, options)?
+        .into_iter()
+        .find_map(|step| match step {
+            Aligned::Paired(idx1, idx2) | Aligned::Moved(idx1, idx2) if idx1 == idx => Some(idx2),
+            _ => None,
+        })
+}
+
+/// `path` of a repetition change, reported at the first copy of the item in
+/// `t1`, at its first copy in `t2` instead.
+fn first_copy(path: &str, entry: &Value) -> String {
+    let first = entry["new_indexes"][0].as_u64();
+    match (parse_path(path), first) {
+        (Some(mut segments), Some(first)) => {
+            if let Some(last) = segments.last_mut() {
+                *last = PathSegment::Index(first as usize);
+            }
+            format_path("root", &segments)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// `entry` with its `old_*` and `new_*` fields swapped, including those of a
+/// large-string `summary`; values are kept as they are.
+fn swapped_entry(entry: &Value) -> Value {
+    let Value::Object(fields) = entry else {
+        return entry.clone();
+    };
+    Value::Object(
+        fields
+            .iter()
+            .map(|(name, value)| {
+                let value = match name.as_str() {
+                    "summary" => swapped_entry(value),
+                    "changed_ranges" => Value::Array(
+                        value
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .map(swapped_entry)
+                            .collect(),
+                    ),
+                    _ => value.clone(),
+                };
+                (swapped_name(name), value)
+            })
+            .collect(),
+    )
+}
+
+fn swapped_name(name: &str) -> String {
+    if let Some(rest) = name.strip_prefix("old") {
+        format!("new{}", rest)
+    } else if let Some(rest) = name.strip_prefix("new") {
+        format!("old{}", rest)
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,183 @@
+use crate::error::TurboDiffError;
+use crate::options::DeepDiffOptions;
+use crate::path::is_well_formed;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A rules document: comparison settings per path pattern, kept in config
+/// (JSON, or YAML and the like through their serde crates) instead of code
+/// and compiled into [`DeepDiffOptions`] with [`DeepDiffOptions::rules`].
+///
+/// ```json
+/// {"rules": [
+///     {"path": "root['metrics']", "atol": 0.01},
+///     {"path": "root['users']", "match_by": "id"},
+///     {"path": "root['users'][*]['token']", "redact": true},
+///     {"path": "root['debug']", "ignore": true}
+/// ]}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// Settings for the subtrees at the paths matching `path`, where `[*]` (or
+/// `['*']`) matches any one key or index. Unset settings keep the options'
+/// own; where several rules match a path, later rules win.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub path: String,
+    /// Leaves the subtree out of the diff.
+    #[serde(default)]
+    pub ignore: bool,
+    /// Reports the values in the subtree as `"***"`.
+    #[serde(default)]
+    pub redact: bool,
+    pub ignore_order: Option<bool>,
+    pub ignore_numeric_type_changes: Option<bool>,
+    pub significant_digits: Option<u32>,
+    pub atol: Option<f64>,
+    pub rtol: Option<f64>,
+    /// Pairs the items of lists of objects by this key instead of by index.
+    pub match_by: Option<String>,
+}
+
+impl Rules {
+    /// Reads a rules document from JSON text.
+    pub fn from_json(text: &str) -> Result<Self, TurboDiffError> {
+        let value = serde_json::from_str(text)
+            .map_err(|err| TurboDiffError::InvalidRule(err.to_string()))?;
+        Self::from_value(value)
+    }
+
+    /// Reads a rules document already parsed into a value, such as one loaded
+    /// from YAML; a bare list of rules is accepted too.
+    pub fn from_value(value: Value) -> Result<Self, TurboDiffError> {
+        let value = match value {
+            Value::Array(rules) => serde_json::json!({ "rules": rules }),
+            document => document,
+        };
+        let rules: Self = serde_json::from_value(value)
+            .map_err(|err| TurboDiffError::InvalidRule(err.to_string()))?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Checks that every rule's `path` is a path pattern.
+    pub fn validate(&self) -> Result<(), TurboDiffError> {
+        match self.rules.iter().find(|rule| !is_pattern(&rule.path)) {
+            Some(rule) => Err(TurboDiffError::InvalidRule(rule.path.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn is_pattern(pattern: &str) -> bool {
+    is_well_formed(&pattern.replace("[*]", "['*']"))
+}
+
+/// A rule whose settings apply from the nodes its pattern matches down.
+#[derive(Clone, Debug)]
+pub(crate) struct PathRule {
+    pub(crate) source: String,
+    exact: Regex,
+    rule: Rule,
+}
+
+impl PathRule {
+    pub(crate) fn new(rule: Rule) -> Self {
+        Self {
+            source: rule.path.clone(),
+            exact: Regex::new(&format!("^{}$", pattern_regex(&rule.path, false)))
+                .unwrap_or_else(|_| Regex::new("^$").expect("empty pattern")),
+            rule,
+        }
+    }
+
+    pub(crate) fn matches_by_key(&self) -> bool {
+        self.rule.match_by.is_some()
+    }
+
+    pub(crate) fn tolerances_valid(&self) -> bool {
+        [self.rule.atol, self.rule.rtol]
+            .into_iter()
+            .flatten()
+            .all(|value| value.is_finite() && value >= 0.0)
+    }
+}
+
+/// A regex for `pattern` matching its paths and everything under them.
+pub(crate) fn subtree_regex(pattern: &str) -> Regex {
+    Regex::new(&format!("^{}(?:$|[\\[.])", pattern_regex(pattern, false)))
+        .unwrap_or_else(|_| Regex::new("^$").expect("empty pattern"))
+}
+
+/// Regex for one bracketed path step, reading quoted keys to their closing
+/// quote so that keys holding `]` stay one step.
+const ANY_STEP: &str = r#"\[(?:[^\]'"]|'(?:[^'\\]|\\.)*'|"(?:[^"\\]|\\.)*")*\]"#;
+
+/// `pattern` as regex syntax, with `[*]` and `['*']` matching any one
+/// bracketed step, in a capture group each when `capture` is set.
+pub(crate) fn pattern_regex(pattern: &str, capture: bool) -> String {
+    let mut out = String::with_capacity(pattern.len() * 2);
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        if let Some(after) = rest
+            .strip_prefix("[*]")
+            .or_else(|| rest.strip_prefix("['*']"))
+        {
+            if capture {
+                out.push('(');
+                out.push_str(ANY_STEP);
+                out.push(')');
+            } else {
+                out.push_str(ANY_STEP);
+            }
+            rest = after;
+            continue;
+        }
+        let ch = rest.chars().next().unwrap_or_default();
+        out.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4])));
+        rest = &rest[ch.len_utf8()..];
+    }
+    out
+}
+
+/// The options for the subtree at `path` when rules match it exactly: the
+/// options with the settings of those rules applied, without the rules.
+pub(crate) fn scoped(options: &DeepDiffOptions, path: &str) -> Option<DeepDiffOptions> {
+    let (matched, rest): (Vec<&PathRule>, Vec<&PathRule>) = options
+        .path_rules
+        .iter()
+        .partition(|rule| rule.exact.is_match(path));
+    if matched.is_empty() {
+        return None;
+    }
+    let mut scoped = options.clone();
+    scoped.path_rules = rest.into_iter().cloned().collect();
+    for PathRule { rule, .. } in matched {
+        if let Some(value) = rule.ignore_order {
+            scoped.ignore_order = value;
+        }
+        if let Some(value) = rule.ignore_numeric_type_changes {
+            scoped.ignore_numeric_type_changes = value;
+        }
+        if rule.significant_digits.is_some() {
+            scoped.significant_digits = rule.significant_digits;
+        }
+        if rule.atol.is_some() {
+            scoped.atol = rule.atol;
+        }
+        if rule.rtol.is_some() {
+            scoped.rtol = rule.rtol;
+        }
+        if rule.match_by.is_some() {
+            scoped.match_by = rule.match_by.clone();
+        }
+    }
+    Some(scoped)
+}
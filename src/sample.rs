@@ -0,0 +1,86 @@
+use crate::hash::FxHasher;
+use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
+
+/// Compares only a sample of the items of large containers, for a quick
+/// "has this drifted?" check of documents too large to diff in full.
+///
+/// Lists and dicts with more than `threshold` items on either side diff
+/// the indices and keys a hash of `seed` picks, about `rate` of them; the
+/// same seed picks the same items on every run. Unordered lists are not
+/// sampled, and no container is compared whole, so the skipped items are
+/// never read. Results of sampled diffs carry a `sampling` block recording
+/// the settings and how many containers were sampled.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sampling {
+    pub rate: f64,
+    #[serde(default = "Sampling::default_threshold")]
+    pub threshold: usize,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Sampling {
+    pub const DEFAULT_THRESHOLD: usize = 10_000;
+
+    /// Samples `rate` of the items of containers past the default threshold
+    /// with seed 0.
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            threshold: Self::DEFAULT_THRESHOLD,
+            seed: 0,
+        }
+    }
+
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn default_threshold() -> usize {
+        Self::DEFAULT_THRESHOLD
+    }
+
+    pub(crate) fn rate_valid(&self) -> bool {
+        self.rate > 0.0 && self.rate <= 1.0
+    }
+
+    /// Whether containers holding `len1` and `len2` items are sampled.
+    pub(crate) fn applies(&self, len1: usize, len2: usize) -> bool {
+        len1.max(len2) > self.threshold
+    }
+
+    pub(crate) fn keeps_index(&self, idx: usize) -> bool {
+        self.keeps(mix(self.seed ^ (idx as u64).wrapping_mul(GOLDEN)))
+    }
+
+    pub(crate) fn keeps_key(&self, key: &str) -> bool {
+        let mut hasher = FxHasher::default();
+        hasher.write_u64(self.seed);
+        hasher.write(key.as_bytes());
+        self.keeps(mix(hasher.finish()))
+    }
+
+    fn keeps(&self, hash: u64) -> bool {
+        // The top 53 bits as a uniform float in [0, 1).
+        ((hash >> 11) as f64 / (1u64 << 53) as f64) < self.rate
+    }
+}
+
+const GOLDEN: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The splitmix64 finalizer, spreading nearby inputs such as consecutive
+/// indices over the whole range.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(GOLDEN);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,142 @@
+//! JSON Schema support: finding the subschema of a node, the keys a schema
+//! leaves out of the comparison, per-`format` tolerances, and values whose
+//! type the schema does not allow.
+
+use crate::options::DeepDiffOptions;
+use crate::path::Step;
+use crate::tagged::is_tagged;
+use regex::Regex;
+use serde_json::Value;
+
+/// How many `$ref`s are followed in a row before giving up on a cycle.
+const MAX_REF_DEPTH: usize = 32;
+
+/// The subschema describing the node at `steps`, or `None` where the schema
+/// does not constrain it.
+pub(crate) fn subschema<'s>(root: &'s Value, steps: &[Step<'_>]) -> Option<&'s Value> {
+    let mut schema = resolve(root, root)?;
+    for step in steps {
+        schema = child(root, schema, step)?;
+    }
+    Some(schema)
+}
+
+/// The subschema of the child of a node described by `schema` reached by
+/// `step`, with `$ref`s followed.
+pub(crate) fn child<'s>(root: &'s Value, schema: &'s Value, step: &Step<'_>) -> Option<&'s Value> {
+    let child = match step {
+        Step::Key(key) | Step::Attr(key) => property(schema, key),
+        Step::Index(idx) => item(schema, *idx),
+        Step::Row(_) | Step::Cell(..) => None,
+    }?;
+    resolve(root, child)
+}
+
+/// Whether the schema of an object leaves its `key` out of the comparison:
+/// the property is marked `deprecated`, or the object declares `properties`
+/// without `key`, so it is only allowed by `additionalProperties`.
+pub(crate) fn ignores_key(root: &Value, schema: &Value, key: &str) -> bool {
+    let Some(Value::Object(properties)) = schema.get("properties") else {
+        return false;
+    };
+    match properties.get(key).and_then(|child| resolve(root, child)) {
+        Some(child) => child.get("deprecated") == Some(&Value::Bool(true)),
+        None => !matches_pattern_property(schema, key),
+    }
+}
+
+/// The tolerance configured for the `format` of `schema`, if any.
+pub(crate) fn format_tolerance(schema: &Value, options: &DeepDiffOptions) -> Option<f64> {
+    let format = schema.get("format")?.as_str()?;
+    options
+        .format_tolerances
+        .iter()
+        .find(|(name, _)| name == format)
+        .map(|(_, tolerance)| *tolerance)
+}
+
+/// The `type` of `schema`, as written, when `value` is not one of its types.
+/// Tagged values are left to the bindings that produced them.
+pub(crate) fn type_violation(schema: &Value, value: &Value) -> Option<String> {
+    if is_tagged(value) {
+        return None;
+    }
+    let allowed = schema.get("type")?;
+    let matches = |name: &Value| match name.as_str() {
+        Some("null") => value.is_null(),
+        Some("boolean") => value.is_boolean(),
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("string") => value.is_string(),
+        Some("number") => value.is_number(),
+        Some("integer") => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    };
+    let ok = match allowed {
+        Value::Array(names) => names.iter().any(matches),
+        name => matches(name),
+    };
+    if ok {
+        return None;
+    }
+    Some(match allowed {
+        Value::String(name) => name.clone(),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        other => other.to_string(),
+    })
+}
+
+fn property<'s>(schema: &'s Value, key: &str) -> Option<&'s Value> {
+    if let Some(child) = schema.get("properties").and_then(|props| props.get(key)) {
+        return Some(child);
+    }
+    if let Some(Value::Object(patterns)) = schema.get("patternProperties") {
+        let matched = patterns
+            .iter()
+            .find(|(pattern, _)| Regex::new(pattern).is_ok_and(|pattern| pattern.is_match(key)));
+        if let Some((_, child)) = matched {
+            return Some(child);
+        }
+    }
+    schema
+        .get("additionalProperties")
+        .filter(|child| child.is_object())
+}
+
+fn matches_pattern_property(schema: &Value, key: &str) -> bool {
+    let Some(Value::Object(patterns)) = schema.get("patternProperties") else {
+        return false;
+    };
+    patterns
+        .keys()
+        .any(|pattern| Regex::new(pattern).is_ok_and(|pattern| pattern.is_match(key)))
+}
+
+fn item(schema: &Value, idx: usize) -> Option<&Value> {
+    for tuple_key in ["prefixItems", "items"] {
+        if let Some(Value::Array(items)) = schema.get(tuple_key) {
+            if let Some(child) = items.get(idx) {
+                return Some(child);
+            }
+        }
+    }
+    schema.get("items").filter(|child| child.is_object())
+}
+
+/// Follows local `$ref`s (`#/$defs/name`, `#/definitions/name`) from
+/// `schema` to the schema they point to.
+fn resolve<'s>(root: &'s Value, mut schema: &'s Value) -> Option<&'s Value> {
+    for _ in 0..MAX_REF_DEPTH {
+        let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+            return Some(schema);
+        };
+        schema = root.pointer(reference.strip_prefix('#')?)?;
+    }
+    None
+}
@@ -0,0 +1,308 @@
+use crate::change::ChangeKind;
+use crate::error::TurboDiffError;
+use crate::options::TypeNames;
+use crate::path::{Path, PathSegment};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// How much a change matters under a set of [`SeverityRules`], from least to
+/// most.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Tolerated drift.
+    #[default]
+    Info,
+    Warning,
+    Breaking,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Breaking => "breaking",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = TurboDiffError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "breaking" => Ok(Self::Breaking),
+            _ => Err(TurboDiffError::InvalidRule(text.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SeverityRule {
+    pattern: Path,
+    kind: Option<ChangeKind>,
+    severity: Severity,
+}
+
+impl SeverityRule {
+    /// Whether the change at `path` is at or under a path the pattern
+    /// matches, a `'*'` key in the pattern matching any one step.
+    fn matches(&self, kind: ChangeKind, path: &Path) -> bool {
+        let (pattern, path) = (self.pattern.segments(), path.segments());
+        self.kind.is_none_or(|rule_kind| rule_kind == kind)
+            && pattern.len() <= path.len()
+            && pattern.iter().zip(path).all(|(expected, actual)| {
+                matches!(expected, PathSegment::Key(key) if key == "*") || expected == actual
+            })
+    }
+}
+
+/// Path patterns with the severity of the changes under them; the first rule
+/// that matches a change decides its severity. Changes no path rule matches
+/// get the severity set for their type change or their kind, if any, and
+/// otherwise the default severity ([`Severity::Info`] unless set).
+#[derive(Clone, Debug, Default)]
+pub struct SeverityRules {
+    rules: Vec<SeverityRule>,
+    type_changes: Vec<(String, String, Severity)>,
+    kinds: Vec<(ChangeKind, Severity)>,
+    default: Severity,
+}
+
+impl SeverityRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rules for comparing API payload examples or specs, old against new:
+    /// removed fields and items and narrowed types are breaking, added fields
+    /// and items, changed example values, and widened numeric types
+    /// (`int` to `float` or `Decimal`) are compatible. Path rules added on
+    /// top take precedence.
+    pub fn api_compat() -> Self {
+        let mut rules = Self::new();
+        for kind in [
+            ChangeKind::DictionaryItemRemoved,
+            ChangeKind::AttributeRemoved,
+            ChangeKind::IterableItemRemoved,
+            ChangeKind::TypeChanged,
+            ChangeKind::SchemaViolation,
+        ] {
+            rules = rules.kind_severity(kind, Severity::Breaking);
+        }
+        for (old_type, new_type) in [("int", "float"), ("int", "Decimal"), ("float", "Decimal")] {
+            rules = rules.type_change(old_type, new_type, Severity::Info);
+        }
+        rules
+    }
+
+    /// Reads one rule per line, `root['api']['*'] => breaking`, optionally
+    /// limited to a report key: `dictionary_item_removed root['api'] =>
+    /// breaking`. A report key alone, `type_changes => warning`, sets the
+    /// severity of that kind and `* => warning` the default. Blank lines and
+    /// lines starting with `#` are skipped; `[*]` may stand for `['*']`.
+    pub fn parse(text: &str) -> Result<Self, TurboDiffError> {
+        Self::new().read(text)
+    }
+
+    /// Adds the rules of `text`, in the format of [`SeverityRules::parse`],
+    /// after the rules already set.
+    pub fn read(self, text: &str) -> Result<Self, TurboDiffError> {
+        let mut rules = self;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || TurboDiffError::InvalidRule(line.to_string());
+            let (target, severity) = line.rsplit_once("=>").ok_or_else(invalid)?;
+            let severity: Severity = severity.parse().map_err(|_| invalid())?;
+            let target = target.trim();
+            if target == "*" {
+                rules = rules.default_severity(severity);
+                continue;
+            }
+            if let Some(kind) = ChangeKind::from_key(target) {
+                rules = rules.kind_severity(kind, severity);
+                continue;
+            }
+            let (kind, pattern) = match target.split_once(char::is_whitespace) {
+                Some((key, pattern)) if !key.starts_with("root") => (
+                    Some(ChangeKind::from_key(key).ok_or_else(invalid)?),
+                    pattern,
+                ),
+                _ => (None, target),
+            };
+            let pattern =
+                Path::parse(&pattern.trim().replace("[*]", "['*']")).ok_or_else(invalid)?;
+            rules.rules.push(SeverityRule {
+                pattern,
+                kind,
+                severity,
+            });
+        }
+        Ok(rules)
+    }
+
+    /// Gives changes at or under `pattern` the severity `severity`. A `'*'`
+    /// key in the pattern, as in `root['api']['*']`, matches any one step.
+    pub fn rule(mut self, pattern: Path, severity: Severity) -> Self {
+        self.rules.push(SeverityRule {
+            pattern,
+            kind: None,
+            severity,
+        });
+        self
+    }
+
+    /// Like [`SeverityRules::rule`], for changes of `kind` only, such as
+    /// removals being breaking where additions are not.
+    pub fn rule_for(mut self, kind: ChangeKind, pattern: Path, severity: Severity) -> Self {
+        self.rules.push(SeverityRule {
+            pattern,
+            kind: Some(kind),
+            severity,
+        });
+        self
+    }
+
+    /// Severity of type changes from `old_type` to `new_type`, such as
+    /// `"int"` to `"float"`, that no path rule matches.
+    pub fn type_change(
+        mut self,
+        old_type: impl Into<String>,
+        new_type: impl Into<String>,
+        severity: Severity,
+    ) -> Self {
+        self.type_changes
+            .push((old_type.into(), new_type.into(), severity));
+        self
+    }
+
+    /// Severity of the changes of `kind` that no path or type change rule
+    /// matches.
+    pub fn kind_severity(mut self, kind: ChangeKind, severity: Severity) -> Self {
+        self.kinds.retain(|(rule_kind, _)| *rule_kind != kind);
+        self.kinds.push((kind, severity));
+        self
+    }
+
+    /// Severity of the changes no rule matches.
+    pub fn default_severity(mut self, severity: Severity) -> Self {
+        self.default = severity;
+        self
+    }
+
+    /// The severity of the change of `kind` at `path`; `types` are the old
+    /// and new type names of a type change.
+    pub fn severity_of(
+        &self,
+        kind: ChangeKind,
+        path: &Path,
+        types: Option<(&str, &str)>,
+    ) -> Severity {
+        if let Some(rule) = self.rules.iter().find(|rule| rule.matches(kind, path)) {
+            return rule.severity;
+        }
+        let type_rule = types.and_then(|(old, new)| {
+            self.type_changes.iter().find(|(rule_old, rule_new, _)| {
+                same_type(rule_old, old) && same_type(rule_new, new)
+            })
+        });
+        if let Some((_, _, severity)) = type_rule {
+            return *severity;
+        }
+        self.kinds
+            .iter()
+            .find(|(rule_kind, _)| *rule_kind == kind)
+            .map_or(self.default, |(_, severity)| *severity)
+    }
+
+    /// Classifies each change of a result dict, as returned by
+    /// [`crate::DeepDiff::to_value`].
+    pub fn classify(&self, result: &Value) -> ClassifiedReport {
+        let mut report = ClassifiedReport::default();
+        for kind in ChangeKind::ALL {
+            let entries: Vec<(&str, Option<&Value>)> = match result.get(kind.as_str()) {
+                Some(Value::Object(map)) => map
+                    .iter()
+                    .map(|(path, entry)| (path.as_str(), Some(entry)))
+                    .collect(),
+                Some(Value::Array(paths)) => paths
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|path| (path, None))
+                    .collect(),
+                _ => continue,
+            };
+            for (path, entry) in entries {
+                let type_name = |name: &str| entry?.get(name)?.as_str();
+                let types = type_name("old_type").zip(type_name("new_type"));
+                let severity = Path::parse(path).map_or(self.default, |parsed| {
+                    self.severity_of(kind, &parsed, types)
+                });
+                report.verdict = report.verdict.max(Some(severity));
+                report.changes.push(ClassifiedChange {
+                    kind,
+                    path: path.to_string(),
+                    severity,
+                });
+            }
+        }
+        report
+    }
+}
+
+/// Whether the type a rule names is `actual`, either of them in either
+/// vocabulary. Two Python names must match exactly, as JSON folds `float`
+/// and `Decimal` into `number`.
+fn same_type(rule: &str, actual: &str) -> bool {
+    let json = |name| TypeNames::Json.name(name);
+    rule == actual || (json(rule) == json(actual) && (json(rule) == rule || json(actual) == actual))
+}
+
+/// A change with the severity [`SeverityRules`] gave it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ClassifiedChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    pub severity: Severity,
+}
+
+/// The changes of a diff with their severities, and the highest of them as
+/// the verdict (`None` when nothing changed).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ClassifiedReport {
+    pub changes: Vec<ClassifiedChange>,
+    pub verdict: Option<Severity>,
+}
+
+impl ClassifiedReport {
+    /// Whether no change is more severe than `allowed`.
+    pub fn passes(&self, allowed: Severity) -> bool {
+        self.verdict.is_none_or(|verdict| verdict <= allowed)
+    }
+
+    /// Whether the changes are backward compatible: none is breaking.
+    pub fn is_compatible(&self) -> bool {
+        self.passes(Severity::Warning)
+    }
+
+    /// Number of changes of `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.severity == severity)
+            .count()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::change::{ChangeKind, Repetition};
+use crate::path::{format_path, get_value_at_path, parse_path};
+use crate::tagged::{as_object, is_tagged, sequence_items};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Change counts of one or more diffs by how deep the changes are, in path
+/// steps below the root, and by the path prefix they fall under, telling
+/// drift in top-level metadata from drift deep in nested data.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ChangeStats {
+    /// Changes per depth; a change at `root['a']['b']` has depth 2.
+    pub by_depth: BTreeMap<usize, usize>,
+    /// Changes per path cut to its first `prefix_depth` steps; changes
+    /// shallower than that count under their own path.
+    pub by_prefix: BTreeMap<String, usize>,
+    pub total: usize,
+}
+
+impl ChangeStats {
+    /// Counts the changes of a result dict with paths starting with `root`,
+    /// writing prefixes from `root_label` instead.
+    pub(crate) fn of(result: &Value, prefix_depth: usize, root_label: &str) -> Self {
+        let mut stats = Self::default();
+        for kind in ChangeKind::ALL {
+            let paths: Vec<&str> = match result.get(kind.as_str()) {
+                Some(Value::Object(map)) => map.keys().map(String::as_str).collect(),
+                Some(Value::Array(paths)) => paths.iter().filter_map(Value::as_str).collect(),
+                _ => continue,
+            };
+            for path in paths {
+                let Some(segments) = parse_path(path) else {
+                    continue;
+                };
+                let prefix = &segments[..segments.len().min(prefix_depth)];
+                *stats.by_depth.entry(segments.len()).or_default() += 1;
+                *stats
+                    .by_prefix
+                    .entry(format_path(root_label, prefix))
+                    .or_default() += 1;
+                stats.total += 1;
+            }
+        }
+        stats
+    }
+
+    /// Adds the counts of `other`, such as those of another document pair.
+    pub fn merge(&mut self, other: &ChangeStats) {
+        for (depth, count) in &other.by_depth {
+            *self.by_depth.entry(*depth).or_default() += count;
+        }
+        for (prefix, count) in &other.by_prefix {
+            *self.by_prefix.entry(prefix.clone()).or_default() += count;
+        }
+        self.total += other.total;
+    }
+
+    /// Share of the changes at depth `depth` or deeper, `0.0` without
+    /// changes.
+    pub fn share_at_least(&self, depth: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let deep: usize = self.by_depth.range(depth..).map(|(_, count)| count).sum();
+        deep as f64 / self.total as f64
+    }
+}
+
+impl<'a> FromIterator<&'a ChangeStats> for ChangeStats {
+    fn from_iter<I: IntoIterator<Item = &'a ChangeStats>>(iter: I) -> Self {
+        let mut total = Self::default();
+        for stats in iter {
+            total.merge(stats);
+        }
+        total
+    }
+}
+
+/// How far apart `t1` and `t2` are, from 0 when equal to 1 when they have
+/// nothing in common, as DeepDiff's `deep_distance` estimates it: the
+/// elements the changes in `result` touch over the elements below the roots
+/// of both documents. A changed value counts once per side, and an added or
+/// removed one with every element in it.
+pub(crate) fn deep_distance(result: &Value, t1: &Value, t2: &Value) -> f64 {
+    let total = element_count(t1) + element_count(t2) - 2;
+    let mut changed = 0;
+    for kind in ChangeKind::ALL {
+        let entries: Vec<(&str, Option<&Value>)> = match result.get(kind.as_str()) {
+            Some(Value::Object(map)) => map
+                .iter()
+                .map(|(path, entry)| (path.as_str(), Some(entry)))
+                .collect(),
+            Some(Value::Array(paths)) => paths
+                .iter()
+                .filter_map(|path| Some((path.as_str()?, None)))
+                .collect(),
+            _ => continue,
+        };
+        for (path, entry) in entries {
+            let at = |root| {
+                let segments = parse_path(path)?;
+                get_value_at_path(root, &segments).map(element_count)
+            };
+            changed += match kind {
+                ChangeKind::ValuesChanged | ChangeKind::TypeChanged => 2,
+                ChangeKind::DictionaryItemAdded
+                | ChangeKind::AttributeAdded
+                | ChangeKind::IterableItemAdded => at(t2).unwrap_or(1),
+                ChangeKind::DictionaryItemRemoved
+                | ChangeKind::AttributeRemoved
+                | ChangeKind::IterableItemRemoved => at(t1).unwrap_or(1),
+                ChangeKind::IterableItemMoved => 1,
+                ChangeKind::RepetitionChange => {
+                    let repetition = entry.and_then(Repetition::from_entry).unwrap_or_default();
+                    let value = entry.and_then(|entry| entry.get("value"));
+                    repetition.old_repeat.abs_diff(repetition.new_repeat)
+                        * value.map_or(1, element_count)
+                }
+                ChangeKind::SchemaViolation => 0,
+            };
+        }
+    }
+    if total == 0 {
+        // Two scalars, or two empty containers.
+        return if changed == 0 { 0.0 } else { 1.0 };
+    }
+    (changed as f64 / total as f64).min(1.0)
+}
+
+/// The values in `value`, itself included; tagged scalars such as dates
+/// count as one.
+fn element_count(value: &Value) -> usize {
+    let children: usize = match (as_object(value), sequence_items(value), value) {
+        (Some((_, attrs)), _, _) => attrs.values().map(element_count).sum(),
+        (_, Some(items), _) => items.iter().map(element_count).sum(),
+        (_, _, Value::Object(map)) if !is_tagged(value) => map.values().map(element_count).sum(),
+        _ => 0,
+    };
+    1 + children
+}
@@ -0,0 +1,131 @@
+use serde_json::Value;
+
+/// Key holding the original type name of a value the bindings could not
+/// represent natively in JSON.
+pub(crate) const TYPE_KEY: &str = "__turbodiff_type__";
+/// Key holding the JSON representation of a tagged value.
+pub(crate) const VALUE_KEY: &str = "__turbodiff_value__";
+
+/// Key holding the attributes of a converted Python object; such objects carry
+/// `TYPE_KEY` with their class name and this key instead of `VALUE_KEY`.
+pub(crate) const ATTRS_KEY: &str = "__turbodiff_attrs__";
+/// Prefix of a map key that holds a non-string key encoded as JSON, keeping
+/// `1` and `"1"` apart.
+pub(crate) const KEY_PREFIX: &str = "__turbodiff_key__:";
+
+pub(crate) fn tagged(type_name: &str, value: Value) -> Value {
+    let mut map = serde_json::Map::with_capacity(2);
+    map.insert(TYPE_KEY.to_string(), Value::String(type_name.to_string()));
+    map.insert(VALUE_KEY.to_string(), value);
+    Value::Object(map)
+}
+
+/// A float as a JSON number, or as a tagged `float` holding `nan`, `inf`, or
+/// `-inf` when it has no JSON representation, the way the Python bindings
+/// pass such floats. NaN is unequal to itself unless
+/// [`DeepDiffOptions::ignore_nan_inequality`](crate::DeepDiffOptions::ignore_nan_inequality)
+/// is set.
+pub fn float_value(f: f64) -> Value {
+    match serde_json::Number::from_f64(f) {
+        Some(number) => Value::Number(number),
+        None if f.is_nan() => tagged("float", Value::String("nan".to_string())),
+        None if f > 0.0 => tagged("float", Value::String("inf".to_string())),
+        None => tagged("float", Value::String("-inf".to_string())),
+    }
+}
+
+#[cfg(feature = "python")]
+pub(crate) fn object(class_name: &str, attrs: serde_json::Map<String, Value>) -> Value {
+    let mut map = serde_json::Map::with_capacity(2);
+    map.insert(TYPE_KEY.to_string(), Value::String(class_name.to_string()));
+    map.insert(ATTRS_KEY.to_string(), Value::Object(attrs));
+    Value::Object(map)
+}
+
+/// Returns the class name and attributes when `value` is a converted object.
+pub(crate) fn as_object(value: &Value) -> Option<(&str, &serde_json::Map<String, Value>)> {
+    let Value::Object(map) = value else {
+        return None;
+    };
+    if map.len() != 2 {
+        return None;
+    }
+    let class_name = map.get(TYPE_KEY)?.as_str()?;
+    let Value::Object(attrs) = map.get(ATTRS_KEY)? else {
+        return None;
+    };
+    Some((class_name, attrs))
+}
+
+/// Returns the type name and inner value when `value` is a tagged value.
+pub(crate) fn as_tagged(value: &Value) -> Option<(&str, &Value)> {
+    let Value::Object(map) = value else {
+        return None;
+    };
+    if map.len() != 2 {
+        return None;
+    }
+    let type_name = map.get(TYPE_KEY)?.as_str()?;
+    let inner = map.get(VALUE_KEY)?;
+    Some((type_name, inner))
+}
+
+/// Returns the class as `module.qualname`, its qualified name alone, and the
+/// member name when `value` is an `Enum` member tagged by the Python bindings.
+pub(crate) fn as_enum_member(value: &Value) -> Option<(&str, &str, &str)> {
+    let ("enum", Value::Object(member)) = as_tagged(value)? else {
+        return None;
+    };
+    let class = member.get("class")?.as_str()?;
+    let module = member.get("module")?.as_str()?;
+    let qualname = class.strip_prefix(module)?.strip_prefix('.')?;
+    Some((class, qualname, member.get("member")?.as_str()?))
+}
+
+pub(crate) fn encode_key(key: &Value) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
+
+/// Returns the original key when `key` was produced by [`encode_key`].
+pub(crate) fn decode_key(key: &str) -> Option<Value> {
+    serde_json::from_str(key.strip_prefix(KEY_PREFIX)?).ok()
+}
+
+pub(crate) fn is_tagged(value: &Value) -> bool {
+    as_tagged(value).is_some()
+}
+
+/// Items of a list or of a tagged `tuple`, the sequences the engine walks.
+pub(crate) fn sequence_items(value: &Value) -> Option<&Vec<Value>> {
+    match (as_tagged(value), value) {
+        (Some(("tuple", Value::Array(items))), _) => Some(items),
+        (None, Value::Array(items)) => Some(items),
+        _ => None,
+    }
+}
+
+/// Mutable counterpart of [`sequence_items`].
+pub(crate) fn sequence_items_mut(value: &mut Value) -> Option<&mut Vec<Value>> {
+    if is_tagged(value) {
+        if as_tagged(value)?.0 != "tuple" {
+            return None;
+        }
+        return match value.get_mut(VALUE_KEY)? {
+            Value::Array(items) => Some(items),
+            _ => None,
+        };
+    }
+    match value {
+        Value::Array(items) => Some(items),
+        _ => None,
+    }
+}
+
+/// Mutable attributes of a converted object.
+pub(crate) fn object_attrs_mut(value: &mut Value) -> Option<&mut serde_json::Map<String, Value>> {
+    as_object(value)?;
+    match value.get_mut(ATTRS_KEY)? {
+        Value::Object(attrs) => Some(attrs),
+        _ => None,
+    }
+}
@@ -1,6 +1,15 @@
 mod common;
 
-use serde_json::json;
+use serde_json::{json, Value};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use turbodiff::{
+    assert_deep_eq, compare_many, deep_equal, for_each_change, write_changes, Change, ChangeKind,
+    ChangeStats, Checkpoint, CustomOperator, DeepDiff, DeepDiffOptions, DiffEngine, DiffJob,
+    DiffReport, ItemChange, Path, PathSegment, PrettyOptions, Severity, SeverityRules, Side,
+    TurboDiffError, TypeChange, TypeNames, ValueChange,
+};
 
 #[test]
 fn same_objects_no_diff() {
@@ -170,3 +179,1215 @@ fn list_none_item_removed() {
     });
     assert_eq!(diff, expected);
 }
+
+#[test]
+fn shared_inputs_are_not_copied() {
+    let t1 = Arc::new(json!({"a": [1, 2]}));
+    let t2 = Arc::new(json!({"a": [1, 3]}));
+    let diff = DeepDiff::with_shared(Arc::clone(&t1), Arc::clone(&t2), DeepDiffOptions::default());
+    assert_eq!(Arc::strong_count(&t1), 2);
+    assert_eq!(
+        diff.to_value(),
+        json!({"values_changed": {"root['a'][1]": {"old_value": 2, "new_value": 3}}})
+    );
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..Default::default()
+    });
+    assert!(pretty.contains("- 2"));
+}
+
+#[test]
+fn changes_deep_inside_large_equal_siblings() {
+    let mut t1 = json!([1]);
+    for depth in 0..50 {
+        t1 = json!({"depth": depth, "items": (0..100).collect::<Vec<_>>(), "next": t1});
+    }
+    let mut t2 = t1.clone();
+    let mut leaf = &mut t2;
+    for _ in 0..50 {
+        leaf = &mut leaf["next"];
+    }
+    leaf[0] = json!(2);
+    let diff = common::diff(t1, t2);
+    let path = format!("root{}[0]", "['next']".repeat(50));
+    assert_eq!(
+        diff,
+        json!({"values_changed": {path: {"old_value": 1, "new_value": 2}}})
+    );
+}
+
+#[test]
+fn negative_zero_inside_large_subtree_is_equal() {
+    let items: Vec<f64> = (0..40).map(f64::from).collect();
+    let mut t2_items = items.clone();
+    t2_items[0] = -0.0;
+    let diff = common::diff(json!({"items": items}), json!({"items": t2_items}));
+    assert_eq!(diff, json!({}));
+}
+
+#[test]
+fn long_integer_arrays_report_each_changed_index() {
+    let t1: Vec<i64> = (0..1000).collect();
+    let mut t2 = t1.clone();
+    t2[3] = -3;
+    t2[999] = 0;
+    let mut t2 = json!(t2);
+    t2[500] = json!(500.0);
+    let diff = common::diff(json!(t1), t2);
+    assert_eq!(
+        diff,
+        json!({
+            "values_changed": {
+                "root[3]": {"old_value": 3, "new_value": -3},
+                "root[500]": {"old_value": 500, "new_value": 500.0},
+                "root[999]": {"old_value": 999, "new_value": 0}
+            }
+        })
+    );
+}
+
+#[test]
+fn streams_changes_without_building_the_result() {
+    let t1 = json!({"a": 1, "b": [1, 2], "gone": true});
+    let t2 = json!({"a": "1", "b": [1, 3, 4]});
+    let mut changes = Vec::new();
+    for_each_change(&t1, &t2, &DeepDiffOptions::default(), |change| {
+        changes.push((
+            change.kind,
+            change.path,
+            change.new_value.map(|value| value.into_owned()),
+        ));
+    });
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        changes,
+        vec![
+            (
+                ChangeKind::TypeChanged,
+                "root['a']".to_string(),
+                Some(json!("1"))
+            ),
+            (
+                ChangeKind::ValuesChanged,
+                "root['b'][1]".to_string(),
+                Some(json!(3))
+            ),
+            (
+                ChangeKind::IterableItemAdded,
+                "root['b'][2]".to_string(),
+                Some(json!(4))
+            ),
+            (
+                ChangeKind::DictionaryItemRemoved,
+                "root['gone']".to_string(),
+                None
+            ),
+        ]
+    );
+}
+
+#[test]
+fn identical_documents_still_report_nan() {
+    let nan = json!({"__turbodiff_type__": "float", "__turbodiff_value__": "nan"});
+    let doc = Arc::new(nan.clone());
+    let diff = DeepDiff::with_shared(doc.clone(), doc, DeepDiffOptions::default());
+    assert_eq!(
+        diff.to_value(),
+        json!({"values_changed": {"root": {"old_value": nan, "new_value": nan}}})
+    );
+}
+
+#[test]
+fn shared_identical_documents_have_no_diff() {
+    let doc = Arc::new(json!({"x": [1.5, {"y": null}], "z": "text"}));
+    let diff = DeepDiff::with_shared(doc.clone(), doc, DeepDiffOptions::default());
+    assert_eq!(diff.to_value(), json!({}));
+}
+
+#[test]
+fn diff_engine_is_reusable_across_documents() {
+    let mut engine = DiffEngine::new(DeepDiffOptions::default().ignore_order(true));
+    for round in 0..3 {
+        let items: Vec<i64> = (0..64).map(|i| i * round).collect();
+        let t1 = json!({"round": round, "items": items, "nested": {"list": items}});
+        let mut t2 = t1.clone();
+        t2["nested"]["list"][5] = json!(-1);
+        let expected = DeepDiff::with_options(
+            t1.clone(),
+            t2.clone(),
+            DeepDiffOptions::default().ignore_order(true),
+        )
+        .to_value();
+        assert_eq!(engine.diff(&t1, &t2), expected);
+        assert_eq!(engine.diff(&t1, &t1), json!({}));
+    }
+}
+
+#[test]
+fn update_rediffs_only_the_changed_region() {
+    let t1 = json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "same"}, "d": [1]});
+    let t2 = json!({"a": {"x": 2, "y": [1, 2]}, "b": {"c": "same"}, "d": [1, 2]});
+    // Each patch applied through `update`, and its outcome on the full sides.
+    let patches = [
+        (
+            Side::T2,
+            "root['a']['x']",
+            json!(1),
+            t1.clone(),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "same"}, "d": [1, 2]}),
+        ),
+        (
+            Side::T1,
+            "root['b']['c']",
+            json!("changed"),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "changed"}, "d": [1]}),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "same"}, "d": [1, 2]}),
+        ),
+        (
+            Side::T2,
+            "root['b']['new']",
+            json!(true),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "changed"}, "d": [1]}),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "same", "new": true}, "d": [1, 2]}),
+        ),
+        (
+            Side::T1,
+            "root['d'][1]",
+            json!(2),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "changed"}, "d": [1, 2]}),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "same", "new": true}, "d": [1, 2]}),
+        ),
+        (
+            Side::T2,
+            "root['a']",
+            json!([1]),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "changed"}, "d": [1, 2]}),
+            json!({"a": [1], "b": {"c": "same", "new": true}, "d": [1, 2]}),
+        ),
+        (
+            Side::T2,
+            "root['a']",
+            json!({"x": 1, "y": [1, 2, 3]}),
+            json!({"a": {"x": 1, "y": [1, 2]}, "b": {"c": "changed"}, "d": [1, 2]}),
+            json!({"a": {"x": 1, "y": [1, 2, 3]}, "b": {"c": "same", "new": true}, "d": [1, 2]}),
+        ),
+    ];
+    let mut diff = DeepDiff::new(t1, t2);
+    for (side, path, value, t1, t2) in patches {
+        diff.update(side, path, value).unwrap();
+        assert_eq!(diff.to_value(), common::diff(t1, t2), "{}", path);
+    }
+}
+
+#[test]
+fn update_rejects_paths_without_a_parent() {
+    let mut diff = DeepDiff::new(json!({"a": 1}), json!({"a": 1}));
+    let err = diff
+        .update(Side::T1, "root['missing']['x']", json!(1))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unable to resolve path root['missing']['x']"
+    );
+}
+
+#[test]
+fn long_ordered_arrays_of_objects_report_absolute_indices() {
+    let t1: Vec<Value> = (0..100_000).map(|i| json!({"id": i, "tag": "a"})).collect();
+    let mut t2 = t1.clone();
+    t2[3] = json!({"id": 3, "tag": "b"});
+    t2[77_777] = json!({"id": 77_777, "tag": "c"});
+    t2[99_999]["extra"] = json!(true);
+    t2.push(json!({"id": 100_000}));
+    let diff = common::diff(Value::Array(t1), Value::Array(t2));
+    let expected = json!({
+        "values_changed": {
+            "root[3]['tag']": {"old_value": "a", "new_value": "b"},
+            "root[77777]['tag']": {"old_value": "a", "new_value": "c"}
+        },
+        "dictionary_item_added": ["root[99999]['extra']"],
+        "iterable_item_added": {"root[100000]": {"id": 100_000}}
+    });
+    assert_eq!(diff, expected);
+}
+
+#[test]
+fn writes_changes_as_json_lines() {
+    let t1 = json!({"a": 1, "b": [1], "gone": true});
+    let t2 = json!({"a": "1", "b": [1, 2]});
+    let mut out = Vec::new();
+    let written = write_changes(&t1, &t2, &DeepDiffOptions::default(), &mut out).unwrap();
+    assert_eq!(written, 3);
+    let mut lines: Vec<Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    lines.sort_by_key(|line| line["path"].as_str().unwrap().to_string());
+    assert_eq!(
+        lines,
+        vec![
+            json!({
+                "kind": "type_changes",
+                "path": "root['a']",
+                "old_type": "int",
+                "new_type": "str",
+                "old_value": 1,
+                "new_value": "1"
+            }),
+            json!({"kind": "iterable_item_added", "path": "root['b'][1]", "new_value": 2}),
+            json!({"kind": "dictionary_item_removed", "path": "root['gone']", "old_value": true}),
+        ]
+    );
+}
+
+#[test]
+fn diff_many_returns_results_in_order() {
+    let pairs: Vec<(Value, Value)> = (0..200)
+        .map(|i| {
+            (
+                json!({"id": i, "n": [i]}),
+                json!({"id": i, "n": [i, i % 7]}),
+            )
+        })
+        .collect();
+    let expected: Vec<Value> = pairs
+        .iter()
+        .map(|(t1, t2)| common::diff(t1.clone(), t2.clone()))
+        .collect();
+    let options = DeepDiffOptions::default().num_threads(Some(4));
+    let diffs = DeepDiff::diff_many(pairs, options);
+    let results: Vec<Value> = diffs.iter().map(DeepDiff::to_value).collect();
+    assert_eq!(results, expected);
+    assert_eq!(
+        results[9],
+        json!({"iterable_item_added": {"root['n'][1]": 2}})
+    );
+}
+
+#[test]
+fn report_exposes_typed_entries() {
+    let t1 = json!({"a": 1, "b": [1], "c": "x", "gone": null});
+    let t2 = json!({"a": 2, "b": [], "c": 3, "new": true});
+    let diff = DeepDiff::new(t1, t2);
+    let report = diff.report();
+    assert_eq!(
+        report,
+        DiffReport {
+            values_changed: vec![ValueChange {
+                path: "root['a']".to_string(),
+                old_value: Some(json!(1)),
+                new_value: Some(json!(2)),
+                summary: None,
+                magnitude: None,
+            }],
+            type_changes: vec![TypeChange {
+                path: "root['c']".to_string(),
+                old_type: "str".to_string(),
+                new_type: "int".to_string(),
+                old_value: Some(json!("x")),
+                new_value: Some(json!(3)),
+            }],
+            dictionary_item_added: vec!["root['new']".to_string()],
+            dictionary_item_removed: vec!["root['gone']".to_string()],
+            iterable_item_removed: vec![ItemChange {
+                path: "root['b'][0]".to_string(),
+                value: Some(json!(1)),
+            }],
+            ..DiffReport::default()
+        }
+    );
+    assert_eq!(report.to_value(), diff.to_value());
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["values_changed"][0]["path"], json!("root['a']"));
+    assert_eq!(serde_json::from_value::<DiffReport>(json).unwrap(), report);
+
+    let paths_only = DeepDiff::with_options(
+        json!({"a": 1, "b": [1]}),
+        json!({"a": 2, "b": []}),
+        DeepDiffOptions::default().verbose_level(0),
+    );
+    assert_eq!(paths_only.report().to_value(), paths_only.to_value());
+    assert!(DeepDiff::new(json!(1), json!(1)).report().is_empty());
+}
+
+#[test]
+fn display_renders_the_pretty_tree_without_colors() {
+    let diff = DeepDiff::new(json!({"a": {"b": 1}}), json!({"a": {"b": 2}}));
+    let compact = diff.pretty(PrettyOptions {
+        compact: true,
+        no_color: true,
+        ..Default::default()
+    });
+    let full = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..Default::default()
+    });
+    assert_eq!(format!("{}", diff), compact);
+    assert_eq!(format!("{:#}", diff), full);
+    assert!(!compact.contains('\u{1b}'));
+    assert_ne!(compact, full);
+}
+
+#[test]
+fn counts_changes_without_serializing() {
+    let diff = DeepDiff::new(
+        json!({"a": 1, "b": [1, 2, 3], "c": "x"}),
+        json!({"a": 2, "b": [1], "c": 3, "d": null}),
+    );
+    assert!(!diff.is_empty());
+    assert_eq!(diff.len(), 4);
+    assert_eq!(diff.change_count(), 5);
+    let counts = diff.count_by_kind();
+    assert_eq!(counts[&ChangeKind::IterableItemRemoved], 2);
+    assert_eq!(counts[&ChangeKind::DictionaryItemAdded], 1);
+    assert!(!counts.contains_key(&ChangeKind::AttributeAdded));
+
+    let same = DeepDiff::new(json!({"a": 1}), json!({"a": 1}));
+    assert!(same.is_empty());
+    assert_eq!(same.change_count(), 0);
+    assert_eq!(
+        ChangeKind::from_key("type_changes"),
+        Some(ChangeKind::TypeChanged)
+    );
+}
+
+#[test]
+fn paths_parse_navigate_and_resolve() {
+    let path: Path = "root['a'][1].b".parse().unwrap();
+    assert_eq!(
+        path.segments(),
+        &[
+            PathSegment::Key("a".to_string()),
+            PathSegment::Index(1),
+            PathSegment::Attr("b".to_string()),
+        ]
+    );
+    assert_eq!(path.to_string(), "root['a'][1].b");
+    let parent = path.parent().unwrap();
+    assert_eq!(parent.to_string(), "root['a'][1]");
+    assert!(path.starts_with(&parent));
+    assert!(!parent.starts_with(&path));
+    assert_eq!(Path::root().parent(), None);
+    assert_eq!(
+        parent.resolve(&json!({"a": [0, {"b": 7}]})),
+        Some(&json!({"b": 7}))
+    );
+    assert!("root['a'".parse::<Path>().is_err());
+    assert_eq!(Path::parse("root[1]x"), None);
+
+    let options = DeepDiffOptions::default()
+        .exclude_paths([Path::root().child(PathSegment::Key("skip".to_string()))]);
+    let diff = DeepDiff::with_options(
+        json!({"skip": 1, "keep": 1}),
+        json!({"skip": 2, "keep": 1}),
+        options,
+    );
+    assert!(diff.is_empty());
+    let mut diff = DeepDiff::new(json!({"a": [1]}), json!({"a": [1]}));
+    diff.update(Side::T2, parent.parent().unwrap(), json!([2]))
+        .unwrap();
+    assert_eq!(diff.change_count(), 1);
+}
+
+#[test]
+fn severity_rules_classify_changes_and_give_a_verdict() {
+    let t1 = json!({"api": {"users": {"id": 1}, "orders": [1]}, "docs": "a"});
+    let t2 = json!({"api": {"users": {"id": "1"}, "orders": [1, 2]}, "docs": "b"});
+    let rules = SeverityRules::parse(
+        "# api contract\n\
+         iterable_item_added root['api']['*'] => warning\n\
+         root['api'][*] => breaking\n",
+    )
+    .unwrap();
+    let report = DeepDiff::new(t1.clone(), t2.clone()).classify(&rules);
+    let severities: Vec<(&str, Severity)> = report
+        .changes
+        .iter()
+        .map(|change| (change.path.as_str(), change.severity))
+        .collect();
+    assert_eq!(
+        severities,
+        vec![
+            ("root['docs']", Severity::Info),
+            ("root['api']['users']['id']", Severity::Breaking),
+            ("root['api']['orders'][1]", Severity::Warning),
+        ]
+    );
+    assert_eq!(report.verdict, Some(Severity::Breaking));
+    assert!(!report.passes(Severity::Warning));
+    assert_eq!(report.count(Severity::Info), 1);
+
+    let lenient = SeverityRules::new()
+        .rule("root['docs']".parse().unwrap(), Severity::Warning)
+        .rule_for(
+            ChangeKind::TypeChanged,
+            "root['api']".parse().unwrap(),
+            Severity::Warning,
+        );
+    assert!(DeepDiff::new(t1, t2)
+        .classify(&lenient)
+        .passes(Severity::Warning));
+    assert!(SeverityRules::parse("root['api'] => fatal").is_err());
+    assert_eq!(
+        DeepDiff::new(json!(1), json!(1)).classify(&rules).verdict,
+        None
+    );
+}
+
+#[test]
+fn api_compat_flags_removals_and_narrowed_types_as_breaking() {
+    let old = json!({"id": 1, "price": 10, "name": "a", "tags": ["x", "y"]});
+    let widened = json!({"id": 2, "price": 10.5, "name": "a", "tags": ["x", "y", "z"], "note": ""});
+    let report = DeepDiff::new(old.clone(), widened).api_compat();
+    assert!(report.is_compatible());
+    assert_eq!(report.verdict, Some(Severity::Info));
+
+    let narrowed = json!({"id": "1", "tags": ["x"]});
+    let report = DeepDiff::new(old.clone(), narrowed.clone()).api_compat();
+    assert!(!report.is_compatible());
+    let breaking: Vec<&str> = report
+        .changes
+        .iter()
+        .filter(|change| change.severity == Severity::Breaking)
+        .map(|change| change.path.as_str())
+        .collect();
+    assert_eq!(
+        breaking,
+        vec![
+            "root['id']",
+            "root['price']",
+            "root['name']",
+            "root['tags'][1]"
+        ]
+    );
+
+    let tolerant = SeverityRules::api_compat()
+        .read("root['tags'] => warning\ndictionary_item_removed => warning")
+        .unwrap();
+    let report = DeepDiff::new(old, narrowed).classify(&tolerant);
+    assert_eq!(report.count(Severity::Breaking), 1);
+}
+
+#[test]
+fn severity_type_rules_match_either_type_vocabulary() {
+    let decimal = json!({"__turbodiff_type__": "Decimal", "__turbodiff_value__": "1.5"});
+    let t1 = json!({"count": 1, "price": 2, "note": null});
+    let t2 = json!({"count": decimal.clone(), "price": "2", "note": "n/a"});
+    let rules = SeverityRules::new()
+        .type_change("integer", "number", Severity::Info)
+        .type_change("null", "str", Severity::Info)
+        .default_severity(Severity::Breaking);
+    for names in [TypeNames::Python, TypeNames::Json] {
+        let diff = DeepDiff::with_options(
+            t1.clone(),
+            t2.clone(),
+            DeepDiffOptions::default().type_names(names),
+        );
+        let report = diff.classify(&rules);
+        assert_eq!(report.count(Severity::Info), 2);
+        assert_eq!(report.count(Severity::Breaking), 1);
+    }
+
+    let narrowed = DeepDiff::new(json!({"n": decimal}), json!({"n": 1.5}));
+    assert!(!narrowed.api_compat().is_compatible());
+}
+
+#[test]
+fn enum_members_are_typed_by_class() {
+    let member = |class: &str, module: &str, name: &str| {
+        json!({
+            "__turbodiff_type__": "enum",
+            "__turbodiff_value__": {"class": class, "module": module, "member": name},
+        })
+    };
+    let red = member("app.Color", "app", "RED");
+    let diff = DeepDiff::new(red.clone(), member("app.Color", "app", "BLUE"));
+    assert_eq!(diff.report().values_changed.len(), 1);
+    assert!(diff.pretty(PrettyOptions::default()).contains("Color.BLUE"));
+
+    let type_names = |t2: Value| {
+        let change = DeepDiff::new(red.clone(), t2).report().type_changes[0].clone();
+        (change.old_type, change.new_type)
+    };
+    assert_eq!(
+        type_names(member("app.Shade", "app", "RED")),
+        ("Color".to_string(), "Shade".to_string())
+    );
+    assert_eq!(
+        type_names(member("other.Color", "other", "RED")),
+        ("app.Color".to_string(), "other.Color".to_string())
+    );
+}
+
+#[test]
+fn assert_deep_eq_passes_for_equal_values() {
+    assert_deep_eq!(
+        json!({"a": [1, {"b": null}]}),
+        json!({"a": [1, {"b": null}]})
+    );
+    assert_deep_eq!(
+        json!({"x": 1.0}),
+        json!({"x": 1.0001}),
+        DeepDiffOptions::default().atol(Some(0.001)),
+    );
+}
+
+#[test]
+#[should_panic(expected = "assertion `left deep-equals right` failed: config v2")]
+fn assert_deep_eq_panics_with_the_pretty_diff() {
+    assert_deep_eq!(
+        json!({"service": {"port": 80}}),
+        json!({"service": {"port": 8080}}),
+        DeepDiffOptions::default(),
+        "config v{}",
+        2
+    );
+}
+
+#[test]
+fn assert_deep_eq_message_shows_the_changed_path() {
+    let panic = std::panic::catch_unwind(|| {
+        assert_deep_eq!(json!({"a": {"b": 1}}), json!({"a": {"b": 2}}));
+    })
+    .unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("╰── b"), "{}", message);
+    assert!(
+        message.contains("- 1") && message.contains("+ 2"),
+        "{}",
+        message
+    );
+    assert!(!message.contains('\u{1b}'), "{}", message);
+}
+
+#[test]
+fn path_aliases_show_reported_paths_in_domain_terms() {
+    let options = DeepDiffOptions::default()
+        .root_label("pod.yaml")
+        .path_alias("root['spec']['containers'][*]", "container[*]")
+        .path_alias("root['metadata']", "meta");
+    let t1 = json!({
+        "metadata": {"name": "web"},
+        "spec": {"containers": [{"image": "nginx:1"}, {"image": "redis:6"}], "replicas": 1},
+    });
+    let t2 = json!({
+        "metadata": {"name": "api"},
+        "spec": {"containers": [{"image": "nginx:2"}, {"image": "redis:6", "port": 6379}], "replicas": 2},
+    });
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "container[0]['image']": {"old_value": "nginx:1", "new_value": "nginx:2"},
+                "meta['name']": {"old_value": "web", "new_value": "api"},
+                "pod.yaml['spec']['replicas']": {"old_value": 1, "new_value": 2}
+            },
+            "dictionary_item_added": ["container[1]['port']"]
+        })
+    );
+
+    let mut streamed = Vec::new();
+    for_each_change(&t1, &t2, &options, |change| streamed.push(change.path));
+    streamed.sort();
+    assert_eq!(
+        streamed,
+        [
+            "container[0]['image']",
+            "container[1]['port']",
+            "meta['name']",
+            "pod.yaml['spec']['replicas']"
+        ]
+    );
+
+    let compact = diff.pretty(PrettyOptions {
+        path_header: true,
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(compact.contains("container[0].image\n"), "{}", compact);
+    assert!(compact.contains("meta.name\n"), "{}", compact);
+    let tree = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(tree.contains("container[0]"), "{}", tree);
+
+    assert!(DeepDiffOptions::default()
+        .path_alias("spec[*]", "x")
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn root_label_replaces_root_in_reported_paths() {
+    let options = DeepDiffOptions::default().root_label("config.json");
+    let t1 = json!({"db": {"port": 5432}, "tags": ["a"]});
+    let t2 = json!({"db": {"port": 5433}, "tags": []});
+    let mut diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {"config.json['db']['port']": {"old_value": 5432, "new_value": 5433}},
+            "iterable_item_removed": {"config.json['tags'][0]": "a"}
+        })
+    );
+    assert_eq!(
+        diff.report().values_changed[0].path,
+        "config.json['db']['port']"
+    );
+    assert_eq!(
+        DiffEngine::new(options.clone()).diff(&t1, &t2),
+        diff.to_value()
+    );
+
+    let mut streamed = Vec::new();
+    for_each_change(&t1, &t2, &options, |change| streamed.push(change.path));
+    streamed.sort();
+    assert_eq!(
+        streamed,
+        ["config.json['db']['port']", "config.json['tags'][0]"]
+    );
+
+    let path = Path::parse_with_root("config.json['db']['port']", "config.json").unwrap();
+    assert_eq!(path.to_string(), "root['db']['port']");
+    assert_eq!(
+        path.to_string_with_root("config.json"),
+        "config.json['db']['port']"
+    );
+    diff.update(Side::T2, "config.json['db']['port']", json!(5432))
+        .unwrap();
+    assert_eq!(
+        diff.to_value(),
+        json!({"iterable_item_removed": {"config.json['tags'][0]": "a"}})
+    );
+
+    let scalar = DeepDiff::with_options(json!(1), json!(2), options);
+    assert!(format!("{:#}", scalar).starts_with("config.json"));
+    assert!(DeepDiffOptions::default()
+        .root_label("")
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn symmetric_diff_matches_diffing_each_way() {
+    let t1 = json!({
+        "a": 1,
+        "b": {"c": "x", "gone": true},
+        "list": [1, 2, 3],
+        "text": "a".repeat(64) + "tail",
+        "kind": 1
+    });
+    let t2 = json!({
+        "a": 2,
+        "b": {"c": "y", "new": null},
+        "list": [1, 2],
+        "text": "a".repeat(64) + "tale",
+        "kind": "1"
+    });
+    for options in [
+        DeepDiffOptions::default(),
+        DeepDiffOptions::default().ignore_order(true),
+        DeepDiffOptions::default().large_string_threshold(Some(16)),
+        DeepDiffOptions::default().verbose_level(0),
+    ] {
+        let both = DeepDiff::symmetric(t1.clone(), t2.clone(), options.clone());
+        assert_eq!(
+            both.forward.to_value(),
+            DeepDiff::with_options(t1.clone(), t2.clone(), options.clone()).to_value()
+        );
+        assert_eq!(
+            both.backward.to_value(),
+            DeepDiff::with_options(t2.clone(), t1.clone(), options).to_value()
+        );
+    }
+
+    let keyed = DeepDiff::symmetric(
+        json!([{"id": 1, "v": 1}, {"id": 2, "v": 1}]),
+        json!([{"id": 2, "v": 2}, {"id": 1, "v": 1}]),
+        DeepDiffOptions::default().match_by(Some("id".to_string())),
+    );
+    assert_eq!(
+        keyed.backward.to_value(),
+        json!({"values_changed": {"root[0]['v']": {"old_value": 2, "new_value": 1}}})
+    );
+}
+
+#[test]
+fn invert_swaps_sides_and_moves_keyed_items_to_their_new_indices() {
+    let t1 = json!({"users": [{"id": 1, "role": "a"}, {"id": 2, "role": "b"}], "v": 1});
+    let t2 = json!({"users": [{"id": 3}, {"id": 2, "role": "c"}, {"id": 1, "role": "a", "x": 0}], "v": 2});
+    let options = DeepDiffOptions::default().match_by(Some("id".to_string()));
+    let inverted = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone()).invert();
+    assert_eq!(
+        inverted.to_value(),
+        DeepDiff::with_options(t2.clone(), t1.clone(), options).to_value()
+    );
+    assert_eq!(
+        inverted.to_value(),
+        json!({
+            "values_changed": {
+                "root['users'][1]['role']": {"old_value": "c", "new_value": "b"},
+                "root['v']": {"old_value": 2, "new_value": 1}
+            },
+            "dictionary_item_removed": ["root['users'][2]['x']"],
+            "iterable_item_removed": {"root['users'][0]": {"id": 3}}
+        })
+    );
+    let round_trip = DeepDiff::new(t1.clone(), t2.clone()).invert().invert();
+    assert_eq!(round_trip.to_value(), DeepDiff::new(t1, t2).to_value());
+}
+
+#[test]
+fn change_stats_bucket_changes_by_depth_and_prefix() {
+    let t1 = json!({"meta": {"rev": 1}, "data": {"orders": [{"qty": 1}, {"qty": 2}]}, "v": 1});
+    let t2 = json!({"meta": {"rev": 2}, "data": {"orders": [{"qty": 3}, {"qty": 4}]}, "v": 2});
+    let diff = DeepDiff::new(t1.clone(), t2.clone());
+    let stats = diff.change_stats(1);
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.by_depth.get(&1), Some(&1));
+    assert_eq!(stats.by_depth.get(&2), Some(&1));
+    assert_eq!(stats.by_depth.get(&4), Some(&2));
+    assert_eq!(stats.by_prefix.get("root['data']"), Some(&2));
+    assert_eq!(stats.by_prefix.get("root['v']"), Some(&1));
+    assert_eq!(stats.share_at_least(3), 0.5);
+
+    let labeled = DeepDiff::with_options(t1, t2, DeepDiffOptions::default().root_label("doc"));
+    let total: ChangeStats = [stats, labeled.change_stats(2)].iter().collect();
+    assert_eq!(total.total, 8);
+    assert_eq!(total.by_depth.get(&4), Some(&4));
+    assert_eq!(total.by_prefix.get("doc['data']['orders']"), Some(&2));
+}
+
+#[test]
+fn deep_equal_agrees_with_an_empty_diff() {
+    let cases = [
+        (json!({"a": [1, 2, 3]}), json!({"a": [3, 2, 1]})),
+        (json!({"a": 1.0, "b": "x"}), json!({"a": 1.0004, "b": "x"})),
+        (
+            json!({"a": {"b": 1}, "c": 2}),
+            json!({"a": {"b": 1}, "c": 3}),
+        ),
+        (json!([1, 2]), json!([1, 2, 3])),
+        (json!({"a": null}), json!({"a": null})),
+    ];
+    let options = [
+        DeepDiffOptions::default(),
+        DeepDiffOptions::default().ignore_order(true),
+        DeepDiffOptions::default().atol(Some(0.001)),
+        DeepDiffOptions::default().exclude_paths(["root['c']"]),
+    ];
+    for (t1, t2) in &cases {
+        for options in &options {
+            let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+            assert_eq!(
+                deep_equal(t1, t2, options),
+                diff.is_empty(),
+                "{} vs {}",
+                t1,
+                t2
+            );
+        }
+    }
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&seen);
+    let options = DeepDiffOptions::default().visitor(move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        ControlFlow::Continue(())
+    });
+    assert!(!deep_equal(&json!([1, 2, 3]), &json!([4, 5, 6]), &options));
+    assert_eq!(seen.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn changes_page_splits_the_result_into_pages() {
+    let t1 = json!({"a": 1, "b": [1, 2, 3], "c": {"x": 1}, "d": "s"});
+    let t2 = json!({"a": 2, "b": [1], "c": {"y": 1}, "d": 4});
+    let options = DeepDiffOptions::default().root_label("doc");
+    let diff = DeepDiff::with_options(t1, t2, options);
+
+    let all: Vec<_> = diff.changes().collect();
+    assert_eq!(all.len(), diff.change_count());
+    let summary: Vec<_> = all
+        .iter()
+        .map(|change| (change.kind, change.path.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (ChangeKind::ValuesChanged, "doc['a']"),
+            (ChangeKind::TypeChanged, "doc['d']"),
+            (ChangeKind::DictionaryItemAdded, "doc['c']['y']"),
+            (ChangeKind::DictionaryItemRemoved, "doc['c']['x']"),
+            (ChangeKind::IterableItemRemoved, "doc['b'][1]"),
+            (ChangeKind::IterableItemRemoved, "doc['b'][2]"),
+        ]
+    );
+    assert_eq!(all[0].new_value.as_deref(), Some(&json!(2)));
+    assert_eq!(
+        (all[1].old_type, all[1].new_type),
+        (Some("str"), Some("int"))
+    );
+    assert_eq!(all[5].old_value.as_deref(), Some(&json!(3)));
+
+    let pages: Vec<_> = (0..all.len())
+        .step_by(4)
+        .map(|offset| diff.changes_page(offset, 4))
+        .collect();
+    assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [4, 2]);
+    assert_eq!(pages.concat(), all);
+    assert!(diff.changes_page(all.len(), 4).is_empty());
+    assert!(diff.changes_page(0, 0).is_empty());
+}
+
+#[test]
+fn diff_job_resumes_from_a_checkpoint_and_matches_a_full_diff() {
+    let rows = |shift: i64| {
+        (0..30_000)
+            .map(|n| json!({"id": n, "v": if n % 997 == 0 { n + shift } else { n }}))
+            .collect::<Vec<_>>()
+    };
+    let index = |skip: i64| {
+        Value::Object(
+            (0..20_000)
+                .filter(|n| n % 5_000 != skip)
+                .map(|n| (format!("k{}", n), json!(n)))
+                .collect(),
+        )
+    };
+    let mut rows2 = rows(1);
+    rows2.push(json!({"id": -1}));
+    let t1 = json!({"rows": rows(0), "index": index(0), "meta": {"rev": 1}});
+    let t2 = json!({"rows": rows2, "index": index(1), "meta": {"rev": 2}});
+    let expected = DeepDiff::new(t1.clone(), t2.clone()).to_value();
+
+    let mut job = DiffJob::new(t1.clone(), t2.clone(), DeepDiffOptions::default());
+    assert!(!job.step(3));
+    let mut resumes = 0;
+    while !job.is_done() {
+        let saved = Checkpoint::from_json(&job.checkpoint().to_json()).unwrap();
+        assert!(saved.remaining() > 0);
+        job = DiffJob::resume(t1.clone(), t2.clone(), DeepDiffOptions::default(), saved).unwrap();
+        job.step(2);
+        resumes += 1;
+    }
+    assert!(resumes > 3);
+    assert_eq!(job.finish().to_value(), expected);
+
+    let checkpoint = DiffJob::new(t1.clone(), t2.clone(), DeepDiffOptions::default()).checkpoint();
+    assert!(matches!(
+        DiffJob::resume(
+            t2.clone(),
+            t1.clone(),
+            DeepDiffOptions::default(),
+            checkpoint
+        ),
+        Err(TurboDiffError::InvalidCheckpoint(_))
+    ));
+
+    let unordered = DeepDiffOptions::default().ignore_order(true);
+    let mut job = DiffJob::new(t1.clone(), t2.clone(), unordered.clone());
+    assert!(job.step(1));
+    assert_eq!(
+        job.finish().to_value(),
+        DeepDiff::with_options(t1, t2, unordered).to_value()
+    );
+}
+
+/// A document whose `big` dict is large enough for `DiffJob` to split.
+fn big_dict_pair() -> (Value, Value) {
+    let big = |shift: i64| {
+        Value::Object(
+            (0..20_000)
+                .map(|n| (format!("k{}", n), json!(n + shift * (n % 2))))
+                .collect(),
+        )
+    };
+    (
+        json!({"big": big(0), "n": 1}),
+        json!({"big": big(1), "n": 2}),
+    )
+}
+
+fn run_job(t1: &Value, t2: &Value, options: DeepDiffOptions) -> Value {
+    let mut job = DiffJob::new(t1.clone(), t2.clone(), options);
+    while !job.step(1) {}
+    job.finish().to_value()
+}
+
+struct IgnoreBig;
+
+impl CustomOperator for IgnoreBig {
+    fn match_path(&self, path: &str, _t1: &Value, _t2: &Value) -> bool {
+        path == "root['big']"
+    }
+
+    fn compare<'a>(&self, _path: &str, _t1: &'a Value, _t2: &'a Value) -> Option<Vec<Change<'a>>> {
+        Some(vec![])
+    }
+}
+
+#[test]
+fn diff_job_runs_custom_operators_on_large_dicts() {
+    let (t1, t2) = big_dict_pair();
+    let options = || DeepDiffOptions::default().custom_operator(IgnoreBig);
+    let expected = DeepDiff::with_options(t1.clone(), t2.clone(), options()).to_value();
+    assert_eq!(
+        expected,
+        json!({"values_changed": {"root['n']": {"old_value": 1, "new_value": 2}}})
+    );
+    assert_eq!(run_job(&t1, &t2, options()), expected);
+}
+
+#[test]
+fn diff_job_honors_threshold_to_diff_deeper_on_large_dicts() {
+    let (t1, mut t2) = big_dict_pair();
+    let renamed = t2["big"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| match key[1..].parse::<u32>() {
+            Ok(n) if n % 4 != 0 => (format!("j{}", n), value.clone()),
+            _ => (key.clone(), value.clone()),
+        })
+        .collect();
+    t2["big"] = Value::Object(renamed);
+    let options = || DeepDiffOptions::default().threshold_to_diff_deeper(Some(0.5));
+    let expected = DeepDiff::with_options(t1.clone(), t2.clone(), options()).to_value();
+    assert!(expected["values_changed"]
+        .as_object()
+        .unwrap()
+        .contains_key("root['big']"));
+    assert_eq!(run_job(&t1, &t2, options()), expected);
+}
+
+#[test]
+fn collapse_index_ranges_summarizes_runs() {
+    let t1 = json!({"rows": (0..10).collect::<Vec<_>>(), "tags": ["a", "b"]});
+    let mut t2 = t1.clone();
+    for n in 2..6 {
+        t2["rows"][n] = json!(n * 10);
+    }
+    t2["rows"][8] = json!(-1);
+    t2["rows"]
+        .as_array_mut()
+        .unwrap()
+        .extend([json!(10), json!(11), json!(12)]);
+    t2["tags"] = json!(["a", "c"]);
+    let options = DeepDiffOptions::default().collapse_index_ranges(Some(3));
+    let diff = DeepDiff::with_options(t1, t2, options);
+
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['rows'][2:6]": {
+                    "count": 4,
+                    "first": {"old_value": 2, "new_value": 20},
+                    "last": {"old_value": 5, "new_value": 50}
+                },
+                "root['rows'][8]": {"old_value": 8, "new_value": -1},
+                "root['tags'][1]": {"old_value": "b", "new_value": "c"}
+            },
+            "iterable_item_added": {
+                "root['rows'][10:13]": {"count": 3, "first": 10, "last": 12}
+            }
+        })
+    );
+    // Counts and deltas still see every item.
+    assert_eq!(diff.change_count(), 9);
+
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        context: 1,
+        ..Default::default()
+    });
+    assert!(pretty.contains("[2:6]"), "{}", pretty);
+    assert!(pretty.contains("~ 4 values changed"), "{}", pretty);
+    assert!(pretty.contains("[10:13]"), "{}", pretty);
+    assert!(pretty.contains("+ 3 items"), "{}", pretty);
+    assert!(!pretty.contains("[3]"), "{}", pretty);
+    let headers = diff.pretty(PrettyOptions {
+        no_color: true,
+        path_header: true,
+        ..Default::default()
+    });
+    assert!(headers.contains("rows[2:6]"), "{}", headers);
+}
+
+#[test]
+fn results_keep_document_order() {
+    let rows: Vec<i64> = (0..12).collect();
+    let t1 = json!({"z": 1, "a": {"rows": rows, "x": 1}, "m": 1});
+    let mut t2 = t1.clone();
+    t2["z"] = json!(2);
+    t2["a"]["rows"][2] = json!(-2);
+    t2["a"]["rows"][10] = json!(-10);
+    t2["m"] = json!(2);
+    t2["y"] = json!(0);
+    t2["b"] = json!(0);
+    let keys = |result: &Value, kind: &str| -> Vec<String> {
+        match &result[kind] {
+            Value::Object(map) => map.keys().cloned().collect(),
+            Value::Array(paths) => paths
+                .iter()
+                .map(|p| p.as_str().unwrap().to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    let mut diff = DeepDiff::new(t1.clone(), t2.clone());
+    let in_order = [
+        "root['z']",
+        "root['a']['rows'][2]",
+        "root['a']['rows'][10]",
+        "root['m']",
+    ];
+    assert_eq!(keys(&diff.to_value(), "values_changed"), in_order);
+    assert_eq!(
+        keys(&diff.to_value(), "dictionary_item_added"),
+        ["root['y']", "root['b']"]
+    );
+    // Re-diffing a region puts its entries back in place.
+    diff.update(Side::T2, "root['a']['rows'][2]", json!(-3))
+        .unwrap();
+    assert_eq!(keys(&diff.to_value(), "values_changed"), in_order);
+
+    let sorted = DeepDiff::with_options(t1, t2, DeepDiffOptions::default().sort_paths(true));
+    assert_eq!(
+        keys(&sorted.to_value(), "values_changed"),
+        [
+            "root['a']['rows'][10]",
+            "root['a']['rows'][2]",
+            "root['m']",
+            "root['z']"
+        ]
+    );
+    assert_eq!(
+        keys(&sorted.to_value(), "dictionary_item_added"),
+        ["root['b']", "root['y']"]
+    );
+}
+
+#[test]
+fn compare_many_groups_documents_by_value() {
+    let documents = [
+        json!({"replicas": 3, "region": "eu", "debug": false, "tags": ["a"]}),
+        json!({"replicas": 3, "region": "us", "debug": false, "tags": ["a"]}),
+        json!({"replicas": 5, "region": "eu", "debug": false, "tags": ["a"]}),
+        json!({"replicas": 3, "region": "ap", "tags": ["a"], "canary": true}),
+    ];
+    let report = compare_many(&documents, &DeepDiffOptions::default());
+    assert_eq!(report.documents, 4);
+    let paths: Vec<&str> = report.divergences.iter().map(|d| d.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "root['replicas']",
+            "root['region']",
+            "root['debug']",
+            "root['canary']"
+        ]
+    );
+
+    let replicas = report.get("root['replicas']").unwrap();
+    assert_eq!(replicas.groups.len(), 2);
+    assert_eq!(replicas.groups[0].documents, [0, 1, 3]);
+    assert_eq!(replicas.groups[0].value, Some(json!(3)));
+    assert_eq!(replicas.groups[1].documents, [2]);
+    assert_eq!(replicas.outliers(), [2]);
+
+    let region = report.get("root['region']").unwrap();
+    let groups: Vec<&[usize]> = region.groups.iter().map(|g| &g.documents[..]).collect();
+    assert_eq!(groups, [&[0, 2][..], &[1], &[3]]);
+
+    let debug = report.get("root['debug']").unwrap();
+    assert_eq!(debug.groups[1].documents, [3]);
+    assert_eq!(debug.groups[1].value, None);
+
+    // Options apply between every pair of documents.
+    let options = DeepDiffOptions::default()
+        .exclude_paths(["root['region']"])
+        .root_label("cfg");
+    let report = compare_many(&documents, &options);
+    assert!(report.get("cfg['region']").is_none());
+    assert!(report.get("cfg['replicas']").is_some());
+
+    assert!(compare_many(
+        &[json!([2, 1]), json!([1, 2])],
+        &DeepDiffOptions::default().ignore_order(true)
+    )
+    .is_empty());
+    assert!(compare_many(&[json!({"a": 1})], &DeepDiffOptions::default()).is_empty());
+    assert!(compare_many(&[], &DeepDiffOptions::default()).is_empty());
+}
+
+/// Keys made of the characters that break naive path quoting, drawn from
+/// a fixed seed so failures reproduce.
+fn awkward_keys(count: usize) -> Vec<String> {
+    const ALPHABET: [char; 16] = [
+        'a', 'Z', '0', ' ', '\'', '"', '[', ']', '.', '\\', '\n', '\r', '\t', '\u{1}', 'é', '😀',
+    ];
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..count)
+        .map(|_| {
+            let len = (next() % 8) as usize;
+            (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn paths_round_trip_for_arbitrary_keys() {
+    let mut keys = awkward_keys(2000);
+    keys.extend(["']", "it's", "say \"hi\"", "both ' and \"", "\\", "a']['b"].map(String::from));
+    for key in keys {
+        let path = Path::from(vec![
+            PathSegment::Key(key.clone()),
+            PathSegment::Index(3),
+            PathSegment::Key(key.clone()),
+        ]);
+        let text = path.to_string();
+        assert_eq!(
+            Path::parse(&text),
+            Some(path.clone()),
+            "{:?} as {}",
+            key,
+            text
+        );
+        let labeled = path.to_string_with_root("cfg");
+        assert_eq!(Path::parse_with_root(&labeled, "cfg"), Some(path));
+    }
+}
+
+#[test]
+fn reported_paths_resolve_for_arbitrary_keys() {
+    let keys = awkward_keys(200);
+    let t1: Value = keys.iter().map(|key| (key.clone(), json!(1))).collect();
+    let t2: Value = keys.iter().map(|key| (key.clone(), json!(2))).collect();
+    let diff = DeepDiff::new(t1.clone(), t2.clone());
+    let result = diff.to_value();
+    let changed = result["values_changed"].as_object().unwrap();
+    assert_eq!(changed.len(), t1.as_object().unwrap().len());
+    for path in changed.keys() {
+        let path = Path::parse(path).unwrap_or_else(|| panic!("unparsable {}", path));
+        assert_eq!(path.resolve(&t1), Some(&json!(1)));
+        assert_eq!(path.resolve(&t2), Some(&json!(2)));
+    }
+    // Pretty rendering looks values up by the reported paths.
+    let pretty = format!("{:#}", diff);
+    assert_eq!(pretty.matches("- 1").count(), changed.len());
+    assert_eq!(pretty.matches("+ 2").count(), changed.len());
+}
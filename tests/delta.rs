@@ -0,0 +1,56 @@
+use serde_json::json;
+use turbodiff::{DeepDiff, Delta, DeltaError};
+
+#[test]
+fn delta_turns_t1_into_t2() {
+    let t1 = json!({"a": 1, "b": [1, 2, 3], "c": {"x": true}, "d": "gone"});
+    let t2 = json!({"a": "one", "b": [1, 5], "c": {"x": true, "y": null}, "e": 2});
+    let delta = Delta::new(&DeepDiff::new(t1.clone(), t2.clone())).unwrap();
+    assert_eq!(delta.apply(&t1).unwrap(), t2);
+}
+
+#[test]
+fn delta_round_trips_through_json() {
+    let t1 = json!([{"id": 1}, {"id": 2}]);
+    let t2 = json!([{"id": 1}, {"id": 3}, {"id": 4}]);
+    let delta = Delta::new(&DeepDiff::new(t1.clone(), t2.clone())).unwrap();
+    let loaded = Delta::from_value(delta.to_value()).unwrap();
+    assert_eq!(loaded.apply(&t1).unwrap(), t2);
+}
+
+#[test]
+fn bidirectional_delta_reverts_and_checks_old_values() {
+    let t1 = json!({"a": 1, "b": [1, 2]});
+    let t2 = json!({"a": 2, "b": [1], "c": 3});
+    let delta = Delta::new(&DeepDiff::new(t1.clone(), t2.clone()))
+        .unwrap()
+        .bidirectional(true)
+        .raise_errors(true);
+    assert_eq!(delta.revert(&t2).unwrap(), t1);
+    let err = delta.apply(&json!({"a": 5, "b": [1, 2]})).unwrap_err();
+    assert!(matches!(err, DeltaError::UnexpectedValue { .. }));
+}
+
+#[test]
+fn raise_errors_controls_unresolved_paths() {
+    let delta = Delta::new(&DeepDiff::new(
+        json!({"a": {"b": 1}}),
+        json!({"a": {"b": 2}}),
+    ))
+    .unwrap();
+    assert_eq!(delta.apply(&json!({"z": 1})).unwrap(), json!({"z": 1}));
+    let err = delta
+        .raise_errors(true)
+        .apply(&json!({"z": 1}))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        DeltaError::UnresolvedPath("root['a']['b']".to_string())
+    );
+    assert_eq!(
+        Delta::new(&DeepDiff::new(json!(1), json!(2)))
+            .unwrap()
+            .revert(&json!(2)),
+        Err(DeltaError::NotBidirectional)
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "ffi")]
+
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::ptr;
+use turbodiff::ffi::{turbodiff_diff_json, turbodiff_last_error, turbodiff_string_free};
+
+fn diff_json(t1: &str, t2: &str, options: Option<&str>) -> Result<Value, String> {
+    let t1 = CString::new(t1).unwrap();
+    let t2 = CString::new(t2).unwrap();
+    let options = options.map(|options| CString::new(options).unwrap());
+    unsafe {
+        let result = turbodiff_diff_json(
+            t1.as_ptr(),
+            t2.as_ptr(),
+            options
+                .as_ref()
+                .map_or(ptr::null(), |options| options.as_ptr()),
+        );
+        if result.is_null() {
+            let message = CStr::from_ptr(turbodiff_last_error());
+            return Err(message.to_str().unwrap().to_string());
+        }
+        let value = serde_json::from_str(CStr::from_ptr(result).to_str().unwrap()).unwrap();
+        turbodiff_string_free(result);
+        Ok(value)
+    }
+}
+
+#[test]
+fn diffs_json_strings() {
+    assert_eq!(
+        diff_json(r#"{"a": 1}"#, r#"{"a": 2}"#, None).unwrap(),
+        json!({"values_changed": {"root['a']": {"old_value": 1, "new_value": 2}}})
+    );
+    assert_eq!(
+        diff_json("[1, 2]", "[2, 1]", Some(r#"{"ignore_order": true}"#)).unwrap(),
+        json!({})
+    );
+    assert!(turbodiff_last_error().is_null());
+}
+
+#[test]
+fn reports_errors_through_last_error() {
+    assert_eq!(
+        diff_json("[1", "[]", None).unwrap_err(),
+        "t1 is not valid JSON: EOF while parsing a list at line 1 column 2"
+    );
+    assert_eq!(
+        diff_json("1", "2", Some(r#"{"ignore_ordr": true}"#)).unwrap_err(),
+        "unsupported option 'ignore_ordr'"
+    );
+    assert_eq!(
+        diff_json("1", "2", Some(r#"{"atol": -1}"#)).unwrap_err(),
+        "atol must be a non-negative number"
+    );
+    unsafe { turbodiff_string_free(ptr::null_mut()) };
+}
@@ -1,7 +1,15 @@
 mod common;
 
-use serde_json::json;
-use turbodiff::{DeepDiffOptions, ValueType};
+use serde_json::{json, Map, Value};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use turbodiff::{
+    deep_equal, deep_hash, float_value, Change, ChangeKind, CustomOperator, DatetimeUnit, DeepDiff,
+    DeepDiffOptions, Delta, DiffEngine, DiffReport, HashAlgorithm, NumberFormat, OptionsError,
+    PrettyOptions, Rules, Sampling, TurboDiffError, TypeNames, ValueType,
+};
 
 #[test]
 fn ignore_numeric_type_changes() {
@@ -51,6 +59,305 @@ fn ignore_order_still_detects_multiplicity_changes() {
     assert_eq!(diff, expected);
 }
 
+#[test]
+fn string_similarity_pairs_edited_strings() {
+    let t1 = json!(["disk full on /var", "service started", "cpu high", 7]);
+    let t2 = json!([
+        "cpu high",
+        "service restarted",
+        "disk full on /tmp",
+        "oom",
+        8
+    ]);
+    let options = DeepDiffOptions::default()
+        .ignore_order(true)
+        .string_similarity(Some(0.7));
+    let diff = common::diff_with_options(t1.clone(), t2.clone(), options);
+    let expected = json!({
+        "values_changed": {
+            "root[0]": {"old_value": "disk full on /var", "new_value": "disk full on /tmp"},
+            "root[1]": {"old_value": "service started", "new_value": "service restarted"}
+        },
+        "iterable_item_added": {"root[3]": "oom", "root[4]": 8},
+        "iterable_item_removed": {"root[3]": 7}
+    });
+    assert_eq!(diff, expected);
+
+    // Without the option, or below the threshold, edits stay add/remove pairs.
+    let plain = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default().ignore_order(true),
+    );
+    assert!(plain.get("values_changed").is_none());
+    let strict = DeepDiffOptions::default()
+        .ignore_order(true)
+        .string_similarity(Some(0.95));
+    assert!(common::diff_with_options(t1, t2, strict)
+        .get("values_changed")
+        .is_none());
+
+    assert!(matches!(
+        DeepDiffOptions::default()
+            .string_similarity(Some(1.5))
+            .validate(),
+        Err(OptionsError::Invalid {
+            option: "string_similarity",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn cutoff_distance_pairs_records_that_differ_in_a_few_fields() {
+    let t1 = json!([
+        {"id": 1, "name": "ada", "role": "admin", "team": "core"},
+        {"id": 2, "name": "bob", "role": "dev", "team": "web"},
+        {"id": 3, "name": "cy", "role": "ops", "team": "infra"}
+    ]);
+    let t2 = json!([
+        {"id": 9, "name": "zed", "role": "qa", "team": "qa"},
+        {"id": 2, "name": "bob", "role": "lead", "team": "web"},
+        {"id": 1, "name": "ada", "role": "admin", "team": "core", "on_call": true}
+    ]);
+    let options = DeepDiffOptions::default()
+        .ignore_order(true)
+        .cutoff_distance_for_pairs(Some(0.3));
+    let diff = common::diff_with_options(t1.clone(), t2.clone(), options);
+    let expected = json!({
+        "dictionary_item_added": ["root[0]['on_call']"],
+        "values_changed": {
+            "root[1]['role']": {"old_value": "dev", "new_value": "lead"}
+        },
+        "iterable_item_added": {"root[0]": {"id": 9, "name": "zed", "role": "qa", "team": "qa"}},
+        "iterable_item_removed": {"root[2]": {"id": 3, "name": "cy", "role": "ops", "team": "infra"}}
+    });
+    assert_eq!(diff, expected);
+
+    // Without the option, changed records are a removal plus an addition.
+    let plain = common::diff_with_options(t1, t2, DeepDiffOptions::default().ignore_order(true));
+    assert!(plain.get("values_changed").is_none());
+    assert_eq!(plain["iterable_item_removed"].as_object().unwrap().len(), 3);
+
+    assert!(matches!(
+        DeepDiffOptions::default()
+            .cutoff_distance_for_pairs(Some(-0.1))
+            .validate(),
+        Err(OptionsError::Invalid {
+            option: "cutoff_distance_for_pairs",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn cutoff_intersection_and_max_passes_bound_pairing() {
+    let t1 = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, 7, 8]);
+    let t2 = json!([{"id": 1, "v": "x"}, {"id": 2, "v": "y"}, 7, 8]);
+    let options = DeepDiffOptions::default()
+        .ignore_order(true)
+        .cutoff_distance_for_pairs(Some(0.5));
+    let paired = common::diff_with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(paired["values_changed"].as_object().unwrap().len(), 2);
+
+    // Half of the eight items are unmatched: a cutoff below that skips pairing.
+    let skipped = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        options.clone().cutoff_intersection_for_pairs(Some(0.4)),
+    );
+    assert!(skipped.get("values_changed").is_none());
+    assert_eq!(
+        skipped["iterable_item_removed"].as_object().unwrap().len(),
+        2
+    );
+    let kept = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        options.clone().cutoff_intersection_for_pairs(Some(0.5)),
+    );
+    assert_eq!(kept, paired);
+
+    // One comparison finds the first pair only.
+    let capped = common::diff_with_options(t1, t2, options.max_passes(Some(1)));
+    assert_eq!(
+        capped["values_changed"],
+        json!({"root[0]['v']": {"old_value": "a", "new_value": "x"}})
+    );
+    assert_eq!(
+        capped["iterable_item_removed"].as_object().unwrap().len(),
+        1
+    );
+
+    assert!(matches!(
+        DeepDiffOptions::default()
+            .cutoff_intersection_for_pairs(Some(1.5))
+            .validate(),
+        Err(OptionsError::Invalid {
+            option: "cutoff_intersection_for_pairs",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn threshold_to_diff_deeper_replaces_dicts_with_few_shared_keys() {
+    let t1 = json!({"cfg": {"a": 1, "b": 2, "c": 3}, "meta": {"x": 1, "y": 2}});
+    let t2 = json!({"cfg": {"a": 1, "d": 4, "e": 5}, "meta": {"x": 1, "y": 3}});
+    let options = DeepDiffOptions::default().threshold_to_diff_deeper(Some(0.33));
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options);
+    // `cfg` shares one of five keys; `meta` shares both and is diffed deeper.
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['cfg']": {
+                    "old_value": {"a": 1, "b": 2, "c": 3},
+                    "new_value": {"a": 1, "d": 4, "e": 5}
+                },
+                "root['meta']['y']": {"old_value": 2, "new_value": 3}
+            }
+        })
+    );
+    assert_eq!(Delta::new(&diff).unwrap().apply(&t1).unwrap(), t2);
+
+    let plain = common::diff(t1, t2);
+    assert_eq!(plain["dictionary_item_added"].as_array().unwrap().len(), 2);
+    assert!(DeepDiffOptions::default()
+        .threshold_to_diff_deeper(Some(2.0))
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn report_repetition_reports_changed_counts() {
+    let t1 = json!(["a", "b", "a", "c", {"k": 1}]);
+    let t2 = json!(["b", "a", "a", "a", {"k": 1}, {"k": 1}, "d"]);
+    let options = DeepDiffOptions::default()
+        .ignore_order(true)
+        .report_repetition(true);
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    let expected = json!({
+        "iterable_item_added": {"root[6]": "d"},
+        "iterable_item_removed": {"root[3]": "c"},
+        "repetition_change": {
+            "root[0]": {
+                "old_repeat": 2,
+                "new_repeat": 3,
+                "old_indexes": [0, 2],
+                "new_indexes": [1, 2, 3],
+                "value": "a"
+            },
+            "root[4]": {
+                "old_repeat": 1,
+                "new_repeat": 2,
+                "old_indexes": [4],
+                "new_indexes": [4, 5],
+                "value": {"k": 1}
+            }
+        }
+    });
+    assert_eq!(diff.to_value(), expected);
+    let report = DiffReport::from_value(&diff.to_value());
+    assert_eq!(report.repetition_change[0].new_indexes, [1, 2, 3]);
+    assert_eq!(report.to_value(), expected);
+
+    // Inverted, counts and indexes swap and the path moves to `t2`.
+    let inverted = diff.invert().to_value();
+    assert_eq!(inverted["repetition_change"]["root[1]"]["old_repeat"], 3);
+    assert_eq!(
+        inverted["repetition_change"]["root[1]"]["new_indexes"],
+        json!([0, 2])
+    );
+
+    // Deltas add or remove the extra copies.
+    let patched = Delta::new(&diff).unwrap().apply(&t1).unwrap();
+    assert!(deep_equal(&patched, &t2, &options));
+
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(pretty.contains("~ 'a' repeated 2 → 3 times"), "{}", pretty);
+
+    // Without the flag, extra copies are added items.
+    let plain = common::diff_with_options(t1, t2, DeepDiffOptions::default().ignore_order(true));
+    assert!(plain.get("repetition_change").is_none());
+    assert_eq!(plain["iterable_item_added"].as_object().unwrap().len(), 3);
+}
+
+#[test]
+fn align_lists_reports_inserted_and_moved_items() {
+    // One inserted item is one addition, not a change at every later index.
+    let t1 = json!(["a", "b", "c", "d"]);
+    let t2 = json!(["x", "a", "b", "c", "d"]);
+    let options = DeepDiffOptions::default().align_lists(true);
+    let diff = common::diff_with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(diff, json!({"iterable_item_added": {"root[0]": "x"}}));
+    let plain = common::diff(t1, t2);
+    assert_eq!(plain["values_changed"].as_object().unwrap().len(), 4);
+
+    // An item that only changed position is moved; items changed in place
+    // between aligned ones are diffed at their old index.
+    let t1 = json!({"steps": [{"id": 1}, {"id": 2, "v": 1}, "keep", {"id": 3}, "tail"]});
+    let t2 = json!({"steps": [{"id": 2, "v": 2}, "keep", "new", {"id": 1}, "tail"]});
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['steps'][1]['v']": {"old_value": 1, "new_value": 2}
+            },
+            "iterable_item_moved": {
+                "root['steps'][0]": {"new_path": "root['steps'][3]", "value": {"id": 1}}
+            },
+            "type_changes": {
+                "root['steps'][3]": {
+                    "old_type": "dict",
+                    "new_type": "str",
+                    "old_value": {"id": 3},
+                    "new_value": "new"
+                }
+            }
+        })
+    );
+    let report = DiffReport::from_value(&diff.to_value());
+    assert_eq!(
+        report.iterable_item_moved[0].new_path.as_deref(),
+        Some("root['steps'][3]")
+    );
+    assert_eq!(report.to_value(), diff.to_value());
+
+    // Deltas find the new side at its own indices, both ways round.
+    let patched = Delta::new(&diff).unwrap().apply(&t1).unwrap();
+    assert_eq!(patched, t2);
+    let inverted = diff.invert();
+    assert_eq!(Delta::new(&inverted).unwrap().apply(&t2).unwrap(), t1);
+
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(pretty.contains("~ {'id': 1} moved to"), "{}", pretty);
+    assert!(pretty.contains("+ 2"), "{}", pretty);
+}
+
+#[test]
+fn ignore_order_matches_records_regardless_of_key_order() {
+    let t1 = json!([{"a": 1, "b": [1, 2]}, {"a": 2}, 1.0]);
+    let t2 = json!([{"a": 2}, 1, {"b": [1, 2], "a": 1}]);
+    let diff = common::diff_with_options(t1, t2, DeepDiffOptions::default().ignore_order(true));
+    let expected = json!({
+        "iterable_item_added": {
+            "root[1]": 1
+        },
+        "iterable_item_removed": {
+            "root[2]": 1.0
+        }
+    });
+    assert_eq!(diff, expected);
+}
+
 #[test]
 fn significant_digits_suppresses_small_changes() {
     let t1 = json!(1.1234);
@@ -75,6 +382,114 @@ fn significant_digits_handles_near_zero_values() {
     assert_eq!(diff, json!({}));
 }
 
+#[test]
+fn use_log_scale_compares_numbers_by_ratio() {
+    let t1 = json!({"big": 1e6, "small": 1e-6, "count": 100, "neg": -5.0, "zero": 0});
+    let t2 = json!({"big": 1.01e6, "small": 5e-6, "count": 110, "neg": 5.0, "zero": 0.001});
+    let options = DeepDiffOptions::default().use_log_scale(true);
+    let diff = common::diff_with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(
+        diff["values_changed"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["root['small']", "root['neg']", "root['zero']"]
+    );
+    let loose = common::diff_with_options(t1, t2, options.log_scale_similarity_threshold(1.0));
+    assert_eq!(loose["values_changed"].as_object().unwrap().len(), 2);
+    assert!(DeepDiffOptions::default()
+        .log_scale_similarity_threshold(-1.0)
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn number_format_notation_and_formatter_decide_number_equality() {
+    let t1 = json!({"big": 123_456.0, "small": 0.001_234, "price": 1.004});
+    let t2 = json!({"big": 123_499.0, "small": 0.001_239, "price": 1.006});
+    // Two digits after the point in scientific notation keep three
+    // significant digits; in fixed notation, two decimal places.
+    let scientific = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default()
+            .significant_digits(Some(2))
+            .number_format_notation(Some(NumberFormat::Scientific)),
+    );
+    assert_eq!(
+        scientific["values_changed"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["root['small']", "root['price']"]
+    );
+    let fixed = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default()
+            .significant_digits(Some(2))
+            .number_format_notation(Some(NumberFormat::Fixed)),
+    );
+    assert_eq!(
+        fixed["values_changed"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["root['big']", "root['price']"]
+    );
+    assert_eq!(NumberFormat::Fixed.format(-0.001, 2), "0.00");
+    assert_eq!(NumberFormat::Scientific.format(1234.5, 2), "1.23e3");
+
+    // The formatter decides alone, here by order of magnitude.
+    let magnitude =
+        |number: f64, _: Option<u32>, _: NumberFormat| format!("{}", number.abs().log10().floor());
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().number_to_string_func(magnitude),
+    );
+    assert_eq!(diff, json!({}));
+    assert!("g".parse::<NumberFormat>().is_err());
+}
+
+#[test]
+fn number_formatter_decides_long_number_arrays() {
+    // Arrays this long take the lane-by-lane fast path, which must leave the
+    // decision to the formatter rather than to `atol`.
+    let options = || {
+        DeepDiffOptions::default()
+            .atol(Some(0.1))
+            .number_to_string_func(|number, _, _| format!("{number:.2}"))
+    };
+    for len in [4, 40] {
+        let t1 = json!(vec![1.5; len]);
+        let mut t2 = t1.clone();
+        t2[len - 1] = json!(1.55);
+        let diff = common::diff_with_options(t1, t2, options());
+        let path = format!("root[{}]", len - 1);
+        assert_eq!(
+            diff,
+            json!({"values_changed": {path: {"old_value": 1.5, "new_value": 1.55}}})
+        );
+    }
+
+    let t1 = json!(vec![1.004; 40]);
+    let mut t2 = t1.clone();
+    t2[0] = json!(1.006);
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default()
+            .atol(Some(0.1))
+            .significant_digits(Some(2))
+            .number_format_notation(Some(NumberFormat::Fixed)),
+    );
+    assert_eq!(diff["values_changed"].as_object().unwrap().len(), 1);
+}
+
 #[test]
 fn significant_digits_for_floats() {
     let t1 = json!([1.2344, 5.67881]);
@@ -112,6 +527,22 @@ fn rtol_suppresses_relative_changes() {
     assert_eq!(diff, json!({}));
 }
 
+#[test]
+fn atol_applies_across_long_numeric_arrays() {
+    let t1: Vec<f64> = (0..100).map(|i| f64::from(i) * 0.5).collect();
+    let mut t2: Vec<f64> = t1.iter().map(|v| v + 0.0004).collect();
+    t2[37] = 99.0;
+    let diff = common::diff_with_options(
+        json!(t1),
+        json!(t2),
+        DeepDiffOptions::default().atol(Some(0.001)),
+    );
+    assert_eq!(
+        diff,
+        json!({"values_changed": {"root[37]": {"old_value": 18.5, "new_value": 99.0}}})
+    );
+}
+
 #[test]
 fn include_paths_filters() {
     let t1 = json!({"foo": {"bar": {"fruit": "apple", "veg": "potato"}}, "ingredients": ["bread"]});
@@ -153,6 +584,295 @@ fn exclude_paths_filters() {
     assert_eq!(diff, json!({}));
 }
 
+#[test]
+fn exclude_paths_accepts_tuple_indices() {
+    let t1 = json!({"grid": [[1, 2], [3, 4]]});
+    let t2 = json!({"grid": [[1, 2], [3, 5]]});
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().exclude_paths(vec!["root['grid'][(1, 1)]".to_string()]),
+    );
+    assert_eq!(diff, json!({}));
+}
+
+#[test]
+fn exclude_paths_filters_dataframe_columns_and_rows() {
+    let frame = |dtypes: Value, index: Value, columns: Value| {
+        json!({
+            "__turbodiff_type__": "DataFrame",
+            "__turbodiff_value__": {"dtypes": dtypes, "index": index, "columns": columns},
+        })
+    };
+    let t1 = frame(
+        json!({"a": "int64", "b": "float64"}),
+        json!(["r1", "r2"]),
+        json!({"a": [1, 2], "b": [3.0, 4.0]}),
+    );
+    let t2 = frame(
+        json!({"a": "float64", "c": "int64"}),
+        json!(["r1", "r3"]),
+        json!({"a": [1.0, 5.0], "c": [1, 1]}),
+    );
+    let excluded = |paths: &[&str]| {
+        common::diff_with_options(
+            t1.clone(),
+            t2.clone(),
+            DeepDiffOptions::default().exclude_paths(paths.iter().copied()),
+        )
+    };
+    assert_eq!(
+        excluded(&["root['a']", "root['c']"]),
+        json!({
+            "dictionary_item_removed": ["root['b']"],
+            "iterable_item_added": {"root.loc['r3']": {"a": 5.0, "c": 1}},
+            "iterable_item_removed": {"root.loc['r2']": {"a": 2, "b": 4.0}},
+        })
+    );
+    assert_eq!(
+        excluded(&[
+            "root['a']",
+            "root['b']",
+            "root['c']",
+            "root.loc['r2']",
+            "root.loc['r3']"
+        ]),
+        json!({})
+    );
+}
+
+#[test]
+fn ignore_keys_skips_keys_by_name_at_any_depth() {
+    let t1 = json!({
+        "etag": "a",
+        "items": [{"id": 1, "updated_at": 10}, {"id": 2, "meta": {"etag": "x"}}],
+    });
+    let t2 = json!({
+        "items": [{"id": 1, "updated_at": 11}, {"id": 3, "meta": {"etag": "y"}}],
+        "updated_at": 5,
+    });
+    let options = DeepDiffOptions::default().ignore_keys(["updated_at", "etag"]);
+    let diff = common::diff_with_options(t1.clone(), t2.clone(), options.clone());
+    let expected = json!({
+        "values_changed": {
+            "root['items'][1]['id']": {"old_value": 2, "new_value": 3}
+        }
+    });
+    assert_eq!(diff, expected);
+
+    // Items differing only in ignored keys match under ignore_order.
+    let t1 = json!([{"id": 1, "etag": "x"}, {"id": 2, "etag": "y"}]);
+    let t2 = json!([{"id": 2, "etag": "z"}, {"id": 1, "etag": "w"}]);
+    let diff = common::diff_with_options(t1, t2, options.ignore_order(true));
+    assert_eq!(diff, json!({}));
+}
+
+#[test]
+fn include_keys_compares_only_named_keys() {
+    let t1 = json!({
+        "name": "a",
+        "orders": [
+            {"id": 1, "price": 10, "quantity": 1, "note": "x"},
+            {"id": 2, "price": {"amount": 5, "currency": "EUR"}},
+        ],
+        "total": {"price": 20},
+    });
+    let t2 = json!({
+        "name": "b",
+        "orders": [
+            {"id": 9, "price": 11, "quantity": 1, "note": "y"},
+            {"id": 2, "price": {"amount": 5, "currency": "USD"}},
+            {"id": 3, "price": 1},
+        ],
+        "total": 20,
+        "extra": {"quantity": 3},
+    });
+    let options = DeepDiffOptions::default().include_keys(["price", "quantity"]);
+    let diff = common::diff_with_options(t1, t2, options);
+    let expected = json!({
+        "values_changed": {
+            "root['orders'][0]['price']": {"old_value": 10, "new_value": 11},
+            "root['orders'][1]['price']['currency']": {"old_value": "EUR", "new_value": "USD"}
+        }
+    });
+    assert_eq!(diff, expected);
+
+    let t1 = json!({"a": {"price": 1, "quantity": 2}});
+    let t2 = json!({"a": {"price": 1}});
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().include_keys(["quantity"]),
+    );
+    assert_eq!(
+        diff,
+        json!({"dictionary_item_removed": ["root['a']['quantity']"]})
+    );
+}
+
+#[test]
+fn exclude_regex_paths_filters() {
+    let t1 = json!({"items": [{"id": 1, "ts": 10}, {"id": 2, "ts": 20}]});
+    let t2 = json!({"items": [{"id": 1, "ts": 11}, {"id": 3, "ts": 21}]});
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().exclude_regex_paths([r"root\['items'\]\[\d+\]\['ts'\]"]),
+    );
+    let expected = json!({
+        "values_changed": {
+            "root['items'][1]['id']": {"old_value": 2, "new_value": 3}
+        }
+    });
+    assert_eq!(diff, expected);
+}
+
+#[test]
+fn invalid_regex_patterns_fail_validation() {
+    let options = DeepDiffOptions::default()
+        .exclude_regex_paths(["root[", "ok"])
+        .redact_values(["("]);
+    let err = options.validate().unwrap_err();
+    assert!(matches!(
+        err,
+        OptionsError::Invalid {
+            option: "exclude_regex_paths",
+            ..
+        }
+    ));
+    assert!(err
+        .to_string()
+        .starts_with("exclude_regex_paths has an invalid pattern"));
+    assert!(DeepDiffOptions::default()
+        .redact_keys(vec!["^secret$".to_string()])
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn ignore_nan_inequality_treats_nan_as_equal() {
+    let nan = float_value(f64::NAN);
+    let t1 = json!({"a": [nan.clone(), 1.0], "b": nan.clone()});
+    let t2 = json!({"a": [nan.clone(), 2.0], "b": nan.clone()});
+    let plain = common::diff(t1.clone(), t2.clone());
+    assert_eq!(plain["values_changed"].as_object().unwrap().len(), 3);
+
+    let options = DeepDiffOptions::default().ignore_nan_inequality(true);
+    let diff = common::diff_with_options(t1.clone(), t1.clone(), options.clone());
+    assert_eq!(diff, json!({}));
+    let diff = common::diff_with_options(t1, t2, options.clone());
+    assert_eq!(
+        diff,
+        json!({"values_changed": {"root['a'][1]": {"old_value": 1.0, "new_value": 2.0}}})
+    );
+    let unordered = common::diff_with_options(
+        json!([nan.clone(), 1]),
+        json!([1, nan]),
+        options.ignore_order(true),
+    );
+    assert_eq!(unordered, json!({}));
+    assert_eq!(float_value(f64::INFINITY)["__turbodiff_value__"], "inf");
+}
+
+#[test]
+fn truncate_datetime_ignores_sub_unit_timestamp_noise() {
+    let t1 = json!({"at": "2024-03-01T12:30:05Z", "id": "2024-03-01", "n": 1});
+    let t2 = json!({"at": "2024-03-01T12:30:59.5Z", "id": "2024-03-01", "n": 2});
+    let plain = common::diff(t1.clone(), t2.clone());
+    assert!(plain["values_changed"].get("root['at']").is_some());
+
+    let options = DeepDiffOptions::default().truncate_datetime(Some(DatetimeUnit::Minute));
+    let diff = common::diff_with_options(t1, t2, options.clone());
+    assert_eq!(
+        diff,
+        json!({"values_changed": {"root['n']": {"old_value": 1, "new_value": 2}}})
+    );
+
+    // Truncation applies to the wall-clock time, before the UTC offset.
+    let diff = common::diff_with_options(
+        json!(["2024-03-01T23:10:00+05:30", "2024-03-01T10:00:00"]),
+        json!(["2024-03-01T23:50:00+05:30", "2024-03-01T10:59:00"]),
+        DeepDiffOptions::default().truncate_datetime(Some(DatetimeUnit::Hour)),
+    );
+    assert_eq!(diff, json!({}));
+    let unordered = common::diff_with_options(
+        json!(["2024-03-01T12:30:05", "x"]),
+        json!(["x", "2024-03-01T12:30:40"]),
+        options.ignore_order(true),
+    );
+    assert_eq!(unordered, json!({}));
+    assert!("week".parse::<DatetimeUnit>().is_err());
+    assert_eq!("day".parse::<DatetimeUnit>().unwrap(), DatetimeUnit::Day);
+}
+
+#[test]
+fn exclude_types_skips_values_of_those_types() {
+    let t1 = json!({"tags": ["a"], "meta": {"v": 1}, "n": 1, "s": "x", "gone": [1]});
+    let t2 = json!({"tags": ["b"], "meta": {"v": 2}, "n": 2, "s": [1], "new": [2]});
+    let diff = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().exclude_types(vec![ValueType::Array]),
+    );
+    // Arrays are skipped whole, on either side: changes inside them, their
+    // addition or removal, and type changes to or from them.
+    let expected = json!({
+        "values_changed": {
+            "root['meta']['v']": {"old_value": 1, "new_value": 2},
+            "root['n']": {"old_value": 1, "new_value": 2}
+        }
+    });
+    assert_eq!(diff, expected);
+
+    assert_eq!("array".parse::<ValueType>().unwrap(), ValueType::Array);
+    assert!("list".parse::<ValueType>().is_err());
+}
+
+#[test]
+fn exclude_obj_callback_skips_values_by_content() {
+    let t1 = json!({
+        "users": [{"name": "a", "internal": true, "seen": 1}, {"name": "b", "seen": 1}],
+        "flags": {"beta": false}
+    });
+    let t2 = json!({
+        "users": [{"name": "a", "internal": true, "seen": 2}, {"name": "b", "seen": 2}],
+        "flags": {"beta": true}
+    });
+    let internal = |value: &Value, _path: &str| value.get("internal") == Some(&json!(true));
+    let diff = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default().exclude_obj_callback(internal),
+    );
+    assert_eq!(
+        diff,
+        json!({"values_changed": {
+            "root['users'][1]['seen']": {"old_value": 1, "new_value": 2},
+            "root['flags']['beta']": {"old_value": false, "new_value": true}
+        }})
+    );
+
+    // The path is passed too; strict callbacks need both sides to match.
+    let flags =
+        |value: &Value, path: &str| path.starts_with("root['flags']") && value == &json!(false);
+    let either = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default().exclude_obj_callback(flags),
+    );
+    assert!(either["values_changed"]
+        .get("root['flags']['beta']")
+        .is_none());
+    let both = common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().exclude_obj_callback_strict(flags),
+    );
+    assert!(both["values_changed"]
+        .get("root['flags']['beta']")
+        .is_some());
+}
+
 #[test]
 fn verbose_level_zero_paths_only() {
     let t1 = json!({"a": 1});
@@ -179,3 +899,838 @@ fn ignore_type_in_groups_treats_bool_and_string_as_value_change() {
     });
     assert_eq!(diff, expected);
 }
+
+#[test]
+fn progress_logger_reports_processed_items() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = Arc::clone(&calls);
+    let t1 = json!((0..5000).collect::<Vec<_>>());
+    let t2 = json!((1..5001).collect::<Vec<_>>());
+    common::diff_with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().progress_logger(Duration::ZERO, move |progress| {
+            assert!(progress.items_processed > 0);
+            seen.fetch_add(1, Ordering::SeqCst);
+        }),
+    );
+    assert!(calls.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn validate_rejects_conflicting_and_invalid_options() {
+    let options = DeepDiffOptions::default()
+        .significant_digits(Some(3))
+        .math_epsilon(Some(0.1));
+    assert_eq!(
+        options.validate(),
+        Err(OptionsError::Conflict("significant_digits", "math_epsilon"))
+    );
+    let err = DeepDiff::try_with_options(
+        json!(1),
+        json!(2),
+        DeepDiffOptions::default().atol(Some(-1.0)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid options: atol must be a non-negative number"
+    );
+    assert!(matches!(
+        DiffEngine::try_new(DeepDiffOptions::default().rtol(Some(-0.1))),
+        Err(TurboDiffError::Options(_))
+    ));
+    assert!(DeepDiffOptions::default()
+        .rtol(Some(0.1))
+        .validate()
+        .is_ok());
+}
+
+#[test]
+fn max_report_bytes_truncates_to_paths() {
+    let t1 = json!({"a": "x".repeat(100), "b": [1], "c": 1});
+    let t2 = json!({"a": "y".repeat(100), "b": [1, 2], "c": "1"});
+    let diff = DeepDiff::with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default().max_report_bytes(Some(64)),
+    );
+    assert!(diff.is_truncated());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": ["root['a']"],
+            "iterable_item_added": ["root['b'][1]"],
+            "type_changes": {"root['c']": {"old_type": "int", "new_type": "str"}},
+            "report_truncated": true
+        })
+    );
+    let diff = DeepDiff::with_options(
+        t1,
+        t2,
+        DeepDiffOptions::default().max_report_bytes(Some(4096)),
+    );
+    assert!(!diff.is_truncated());
+}
+
+#[test]
+fn deep_distance_scores_how_far_apart_documents_are() {
+    let t1 = json!({"a": 1, "b": [1, 2, 3]});
+    let t2 = json!({"a": 2, "b": [1, 2, 3, 4]});
+    // Five elements below the root of `t1` and six of `t2`; the changed
+    // value counts for both sides and the added item for itself.
+    let diff = DeepDiff::new(t1.clone(), t2.clone());
+    assert!((diff.get_deep_distance() - 3.0 / 11.0).abs() < 1e-12);
+    assert!(diff.to_value().get("deep_distance").is_none());
+
+    let diff = DeepDiff::with_options(
+        t1.clone(),
+        t2,
+        DeepDiffOptions::default().get_deep_distance(true),
+    );
+    assert_eq!(diff.to_value()["deep_distance"], json!(3.0 / 11.0));
+    assert_eq!(diff.len(), 2);
+
+    assert_eq!(DeepDiff::new(t1.clone(), t1).get_deep_distance(), 0.0);
+    let unrelated = DeepDiff::new(json!({"a": {"x": 1}}), json!({"b": [1, 2]}));
+    assert_eq!(unrelated.get_deep_distance(), 1.0);
+    assert_eq!(DeepDiff::new(json!(1), json!(2)).get_deep_distance(), 1.0);
+}
+
+#[test]
+fn max_diffs_stops_after_that_many_changes() {
+    let t1 = json!({"a": 1, "b": 2, "c": 3});
+    let t2 = json!({"a": 10, "b": 20, "c": 30});
+    let options = DeepDiffOptions::default().max_diffs(Some(2));
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    assert!(diff.reached_max_diffs());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['a']": {"old_value": 1, "new_value": 10},
+                "root['b']": {"old_value": 2, "new_value": 20}
+            },
+            "max_diffs_reached": true
+        })
+    );
+    let report = DiffReport::from_value(&diff.to_value());
+    assert!(report.max_diffs_reached);
+    assert_eq!(report.to_value(), diff.to_value());
+
+    let diff = DeepDiff::with_options(t1, t2, options.clone().max_diffs(Some(3)));
+    assert!(!diff.reached_max_diffs());
+    assert_eq!(diff.change_count(), 3);
+
+    // Long lists are not split across threads past the limit.
+    let long1: Vec<u32> = (0..50_000).collect();
+    let long2: Vec<u32> = (1..50_001).collect();
+    let diff = DeepDiff::with_options(json!(long1), json!(long2), options);
+    assert_eq!(diff.change_count(), 2);
+    assert!(diff.reached_max_diffs());
+}
+
+#[test]
+fn large_string_threshold_reports_changed_ranges() {
+    let base: String = (0..200_000u32).map(|i| format!("{:x}", i * 7919)).collect();
+    let mut edited = base.clone();
+    edited.replace_range(100_000..100_004, "EDIT");
+    edited.push_str("tail");
+    let diff = DeepDiff::with_options(
+        json!({"doc": base.clone()}),
+        json!({"doc": edited.clone()}),
+        DeepDiffOptions::default().large_string_threshold(Some(1024)),
+    );
+    let entry = &diff.to_value()["values_changed"]["root['doc']"];
+    assert_eq!(entry["old_length"], json!(base.len()));
+    assert_eq!(entry["new_length"], json!(edited.len()));
+    assert_eq!(
+        entry["changed_ranges"],
+        json!([
+            {"old": [100_000, 100_004], "new": [100_000, 100_004]},
+            {"old": [base.len(), base.len()], "new": [base.len(), base.len() + 4]}
+        ])
+    );
+    assert!(entry.get("old_value").is_none());
+
+    let diff = DeepDiff::with_options(
+        json!("short"),
+        json!("shorter"),
+        DeepDiffOptions::default().large_string_threshold(Some(1024)),
+    );
+    assert_eq!(
+        diff.to_value(),
+        json!({"values_changed": {"root": {"old_value": "short", "new_value": "shorter"}}})
+    );
+}
+
+#[test]
+fn verbose_level_zero_reports_items_and_type_changes_without_values() {
+    let t1 = json!({"a": 1, "b": [1], "c": [1, 2]});
+    let t2 = json!({"a": "1", "b": [1, {"big": [1, 2, 3]}], "c": [1]});
+    let diff = common::diff_with_options(t1, t2, DeepDiffOptions::default().verbose_level(0));
+    let expected = json!({
+        "iterable_item_added": ["root['b'][1]"],
+        "iterable_item_removed": ["root['c'][1]"],
+        "type_changes": {"root['a']": {"old_type": "int", "new_type": "str"}}
+    });
+    assert_eq!(diff, expected);
+}
+
+#[test]
+fn num_threads_does_not_change_the_report() {
+    let t1 = json!((0..50_000).map(|i| json!({"id": i})).collect::<Vec<_>>());
+    let mut t2 = t1.clone();
+    t2[10]["id"] = json!("10");
+    t2[40_000]["id"] = json!(-1);
+    let single = common::diff_with_options(
+        t1.clone(),
+        t2.clone(),
+        DeepDiffOptions::default().num_threads(Some(1)),
+    );
+    let three = common::diff_with_options(t1, t2, DeepDiffOptions::default().num_threads(Some(3)));
+    assert_eq!(single, three);
+    assert_eq!(
+        single["values_changed"],
+        json!({"root[40000]['id']": {"old_value": 40_000, "new_value": -1}})
+    );
+    let err = DeepDiffOptions::default()
+        .num_threads(Some(0))
+        .validate()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "num_threads must be at least 1");
+}
+
+#[test]
+fn ignore_order_long_mostly_disjoint_lists() {
+    let mut t1: Vec<Value> = (0..10_000).map(|i| json!(format!("old-{}", i))).collect();
+    let mut t2: Vec<Value> = (0..10_000).map(|i| json!(format!("new-{}", i))).collect();
+    t1.extend([json!("shared"), json!("shared"), json!({"k": 1})]);
+    t2.extend([json!({"k": 1}), json!("shared")]);
+    let diff = common::diff_with_options(
+        Value::Array(t1),
+        Value::Array(t2),
+        DeepDiffOptions::default().ignore_order(true),
+    );
+    let removed = diff["iterable_item_removed"].as_object().unwrap();
+    let added = diff["iterable_item_added"].as_object().unwrap();
+    assert_eq!(removed.len(), 10_001);
+    assert_eq!(added.len(), 10_000);
+    assert_eq!(removed["root[10001]"], json!("shared"));
+    assert_eq!(removed["root[42]"], json!("old-42"));
+    assert!(!removed.contains_key("root[10002]"));
+    assert_eq!(added["root[9999]"], json!("new-9999"));
+}
+
+#[test]
+fn build_rejects_malformed_paths_and_conflicts() {
+    assert!(DeepDiffOptions::default()
+        .include_paths(vec![
+            "root['a'][0].b".to_string(),
+            "root[(1, 2)]".to_string()
+        ])
+        .build()
+        .is_ok());
+
+    let err = DeepDiffOptions::default()
+        .include_paths(vec!["root['a'".to_string()])
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid options: include_paths entries must be paths like root['key'][0].attr, got 'root['a''"
+    );
+    let err = DeepDiffOptions::default()
+        .exclude_paths(vec!["root['a']x".to_string()])
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TurboDiffError::Options(OptionsError::Invalid {
+            option: "exclude_paths",
+            ..
+        })
+    ));
+    let err = DeepDiffOptions::default()
+        .significant_digits(Some(2))
+        .math_epsilon(Some(0.1))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TurboDiffError::Options(OptionsError::Conflict("significant_digits", "math_epsilon"))
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn visitor_sees_each_change_and_can_stop_the_diff() {
+    let t1 = json!({"a": 1, "b": 2, "c": 3, "d": 4});
+    let t2 = json!({"a": 10, "b": 20, "c": 30, "d": 40});
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&seen);
+    let options = DeepDiffOptions::default().visitor(move |change| {
+        assert_eq!(change.kind, turbodiff::ChangeKind::ValuesChanged);
+        if counter.fetch_add(1, Ordering::SeqCst) + 1 == 2 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options);
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+    assert_eq!(diff.change_count(), 2);
+
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&seen);
+    let options = DeepDiffOptions::default().visitor(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        ControlFlow::Continue(())
+    });
+    let diff = DeepDiff::with_options(t1, t2, options);
+    assert_eq!(seen.load(Ordering::SeqCst), 4);
+    assert_eq!(diff.change_count(), 4);
+}
+
+struct SemverEq;
+
+impl turbodiff::ApproxEq for SemverEq {
+    fn approx_eq(&self, t1: &Value, t2: &Value) -> Option<bool> {
+        let parts = |value: &Value| -> Option<Vec<u64>> {
+            let mut parts = value
+                .as_str()?
+                .split('.')
+                .map(|part| part.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            parts.resize(3, 0);
+            Some(parts)
+        };
+        Some(parts(t1)? == parts(t2)?)
+    }
+}
+
+#[test]
+fn comparators_decide_scalar_equality() {
+    let t1 = json!({"version": "1.2", "name": "a", "deps": ["3.0.0", "4"], "size": 10.2});
+    let t2 = json!({"version": "1.2.0", "name": "b", "deps": ["3", "4.0"], "size": 10.4});
+    let options = DeepDiffOptions::default()
+        .comparator_for(ValueType::String, SemverEq)
+        .comparator(|t1: &Value, t2: &Value| Some(t1.as_f64()?.round() == t2.as_f64()?.round()));
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options);
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['name']": {"old_value": "a", "new_value": "b"}
+            }
+        })
+    );
+
+    let numbers_only = DeepDiffOptions::default()
+        .comparator_for(ValueType::Number, |_: &Value, _: &Value| Some(true));
+    let diff = DeepDiff::with_options(t1, t2, numbers_only);
+    assert_eq!(diff.change_count(), 4);
+}
+
+/// Compares shapes by area only, reporting a changed area at the shape.
+struct AreaOperator;
+
+impl CustomOperator for AreaOperator {
+    fn match_path(&self, _path: &str, t1: &Value, t2: &Value) -> bool {
+        t1.get("w").is_some() && t2.get("w").is_some()
+    }
+
+    fn compare<'a>(&self, path: &str, t1: &'a Value, t2: &'a Value) -> Option<Vec<Change<'a>>> {
+        let area = |shape: &Value| Some(shape["w"].as_f64()? * shape["h"].as_f64()?);
+        let changed = area(t1)? != area(t2)?;
+        Some(
+            changed
+                .then(|| {
+                    Change::new(ChangeKind::ValuesChanged, path.to_string())
+                        .values(Some(t1), Some(t2))
+                })
+                .into_iter()
+                .collect(),
+        )
+    }
+}
+
+#[test]
+fn custom_operators_take_over_matching_values() {
+    let t1 = json!({"a": {"w": 2, "h": 6}, "b": {"w": 2, "h": 2}, "c": {"w": 1}, "n": 1});
+    let t2 = json!({"a": {"w": 3, "h": 4}, "b": {"w": 1, "h": 1}, "c": {"w": 2}, "n": 2});
+    let options = DeepDiffOptions::default().custom_operator(AreaOperator);
+    let diff = DeepDiff::with_options(t1, t2, options);
+    // `c` has no area, so the operator leaves it to the built-in comparison.
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['b']": {"old_value": {"w": 2, "h": 2}, "new_value": {"w": 1, "h": 1}},
+                "root['c']['w']": {"old_value": 1, "new_value": 2},
+                "root['n']": {"old_value": 1, "new_value": 2}
+            }
+        })
+    );
+}
+
+#[test]
+fn redaction_masks_values_in_the_result_and_pretty_output() {
+    let t1 = json!({
+        "user": "ann",
+        "credentials": {"password": "hunter2"},
+        "api_key": "sk-live-123",
+        "hosts": ["a"],
+    });
+    let t2 = json!({
+        "user": "bob",
+        "credentials": {"password": "hunter3"},
+        "api_key": "sk-live-456",
+        "hosts": ["a", {"name": "b", "secret": "s3"}],
+    });
+    let options = DeepDiffOptions::default()
+        .redact_paths(["root['credentials']"])
+        .redact_keys(["^secret$"])
+        .redact_values(["^sk-live-"]);
+    let diff = DeepDiff::with_options(t1, t2, options);
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['api_key']": {"old_value": "***", "new_value": "***"},
+                "root['credentials']['password']": {"old_value": "***", "new_value": "***"},
+                "root['user']": {"old_value": "ann", "new_value": "bob"}
+            },
+            "iterable_item_added": {
+                "root['hosts'][1]": {"name": "b", "secret": "***"}
+            }
+        })
+    );
+    let pretty = format!("{:#}", diff);
+    for secret in ["hunter", "sk-live", "s3"] {
+        assert!(
+            !pretty.contains(secret),
+            "{} leaked into {}",
+            secret,
+            pretty
+        );
+    }
+}
+
+#[test]
+fn schema_drives_the_comparison_and_reports_violations() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "id": {"type": "integer"},
+            "legacy_name": {"type": "string", "deprecated": true},
+            "price": {"$ref": "#/$defs/money"},
+            "tags": {"type": "array", "items": {"type": "string"}}
+        },
+        "additionalProperties": true,
+        "$defs": {"money": {"type": "number", "format": "currency"}}
+    });
+    let t1 = json!({"id": 1, "legacy_name": "a", "price": 10.001, "tags": ["x"], "extra": 1});
+    let t2 = json!({"id": "1", "legacy_name": "b", "price": 10.004, "tags": ["x", 2], "note": "n"});
+    let options = DeepDiffOptions::default()
+        .schema(Some(schema))
+        .format_tolerance("currency", 0.005);
+    let diff = DeepDiff::with_options(t1, t2, options);
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "type_changes": {
+                "root['id']": {
+                    "old_type": "int",
+                    "new_type": "str",
+                    "old_value": 1,
+                    "new_value": "1"
+                }
+            },
+            "iterable_item_added": {"root['tags'][1]": 2},
+            "schema_violations": {
+                "root['id']": {"expected_type": "integer", "new_value": "1"},
+                "root['tags'][1]": {"expected_type": "string", "new_value": 2}
+            }
+        })
+    );
+    assert_eq!(diff.report().schema_violations.len(), 2);
+    assert_eq!(
+        DiffReport::from_value(&diff.to_value()).to_value(),
+        diff.to_value()
+    );
+}
+
+#[test]
+fn rule_wildcards_match_keys_with_brackets_and_quotes() {
+    let rules = Rules::from_value(json!([{"path": "root[*]['v']", "atol": 0.1}])).unwrap();
+    let t1 = json!({"a]b": {"v": 1.0}, "it's": {"v": 1.0}, "x": {"v": 1.0}});
+    let t2 = json!({"a]b": {"v": 1.05}, "it's": {"v": 1.05}, "x": {"v": 1.05}});
+    let diff = DeepDiff::with_options(t1, t2, DeepDiffOptions::default().rules(rules));
+    assert!(diff.is_empty(), "{}", diff.to_value());
+}
+
+#[test]
+fn rules_document_scopes_settings_to_paths() {
+    let rules = Rules::from_json(
+        r#"{"rules": [
+            {"path": "root['metrics']", "atol": 0.01},
+            {"path": "root['users']", "match_by": "id"},
+            {"path": "root['users'][*]['token']", "redact": true},
+            {"path": "root['debug']", "ignore": true},
+            {"path": "root['tags']", "ignore_order": true}
+        ]}"#,
+    )
+    .unwrap();
+    let t1 = json!({
+        "metrics": {"cpu": 0.500, "mem": 2.0},
+        "limits": {"cpu": 0.500},
+        "users": [{"id": 1, "token": "a", "name": "x"}, {"id": 2, "token": "b"}],
+        "debug": {"trace": 1},
+        "tags": ["a", "b"]
+    });
+    let t2 = json!({
+        "metrics": {"cpu": 0.505, "mem": 2.5},
+        "limits": {"cpu": 0.505},
+        "users": [{"id": 3, "token": "c"}, {"id": 2, "token": "d"}, {"id": 1, "token": "a", "name": "y"}],
+        "debug": {"trace": 2},
+        "tags": ["b", "a"]
+    });
+    let diff = DeepDiff::with_options(t1, t2, DeepDiffOptions::default().rules(rules));
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['metrics']['mem']": {"old_value": 2.0, "new_value": 2.5},
+                "root['limits']['cpu']": {"old_value": 0.5, "new_value": 0.505},
+                "root['users'][0]['name']": {"old_value": "x", "new_value": "y"},
+                "root['users'][1]['token']": {"old_value": "***", "new_value": "***"}
+            },
+            "iterable_item_added": {"root['users'][0]": {"id": 3, "token": "***"}}
+        })
+    );
+}
+
+#[test]
+fn rules_document_rejects_unknown_settings_and_bad_paths() {
+    assert!(matches!(
+        Rules::from_json(r#"{"rules": [{"path": "root", "atoll": 1}]}"#),
+        Err(TurboDiffError::InvalidRule(_))
+    ));
+    assert!(matches!(
+        Rules::from_value(json!([{"path": "users[*]"}])),
+        Err(TurboDiffError::InvalidRule(path)) if path == "users[*]"
+    ));
+    let rules = Rules::from_value(json!([{"path": "root['a']", "atol": -1.0}])).unwrap();
+    assert!(matches!(
+        DeepDiffOptions::default().rules(rules).validate(),
+        Err(OptionsError::Invalid {
+            option: "rules",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn match_by_pairs_list_items_by_key() {
+    let t1 = json!([{"id": "a", "v": 1}, {"id": "b", "v": 2}]);
+    let t2 = json!([{"id": "b", "v": 3}, {"id": "a", "v": 1}]);
+    let options = DeepDiffOptions::default().match_by(Some("id".to_string()));
+    assert_eq!(
+        common::diff_with_options(t1, t2.clone(), options.clone()),
+        json!({"values_changed": {"root[1]['v']": {"old_value": 2, "new_value": 3}}})
+    );
+    let unkeyed = json!([{"v": 1}, {"id": "b", "v": 2}]);
+    assert_eq!(
+        common::diff_with_options(unkeyed, t2, options),
+        json!({
+            "dictionary_item_added": ["root[0]['id']"],
+            "values_changed": {
+                "root[0]['v']": {"old_value": 1, "new_value": 3},
+                "root[1]['v']": {"old_value": 2, "new_value": 1},
+                "root[1]['id']": {"old_value": "b", "new_value": "a"}
+            }
+        })
+    );
+}
+
+#[test]
+fn group_by_rekeys_lists_of_records() {
+    let t1 = json!([
+        {"id": "AA", "name": "Joe", "last_name": "Nobody"},
+        {"id": "BB", "name": "James", "last_name": "Blue"},
+        {"id": "CC", "name": "Mike", "last_name": "Apple"}
+    ]);
+    let t2 = json!([
+        {"id": "AA", "name": "Joe", "last_name": "Nobody"},
+        {"id": "CC", "name": "Mike", "last_name": "Apple"},
+        {"id": "BB", "name": "James", "last_name": "Brown"},
+        {"id": "DD", "name": "Anna", "last_name": "Smith"}
+    ]);
+    let options = DeepDiffOptions::default().group_by(Some("id".to_string()));
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options.clone());
+    assert_eq!(
+        diff.to_value(),
+        json!({
+            "values_changed": {
+                "root['BB']['last_name']": {"old_value": "Blue", "new_value": "Brown"}
+            },
+            "dictionary_item_added": ["root['DD']"]
+        })
+    );
+    // Paths resolve in the grouped sides the diff keeps.
+    let group = |records: &Value| -> Value {
+        let mut grouped = Map::new();
+        for record in records.as_array().unwrap() {
+            let mut record = record.as_object().unwrap().clone();
+            let id = record.shift_remove("id").unwrap();
+            grouped.insert(id.as_str().unwrap().to_string(), Value::Object(record));
+        }
+        Value::Object(grouped)
+    };
+    let patched = Delta::new(&diff).unwrap().apply(&group(&t1)).unwrap();
+    assert_eq!(patched, group(&t2));
+
+    // Numeric ids are keys of their own type.
+    let numbered = common::diff_with_options(
+        json!([{"id": 1, "v": "a"}]),
+        json!([{"id": 1, "v": "b"}]),
+        options.clone(),
+    );
+    assert_eq!(
+        numbered,
+        json!({"values_changed": {"root[1]['v']": {"old_value": "a", "new_value": "b"}}})
+    );
+
+    // Lists with a record lacking the field, or sharing an id, stay lists.
+    let duplicated = json!([{"id": "AA", "v": 1}, {"id": "AA", "v": 2}]);
+    let diff = common::diff_with_options(duplicated, json!([]), options);
+    assert_eq!(diff["iterable_item_removed"].as_object().unwrap().len(), 2);
+}
+
+#[test]
+fn type_names_choose_between_python_and_json_vocabularies() {
+    let t1 = json!({"a": 1, "b": null, "c": "x"});
+    let t2 = json!({"a": "1", "b": [1], "c": {"k": 1}});
+    let types = |names: TypeNames| {
+        let diff = DeepDiff::with_options(
+            t1.clone(),
+            t2.clone(),
+            DeepDiffOptions::default().type_names(names),
+        );
+        diff.report()
+            .type_changes
+            .into_iter()
+            .map(|change| (change.old_type, change.new_type))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        types(TypeNames::default()),
+        [("int", "str"), ("null", "list"), ("str", "dict")]
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+    );
+    assert_eq!(
+        types(TypeNames::Python),
+        [("int", "str"), ("NoneType", "list"), ("str", "dict")]
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+    );
+    assert_eq!(
+        types(TypeNames::Json),
+        [
+            ("integer", "string"),
+            ("null", "array"),
+            ("string", "object")
+        ]
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+    );
+    let widened = DeepDiff::with_options(
+        json!({"n": 1}),
+        json!({"n": 1.5}),
+        DeepDiffOptions::default().type_names(TypeNames::Json),
+    );
+    assert!(widened.api_compat().is_compatible());
+    assert_eq!("json".parse::<TypeNames>(), Ok(TypeNames::Json));
+    assert!("rust".parse::<TypeNames>().is_err());
+}
+
+#[test]
+fn hasher_changes_hashes_but_not_the_diff() {
+    let t1 = json!({"items": [{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, [1, 2], "x"]});
+    let t2 = json!({"items": ["x", [2, 1], {"id": 2, "v": "b"}, {"id": 1, "v": "c"}]});
+    let diff = |hasher| {
+        let options = DeepDiffOptions::default().ignore_order(true).hasher(hasher);
+        DeepDiff::with_options(t1.clone(), t2.clone(), options).to_value()
+    };
+    let expected = diff(HashAlgorithm::Fx);
+    assert!(!expected.as_object().unwrap().is_empty());
+    assert_eq!(diff(HashAlgorithm::XxHash64), expected);
+    assert_eq!(diff(HashAlgorithm::Sha256), expected);
+
+    // The SHA-256 of the string's encoding: tag 6, length 3 little-endian,
+    // then the bytes.
+    assert_eq!(
+        deep_hash(&json!("abc"), HashAlgorithm::Sha256),
+        "1a4969195ba6a041e54bb0bd317d604d3f3cf3ae263c7cc3dcea29e0dab11647"
+    );
+    for algorithm in [HashAlgorithm::Fx, HashAlgorithm::XxHash64] {
+        let hash = deep_hash(&t1, algorithm);
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, deep_hash(&t1.clone(), algorithm));
+        assert_ne!(hash, deep_hash(&t2, algorithm));
+    }
+    assert_ne!(
+        deep_hash(&json!(["ab", "c"]), HashAlgorithm::Sha256),
+        deep_hash(&json!(["a", "bc"]), HashAlgorithm::Sha256)
+    );
+    assert_eq!("xxhash".parse(), Ok(HashAlgorithm::XxHash64));
+    assert!(matches!(
+        "md5".parse::<HashAlgorithm>(),
+        Err(OptionsError::Invalid {
+            option: "hasher",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn sampling_diffs_a_deterministic_share_of_large_containers() {
+    let t1 = json!({
+        "items": (0..2000).collect::<Vec<_>>(),
+        "meta": {"version": 1},
+    });
+    let t2 = json!({
+        "items": (0..2000).map(|n| n + 1).collect::<Vec<_>>(),
+        "meta": {"version": 2},
+    });
+    let sampling = Sampling::new(0.1).threshold(100).seed(7);
+    let sampled = |t1: &Value, t2: &Value, sampling: Sampling| {
+        DeepDiff::with_options(
+            t1.clone(),
+            t2.clone(),
+            DeepDiffOptions::default().sampling(Some(sampling)),
+        )
+    };
+
+    let diff = sampled(&t1, &t2, sampling);
+    let changed = diff.report().values_changed;
+    // Small containers are diffed in full, large ones in part.
+    assert!(changed.iter().any(|c| c.path == "root['meta']['version']"));
+    let items = changed.len() - 1;
+    assert!((100..300).contains(&items), "sampled {} of 2000", items);
+    let report = diff.sampling().expect("sampling block");
+    assert_eq!((report.rate, report.threshold, report.seed), (0.1, 100, 7));
+    assert_eq!(report.sampled_containers, 1);
+    assert_eq!(diff.report().sampling, Some(report));
+
+    assert_eq!(sampled(&t1, &t2, sampling).to_value(), diff.to_value());
+    assert_ne!(
+        sampled(&t1, &t2, sampling.seed(8)).to_value(),
+        diff.to_value()
+    );
+
+    // Keys are picked by name, so a sampled dict compares the same keys on
+    // both sides.
+    let wide = |shift: i64| {
+        Value::Object(
+            (0..500)
+                .map(|n| (format!("k{}", n), json!(n + shift)))
+                .collect(),
+        )
+    };
+    let diff = sampled(&wide(0), &wide(1), sampling);
+    assert!(diff.change_count() < 150);
+    assert!(diff.report().dictionary_item_added.is_empty());
+
+    let unchanged = sampled(&t1, &t1, sampling);
+    assert!(unchanged.is_empty());
+    assert!(unchanged.sampling().is_some());
+    let small = sampled(&json!([1, 2]), &json!([1, 3]), sampling);
+    assert!(small.sampling().is_none());
+    assert_eq!(small.change_count(), 1);
+
+    assert!(matches!(
+        DeepDiffOptions::default()
+            .sampling(Some(Sampling::new(0.0)))
+            .validate(),
+        Err(OptionsError::Invalid {
+            option: "sampling",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn annotate_attaches_fields_to_changes() {
+    let t1 = json!({"billing": {"plan": "free"}, "name": "a"});
+    let t2 = json!({"billing": {"plan": "pro"}, "name": "b"});
+    let options = DeepDiffOptions::default()
+        .root_label("cfg")
+        .annotate(|change| {
+            if !change.path.starts_with("cfg['billing']") {
+                return None;
+            }
+            let mut fields = Map::new();
+            fields.insert("owner".into(), json!("payments"));
+            fields.insert("severity".into(), json!(2));
+            Some(fields)
+        });
+    let diff = DeepDiff::with_options(t1, t2, options);
+
+    let annotations = diff.annotations();
+    assert_eq!(
+        annotations.keys().collect::<Vec<_>>(),
+        ["cfg['billing']['plan']"]
+    );
+    assert_eq!(
+        diff.to_value()["annotations"]["cfg['billing']['plan']"],
+        json!({"owner": "payments", "severity": 2})
+    );
+    assert_eq!(diff.report().annotations, annotations);
+    // Annotations are not changes.
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff.change_count(), 2);
+
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(pretty.contains("# owner: payments"), "{}", pretty);
+    assert!(pretty.contains("# severity: 2"), "{}", pretty);
+}
+
+#[test]
+fn magnitude_scores_rank_numeric_changes() {
+    let t1 = json!({"cpu": 100, "mem": 2.0, "zero": 0, "name": "a"});
+    let t2 = json!({"cpu": 101, "mem": 8.0, "zero": 5, "name": "b"});
+    let options = DeepDiffOptions::default().magnitude_scores(true);
+    let diff = DeepDiff::with_options(t1.clone(), t2.clone(), options);
+
+    let values = diff.to_value();
+    assert_eq!(
+        values["values_changed"]["root['mem']"]["magnitude"],
+        json!(0.75)
+    );
+    assert_eq!(
+        values["values_changed"]["root['zero']"]["magnitude"],
+        json!(1.0)
+    );
+    assert!(values["values_changed"]["root['name']"]
+        .get("magnitude")
+        .is_none());
+
+    let report = diff.report();
+    let ranked: Vec<&str> = report
+        .by_magnitude(0.0)
+        .iter()
+        .map(|change| change.path.as_str())
+        .collect();
+    assert_eq!(ranked, ["root['zero']", "root['mem']", "root['cpu']"]);
+    assert_eq!(report.by_magnitude(0.5).len(), 2);
+    assert_eq!(DiffReport::from_value(&values).to_value(), values);
+
+    // Scores are opt-in.
+    let plain = DeepDiff::new(t1, t2);
+    assert!(plain.to_value()["values_changed"]["root['mem']"]
+        .get("magnitude")
+        .is_none());
+}
@@ -1,8 +1,20 @@
+import asyncio
+import collections
+import dataclasses
+import datetime as dt
+import enum
+import math
+import json
+import re
+import uuid
+from decimal import Decimal
+
 import numpy as np
 import pandas as pd
 import pytest
 
-from turbodiff import DeepDiff
+import turbodiff
+from turbodiff import DeepDiff, Delta, DeltaError, write_changes
 
 
 def test_python_value_change():
@@ -31,6 +43,253 @@ def test_python_verbose_level_zero():
     assert diff.to_dict() == {"values_changed": ["root['a']"]}
 
 
+def test_python_max_report_bytes_keeps_paths_only():
+    t1 = {"a": "x" * 100, "b": [1]}
+    t2 = {"a": "y" * 100, "b": [1, 2]}
+    diff = DeepDiff(t1, t2, max_report_bytes=64)
+    assert diff.to_dict() == {
+        "values_changed": ["root['a']"],
+        "iterable_item_added": ["root['b'][1]"],
+        "report_truncated": True,
+    }
+
+
+def test_python_max_diffs_stops_early():
+    diff = DeepDiff({"a": 1, "b": 2, "c": 3}, {"a": 9, "b": 9, "c": 9}, max_diffs=1)
+    assert diff.to_dict() == {
+        "values_changed": {"root['a']": {"old_value": 1, "new_value": 9}},
+        "max_diffs_reached": True,
+    }
+    assert len(diff) == 2
+
+
+def test_python_get_deep_distance():
+    t1 = {"a": 1, "b": [1, 2, 3]}
+    t2 = {"a": 2, "b": [1, 2, 3, 4]}
+    diff = DeepDiff(t1, t2, get_deep_distance=True)
+    assert diff["deep_distance"] == pytest.approx(3 / 11)
+    assert len(diff) == 3
+    assert DeepDiff(t1, t1, get_deep_distance=True).to_dict() == {"deep_distance": 0.0}
+    assert "deep_distance" not in DeepDiff(t1, t2).to_dict()
+
+
+def test_python_large_string_threshold_reports_ranges():
+    t1 = "a" * 5000 + "b" * 5000
+    t2 = "a" * 5000 + "c" + "b" * 5000
+    diff = DeepDiff(t1, t2, large_string_threshold=1024)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root": {
+                "old_length": 10000,
+                "new_length": 10001,
+                "changed_ranges": [{"old": [5000, 5000], "new": [5000, 5001]}],
+            }
+        }
+    }
+
+
+def test_python_write_changes_streams_json_lines(tmp_path):
+    lines = []
+    count = write_changes({"a": 1, "b": [1]}, {"a": 2, "b": [1, 2]}, lines.append)
+    assert count == 2
+    assert [json.loads(line) for line in lines] == [
+        {
+            "kind": "values_changed",
+            "path": "root['a']",
+            "old_value": 1,
+            "new_value": 2,
+        },
+        {"kind": "iterable_item_added", "path": "root['b'][1]", "new_value": 2},
+    ]
+    out = tmp_path / "changes.jsonl"
+    with out.open("w") as sink:
+        assert write_changes({"a": 1}, {}, sink) == 1
+    assert json.loads(out.read_text()) == {
+        "kind": "dictionary_item_removed",
+        "path": "root['a']",
+        "old_value": 1,
+    }
+
+
+def test_python_num_threads_and_env_override(monkeypatch):
+    t1 = list(range(100_000))
+    t2 = t1[:-1] + [-1]
+    expected = {"values_changed": {"root[99999]": {"old_value": 99999, "new_value": -1}}}
+    assert DeepDiff(t1, t2, num_threads=1).to_dict() == expected
+    monkeypatch.setenv("TURBODIFF_THREADS", "2")
+    assert DeepDiff(t1, t2).to_dict() == expected
+    monkeypatch.setenv("TURBODIFF_THREADS", "many")
+    with pytest.raises(ValueError, match="TURBODIFF_THREADS"):
+        DeepDiff(t1, t2)
+    monkeypatch.delenv("TURBODIFF_THREADS")
+    with pytest.raises(ValueError, match="num_threads"):
+        DeepDiff(t1, t2, num_threads=0)
+
+
+def test_python_symmetric_returns_both_directions():
+    t1, t2 = {"a": 1, "b": [1, 2]}, {"a": 2, "b": [1], "c": 3}
+    forward, backward = DeepDiff.symmetric(t1, t2)
+    assert forward.to_dict() == DeepDiff(t1, t2).to_dict()
+    assert backward.to_dict() == DeepDiff(t2, t1).to_dict()
+
+
+def test_python_group_by():
+    t1 = [
+        {"id": "AA", "name": "Joe", "last_name": "Nobody"},
+        {"id": "BB", "name": "James", "last_name": "Blue"},
+    ]
+    t2 = [
+        {"id": "BB", "name": "James", "last_name": "Brown"},
+        {"id": "AA", "name": "Joe", "last_name": "Nobody"},
+    ]
+    diff = DeepDiff(t1, t2, group_by="id")
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['BB']['last_name']": {"old_value": "Blue", "new_value": "Brown"}
+        }
+    }
+    numbered = DeepDiff([{"id": 1, "v": 1}], [{"id": 1, "v": 2}], group_by="id")
+    assert numbered.to_dict() == {
+        "values_changed": {"root[1]['v']": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_python_truncate_datetime():
+    t1 = {"at": dt.datetime(2024, 3, 1, 12, 30, 5), "ts": "2024-03-01T12:30:05Z"}
+    t2 = {"at": dt.datetime(2024, 3, 1, 12, 30, 59), "ts": "2024-03-01T12:30:41Z"}
+    assert set(DeepDiff(t1, t2).to_dict()["values_changed"]) == {
+        "root['at']",
+        "root['ts']",
+    }
+    assert DeepDiff(t1, t2, truncate_datetime="minute").to_dict() == {}
+    assert DeepDiff(t1, t2, truncate_datetime="second").to_dict() != {}
+    with pytest.raises(ValueError):
+        DeepDiff(t1, t2, truncate_datetime="week")
+
+
+def test_python_invert():
+    t1 = [{"id": 1, "v": 1}, {"id": 2, "v": 1}]
+    t2 = [{"id": 2, "v": 2}, {"id": 1, "v": 1}]
+    inverted = DeepDiff(t1, t2, match_by="id").invert()
+    assert inverted.to_dict() == DeepDiff(t2, t1, match_by="id").to_dict()
+    assert inverted.to_dict() == {
+        "values_changed": {"root[0]['v']": {"old_value": 2, "new_value": 1}}
+    }
+
+
+def test_python_change_stats():
+    t1 = {"meta": {"rev": 1}, "data": {"rows": [1, 2]}}
+    t2 = {"meta": {"rev": 2}, "data": {"rows": [3, 4]}}
+    stats = DeepDiff(t1, t2).change_stats()
+    assert stats == {
+        "by_depth": {2: 1, 3: 2},
+        "by_prefix": {"root['data']": 2, "root['meta']": 1},
+        "total": 3,
+    }
+
+
+def test_python_changes_page_and_iter_changes():
+    t1 = {"a": 1, "b": [1, 2, 3], "c": {"x": 1}}
+    t2 = {"a": 2, "b": [1], "c": {"y": 1}}
+    diff = DeepDiff(t1, t2)
+    assert diff.changes_page(0, 2) == [
+        {"kind": "values_changed", "path": "root['a']", "old_value": 1, "new_value": 2},
+        {"kind": "dictionary_item_added", "path": "root['c']['y']"},
+    ]
+    pages = list(diff.iter_changes(chunk_size=2))
+    assert [len(page) for page in pages] == [2, 2, 1]
+    assert pages[0] == diff.changes_page(0, 2)
+    assert pages[2] == [
+        {"kind": "iterable_item_removed", "path": "root['b'][2]", "old_value": 3}
+    ]
+    assert diff.changes_page(10, 5) == []
+    with pytest.raises(ValueError, match="chunk_size"):
+        diff.iter_changes(chunk_size=0)
+
+
+def test_python_diff_job_resumes_from_checkpoint(tmp_path):
+    t1 = {"rows": [{"id": n, "v": n} for n in range(20000)], "rev": 1}
+    rows = [{"id": n, "v": n + (n % 1000 == 0)} for n in range(20000)]
+    t2 = {"rows": rows, "rev": 2}
+    checkpoint = tmp_path / "diff.checkpoint"
+    job = turbodiff.DiffJob(t1, t2)
+    assert not job.step()
+    job.save(checkpoint)
+    resumed = turbodiff.DiffJob(t1, t2, resume_from=checkpoint)
+    while not resumed.run_for(0.01):
+        resumed.save(checkpoint)
+    assert resumed.done
+    assert resumed.finish().to_dict() == DeepDiff(t1, t2).to_dict()
+    with pytest.raises(RuntimeError):
+        resumed.finish()
+    with pytest.raises(ValueError, match="checkpoint"):
+        turbodiff.DiffJob(t2, t1, resume_from=checkpoint)
+
+
+def test_python_deep_equal():
+    assert turbodiff.deep_equal({"a": [1, 2]}, {"a": [2, 1]}, ignore_order=True)
+    assert not turbodiff.deep_equal({"a": [1, 2]}, {"a": [2, 1]})
+    assert turbodiff.deep_equal({"x": 1.0}, {"x": 1.001}, atol=0.01)
+    assert turbodiff.deep_equal({"x": 1, "t": 2}, {"x": 1, "t": 3}, exclude_paths=["root['t']"])
+
+
+
+def test_compare_many_groups_documents():
+    documents = [{"replicas": 3}, {"replicas": 3}, {"replicas": 5}, {}]
+    report = turbodiff.compare_many(documents)
+    assert report["documents"] == 4
+    assert report["divergences"] == [
+        {
+            "path": "root['replicas']",
+            "groups": [
+                {"documents": [0, 1], "value": 3},
+                {"documents": [2], "value": 5},
+                {"documents": [3], "value": None},
+            ],
+        }
+    ]
+    same = turbodiff.compare_many([[1, 2], [2, 1]], ignore_order=True)
+    assert same["divergences"] == []
+
+def test_hasher_option_and_deep_hash():
+    t1 = [{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]
+    t2 = [{"id": 2, "v": "b"}, {"id": 1, "v": "c"}]
+    expected = DeepDiff(t1, t2, ignore_order=True).to_dict()
+    for hasher in ("fx", "xxhash", "sha256"):
+        assert DeepDiff(t1, t2, ignore_order=True, hasher=hasher).to_dict() == expected
+    with pytest.raises(ValueError, match="hasher"):
+        DeepDiff(t1, t2, hasher="md5")
+
+    digest = turbodiff.deep_hash({"b": [1, 2], "a": None})
+    assert len(digest) == 64
+    assert digest == turbodiff.deep_hash({"a": None, "b": [1, 2]})
+    assert digest != turbodiff.deep_hash({"a": None, "b": [2, 1]})
+    assert len(turbodiff.deep_hash("x", hasher="xxhash")) == 16
+
+
+def test_python_sampling():
+    t1 = {"rows": list(range(5000)), "rev": 1}
+    t2 = {"rows": [n + 1 for n in range(5000)], "rev": 1}
+    sampling = {"rate": 0.05, "threshold": 1000, "seed": 3}
+    diff = DeepDiff(t1, t2, sampling=sampling, verbose_level=0)
+    assert 100 < len(diff["values_changed"]) < 400
+    assert diff["sampling"] == {**sampling, "sampled_containers": 1}
+    again = DeepDiff(t1, t2, sampling=sampling, verbose_level=0)
+    assert again.to_dict() == diff.to_dict()
+    assert not DeepDiff(t1, t1, sampling=0.01)
+    with pytest.raises(ValueError, match="sampling"):
+        DeepDiff(t1, t2, sampling=2.0)
+
+
+def test_python_diff_many_returns_diffs_in_order():
+    pairs = [({"a": i}, {"a": i if i % 3 else -i}) for i in range(1, 301)]
+    diffs = DeepDiff.diff_many(pairs, verbose_level=0)
+    assert len(diffs) == 300
+    assert diffs[0].to_dict() == {}
+    assert diffs[2].to_dict() == {"values_changed": ["root['a']"]}
+    assert [bool(diff) for diff in diffs] == [i % 3 == 0 for i in range(1, 301)]
+
+
 def test_python_ignore_order():
     diff = DeepDiff([1, 2, 3], [3, 2, 1], ignore_order=True)
     assert diff.to_dict() == {}
@@ -69,6 +328,31 @@ def test_python_significant_digits():
     assert diff.to_dict() == {}
 
 
+def test_python_number_format_notation():
+    t1, t2 = {"big": 123456.0}, {"big": 123499.0}
+    kwargs = {"significant_digits": 2}
+    assert DeepDiff(t1, t2, number_format_notation="e", **kwargs).to_dict() == {}
+    assert DeepDiff(t1, t2, number_format_notation="f", **kwargs).to_dict() != {}
+    with pytest.raises(ValueError):
+        DeepDiff(t1, t2, number_format_notation="g", **kwargs)
+
+    calls = []
+
+    def to_string(number, significant_digits, notation):
+        calls.append((significant_digits, notation))
+        return f"{number:.0e}"
+
+    assert DeepDiff(t1, t2, number_to_string_func=to_string).to_dict() == {}
+    assert calls[0] == (None, "f")
+
+
+def test_python_use_log_scale():
+    assert DeepDiff(1e6, 1.01e6, use_log_scale=True).to_dict() == {}
+    assert DeepDiff(1e-6, 5e-6, use_log_scale=True).to_dict() != {}
+    loose = DeepDiff(1e-6, 5e-6, use_log_scale=True, log_scale_similarity_threshold=1)
+    assert loose.to_dict() == {}
+
+
 def test_python_math_epsilon():
     diff = DeepDiff(1.0, 1.0005, math_epsilon=0.001)
     assert diff.to_dict() == {}
@@ -117,6 +401,408 @@ def test_python_exclude_paths_set():
     assert diff.to_dict() == {}
 
 
+def test_python_exclude_regex_paths():
+    t1 = {"items": [{"id": 1, "ts": 10}, {"id": 2, "ts": 20}]}
+    t2 = {"items": [{"id": 1, "ts": 11}, {"id": 2, "ts": 21}]}
+    diff = DeepDiff(t1, t2, exclude_regex_paths=r"root\['items'\]\[\d+\]\['ts'\]")
+    assert diff.to_dict() == {}
+
+
+def test_python_ignore_keys():
+    t1 = {"a": {"updated_at": 1, "v": 1}, "etag": "x", "items": [{"etag": "y"}]}
+    t2 = {"a": {"updated_at": 2, "v": 2}, "items": [{"etag": "z"}]}
+    diff = DeepDiff(t1, t2, ignore_keys=["updated_at", "etag"])
+    assert diff.to_dict() == {
+        "values_changed": {"root['a']['v']": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_python_include_keys():
+    t1 = {"items": [{"id": 1, "price": 10, "note": "a"}], "name": "x"}
+    t2 = {"items": [{"id": 2, "price": 12, "note": "b"}], "name": "y"}
+    diff = DeepDiff(t1, t2, include_keys=["price", "quantity"])
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['items'][0]['price']": {"old_value": 10, "new_value": 12}
+        }
+    }
+
+
+def test_python_exclude_regex_paths_compiled_list():
+    t1 = {"a": {"TS": 1}, "b": {"ts": 1}, "c": 1}
+    t2 = {"a": {"TS": 2}, "b": {"ts": 2}, "c": 2}
+    diff = DeepDiff(
+        t1, t2, exclude_regex_paths=[re.compile(r"\['ts'\]$", re.IGNORECASE)]
+    )
+    assert diff.to_dict() == {
+        "values_changed": {"root['c']": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_python_root_label():
+    diff = DeepDiff({"port": 1}, {"port": 2}, root_label="app.yaml")
+    assert diff.to_dict() == {
+        "values_changed": {"app.yaml['port']": {"old_value": 1, "new_value": 2}}
+    }
+    assert diff.pretty(style="deepdiff") == (
+        "Value of app.yaml['port'] changed from 1 to 2."
+    )
+
+
+def test_python_path_aliases():
+    t1 = {"spec": {"containers": [{"image": "a"}]}}
+    t2 = {"spec": {"containers": [{"image": "b"}]}}
+    aliases = {"root['spec']['containers'][*]": "container[*]"}
+    diff = DeepDiff(t1, t2, path_aliases=aliases)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "container[0]['image']": {"old_value": "a", "new_value": "b"}
+        }
+    }
+    assert "container[0]" in diff.pretty(no_color=True)
+
+
+def test_python_annotate_attaches_fields():
+    t1 = {"billing": {"plan": "free"}, "name": "a"}
+    t2 = {"billing": {"plan": "pro"}, "name": "b"}
+
+    def owner(change):
+        if change["path"].startswith("root['billing']"):
+            return {"owner": "payments"}
+        return None
+
+    diff = DeepDiff(t1, t2, annotate=owner)
+    assert diff["annotations"] == {"root['billing']['plan']": {"owner": "payments"}}
+    assert len(diff) == 2
+    assert "# owner: payments" in diff.pretty(no_color=True)
+    with pytest.raises(TypeError):
+        DeepDiff(t1, t2, annotate=lambda change: "payments")
+
+
+def test_python_string_similarity_pairs_edited_strings():
+    t1 = ["disk full on /var", "cpu high"]
+    t2 = ["cpu high", "disk full on /tmp"]
+    diff = DeepDiff(t1, t2, ignore_order=True, string_similarity=0.7)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root[0]": {
+                "old_value": "disk full on /var",
+                "new_value": "disk full on /tmp",
+            }
+        }
+    }
+    with pytest.raises(ValueError):
+        DeepDiff(t1, t2, ignore_order=True, string_similarity=2)
+
+
+def test_python_cutoff_distance_for_pairs():
+    t1 = [{"id": 1, "role": "dev", "team": "web"}, {"id": 2, "role": "ops"}]
+    t2 = [{"id": 2, "role": "ops"}, {"id": 1, "role": "lead", "team": "web"}]
+    diff = DeepDiff(t1, t2, ignore_order=True, cutoff_distance_for_pairs=0.4)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root[0]['role']": {"old_value": "dev", "new_value": "lead"}
+        }
+    }
+    assert "iterable_item_added" in DeepDiff(t1, t2, ignore_order=True).to_dict()
+
+
+def test_python_report_repetition():
+    t1 = ["a", "b", "a"]
+    t2 = ["b", "a", "a", "a"]
+    diff = DeepDiff(t1, t2, ignore_order=True, report_repetition=True)
+    assert diff.to_dict() == {
+        "repetition_change": {
+            "root[0]": {
+                "old_repeat": 2,
+                "new_repeat": 3,
+                "old_indexes": [0, 2],
+                "new_indexes": [1, 2, 3],
+                "value": "a",
+            }
+        }
+    }
+    assert "~ 'a' repeated 2 → 3 times" in diff.pretty(no_color=True)
+    expected = "Repetition change for item root[0]."
+    assert diff.pretty(style="deepdiff") == expected
+
+
+def test_python_align_lists():
+    t1 = ["a", "b", "c", "d"]
+    t2 = ["x", "a", "b", "c", "d"]
+    diff = DeepDiff(t1, t2, align_lists=True)
+    assert diff.to_dict() == {"iterable_item_added": {"root[0]": "x"}}
+    assert len(DeepDiff(t1, t2)["values_changed"]) == 4
+
+    moved = DeepDiff(["a", "b", "c"], ["b", "c", "a"], align_lists=True)
+    assert moved.to_dict() == {
+        "iterable_item_moved": {"root[0]": {"new_path": "root[2]", "value": "a"}}
+    }
+    expected = "Item root[0] moved to root[2]."
+    assert moved.pretty(style="deepdiff") == expected
+
+
+def test_python_results_keep_document_order():
+    t1 = {"z": 1, "rows": list(range(12)), "a": 1}
+    t2 = {"z": 2, "rows": list(range(12)), "a": 2}
+    t2["rows"][2] = -2
+    t2["rows"][10] = -10
+    expected = ["root['z']", "root['rows'][2]", "root['rows'][10]", "root['a']"]
+    assert list(DeepDiff(t1, t2)["values_changed"]) == expected
+    sorted_diff = DeepDiff(t1, t2, sort_paths=True)
+    assert list(sorted_diff["values_changed"]) == sorted(expected)
+
+
+def test_python_cutoff_intersection_and_max_passes():
+    t1 = [{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, 7, 8]
+    t2 = [{"id": 1, "v": "x"}, {"id": 2, "v": "y"}, 7, 8]
+    kwargs = {"ignore_order": True, "cutoff_distance_for_pairs": 0.5}
+    assert len(DeepDiff(t1, t2, **kwargs)["values_changed"]) == 2
+    skipped = DeepDiff(t1, t2, cutoff_intersection_for_pairs=0.4, **kwargs)
+    assert "values_changed" not in skipped.to_dict()
+    capped = DeepDiff(t1, t2, max_passes=1, **kwargs)
+    assert list(capped["values_changed"]) == ["root[0]['v']"]
+
+
+def test_python_threshold_to_diff_deeper():
+    t1 = {"cfg": {"a": 1, "b": 2, "c": 3}}
+    t2 = {"cfg": {"a": 1, "d": 4, "e": 5}}
+    diff = DeepDiff(t1, t2, threshold_to_diff_deeper=0.33)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['cfg']": {"old_value": t1["cfg"], "new_value": t2["cfg"]}
+        }
+    }
+    assert "dictionary_item_added" in DeepDiff(t1, t2).to_dict()
+
+
+def test_python_collapse_index_ranges():
+    t1 = {"rows": list(range(6))}
+    t2 = {"rows": [0, 10, 20, 30, 4, 5, 6, 7, 8]}
+    diff = DeepDiff(t1, t2, collapse_index_ranges=3)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['rows'][1:4]": {
+                "count": 3,
+                "first": {"old_value": 1, "new_value": 10},
+                "last": {"old_value": 3, "new_value": 30},
+            }
+        },
+        "iterable_item_added": {
+            "root['rows'][6:9]": {"count": 3, "first": 6, "last": 8}
+        },
+    }
+    assert "~ 3 values changed" in diff.pretty(no_color=True)
+
+
+def test_python_magnitude_scores():
+    t1 = {"cpu": 100, "mem": 2.0, "name": "a"}
+    t2 = {"cpu": 101, "mem": 8.0, "name": "b"}
+    diff = DeepDiff(t1, t2, magnitude_scores=True)
+    assert diff["values_changed"]["root['mem']"]["magnitude"] == 0.75
+    assert "magnitude" not in diff["values_changed"]["root['name']"]
+    ranked = [change["path"] for change in diff.by_magnitude()]
+    assert ranked == ["root['mem']", "root['cpu']"]
+    assert [change["path"] for change in diff.by_magnitude(0.5)] == ["root['mem']"]
+
+
+def test_python_type_names():
+    t1, t2 = {"a": None, "b": 1}, {"a": "x", "b": [1]}
+    assert DeepDiff(t1, t2)["type_changes"]["root['a']"]["old_type"] == "null"
+    python = DeepDiff(t1, t2, type_names="python")["type_changes"]
+    assert python["root['a']"]["old_type"] == "NoneType"
+    changes = DeepDiff(t1, t2, type_names="json")["type_changes"]
+    assert changes["root['a']"]["old_type"] == "null"
+    assert changes["root['a']"]["new_type"] == "string"
+    assert changes["root['b']"]["new_type"] == "array"
+    with pytest.raises(ValueError):
+        DeepDiff(t1, t2, type_names="rust")
+
+
+def test_python_redaction_masks_values():
+    t1 = {"user": "ann", "password": "hunter2", "token": "tok_123", "db": {"host": "a"}}
+    t2 = {"user": "bob", "password": "hunter3", "token": "tok_456", "db": {"host": "b"}}
+    diff = DeepDiff(
+        t1,
+        t2,
+        redact_keys="pass",
+        redact_values=[re.compile(r"^TOK_", re.IGNORECASE)],
+        redact_paths=["root['db']"],
+    )
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['user']": {"old_value": "ann", "new_value": "bob"},
+            "root['password']": {"old_value": "***", "new_value": "***"},
+            "root['token']": {"old_value": "***", "new_value": "***"},
+            "root['db']['host']": {"old_value": "***", "new_value": "***"},
+        }
+    }
+    assert "hunter" not in diff.pretty(no_color=True)
+
+
+def test_python_classify_by_severity():
+    t1 = {"api": {"id": 1}, "docs": "a"}
+    t2 = {"api": {"id": 2}, "docs": "b"}
+    diff = DeepDiff(t1, t2)
+    report = diff.classify({"root['api']": "breaking"})
+    assert report["verdict"] == "breaking"
+    assert {change["path"]: change["severity"] for change in report["changes"]} == {
+        "root['api']['id']": "breaking",
+        "root['docs']": "info",
+    }
+    assert diff.classify("root['api'] => info", default="warning")["verdict"] == "warning"
+    with pytest.raises(ValueError):
+        diff.classify("root['api'] -> breaking")
+
+
+def test_python_schema_aware_comparison():
+    schema = {
+        "type": "object",
+        "properties": {
+            "id": {"type": "integer"},
+            "old": {"deprecated": True},
+            "ratio": {"type": "number", "format": "float"},
+        },
+    }
+    t1 = {"id": 1, "old": 1, "ratio": 0.5, "extra": 1}
+    t2 = {"id": 1.5, "old": 2, "ratio": 0.5000001, "extra": 2}
+    diff = DeepDiff(t1, t2, schema=schema, format_tolerances={"float": 1e-6})
+    assert diff.to_dict() == {
+        "values_changed": {"root['id']": {"old_value": 1, "new_value": 1.5}},
+        "schema_violations": {
+            "root['id']": {"expected_type": "integer", "new_value": 1.5}
+        },
+    }
+
+
+def test_python_rules_document(tmp_path):
+    rules = {
+        "rules": [
+            {"path": "root['metrics']", "atol": 0.01},
+            {"path": "root['users']", "match_by": "id"},
+            {"path": "root['debug']", "ignore": True},
+        ]
+    }
+    t1 = {
+        "metrics": {"cpu": 0.5},
+        "users": [{"id": 1, "n": 1}, {"id": 2}],
+        "debug": 1,
+    }
+    t2 = {
+        "metrics": {"cpu": 0.505},
+        "users": [{"id": 2}, {"id": 1, "n": 2}],
+        "debug": 2,
+    }
+    expected = {
+        "values_changed": {"root['users'][0]['n']": {"old_value": 1, "new_value": 2}}
+    }
+    assert DeepDiff(t1, t2, rules=rules).to_dict() == expected
+    path = tmp_path / "rules.json"
+    path.write_text(json.dumps(rules))
+    assert DeepDiff(t1, t2, rules=path).to_dict() == expected
+    with pytest.raises(ValueError):
+        DeepDiff(t1, t2, rules=[{"path": "root", "tolerance": 1}])
+
+
+def test_python_api_compat():
+    old = {"id": 1, "name": "a"}
+    assert DeepDiff(old, {"id": 1.5, "name": "a", "extra": True}).api_compat()[
+        "compatible"
+    ]
+    report = DeepDiff(old, {"id": 1}).api_compat()
+    assert report["compatible"] is False
+    assert report["verdict"] == "breaking"
+    assert DeepDiff(old, {"id": 1}).api_compat({"root['name']": "warning"})[
+        "compatible"
+    ]
+
+
+def test_python_exclude_regex_paths_invalid():
+    with pytest.raises(ValueError):
+        DeepDiff(1, 2, exclude_regex_paths="root[")
+
+
+def test_python_exclude_obj_callback():
+    def exclude_floats(obj, path):
+        return isinstance(obj, float)
+
+    t1 = {"a": 1.5, "b": 1, "c": [1, 2.5]}
+    t2 = {"a": 2.5, "b": 2, "c": [1, 2.5, 3.5]}
+    diff = DeepDiff(t1, t2, exclude_obj_callback=exclude_floats)
+    assert diff.to_dict() == {
+        "values_changed": {"root['b']": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_python_exclude_types():
+    t1 = {"a": 1.5, "b": "x", "c": [1, 2], "d": {"e": 1}}
+    t2 = {"a": 2.5, "b": "y", "c": [1, 3], "d": {"e": 2}}
+    diff = DeepDiff(t1, t2, exclude_types=[list, float])
+    assert diff.to_dict() == {
+        "values_changed": {"root['b']": {"old_value": "x", "new_value": "y"}}
+    }
+    with pytest.raises(TypeError):
+        DeepDiff(t1, t2, exclude_types=[object])
+
+
+def test_python_exclude_obj_callback_receives_path():
+    seen = []
+
+    def record(obj, path):
+        seen.append(path)
+        return path == "root['skip']"
+
+    diff = DeepDiff(
+        {"skip": 1, "keep": 1}, {"skip": 2, "keep": 1}, exclude_obj_callback=record
+    )
+    assert diff.to_dict() == {}
+    assert "root['skip']" in seen
+
+
+def test_python_exclude_obj_callback_strict():
+    def is_int(obj, path):
+        return isinstance(obj, int)
+
+    t1 = {"a": 1, "b": 1}
+    t2 = {"a": 2, "b": "1"}
+    diff = DeepDiff(t1, t2, exclude_obj_callback_strict=is_int)
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root['b']": {
+                "old_type": "int",
+                "new_type": "str",
+                "old_value": 1,
+                "new_value": "1",
+            }
+        }
+    }
+
+
+def test_python_exclude_obj_callback_error_propagates():
+    def boom(obj, path):
+        raise RuntimeError("boom")
+
+    with pytest.raises(RuntimeError, match="boom"):
+        DeepDiff({"a": 1}, {"a": 2}, exclude_obj_callback=boom)
+
+
+def test_python_progress_logger():
+    messages = []
+    t1 = list(range(5000))
+    t2 = list(range(1, 5001))
+    DeepDiff(t1, t2, progress_logger=messages.append, log_frequency_in_sec=1e-9)
+    assert messages
+    assert messages[0].startswith("DeepDiff ")
+    assert "Items #" in messages[0]
+
+
+def test_python_progress_logger_disabled_by_default():
+    messages = []
+    t1 = list(range(5000))
+    DeepDiff(t1, list(t1), progress_logger=messages.append)
+    assert messages == []
+
+
 class DummyModel:
     def __init__(self, value: int):
         self.value = value
@@ -130,6 +816,49 @@ def test_python_model_dump_object():
     assert diff.to_dict() == {}
 
 
+class RecordingModel:
+    model_computed_fields = {"total": None}
+
+    def __init__(self, value, note=None):
+        self.value = value
+        self.note = note
+
+    def model_dump(self, exclude=(), exclude_none=False, **kwargs):
+        data = {"value": self.value, "note": self.note, "total": self.value * 2}
+        if exclude_none:
+            data = {k: v for k, v in data.items() if v is not None}
+        return {k: v for k, v in data.items() if k not in exclude}
+
+
+def test_model_dump_options_forwarded():
+    t1, t2 = RecordingModel(1, "x"), RecordingModel(2)
+    assert list(DeepDiff(t1, t2).to_dict()["type_changes"]) == ["root['note']"]
+    diff = DeepDiff(t1, t2, model_exclude_none=True)
+    assert diff.to_dict()["dictionary_item_removed"] == ["root['note']"]
+    diff = DeepDiff(t1, t2, model_include_computed_fields=False)
+    assert list(diff.to_dict()["values_changed"]) == ["root['value']"]
+
+
+def test_pydantic_exclude_unset_and_computed_fields():
+    pydantic = pytest.importorskip("pydantic")
+
+    class Item(pydantic.BaseModel):
+        name: str
+        qty: int = 1
+
+        @pydantic.computed_field
+        @property
+        def label(self) -> str:
+            return f"{self.name}x{self.qty}"
+
+    t1, t2 = Item(name="a"), Item(name="a", qty=1)
+    assert DeepDiff(t1, t2).to_dict() == {}
+    diff = DeepDiff(t1, t2, model_exclude_unset=True)
+    assert diff.to_dict() == {"dictionary_item_added": ["root['qty']"]}
+    diff = DeepDiff(Item(name="a"), Item(name="b"), model_include_computed_fields=False)
+    assert list(diff.to_dict()["values_changed"]) == ["root['name']"]
+
+
 def test_python_dict_int_keys():
     diff = DeepDiff({1: 2, 3: 4}, {1: 2, 3: 4})
     assert diff.to_dict() == {}
@@ -221,3 +950,718 @@ def test_pretty_continuation_with_ellipsis(pretty_print):
     if pretty_print:
         print(output)
     assert output == "a\n├── b\n│   - 1\n│   + 2\n├── ...\n╰── j\n    - 1\n    + 2"
+
+
+def test_tree_view_levels():
+    diff = DeepDiff({"a": {"b": 1}}, {"a": {"b": 2}}, view="tree")
+    tree = diff.to_dict()
+    (level,) = tree["values_changed"]
+    assert level.t1 == 1
+    assert level.t2 == 2
+    assert level.report_type == "values_changed"
+    assert level.path() == "root['a']['b']"
+    assert level.path(output_format="list") == ["a", "b"]
+    assert level.up.path() == "root['a']"
+    assert level.up.t1 == {"b": 1}
+    assert level.up.up.up is None
+    assert level.up.down is level
+
+
+def test_tree_view_added_item_not_present():
+    from turbodiff import notpresent
+
+    diff = DeepDiff([1], [1, 2], view="tree")
+    (level,) = diff["iterable_item_added"]
+    assert level.t1 is notpresent
+    assert not level.t1
+    assert level.t2 == 2
+    assert level.path(root="data") == "data[1]"
+    assert repr(level) == "<root[1] t1:not present, t2:2>"
+
+
+def test_invalid_view():
+    with pytest.raises(ValueError):
+        DeepDiff(1, 2, view="graph")
+
+
+def test_datetime_values_changed():
+    t1 = {"at": dt.datetime(2024, 1, 1, 12, 0)}
+    t2 = {"at": dt.datetime(2024, 1, 1, 12, 5)}
+    diff = DeepDiff(t1, t2)
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root['at']": {
+                "old_value": dt.datetime(2024, 1, 1, 12, 0),
+                "new_value": dt.datetime(2024, 1, 1, 12, 5),
+            }
+        }
+    }
+
+
+def test_datetime_equal_across_timezones():
+    utc = dt.datetime(2024, 1, 1, 12, 0, tzinfo=dt.timezone.utc)
+    plus_one = dt.datetime(
+        2024, 1, 1, 13, 0, tzinfo=dt.timezone(dt.timedelta(hours=1))
+    )
+    assert DeepDiff(utc, plus_one).to_dict() == {}
+
+
+def test_datetime_type_change_reports_type_name():
+    diff = DeepDiff(dt.date(2024, 1, 1), "2024-01-01")
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": "date",
+                "new_type": "str",
+                "old_value": dt.date(2024, 1, 1),
+                "new_value": "2024-01-01",
+            }
+        }
+    }
+
+
+def test_time_and_timedelta():
+    assert DeepDiff(dt.time(1, 2, 3), dt.time(1, 2, 3)).to_dict() == {}
+    diff = DeepDiff(dt.timedelta(seconds=1), dt.timedelta(seconds=2))
+    assert diff.to_dict() == {
+        "values_changed": {
+            "root": {
+                "old_value": dt.timedelta(seconds=1),
+                "new_value": dt.timedelta(seconds=2),
+            }
+        }
+    }
+
+
+def test_datetime_epsilon():
+    t1 = dt.datetime(2024, 1, 1, 12, 0, 0)
+    t2 = dt.datetime(2024, 1, 1, 12, 0, 0, 500000)
+    assert DeepDiff(t1, t2, datetime_epsilon=1).to_dict() == {}
+    assert DeepDiff(t1, t2, datetime_epsilon=dt.timedelta(seconds=1)).to_dict() == {}
+    assert DeepDiff(t1, t2, datetime_epsilon=0.1).to_dict() != {}
+
+
+def test_pretty_datetime(pretty_print):
+    diff = DeepDiff({"at": dt.date(2024, 1, 1)}, {"at": dt.date(2024, 1, 2)})
+    output = diff.pretty(no_color=True)
+    if pretty_print:
+        print(output)
+    assert output == "at\n│   - date('2024-01-01')\n│   + date('2024-01-02')"
+
+
+def test_decimal_preserves_precision():
+    t1 = Decimal("0.10000000000000000000000000001")
+    t2 = Decimal("0.10000000000000000000000000002")
+    assert DeepDiff(t1, t2).to_dict() == {
+        "values_changed": {"root": {"old_value": t1, "new_value": t2}}
+    }
+    assert DeepDiff(Decimal("1.10"), Decimal("1.1")).to_dict() == {}
+
+
+def test_decimal_tolerances():
+    diff = DeepDiff(Decimal("1.1234"), Decimal("1.1235"), significant_digits=3)
+    assert diff.to_dict() == {}
+    assert DeepDiff(Decimal("1000.0"), Decimal("1000.1"), rtol=1e-3).to_dict() == {}
+    assert DeepDiff(Decimal("1.0"), Decimal("1.0005"), atol=0.001).to_dict() == {}
+    diff = DeepDiff(Decimal("1.1234"), Decimal("1.1299"), significant_digits=3)
+    assert diff.to_dict() != {}
+
+
+def test_decimal_type_change():
+    diff = DeepDiff({"a": Decimal("1")}, {"a": 1})
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root['a']": {
+                "old_type": "Decimal",
+                "new_type": "int",
+                "old_value": Decimal("1"),
+                "new_value": 1,
+            }
+        }
+    }
+    diff = DeepDiff(Decimal("1.5"), 1.5, ignore_numeric_type_changes=True)
+    assert diff.to_dict() == {}
+
+
+def test_uuid_values():
+    a = uuid.UUID("12345678-1234-5678-1234-567812345678")
+    b = uuid.UUID("87654321-4321-8765-4321-876543218765")
+    assert DeepDiff({"id": a}, {"id": uuid.UUID(str(a))}).to_dict() == {}
+    assert DeepDiff({"id": a}, {"id": b}).to_dict() == {
+        "values_changed": {"root['id']": {"old_value": a, "new_value": b}}
+    }
+
+
+def test_uuid_vs_string():
+    a = uuid.UUID("12345678-1234-5678-1234-567812345678")
+    diff = DeepDiff(a, str(a))
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": "UUID",
+                "new_type": "str",
+                "old_value": a,
+                "new_value": str(a),
+            }
+        }
+    }
+    assert DeepDiff(a, str(a).upper(), ignore_uuid_types=True).to_dict() == {}
+    diff = DeepDiff(a, "not-a-uuid", ignore_uuid_types=True)
+    assert diff.to_dict() == {
+        "values_changed": {"root": {"old_value": a, "new_value": "not-a-uuid"}}
+    }
+
+
+class Color(enum.Enum):
+    RED = 1
+    BLUE = 2
+
+
+class Shade(enum.Enum):
+    RED = 1
+
+
+class Level(enum.IntEnum):
+    LOW = 1
+
+
+def test_enum_compared_by_member():
+    assert DeepDiff({"c": Color.RED}, {"c": Color.RED}).to_dict() == {}
+    assert DeepDiff({"c": Color.RED}, {"c": Color.BLUE}).to_dict() == {
+        "values_changed": {
+            "root['c']": {"old_value": Color.RED, "new_value": Color.BLUE}
+        }
+    }
+
+
+def test_enum_type_change():
+    diff = DeepDiff(Color.RED, Shade.RED)
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": "Color",
+                "new_type": "Shade",
+                "old_value": Color.RED,
+                "new_value": Shade.RED,
+            }
+        }
+    }
+    assert DeepDiff(Level.LOW, 1).to_dict()["type_changes"]["root"]["old_type"] == (
+        "Level"
+    )
+
+
+def test_enum_classes_with_same_name_differ():
+    other = enum.Enum("Color", {"RED": 1, "BLUE": 2}, module="other_colors")
+    assert DeepDiff(Color.RED, other.RED).to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": f"{__name__}.Color",
+                "new_type": "other_colors.Color",
+                "old_value": Color.RED,
+                "new_value": other.RED,
+            }
+        }
+    }
+    assert DeepDiff(Color.RED, Color.BLUE).to_dict()["values_changed"]["root"][
+        "old_value"
+    ] is Color.RED
+
+
+def test_use_enum_value():
+    assert DeepDiff(Color.RED, Shade.RED, use_enum_value=True).to_dict() == {}
+    assert DeepDiff({"c": Color.RED}, {"c": 1}, use_enum_value=True).to_dict() == {}
+    diff = DeepDiff(Color.RED, Color.BLUE, use_enum_value=True)
+    assert diff.to_dict() == {
+        "values_changed": {"root": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_bytes_compared_as_bytes():
+    assert DeepDiff(b"abc", b"abc").to_dict() == {}
+    assert DeepDiff({"a": b"abc"}, {"a": b"abd"}).to_dict() == {
+        "values_changed": {"root['a']": {"old_value": b"abc", "new_value": b"abd"}}
+    }
+    diff = DeepDiff(b"abc", "abc")
+    assert diff.to_dict()["type_changes"]["root"]["old_type"] == "bytes"
+    assert DeepDiff(b"abc", "abc", ignore_string_type_changes=True).to_dict() == {}
+
+
+def test_bytes_encodings():
+    latin = "café".encode("latin-1")
+    with pytest.raises(UnicodeDecodeError):
+        DeepDiff(latin, "café")
+    diff = DeepDiff(
+        latin,
+        "café",
+        encodings=["utf-8", "latin-1"],
+        ignore_string_type_changes=True,
+    )
+    assert diff.to_dict() == {}
+    diff = DeepDiff({"a": latin}, {"a": b"x"}, encodings=["latin-1"])
+    assert diff.to_dict()["values_changed"]["root['a']"]["old_value"] == latin
+
+
+def test_ignore_encoding_errors():
+    diff = DeepDiff(b"\xff", b"\xfe", ignore_encoding_errors=True)
+    assert diff.to_dict() == {}
+    diff = DeepDiff(b"\xffa", b"\xffb", ignore_encoding_errors=True)
+    assert "values_changed" in diff.to_dict()
+
+
+def test_tuple_vs_list_type_change():
+    assert DeepDiff((1, 2), (1, 2)).to_dict() == {}
+    assert DeepDiff((1, 2), [1, 2]).to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": "tuple",
+                "new_type": "list",
+                "old_value": (1, 2),
+                "new_value": [1, 2],
+            }
+        }
+    }
+
+
+def test_tuple_items_are_diffed():
+    diff = DeepDiff({"t": (1, 2, 3)}, {"t": (1, 5)})
+    assert diff.to_dict() == {
+        "values_changed": {"root['t'][1]": {"old_value": 2, "new_value": 5}},
+        "iterable_item_removed": {"root['t'][2]": 3},
+    }
+
+
+def test_tuple_vs_list_ignore_type_in_groups():
+    diff = DeepDiff((1, 2), [1, 3], ignore_type_in_groups=[(list, tuple)])
+    assert diff.to_dict() == {
+        "values_changed": {"root[1]": {"old_value": 2, "new_value": 3}}
+    }
+
+
+def test_iterables_are_consumed_as_lists():
+    assert DeepDiff(range(3), [0, 1, 2]).to_dict() == {}
+    assert DeepDiff(collections.deque([1, 2]), [1, 2]).to_dict() == {}
+    assert DeepDiff((x * 2 for x in range(3)), [0, 2, 4]).to_dict() == {}
+    diff = DeepDiff({"a": 1}.keys(), {"b": 1}.keys())
+    assert diff.to_dict() == {
+        "values_changed": {"root[0]": {"old_value": "a", "new_value": "b"}}
+    }
+    assert DeepDiff({"a": 1}.values(), [1]).to_dict() == {}
+
+
+def test_non_string_dict_keys():
+    assert DeepDiff({1: "a"}, {1: "a"}).to_dict() == {}
+    assert DeepDiff({1: "a"}, {1: "b"}).to_dict() == {
+        "values_changed": {"root[1]": {"old_value": "a", "new_value": "b"}}
+    }
+    assert DeepDiff({1: "a"}, {"1": "a"}).to_dict() == {
+        "dictionary_item_added": ["root['1']"],
+        "dictionary_item_removed": ["root[1]"],
+    }
+    diff = DeepDiff({(1, "x"): 1}, {(1, "x"): 2})
+    assert diff.to_dict() == {
+        "values_changed": {"root[(1, 'x')]": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_non_string_dict_keys_in_paths():
+    t1, t2 = {(1, 2): {"a": 1}}, {(1, 2): {"a": 2}}
+    assert DeepDiff(t1, t2, exclude_paths=["root[(1, 2)]"]).to_dict() == {}
+    diff = DeepDiff({1: {"a": 1}}, {1: {"a": 2}}, view="tree")
+    level = diff["values_changed"][0]
+    assert level.path() == "root[1]['a']"
+    assert level.path(output_format="list") == [1, "a"]
+    assert level.up.t1 == {"a": 1}
+
+
+def test_circular_references():
+    a = [1]
+    a.append(a)
+    b = [1]
+    b.append(b)
+    assert DeepDiff(a, b).to_dict() == {}
+    c = [2]
+    c.append(c)
+    assert DeepDiff(a, c).to_dict() == {
+        "values_changed": {"root[0]": {"old_value": 1, "new_value": 2}}
+    }
+    d = {"x": 1}
+    d["self"] = d
+    e = {"x": 1, "self": {"x": 1}}
+    diff = DeepDiff(d, e).to_dict()
+    assert diff["type_changes"]["root['self']"]["old_type"] == "cycle"
+
+
+def test_python_large_inputs_convert_consistently():
+    t1 = {"rows": [{"id": i, "tags": ["a", "b"], "score": i / 2} for i in range(5000)]}
+    t2 = {"rows": [{"id": i, "tags": ["a", "b"], "score": i / 2} for i in range(5000)]}
+    t2["rows"][4321]["tags"].append("c")
+    t2["rows"][10]["self"] = t2["rows"]
+    diff = DeepDiff(t1, t2).to_dict()
+    assert diff == {
+        "dictionary_item_added": ["root['rows'][10]['self']"],
+        "iterable_item_added": {"root['rows'][4321]['tags'][2]": "c"},
+    }
+
+
+@dataclasses.dataclass
+class Point:
+    x: int
+    y: int
+    label: str = dataclasses.field(default="", compare=False)
+
+
+@dataclasses.dataclass(frozen=True)
+class Vector:
+    x: int
+    y: int
+
+
+def test_dataclass_fields_are_diffed():
+    assert DeepDiff(Point(1, 2, "a"), Point(1, 2, "b")).to_dict() == {}
+    assert DeepDiff(Point(1, 2), Point(1, 3)).to_dict() == {
+        "values_changed": {"root.y": {"old_value": 2, "new_value": 3}}
+    }
+    diff = DeepDiff({"p": [Point(1, 2)]}, {"p": [Point(5, 2)]}, view="tree")
+    assert diff["values_changed"][0].path() == "root['p'][0].x"
+    assert diff["values_changed"][0].up.t1 == Point(1, 2)
+
+
+def test_dataclass_type_change():
+    diff = DeepDiff(Point(1, 2), Vector(1, 2))
+    assert diff.to_dict() == {
+        "type_changes": {
+            "root": {
+                "old_type": "Point",
+                "new_type": "Vector",
+                "old_value": Point(1, 2),
+                "new_value": Vector(1, 2),
+            }
+        }
+    }
+
+
+class Custom:
+    def __init__(self, **attrs):
+        self.__dict__.update(attrs)
+
+    def __eq__(self, other):
+        return type(other) is Custom and vars(self) == vars(other)
+
+
+class Slotted:
+    __slots__ = ("a", "b")
+
+    def __init__(self, a, b=None):
+        self.a = a
+        if b is not None:
+            self.b = b
+
+
+def test_object_attributes_are_diffed():
+    assert DeepDiff(Custom(a=1), Custom(a=1)).to_dict() == {}
+    assert DeepDiff(Custom(a=1, b=2), Custom(a=2, c=3)).to_dict() == {
+        "values_changed": {"root.a": {"old_value": 1, "new_value": 2}},
+        "attribute_added": ["root.c"],
+        "attribute_removed": ["root.b"],
+    }
+    assert DeepDiff(Slotted(1, 2), Slotted(1)).to_dict() == {
+        "attribute_removed": ["root.b"]
+    }
+    diff = DeepDiff({"o": Custom(a=1)}, {"o": Custom(a=2)}).to_dict()
+    assert diff == {"values_changed": {"root['o'].a": {"old_value": 1, "new_value": 2}}}
+
+
+def test_ignore_private_variables():
+    assert DeepDiff(Custom(__x=1), Custom(__x=2)).to_dict() == {}
+    diff = DeepDiff(Custom(__x=1), Custom(__x=2), ignore_private_variables=False)
+    assert diff.to_dict() == {
+        "values_changed": {"root.__x": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_numpy_arrays_compared_elementwise():
+    a = np.array([[1.0, 2.0], [3.0, 4.0]])
+    b = np.array([[1.0, 2.0], [3.0, 4.5]])
+    assert DeepDiff(a, a.copy()).to_dict() == {}
+    assert DeepDiff(a, b).to_dict() == {
+        "values_changed": {"root[1][1]": {"old_value": 4.0, "new_value": 4.5}}
+    }
+    assert DeepDiff(a, b, atol=0.5).to_dict() == {}
+    assert DeepDiff(a.T, b.T).to_dict() == {
+        "values_changed": {"root[1][1]": {"old_value": 4.0, "new_value": 4.5}}
+    }
+
+
+def test_numpy_integer_and_bool_arrays():
+    ints = np.arange(4, dtype=np.int32)
+    assert DeepDiff(ints, [0, 1, 2, 3]).to_dict() == {}
+    flags = np.array([True, False])
+    assert DeepDiff(flags, np.array([True, True])).to_dict() == {
+        "values_changed": {"root[1]": {"old_value": False, "new_value": True}}
+    }
+
+
+def test_tuple_index_paths():
+    t1 = {"grid": [[1, 2], [3, 4]]}
+    t2 = {"grid": [[1, 2], [3, 5]]}
+    assert DeepDiff(t1, t2, exclude_paths=["root['grid'][(1, 1)]"]).to_dict() == {}
+    diff = DeepDiff(t1, t2, include_paths=["root['grid'][(1, 1)]"])
+    assert list(diff.to_dict()["values_changed"]) == ["root['grid'][1][1]"]
+
+
+def test_pandas_dataframe_rows_columns_and_cells():
+    df1 = pd.DataFrame({"a": [1, 2], "b": [3.0, 4.0]}, index=[0, 1])
+    df2 = pd.DataFrame({"a": [1.0, 5.0], "c": [1, 1]}, index=[0, 2])
+    assert DeepDiff(df1, df2).to_dict() == {
+        "dictionary_item_added": ["root['c']"],
+        "dictionary_item_removed": ["root['b']"],
+        "iterable_item_added": {"root.loc[2]": {"a": 5.0, "c": 1}},
+        "iterable_item_removed": {"root.loc[1]": {"a": 2, "b": 4.0}},
+        "type_changes": {"root['a']": {"old_type": "int64", "new_type": "float64"}},
+    }
+
+
+def test_pandas_dataframe_exclude_paths():
+    df1 = pd.DataFrame({"a": [1, 2], "b": [3.0, 4.0]}, index=[0, 1])
+    df2 = pd.DataFrame({"a": [1.0, 5.0], "c": [1, 1]}, index=[0, 2])
+    diff = DeepDiff(df1, df2, exclude_paths=["root['a']", "root['b']", "root['c']"])
+    assert diff.to_dict() == {
+        "iterable_item_added": {"root.loc[2]": {"a": 5.0, "c": 1}},
+        "iterable_item_removed": {"root.loc[1]": {"a": 2, "b": 4.0}},
+    }
+
+
+def test_pandas_dataframe_cell_tolerance():
+    df1 = pd.DataFrame({"x": [1.0, 2.0]}, index=["r1", "r2"])
+    df2 = pd.DataFrame({"x": [1.0, 2.01]}, index=["r1", "r2"])
+    assert DeepDiff(df1, df2).to_dict() == {
+        "values_changed": {"root[('r2', 'x')]": {"old_value": 2.0, "new_value": 2.01}}
+    }
+    assert DeepDiff(df1, df2, atol=0.1).to_dict() == {}
+
+
+def test_polars_dataframe_and_series():
+    pl = pytest.importorskip("polars")
+    df1 = pl.DataFrame({"a": [1, 2], "b": ["x", "y"]})
+    df2 = pl.DataFrame({"a": [1, 3, 4], "b": ["x", "y", "z"]})
+    assert DeepDiff(df1, df1.clone()).to_dict() == {}
+    assert DeepDiff(df1, df2).to_dict() == {
+        "values_changed": {"root[(1, 'a')]": {"old_value": 2, "new_value": 3}},
+        "iterable_item_added": {"root.loc[2]": {"a": 4, "b": "z"}},
+    }
+    assert DeepDiff(pl.Series([1.0, 2.0]), [1.0, 2.0]).to_dict() == {}
+
+
+def test_pyarrow_table_schema_aware_diff():
+    pa = pytest.importorskip("pyarrow")
+    t1 = pa.table({"a": [1, 2], "b": ["x", "y"]})
+    t2 = pa.table({"a": [1.0, 3.0], "c": [True, False]})
+    assert DeepDiff(t1, t1).to_dict() == {}
+    assert DeepDiff(t1, t2).to_dict() == {
+        "dictionary_item_added": ["root['c']"],
+        "dictionary_item_removed": ["root['b']"],
+        "type_changes": {"root['a']": {"old_type": "int64", "new_type": "double"}},
+        "values_changed": {"root[(1, 'a')]": {"old_value": 2, "new_value": 3.0}},
+    }
+
+
+def test_pyarrow_arrays():
+    pa = pytest.importorskip("pyarrow")
+    assert DeepDiff(pa.array([1, 2, None]), [1, 2, None]).to_dict() == {}
+    chunked = pa.chunked_array([[1, 2], [3]])
+    assert DeepDiff(chunked, [1, 2, 4]).to_dict() == {
+        "values_changed": {"root[2]": {"old_value": 3, "new_value": 4}}
+    }
+
+
+def test_torch_tensors_compared_elementwise():
+    torch = pytest.importorskip("torch")
+    t1 = torch.tensor([[1.0, 2.0], [3.0, 4.0]], requires_grad=True)
+    t2 = torch.tensor([[1.0, 2.0], [3.0, 4.25]])
+    assert DeepDiff(t1, t1.detach().clone()).to_dict() == {}
+    assert DeepDiff(t1, t2).to_dict() == {
+        "values_changed": {"root[1][1]": {"old_value": 4.0, "new_value": 4.25}}
+    }
+    assert DeepDiff(t1, t2, atol=0.5).to_dict() == {}
+
+
+def test_unknown_option_suggests_nearest_match():
+    with pytest.raises(ValueError, match="did you mean 'ignore_order'"):
+        DeepDiff(1, 2, ignore_ordr=True)
+
+
+def test_option_values_validated():
+    with pytest.raises(TypeError, match="verbose_level"):
+        DeepDiff(1, 2, verbose_level="high")
+    with pytest.raises(ValueError, match="cannot be used together"):
+        DeepDiff(1.0, 1.1, significant_digits=2, atol=0.5)
+    with pytest.raises(ValueError, match="rtol must be a non-negative number"):
+        DeepDiff(1.0, 1.1, rtol=-0.1)
+
+
+def test_ignore_unknown_kwargs_warns_instead_of_raising():
+    with pytest.warns(UserWarning, match="cache_size"):
+        diff = DeepDiff(1, 2, cache_size=5000, ignore_unknown_kwargs=True)
+    assert diff.to_dict() == {
+        "values_changed": {"root": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_ignore_unknown_kwargs_from_environment(monkeypatch):
+    monkeypatch.setenv("TURBODIFF_IGNORE_UNKNOWN_KWARGS", "1")
+    with pytest.warns(UserWarning, match="cache_purge_level"):
+        assert DeepDiff(1, 1, cache_purge_level=1).to_dict() == {}
+    with pytest.raises(ValueError, match="Unsupported option"):
+        DeepDiff(1, 1, cache_purge_level=1, ignore_unknown_kwargs=False)
+
+
+def test_delta_patches_t1_into_t2():
+    t1 = {"a": 1, "b": [1, 2, 3], "c": (1, 2), 3: "x"}
+    t2 = {"a": "one", "b": [1, 5], "c": (1, 2, 4), 3: "y", "d": None}
+    delta = Delta(DeepDiff(t1, t2))
+    assert t1 + delta == t2
+    assert t1 == {"a": 1, "b": [1, 2, 3], "c": (1, 2), 3: "x"}
+
+
+def test_delta_dumps_and_loads():
+    t1, t2 = [{"id": 1}, {"id": 2}], [{"id": 1}, {"id": 3}, {"id": 4}]
+    dump = Delta(DeepDiff(t1, t2)).dumps()
+    assert isinstance(dump, bytes)
+    assert t1 + Delta(dump) == t2
+    assert t1 + Delta.loads(dump) == t2
+
+
+def test_bidirectional_delta_subtracts():
+    t1, t2 = {"a": 1, "b": [1, 2]}, {"a": 2, "b": [1], "c": 3}
+    delta = Delta(DeepDiff(t1, t2), bidirectional=True)
+    assert t2 - delta == t1
+    with pytest.raises(DeltaError, match="bidirectional"):
+        t2 - Delta(DeepDiff(t1, t2))
+
+
+def test_delta_raise_errors():
+    delta = Delta(DeepDiff({"a": {"b": 1}}, {"a": {"b": 2}}), raise_errors=True)
+    with pytest.raises(DeltaError, match=r"root\['a'\]\['b'\]"):
+        {"z": 1} + delta
+    assert {"z": 1} + Delta(DeepDiff({"a": {"b": 1}}, {"a": {"b": 2}})) == {"z": 1}
+
+
+def test_module_level_diff_returns_dict():
+    t1, t2 = {"a": [1, 2], "b": 1.0}, {"a": [1, 3], "b": 1.05}
+    result = turbodiff.diff(t1, t2, atol=0.1)
+    assert result == DeepDiff(t1, t2, atol=0.1).to_dict()
+    assert result == {
+        "values_changed": {"root['a'][1]": {"old_value": 2, "new_value": 3}}
+    }
+    assert turbodiff.diff(1, 1) == {}
+    with pytest.raises(ValueError, match="tree"):
+        turbodiff.diff(1, 2, view="tree")
+
+
+def test_compute_async_returns_future():
+    future = DeepDiff.compute_async({"a": 1}, {"a": 2}, verbose_level=0)
+    assert future.result(timeout=10).to_dict() == {"values_changed": ["root['a']"]}
+
+    def boom(obj, path):
+        raise RuntimeError("boom")
+
+    future = DeepDiff.compute_async({"a": 1}, {"a": 2}, exclude_obj_callback=boom)
+    with pytest.raises(RuntimeError, match="boom"):
+        future.result(timeout=10)
+
+
+def test_compute_async_awaitable():
+    async def run():
+        future = DeepDiff.compute_async([1, 2], [2, 1], ignore_order=True)
+        return await asyncio.wrap_future(future)
+
+    assert asyncio.run(run()).to_dict() == {}
+
+
+def test_from_json_parses_in_rust():
+    t1 = '{"a": [1, 2, 3], "b": {"c": "x"}}'
+    t2 = b'{"a": [1, 2], "b": {"c": "y"}, "d": null}'
+    diff = DeepDiff.from_json(t1, t2)
+    assert diff.to_dict() == {
+        "dictionary_item_added": ["root['d']"],
+        "iterable_item_removed": {"root['a'][2]": 3},
+        "values_changed": {"root['b']['c']": {"old_value": "x", "new_value": "y"}},
+    }
+    assert not DeepDiff.from_json(bytearray(b"[2, 1]"), "[1, 2]", ignore_order=True)
+    with pytest.raises(ValueError, match="Invalid JSON"):
+        DeepDiff.from_json("{", "{}")
+
+
+class JsonPoint:
+    def __init__(self, x):
+        self.x = x
+
+    def __json__(self):
+        return {"x": self.x}
+
+
+def test_json_protocol_converts_objects():
+    diff = DeepDiff({"p": JsonPoint(1)}, {"p": JsonPoint(2)})
+    assert diff.to_dict() == {
+        "values_changed": {"root['p']['x']": {"old_value": 1, "new_value": 2}}
+    }
+
+
+def test_default_converts_unsupported_objects():
+    with pytest.raises(TypeError, match="Unsupported Python type"):
+        DeepDiff(complex(1, 2), complex(1, 3))
+    diff = DeepDiff(complex(1, 2), complex(1, 3), default=lambda c: [c.real, c.imag])
+    assert diff.to_dict() == {
+        "values_changed": {"root[1]": {"old_value": 2.0, "new_value": 3.0}}
+    }
+    with pytest.raises(TypeError, match="returned the object itself"):
+        DeepDiff(complex(1, 2), complex(1, 3), default=lambda c: c)
+
+
+def test_big_integers_compared_exactly():
+    big = 2**64 + 1
+    assert DeepDiff({"id": big}, {"id": big}).to_dict() == {}
+    assert DeepDiff({"id": big}, {"id": big + 1}).to_dict() == {
+        "values_changed": {"root['id']": {"old_value": big, "new_value": big + 1}}
+    }
+    assert DeepDiff(-(2**70), 5).to_dict() == {
+        "values_changed": {"root": {"old_value": -(2**70), "new_value": 5}}
+    }
+    assert DeepDiff([big, 1], [1, big], ignore_order=True).to_dict() == {}
+    assert DeepDiff(big, big + 1, atol=2).to_dict() == {}
+
+
+def test_non_finite_floats():
+    inf, nan = float("inf"), float("nan")
+    assert DeepDiff([inf, -inf], [inf, -inf]).to_dict() == {}
+    assert DeepDiff({"a": 1.5}, {"a": inf}).to_dict() == {
+        "values_changed": {"root['a']": {"old_value": 1.5, "new_value": inf}}
+    }
+    changed = DeepDiff(nan, nan).to_dict()["values_changed"]["root"]
+    assert math.isnan(changed["old_value"]) and math.isnan(changed["new_value"])
+    assert "-inf" in DeepDiff(-inf, 0.0).pretty(no_color=True)
+    t1, t2 = {"a": [nan, 1.0]}, {"a": [nan, 2.0]}
+    assert DeepDiff(t1, t2, ignore_nan_inequality=True).to_dict() == {
+        "values_changed": {"root['a'][1]": {"old_value": 1.0, "new_value": 2.0}}
+    }
+    unordered = DeepDiff(
+        [nan, 1], [1, nan], ignore_order=True, ignore_nan_inequality=True
+    )
+    assert unordered.to_dict() == {}
+
+
+def test_pretty_deepdiff_style():
+    diff = DeepDiff({2: 2, 4: 4, "l": [1]}, {2: "b", 5: 5, "l": [1, None]})
+    assert diff.pretty(style="deepdiff") == (
+        "Item root[5] added to dictionary.\n"
+        "Item root[4] removed from dictionary.\n"
+        "Item root['l'][1] added to iterable.\n"
+        'Type of root[2] changed from int to str and value changed from 2 to "b".'
+    )
+    diff = DeepDiff({"a": "x"}, {"a": "y"})
+    assert diff.pretty(style="deepdiff", prefix="Diff: ") == (
+        'Diff: Value of root[\'a\'] changed from "x" to "y".'
+    )
+    with pytest.raises(ValueError, match="style"):
+        diff.pretty(style="table")
@@ -0,0 +1,38 @@
+#![cfg(feature = "raw-numbers")]
+
+use serde_json::{json, Value};
+use turbodiff::{DeepDiff, DeepDiffOptions, PrettyOptions};
+
+fn parse(text: &str) -> Value {
+    serde_json::from_str(text).unwrap()
+}
+
+#[test]
+fn reports_numbers_as_written_in_the_source() {
+    let t1 = parse(r#"{"a": 1.10, "b": 1e3, "c": 2.50, "d": 7}"#);
+    let t2 = parse(r#"{"a": 1.1, "b": 1000.0, "c": 2.750, "d": 7}"#);
+    let diff = DeepDiff::new(t1, t2);
+    let result = diff.to_value();
+    let change = &result["values_changed"]["root['c']"];
+    assert_eq!(result["values_changed"].as_object().unwrap().len(), 1);
+    assert_eq!(change["old_value"].to_string(), "2.50");
+    assert_eq!(change["new_value"].to_string(), "2.750");
+    let pretty = diff.pretty(PrettyOptions {
+        no_color: true,
+        ..PrettyOptions::default()
+    });
+    assert!(
+        pretty.contains("2.50") && pretty.contains("2.750"),
+        "{}",
+        pretty
+    );
+}
+
+#[test]
+fn compares_numbers_by_value_not_lexeme() {
+    let t1 = parse(r#"[1.10, 2e0, {"x": 0.50}]"#);
+    let t2 = parse(r#"[{"x": 0.5}, 2.0, 1.1]"#);
+    let options = DeepDiffOptions::default().ignore_order(true);
+    assert!(DeepDiff::with_options(t1, t2, options).is_empty());
+    assert!(DeepDiff::new(parse("[1.10]"), json!([1.1])).is_empty());
+}
@@ -1,7 +1,30 @@
-from typing import Any, Iterable
+from concurrent.futures import Future
+from datetime import timedelta
+from os import PathLike
+from re import Pattern
+from typing import Any, Callable, Iterable, Iterator, Literal, Mapping
 
 __all__: list[str]
 
+class NotPresent:
+    def __bool__(self) -> bool: ...
+
+notpresent: NotPresent
+
+class DiffLevel:
+    t1: Any
+    t2: Any
+    report_type: str | None
+    up: DiffLevel | None
+    down: DiffLevel | None
+    def path(
+        self, root: str = ..., output_format: Literal["str", "list"] = ...
+    ) -> Any: ...
+
+class ChangePages(Iterator[list[dict[str, Any]]]):
+    def __iter__(self) -> ChangePages: ...
+    def __next__(self) -> list[dict[str, Any]]: ...
+
 class DeepDiff:
     def __init__(
         self,
@@ -9,18 +32,100 @@ class DeepDiff:
         t2: Any,
         *,
         ignore_order: bool = ...,
+        string_similarity: float | None = ...,
+        cutoff_distance_for_pairs: float | None = ...,
+        cutoff_intersection_for_pairs: float | None = ...,
+        max_passes: int | None = ...,
+        threshold_to_diff_deeper: float | None = ...,
+        report_repetition: bool = ...,
+        align_lists: bool = ...,
         ignore_numeric_type_changes: bool = ...,
         ignore_string_type_changes: bool = ...,
+        ignore_uuid_types: bool = ...,
+        ignore_nan_inequality: bool = ...,
+        use_enum_value: bool = ...,
+        encodings: Iterable[str] | None = ...,
+        ignore_encoding_errors: bool = ...,
+        ignore_private_variables: bool = ...,
+        model_exclude_unset: bool = ...,
+        model_exclude_defaults: bool = ...,
+        model_exclude_none: bool = ...,
+        model_include_computed_fields: bool = ...,
+        default: Callable[[Any], Any] | None = ...,
         ignore_type_in_groups: Iterable[Iterable[type]] | None = ...,
+        exclude_types: Iterable[type] = ...,
         significant_digits: int | None = ...,
+        number_format_notation: Literal["f", "e"] | None = ...,
+        number_to_string_func: Callable[[float, int | None, str], str] | None = ...,
         math_epsilon: float | None = ...,
+        use_log_scale: bool = ...,
+        log_scale_similarity_threshold: float = ...,
         atol: float | None = ...,
         rtol: float | None = ...,
+        datetime_epsilon: float | timedelta | None = ...,
+        truncate_datetime: Literal["second", "minute", "hour", "day"] | None = ...,
         include_paths: Iterable[str] | None = ...,
         exclude_paths: Iterable[str] | None = ...,
+        exclude_regex_paths: str | Pattern[str] | Iterable[str | Pattern[str]] | None = ...,
+        ignore_keys: Iterable[str] | None = ...,
+        include_keys: Iterable[str] | None = ...,
+        exclude_obj_callback: Callable[[Any, str], bool] | None = ...,
+        exclude_obj_callback_strict: Callable[[Any, str], bool] | None = ...,
+        annotate: Callable[[dict[str, Any]], dict[str, Any] | None] | None = ...,
+        schema: dict[str, Any] | None = ...,
+        format_tolerances: Mapping[str, float] | None = ...,
+        rules: Mapping[str, Any] | list[Mapping[str, Any]] | str | PathLike[str] | None = ...,
+        match_by: str | None = ...,
+        group_by: str | None = ...,
+        sampling: float | Mapping[str, float | int] | None = ...,
+        redact_paths: Iterable[str] | None = ...,
+        redact_keys: str | Pattern[str] | Iterable[str | Pattern[str]] | None = ...,
+        redact_values: str | Pattern[str] | Iterable[str | Pattern[str]] | None = ...,
+        progress_logger: Callable[[str], Any] | None = ...,
+        log_frequency_in_sec: float = ...,
         verbose_level: int = ...,
+        root_label: str = ...,
+        path_aliases: dict[str, str] | None = ...,
+        type_names: Literal["python", "json"] = ...,
+        hasher: Literal["fx", "xxhash", "sha256"] = ...,
+        max_report_bytes: int | None = ...,
+        max_diffs: int | None = ...,
+        get_deep_distance: bool = ...,
+        large_string_threshold: int | None = ...,
+        magnitude_scores: bool = ...,
+        collapse_index_ranges: int | None = ...,
+        sort_paths: bool = ...,
+        num_threads: int | None = ...,
+        view: Literal["text", "tree"] = ...,
+        ignore_unknown_kwargs: bool = ...,
     ) -> None: ...
+    @staticmethod
+    def from_json(
+        t1_json: str | bytes | bytearray, t2_json: str | bytes | bytearray, **kwargs: Any
+    ) -> DeepDiff: ...
+    @staticmethod
+    def compute_async(t1: Any, t2: Any, **kwargs: Any) -> Future[DeepDiff]: ...
+    @staticmethod
+    def symmetric(t1: Any, t2: Any, **kwargs: Any) -> tuple[DeepDiff, DeepDiff]: ...
+    @staticmethod
+    def diff_many(pairs: Iterable[tuple[Any, Any]], **kwargs: Any) -> list[DeepDiff]: ...
     def to_dict(self) -> dict[str, Any]: ...
+    def invert(self) -> DeepDiff: ...
+    def change_stats(self, prefix_depth: int = 1) -> dict[str, Any]: ...
+    def changes_page(self, offset: int, limit: int) -> list[dict[str, Any]]: ...
+    def iter_changes(self, chunk_size: int = 1000) -> ChangePages: ...
+    def __getitem__(self, key: str) -> Any: ...
+    def classify(
+        self,
+        rules: str | Mapping[str, Literal["info", "warning", "breaking"]],
+        *,
+        default: Literal["info", "warning", "breaking"] = ...,
+    ) -> dict[str, Any]: ...
+    def api_compat(
+        self,
+        rules: str | Mapping[str, Literal["info", "warning", "breaking"]] | None = ...,
+    ) -> dict[str, Any]: ...
+    def by_magnitude(self, min_magnitude: float = ...) -> list[dict[str, Any]]: ...
     def pretty(
         self,
         *,
@@ -29,7 +134,56 @@ class DeepDiff:
         context: int = ...,
         no_color: bool = ...,
         path_header: bool = ...,
+        style: Literal["tree", "deepdiff"] = ...,
+        prefix: str | Callable[..., str] | None = ...,
     ) -> str: ...
     def __repr__(self) -> str: ...
     def __bool__(self) -> bool: ...
     def __len__(self) -> int: ...
+
+def diff(t1: Any, t2: Any, **kwargs: Any) -> dict[str, Any]: ...
+def deep_equal(t1: Any, t2: Any, **kwargs: Any) -> bool: ...
+def compare_many(documents: Iterable[Any], **kwargs: Any) -> dict[str, Any]: ...
+def deep_hash(
+    obj: Any, hasher: Literal["fx", "xxhash", "sha256"] = "sha256"
+) -> str: ...
+def write_changes(
+    t1: Any, t2: Any, sink: Callable[[str], Any] | Any, **kwargs: Any
+) -> int: ...
+
+class DiffJob:
+    def __init__(
+        self,
+        t1: Any,
+        t2: Any,
+        *,
+        resume_from: str | PathLike[str] | None = ...,
+        **kwargs: Any,
+    ) -> None: ...
+    @property
+    def done(self) -> bool: ...
+    def step(self, parts: int = 1) -> bool: ...
+    def run_for(self, seconds: float) -> bool: ...
+    def save(self, path: str | PathLike[str]) -> None: ...
+    def finish(self) -> DeepDiff: ...
+
+class DeltaError(ValueError): ...
+
+class Delta:
+    def __init__(
+        self,
+        diff: DeepDiff | bytes | str,
+        *,
+        bidirectional: bool = ...,
+        raise_errors: bool = ...,
+    ) -> None: ...
+    @staticmethod
+    def loads(
+        data: bytes | str, *, bidirectional: bool = ..., raise_errors: bool = ...
+    ) -> Delta: ...
+    def dumps(self) -> bytes: ...
+    def to_dict(self) -> dict[str, Any]: ...
+    def __add__(self, other: Any) -> Any: ...
+    def __radd__(self, other: Any) -> Any: ...
+    def __rsub__(self, other: Any) -> Any: ...
+    def __repr__(self) -> str: ...